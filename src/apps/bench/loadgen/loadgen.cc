@@ -36,10 +36,11 @@ static char http_req[] =
 
 class LoadGenSession : public m3::ServerSession {
 public:
-    explicit LoadGenSession(RecvGate *rgate, size_t crt, capsel_t srv_sel)
+    explicit LoadGenSession(RecvGate *rgate, size_t crt, capsel_t srv_sel, label_t id)
         : m3::ServerSession(crt, srv_sel),
+          id(id),
           rem_req(),
-          clisgate(SendGate::create(rgate, SendGateArgs().label(ptr_to_label(this)).credits(1))),
+          clisgate(SendGate::create(rgate, SendGateArgs().label(id).credits(1))),
           sgate(),
           mgate() {
     }
@@ -48,11 +49,12 @@ public:
         if(rem_req > 0) {
             mgate->write(http_req, sizeof(http_req), 0);
             auto msg = create_vmsg(sizeof(http_req));
-            sgate->send(msg.finish(), ptr_to_label(this));
+            sgate->send(msg.finish(), id);
             rem_req--;
         }
     }
 
+    label_t id;
     uint rem_req;
     SendGate clisgate;
     std::unique_ptr<SendGate> sgate;
@@ -69,7 +71,8 @@ public:
 
     explicit ReqHandler(WorkLoop *wl)
         : base_class_t(),
-          _rgate(RecvGate::create(nextlog2<BUF_SIZE>::val, nextlog2<MSG_SIZE>::val)) {
+          _rgate(RecvGate::create(nextlog2<BUF_SIZE>::val, nextlog2<MSG_SIZE>::val)),
+          _sessions() {
         add_operation(LoadGen::START, &ReqHandler::start);
         add_operation(LoadGen::RESPONSE, &ReqHandler::response);
 
@@ -79,8 +82,15 @@ public:
 
     virtual Errors::Code open(LoadGenSession **sess, size_t crt, capsel_t srv_sel,
                               const std::string_view &) override {
-        *sess = new LoadGenSession(&_rgate, crt, srv_sel);
-        return Errors::NONE;
+        // the sessions are identified by their index + 1 instead of their address, because
+        // addresses might use the label bits that are reserved for derived send gates
+        for(size_t i = 0; i < ARRAY_SIZE(_sessions); ++i) {
+            if(!_sessions[i]) {
+                *sess = _sessions[i] = new LoadGenSession(&_rgate, crt, srv_sel, i + 1);
+                return Errors::NONE;
+            }
+        }
+        return Errors::NO_SPACE;
     }
 
     virtual Errors::Code obtain(LoadGenSession *sess, size_t, CapExchange &xchg) override {
@@ -109,6 +119,7 @@ public:
     }
 
     virtual Errors::Code close(LoadGenSession *sess, size_t) override {
+        _sessions[sess->id - 1] = nullptr;
         delete sess;
         return Errors::NONE;
     }
@@ -118,7 +129,7 @@ public:
     }
 
     void start(GateIStream &is) {
-        LoadGenSession *sess = is.label<LoadGenSession *>();
+        LoadGenSession *sess = session(is);
         uint count;
         is >> count;
         sess->rem_req = count;
@@ -130,7 +141,7 @@ public:
     }
 
     void response(GateIStream &is) {
-        LoadGenSession *sess = is.label<LoadGenSession *>();
+        LoadGenSession *sess = session(is);
         size_t amount;
         is >> amount;
 
@@ -140,7 +151,13 @@ public:
     }
 
 private:
+    LoadGenSession *session(GateIStream &is) {
+        // ignore the bits that clients might have added by deriving the send gate
+        return _sessions[(is.label<label_t>() & ~KIF::SGATE_DERIVE_LABEL_BITS) - 1];
+    }
+
     RecvGate _rgate;
+    LoadGenSession *_sessions[Server<ReqHandler>::MAX_SESSIONS];
 };
 
 int main(int argc, char **argv) {
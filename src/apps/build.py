@@ -20,6 +20,7 @@ dirs = [
     'parchksum',
     'ping',
     'plasma',
+    'pmap',
     'queue',
     'rusthello',
     'rustnettests',
@@ -28,6 +29,7 @@ dirs = [
     'shell',
     'spammer',
     'standalone',
+    'svcctl',
     'timertest',
     'unittests',
 ]
@@ -15,25 +15,87 @@
 
 #![no_std]
 
+use m3::col::{ToString, Vec};
+use m3::env;
+use m3::format;
 use m3::println;
+use m3::session::ResMngQuotaKind;
 use m3::tiles::Activity;
 
+fn usage() -> ! {
+    let name = env::args().next().unwrap();
+    println!("Usage: {} [tree]", name);
+    println!();
+    println!("  without arguments: lists all activities with their quotas and usage");
+    println!("  tree             : prints the domains and activities with their quotas as a tree");
+    m3::exit(1);
+}
+
+fn print_tree() -> i32 {
+    let (num, _) = Activity::own()
+        .resmng()
+        .unwrap()
+        .get_quota_count()
+        .expect("Unable to get quota count");
+    println!(
+        "{:>16} | {:>16} | {:>20} | {:>9} | {:>10} | Name",
+        "UserMem", "KernelMem", "Time", "Endpoints", "Pagetables"
+    );
+    for i in 0..num {
+        match Activity::own().resmng().unwrap().get_quota_info(i) {
+            Ok(node) => {
+                let name = match node.kind {
+                    ResMngQuotaKind::Domain if node.name.is_empty() => "[domain]".to_string(),
+                    ResMngQuotaKind::Domain => format!("[domain {}]", node.name),
+                    ResMngQuotaKind::Activity => node.name,
+                };
+                // show the used and assigned quotas
+                println!(
+                    "{:6}K/{:7}K | {:6}K/{:7}K | {:7}us/{:8}us | {:4}/{:4} | {:4}/{:5} | {:0l$}{}",
+                    (node.umem.total() - node.umem.left()) / 1024,
+                    node.umem.total() / 1024,
+                    (node.kmem.total() - node.kmem.left()) / 1024,
+                    node.kmem.total() / 1024,
+                    (node.time.total() - node.time.left()) / 1000,
+                    node.time.total() / 1000,
+                    node.eps.total() - node.eps.left(),
+                    node.eps.total(),
+                    node.pts.total() - node.pts.left(),
+                    node.pts.total(),
+                    "",
+                    name,
+                    l = node.layer as usize * 2,
+                );
+            },
+            Err(e) => println!("Unable to get quota info with idx {}: {:?}", i, e.code()),
+        }
+    }
+    0
+}
+
 #[no_mangle]
 pub fn main() -> i32 {
+    let args: Vec<&str> = env::args().collect();
+    match args.get(1) {
+        Some(&"tree") if args.len() == 2 => return print_tree(),
+        None => {},
+        _ => usage(),
+    }
+
     let (num, _) = Activity::own()
         .resmng()
         .unwrap()
         .get_activity_count()
         .expect("Unable to get Activity count");
     println!(
-        "{:2} | {:4} | {:>10} | {:>22} | {:>14} | {:>14} | {:>12} | Name",
-        "ID", "Tile", "Endpoints", "Time", "UserMem", "KernelMem", "Pagetables"
+        "{:2} | {:4} | {:>10} | {:>22} | {:>14} | {:>14} | {:>12} | {:>10} | {:>7} | {:>7} | {:>7} | Name",
+        "ID", "Tile", "Endpoints", "Time", "UserMem", "KernelMem", "Pagetables", "CPU", "CtxSws", "PFs", "Msgs"
     );
     for i in 0..num {
         match Activity::own().resmng().unwrap().get_activity_info(i) {
             Ok(act) => {
                 println!(
-                    "{:2} | {:4} | {:2}:{:3}/{:3} | {:4}:{:6}us/{:6}us | {:2}:{:4}M/{:4}M | {:2}:{:4}M/{:4}M | {:4}:{:3}/{:3} | {:0l$}{}",
+                    "{:2} | {:4} | {:2}:{:3}/{:3} | {:4}:{:6}us/{:6}us | {:2}:{:4}M/{:4}M | {:2}:{:4}M/{:4}M | {:4}:{:3}/{:3} | {:8}us | {:7} | {:7} | {:7} | {:0l$}{}",
                     act.id,
                     act.tile,
                     act.eps.id(),
@@ -51,6 +113,10 @@ pub fn main() -> i32 {
                     act.pts.id(),
                     act.pts.left(),
                     act.pts.total(),
+                    act.usage.cpu_time / 1000,
+                    act.usage.ctxsws,
+                    act.usage.pagefaults,
+                    act.usage.msgs,
                     "",
                     act.name,
                     l = act.layer as usize * 2,
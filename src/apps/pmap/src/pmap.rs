@@ -0,0 +1,159 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+#![no_std]
+
+use m3::cfg;
+use m3::col::{String, ToString, Vec};
+use m3::env;
+use m3::kif::Perm;
+use m3::println;
+use m3::session::{MapFlags, Pager, PagerAddrSpaceInfo, PagerMemKind};
+use m3::tiles::Activity;
+
+fn usage() -> ! {
+    let name = env::args().next().unwrap();
+    println!("Usage: {} [-s] [<id>...]", name);
+    println!();
+    println!("  without arguments: lists all address spaces of the pager with their mappings");
+    println!("  -s               : lists only the address spaces with their memory usage");
+    println!("  <id>             : lists only the address spaces with given ids");
+    m3::exit(1);
+}
+
+fn perm_str(perm: Perm) -> String {
+    let mut res = String::new();
+    res.push(if perm.contains(Perm::R) { 'r' } else { '-' });
+    res.push(if perm.contains(Perm::W) { 'w' } else { '-' });
+    res.push(if perm.contains(Perm::X) { 'x' } else { '-' });
+    res
+}
+
+fn kind_str(kind: PagerMemKind) -> &'static str {
+    match kind {
+        PagerMemKind::Anon => "anon",
+        PagerMemKind::Mem => "mem",
+        PagerMemKind::File => "file",
+        PagerMemKind::Shm => "shm",
+    }
+}
+
+fn print_aspace(pager: &Pager, idx: usize, aspace: &PagerAddrSpaceInfo) {
+    println!(
+        "{:>21} | {:>8} | {:4}/{:4} | {:5} | {:4} | {:>10} | {:>8} | {:>8} | {:>8} | Name",
+        "Range", "Size", "Prot", "Max", "Flags", "Kind", "Offset", "Resident", "Swapped", "COW"
+    );
+
+    for i in 0..aspace.mappings {
+        match pager.get_mapping_info(idx, i) {
+            Ok(map) => {
+                let page_kb = cfg::PAGE_SIZE / 1024;
+                println!(
+                    "{:#010x}-{:#010x} | {:7}K | {:4}/{:4} | {:5} | {:4} | {:#10x} | {:7}K | {:7}K | {:7}K | {}",
+                    map.virt,
+                    map.virt + map.size as u64 - 1,
+                    map.size / 1024,
+                    perm_str(map.prot),
+                    perm_str(map.perm),
+                    if map.flags.contains(MapFlags::SHARED) {
+                        "s"
+                    }
+                    else {
+                        "p"
+                    },
+                    kind_str(map.kind),
+                    map.offset,
+                    map.resident * page_kb,
+                    map.swapped * page_kb,
+                    map.cow * page_kb,
+                    map.name,
+                );
+            },
+            Err(e) => println!(
+                "Unable to get info about mapping {} of address space {}: {:?}",
+                i,
+                aspace.id,
+                e.code()
+            ),
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut summary = false;
+    let mut ids = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg {
+            "-s" => summary = true,
+            id => match id.parse::<usize>() {
+                Ok(id) => ids.push(id),
+                Err(_) => usage(),
+            },
+        }
+    }
+
+    let pager = Activity::own()
+        .pager()
+        .expect("Not running under the control of a pager");
+
+    let num = pager
+        .get_aspace_count()
+        .expect("Unable to get address space count");
+    if summary {
+        println!(
+            "{:>4} | {:>6} | {:>8} | {:>10} | Name",
+            "ID", "Parent", "Mappings", "Charged"
+        );
+    }
+    for i in 0..num {
+        match pager.get_aspace_info(i) {
+            Ok(aspace) if !ids.is_empty() && !ids.contains(&aspace.id) => {},
+            Ok(aspace) => {
+                let parent = match aspace.parent {
+                    Some(p) => p.to_string(),
+                    None => "-".to_string(),
+                };
+                if summary {
+                    println!(
+                        "{:4} | {:>6} | {:8} | {:9}K | {}",
+                        aspace.id,
+                        parent,
+                        aspace.mappings,
+                        aspace.charged / 1024,
+                        aspace.name,
+                    );
+                }
+                else {
+                    println!(
+                        "Address space {} (parent {}) of {}: {}K charged",
+                        aspace.id,
+                        parent,
+                        aspace.name,
+                        aspace.charged / 1024
+                    );
+                    print_aspace(pager, i, &aspace);
+                    println!();
+                }
+            },
+            Err(e) => println!(
+                "Unable to get info about address space with idx {}: {:?}",
+                i,
+                e.code()
+            ),
+        }
+    }
+    0
+}
@@ -18,6 +18,8 @@
 
 #![no_std]
 
+use m3::col::Vec;
+use m3::env;
 use m3::test::{DefaultWvTester, WvTester};
 use m3::{println, wv_run_suite};
 
@@ -30,12 +32,16 @@ mod tenvvars;
 mod tfilemux;
 mod tfloat;
 mod tgenfile;
+mod tlineedit;
 mod tm3fs;
 mod tmemmap;
 mod tmgate;
 mod tnonblock;
 mod tpaging;
 mod tpipe;
+// requires a TileMux to share the tile with the childs we start
+#[cfg(not(target_vendor = "host"))]
+mod tresmng;
 mod trgate;
 mod tsems;
 mod tserver;
@@ -44,34 +50,51 @@ mod tsgate;
 mod tsrvmsgs;
 mod tsyscalls;
 mod ttreap;
+mod tvterm;
+
+/// Runs the given suite, unless other suites have been selected via the arguments
+macro_rules! run_suite {
+    ($t:expr, $sel:expr, $suite:ident) => {
+        if $sel.is_empty() || $sel.contains(&stringify!($suite)) {
+            wv_run_suite!($t, $suite::run);
+        }
+    };
+}
 
 #[no_mangle]
 pub fn main() -> i32 {
+    // the names of the suites to run (all by default)
+    let sel: Vec<&str> = env::args().skip(1).collect();
+
     let mut tester = DefaultWvTester::default();
-    wv_run_suite!(tester, tboxlist::run);
-    wv_run_suite!(tester, tbufio::run);
-    wv_run_suite!(tester, tdir::run);
-    wv_run_suite!(tester, tdlist::run);
-    wv_run_suite!(tester, tenvvars::run);
-    wv_run_suite!(tester, tfilemux::run);
-    wv_run_suite!(tester, tfloat::run);
-    wv_run_suite!(tester, tgenfile::run);
-    wv_run_suite!(tester, tm3fs::run);
-    wv_run_suite!(tester, tmemmap::run);
-    wv_run_suite!(tester, tmgate::run);
-    wv_run_suite!(tester, tnonblock::run);
-    wv_run_suite!(tester, tpaging::run);
-    wv_run_suite!(tester, tpipe::run);
-    wv_run_suite!(tester, trgate::run);
-    wv_run_suite!(tester, tsgate::run);
-    wv_run_suite!(tester, tsems::run);
-    wv_run_suite!(tester, tserver::run);
+    run_suite!(tester, sel, tboxlist);
+    run_suite!(tester, sel, tbufio);
+    run_suite!(tester, sel, tdir);
+    run_suite!(tester, sel, tdlist);
+    run_suite!(tester, sel, tenvvars);
+    run_suite!(tester, sel, tfilemux);
+    run_suite!(tester, sel, tfloat);
+    run_suite!(tester, sel, tgenfile);
+    run_suite!(tester, sel, tlineedit);
+    run_suite!(tester, sel, tm3fs);
+    run_suite!(tester, sel, tmemmap);
+    run_suite!(tester, sel, tmgate);
+    run_suite!(tester, sel, tnonblock);
+    run_suite!(tester, sel, tpaging);
+    run_suite!(tester, sel, tpipe);
+    #[cfg(not(target_vendor = "host"))]
+    run_suite!(tester, sel, tresmng);
+    run_suite!(tester, sel, trgate);
+    run_suite!(tester, sel, tsgate);
+    run_suite!(tester, sel, tsems);
+    run_suite!(tester, sel, tserver);
     // requires a TileMux with notification support
     #[cfg(not(target_vendor = "host"))]
-    wv_run_suite!(tester, tsrvmsgs::run);
-    wv_run_suite!(tester, tsyscalls::run);
-    wv_run_suite!(tester, ttreap::run);
-    wv_run_suite!(tester, tactivity::run);
+    run_suite!(tester, sel, tsrvmsgs);
+    run_suite!(tester, sel, tsyscalls);
+    run_suite!(tester, sel, ttreap);
+    run_suite!(tester, sel, tvterm);
+    run_suite!(tester, sel, tactivity);
     println!("{}", tester);
     0
 }
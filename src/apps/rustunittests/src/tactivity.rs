@@ -24,14 +24,22 @@ use m3::test::{DefaultWvTester, WvTester};
 use m3::tiles::{Activity, ActivityArgs, ChildActivity, RunningActivity, Tile};
 use m3::time::TimeDuration;
 
-use m3::{send_vmsg, wv_assert_eq, wv_assert_ok, wv_run_test};
+use m3::{send_vmsg, wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, run_stop);
     wv_run_test!(t, run_arguments);
     wv_run_test!(t, run_send_receive);
     #[cfg(not(target_vendor = "host"))]
+    wv_run_test!(t, run_crash);
+    #[cfg(not(target_vendor = "host"))]
+    wv_run_test!(t, run_signal);
+    #[cfg(not(target_vendor = "host"))]
+    wv_run_test!(t, alarm);
+    #[cfg(not(target_vendor = "host"))]
     wv_run_test!(t, exec_fail);
+    #[cfg(not(target_vendor = "host"))]
+    wv_run_test!(t, migrate);
     wv_run_test!(t, exec_hello);
     wv_run_test!(t, exec_rust_hello);
 }
@@ -137,6 +145,190 @@ fn run_send_receive(t: &mut dyn WvTester) {
     wv_assert_eq!(t, act.wait(), Ok(42 + 23));
 }
 
+#[cfg(not(target_vendor = "host"))]
+fn run_crash(t: &mut dyn WvTester) {
+    use m3::errors::Code;
+    use m3::kif::tilemux;
+    use m3::syscalls;
+
+    let tile = wv_assert_ok!(Tile::get("clone|own"));
+    if !tile.desc().has_virtmem() {
+        return;
+    }
+
+    let act = wv_assert_ok!(ChildActivity::new_with(tile, ActivityArgs::new("test")));
+    let sel = act.sel();
+
+    // the activity did not crash (yet)
+    wv_assert_err!(t, syscalls::activity_crash(sel), Code::NotFound);
+
+    let act = wv_assert_ok!(act.run(|| {
+        // access an address that is not mapped
+        unsafe { (0x1000 as *mut u64).write_volatile(0) };
+        0
+    }));
+
+    wv_assert_eq!(t, act.wait(), Ok(1));
+
+    let info = wv_assert_ok!(syscalls::activity_crash(act.activity().sel()));
+    wv_assert_eq!(t, info.signal, tilemux::SIGSEGV);
+    wv_assert_eq!(t, info.fault_addr, 0x1000);
+    wv_assert!(t, info.reg_count > 0);
+}
+
+#[cfg(not(target_vendor = "host"))]
+fn migrate(t: &mut dyn WvTester) {
+    use m3::errors::Code;
+    use m3::{reply_vmsg, send_recv};
+
+    let tile1 = wv_assert_ok!(Tile::get("clone"));
+    let tile2 = wv_assert_ok!(Tile::get("clone"));
+    if !tile1.desc().supports_tilemux() || !tile1.desc().has_virtmem() {
+        return;
+    }
+
+    let mut act = wv_assert_ok!(ChildActivity::new_with(tile1, ActivityArgs::new("test")));
+
+    let rgate = wv_assert_ok!(RecvGate::new(math::next_log2(256), math::next_log2(256)));
+    let sgate = wv_assert_ok!(SendGate::new_with(SGateArgs::new(&rgate).credits(1)));
+
+    wv_assert_ok!(act.delegate_obj(rgate.sel()));
+
+    let mut dst = act.data_sink();
+    dst.push(rgate.sel());
+
+    let mut act = wv_assert_ok!(act.run(|| {
+        let mut src = Activity::own().data_source();
+        let rg_sel: Selector = src.pop().unwrap();
+
+        let mut rgate = RecvGate::new_bind(rg_sel, math::next_log2(256), math::next_log2(256));
+        wv_assert_ok!(rgate.activate());
+        for _ in 0..2 {
+            let mut msg = wv_assert_ok!(recv_msg(&rgate));
+            let val = wv_assert_ok!(msg.pop::<u32>());
+            wv_assert_ok!(reply_vmsg!(msg, val + 1));
+        }
+        0
+    }));
+
+    let mut reply = wv_assert_ok!(send_recv!(&sgate, RecvGate::def(), 1u32));
+    wv_assert_eq!(t, reply.pop::<u32>(), Ok(2));
+
+    // the activity is blocked in recv_msg now, which allows us to migrate it
+    match act.migrate(tile2.clone()) {
+        // the tiles are not of the same type
+        Err(e) if e.code() == Code::InvArgs => {
+            wv_assert_ok!(act.stop());
+            return;
+        },
+        res => wv_assert_ok!(res),
+    }
+    wv_assert_eq!(t, act.activity().tile().id(), tile2.id());
+
+    // the send gate has to refer to the new tile and the child continues there
+    let mut reply = wv_assert_ok!(send_recv!(&sgate, RecvGate::def(), 3u32));
+    wv_assert_eq!(t, reply.pop::<u32>(), Ok(4));
+
+    wv_assert_eq!(t, act.wait(), Ok(0));
+}
+
+#[cfg(not(target_vendor = "host"))]
+static GOT_SIGNAL: m3::cell::StaticCell<bool> = m3::cell::StaticCell::new(false);
+
+#[cfg(not(target_vendor = "host"))]
+fn signal_handler(_sig: m3::signal::Signal) {
+    GOT_SIGNAL.set(true);
+}
+
+#[cfg(not(target_vendor = "host"))]
+fn run_signal(t: &mut dyn WvTester) {
+    use m3::com::RGateArgs;
+    use m3::signal::{self, Signal};
+
+    let tile = wv_assert_ok!(Tile::get("clone|own"));
+    if !tile.desc().supports_tilemux() {
+        return;
+    }
+
+    let mut rg = wv_assert_ok!(RecvGate::new_with(
+        RGateArgs::default().order(6).msg_order(6)
+    ));
+    wv_assert_ok!(rg.activate());
+
+    for handle in [true, false] {
+        let mut act = wv_assert_ok!(ChildActivity::new_with(
+            tile.clone(),
+            ActivityArgs::new("test")
+        ));
+
+        let sg = wv_assert_ok!(SendGate::new_with(SGateArgs::new(&rg).credits(1)));
+        wv_assert_ok!(act.delegate_obj(sg.sel()));
+
+        let mut dst = act.data_sink();
+        dst.push(sg.sel());
+        dst.push(handle);
+
+        let act = wv_assert_ok!(act.run(|| {
+            let mut src = Activity::own().data_source();
+            let sg_sel: Selector = src.pop().unwrap();
+            let handle: bool = src.pop().unwrap();
+
+            GOT_SIGNAL.set(false);
+            if handle {
+                signal::set_handler(Signal::USR1, Some(signal_handler)).unwrap();
+            }
+
+            // notify parent that we're ready
+            let sg = SendGate::new_bind(sg_sel);
+            send_vmsg!(&sg, RecvGate::def(), 1).unwrap();
+
+            while !GOT_SIGNAL.get() {
+                Activity::own()
+                    .sleep_for(TimeDuration::from_millis(1))
+                    .unwrap();
+            }
+            42
+        }));
+
+        wv_assert_ok!(recv_msg(&rg));
+        wv_assert_ok!(act.signal(Signal::USR1));
+
+        let exp = if handle {
+            42
+        }
+        else {
+            128 + Signal::USR1.val as i32
+        };
+        wv_assert_eq!(t, act.wait(), Ok(exp));
+    }
+}
+
+#[cfg(not(target_vendor = "host"))]
+fn alarm(t: &mut dyn WvTester) {
+    use m3::signal::{self, Signal};
+
+    if !Activity::own().tile_desc().supports_tilemux() {
+        return;
+    }
+
+    GOT_SIGNAL.set(false);
+    wv_assert_ok!(signal::set_handler(Signal::ALRM, Some(signal_handler)));
+    wv_assert_ok!(signal::alarm(Some(TimeDuration::from_millis(1))));
+
+    // the alarm interrupts our sleep
+    for _ in 0..100 {
+        if GOT_SIGNAL.get() {
+            break;
+        }
+        wv_assert_ok!(Activity::own().sleep_for(TimeDuration::from_millis(1)));
+    }
+    wv_assert!(t, GOT_SIGNAL.get());
+
+    // cancelling an alarm that already fired is fine
+    wv_assert_ok!(signal::alarm(None));
+    wv_assert_ok!(signal::set_handler(Signal::ALRM, None));
+}
+
 #[cfg(not(target_vendor = "host"))]
 fn exec_fail(_t: &mut dyn WvTester) {
     use m3::errors::Code;
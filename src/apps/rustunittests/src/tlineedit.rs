@@ -0,0 +1,262 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::Vec;
+use m3::test::WvTester;
+use m3::vfs::{EditAction, LineEditor};
+use m3::{vec, wv_assert_eq, wv_run_test};
+
+pub fn run(t: &mut dyn WvTester) {
+    wv_run_test!(t, insert);
+    wv_run_test!(t, cursor);
+    wv_run_test!(t, delete);
+    wv_run_test!(t, kill);
+    wv_run_test!(t, history);
+    wv_run_test!(t, control);
+    wv_run_test!(t, csi);
+    wv_run_test!(t, raw_report);
+}
+
+fn feed(ed: &mut LineEditor, input: &[u8], out: &mut Vec<u8>) -> EditAction {
+    let mut res = EditAction::None;
+    for b in input {
+        res = ed.input(*b, out);
+    }
+    res
+}
+
+fn feed_raw(ed: &mut LineEditor, input: &[u8], out: &mut Vec<u8>) -> Option<(usize, usize)> {
+    let mut res = None;
+    for b in input {
+        if let Some(size) = ed.filter_report(*b, out) {
+            res = Some(size);
+        }
+    }
+    res
+}
+
+fn insert(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    wv_assert_eq!(t, feed(&mut ed, b"ac\x1b[Db", &mut out), EditAction::None);
+    wv_assert_eq!(t, feed(&mut ed, b"\n", &mut out), EditAction::Line);
+    wv_assert_eq!(t, ed.take_line(), b"abc\n".to_vec());
+
+    // inserting in the middle redraws the rest of the line and moves the cursor back
+    wv_assert_eq!(
+        t,
+        out,
+        b"a\x1b[Kc\x1b[K\x1b[1Dbc\x1b[K\x1b[1D\x1b[1C\n".to_vec()
+    );
+
+    // the editor starts from scratch afterwards
+    out.clear();
+    wv_assert_eq!(t, feed(&mut ed, b"x\n", &mut out), EditAction::Line);
+    wv_assert_eq!(t, ed.take_line(), b"x\n".to_vec());
+}
+
+fn cursor(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    // ^A, ^E
+    feed(&mut ed, b"bc\x01a\x05d\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"abcd\n".to_vec());
+
+    // ^B, ^F and the arrow keys
+    feed(&mut ed, b"ad\x02\x02\x06b\x1b[C\x1b[D\x1b[Cc\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"abdc\n".to_vec());
+
+    // home and end in all variants
+    feed(
+        &mut ed,
+        b"c\x1b[Hb\x1bOHa\x1b[Fd\x1b[1~\x1b[4~e\x1bOF\x1b[7~\x1b[8~f\n",
+        &mut out,
+    );
+    wv_assert_eq!(t, ed.take_line(), b"abcdef\n".to_vec());
+
+    // the cursor stays within the line
+    feed(&mut ed, b"b\x1b[D\x1b[D\x02a\x1b[C\x1b[C\x06c\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"abc\n".to_vec());
+
+    // moving the cursor produces the according escape sequences
+    out.clear();
+    feed(&mut ed, b"abc\x01\x05", &mut out);
+    wv_assert_eq!(t, out, b"a\x1b[Kb\x1b[Kc\x1b[K\x1b[3D\x1b[3C".to_vec());
+}
+
+fn delete(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    // backspace
+    feed(&mut ed, b"abx\x7fc\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"abc\n".to_vec());
+    feed(&mut ed, b"axbc\x02\x02\x08\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"abc\n".to_vec());
+
+    // nothing to delete at the beginning
+    feed(&mut ed, b"\x7fa\x01\x7f\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"a\n".to_vec());
+
+    // delete key
+    feed(&mut ed, b"xabc\x01\x1b[3~\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"abc\n".to_vec());
+
+    // nothing to delete at the end
+    feed(&mut ed, b"abc\x1b[3~\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"abc\n".to_vec());
+}
+
+fn kill(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    // ^U removes everything before the cursor
+    feed(&mut ed, b"foo bar\x1b[D\x1b[D\x1b[D\x15x\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"xbar\n".to_vec());
+
+    // ^K removes everything after the cursor
+    feed(&mut ed, b"foo bar\x01\x06\x06\x06", &mut out);
+    out.clear();
+    feed(&mut ed, b"\x0b", &mut out);
+    wv_assert_eq!(t, out, b"\x1b[K".to_vec());
+    feed(&mut ed, b"\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"foo\n".to_vec());
+
+    // both at once
+    feed(&mut ed, b"abc\x02\x0b\x15\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"\n".to_vec());
+}
+
+fn history(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    // nothing to browse yet
+    feed(&mut ed, b"\x1b[A\x1b[B\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"\n".to_vec());
+
+    for l in &[&b"one\n"[..], b"two\n", b"two\n"] {
+        feed(&mut ed, l, &mut out);
+        ed.take_line();
+    }
+
+    // duplicates and empty lines are not recorded
+    feed(&mut ed, b"\x1b[A\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"two\n".to_vec());
+    feed(&mut ed, b"\x1b[A\x1b[A\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"one\n".to_vec());
+    feed(&mut ed, b"\x1b[A\x1b[A\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"two\n".to_vec());
+
+    // the history is now: one, two, one, two
+    feed(&mut ed, b"\x1b[A\x1b[A\x1b[A\x1b[A\x1b[A\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"one\n".to_vec());
+
+    // going forward again restores the line we started with
+    feed(&mut ed, b"th\x1b[A\x1b[A\x1b[B\x1b[B\x1b[Bree\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"three\n".to_vec());
+
+    // history lines can be edited
+    feed(&mut ed, b"\x1bOA\x7f\x7f\x7fee\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"thee\n".to_vec());
+
+    // the cursor is placed at the end of the history line
+    out.clear();
+    feed(&mut ed, b"x\x1b[A", &mut out);
+    wv_assert_eq!(t, out, b"x\x1b[K\x1b[1Dthee\x1b[K".to_vec());
+}
+
+fn control(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    wv_assert_eq!(t, feed(&mut ed, b"abc\x03", &mut out), EditAction::Signal);
+    wv_assert_eq!(t, feed(&mut ed, b"d\x04", &mut out), EditAction::Eof);
+    wv_assert_eq!(t, ed.take_partial(), b"abcd".to_vec());
+
+    // partial lines are not recorded in the history
+    wv_assert_eq!(t, feed(&mut ed, b"\x1b[A\n", &mut out), EditAction::Line);
+    wv_assert_eq!(t, ed.take_line(), b"\n".to_vec());
+
+    // other control characters are ignored
+    out.clear();
+    wv_assert_eq!(t, feed(&mut ed, b"\x07\x0c\t", &mut out), EditAction::None);
+    wv_assert_eq!(t, out, Vec::<u8>::new());
+}
+
+fn csi(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    // cursor position reports
+    wv_assert_eq!(
+        t,
+        feed(&mut ed, b"a\x1b[24;80R", &mut out),
+        EditAction::WinSize(80, 24)
+    );
+    wv_assert_eq!(
+        t,
+        feed(&mut ed, b"\x1b[100;300R", &mut out),
+        EditAction::WinSize(300, 100)
+    );
+    // incomplete reports are ignored
+    wv_assert_eq!(t, feed(&mut ed, b"\x1b[24R", &mut out), EditAction::None);
+    wv_assert_eq!(t, feed(&mut ed, b"\x1b[;80R", &mut out), EditAction::None);
+
+    // unknown sequences do not end up in the line
+    feed(
+        &mut ed,
+        b"\x1b[5~\x1b[?25h\x1b[1;5Sb\x1bOPc\x1bxd\n",
+        &mut out,
+    );
+    wv_assert_eq!(t, ed.take_line(), b"abcd\n".to_vec());
+
+    // parameters of arbitrary length are ignored
+    feed(&mut ed, b"\x1b[", &mut out);
+    for _ in 0..100 {
+        feed(&mut ed, b"1;", &mut out);
+    }
+    feed(&mut ed, b"~ok\n", &mut out);
+    wv_assert_eq!(t, ed.take_line(), b"ok\n".to_vec());
+}
+
+fn raw_report(t: &mut dyn WvTester) {
+    let mut ed = LineEditor::new();
+    let mut out = vec![];
+
+    // the report is removed from the input
+    wv_assert_eq!(
+        t,
+        feed_raw(&mut ed, b"x\x1b[24;80Ry", &mut out),
+        Some((80, 24))
+    );
+    wv_assert_eq!(t, out, b"xy".to_vec());
+
+    // other sequences are passed through
+    out.clear();
+    let seqs = b"\x1b[A\x1bOB\x1ba\x1b\x1b[1;5C\x1b[24R\x1b[;80R\x1b[24;R";
+    wv_assert_eq!(t, feed_raw(&mut ed, seqs, &mut out), None);
+    wv_assert_eq!(t, out, seqs.to_vec());
+
+    // an unfinished sequence is held back
+    out.clear();
+    wv_assert_eq!(t, feed_raw(&mut ed, b"\x1b[12", &mut out), None);
+    wv_assert_eq!(t, out, Vec::<u8>::new());
+    wv_assert_eq!(t, feed_raw(&mut ed, b";40R\x03", &mut out), Some((40, 12)));
+    wv_assert_eq!(t, out, b"\x03".to_vec());
+}
@@ -18,16 +18,18 @@
 
 use m3::col::ToString;
 use m3::errors::Code;
-use m3::io::Write;
+use m3::io::{Read, Write};
+use m3::session::Pipes;
 use m3::test::WvTester;
 use m3::vfs::{FileMode, OpenFlags, VFS};
-use m3::{wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
+use m3::{wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, paths);
     wv_run_test!(t, mkdir_rmdir);
     wv_run_test!(t, link_unlink);
     wv_run_test!(t, rename);
+    wv_run_test!(t, fifo);
 }
 
 fn setup() {
@@ -252,3 +254,51 @@ fn rename(t: &mut dyn WvTester) {
 
     teardown();
 }
+
+fn fifo(t: &mut dyn WvTester) {
+    wv_assert_ok!(VFS::mkfifo("/myfifo", FileMode::from_bits(0o644).unwrap()));
+    wv_assert_err!(
+        t,
+        VFS::mkfifo("/myfifo", FileMode::from_bits(0o644).unwrap()),
+        Code::Exists
+    );
+
+    let info = wv_assert_ok!(VFS::stat("/myfifo"));
+    wv_assert!(t, info.mode.is_pip());
+
+    {
+        let mut reader = wv_assert_ok!(VFS::open("/myfifo", OpenFlags::R));
+        {
+            let mut writer = wv_assert_ok!(VFS::open("/myfifo", OpenFlags::W));
+            wv_assert_ok!(write!(writer, "hello fifo"));
+        }
+
+        // the writer has been closed, so that we get EOF after the data
+        let s = wv_assert_ok!(reader.read_to_string());
+        wv_assert_eq!(t, s, "hello fifo");
+    }
+
+    // the pipe is gone, but the FIFO can be opened again
+    {
+        let mut reader = wv_assert_ok!(VFS::open("/myfifo", OpenFlags::R));
+        {
+            let mut writer = wv_assert_ok!(VFS::open("/myfifo", OpenFlags::W));
+            wv_assert_ok!(write!(writer, "again"));
+        }
+        let s = wv_assert_ok!(reader.read_to_string());
+        wv_assert_eq!(t, s, "again");
+    }
+
+    // FIFOs are uni-directional
+    wv_assert_err!(t, VFS::open("/myfifo", OpenFlags::RW), Code::InvArgs);
+
+    // m3fs checks the permissions and only m3fs can authorize opens at the pipes server
+    wv_assert_ok!(VFS::mkfifo("/wofifo", FileMode::from_bits(0o200).unwrap()));
+    wv_assert_err!(t, VFS::open("/wofifo", OpenFlags::R), Code::NoPerm);
+    let pipes = wv_assert_ok!(Pipes::new("pipes"));
+    wv_assert_err!(t, pipes.auth_named(info.inode, true), Code::NoPerm);
+    wv_assert_err!(t, pipes.open_named(0x1234, true), Code::NoPerm);
+
+    wv_assert_ok!(VFS::unlink("/wofifo"));
+    wv_assert_ok!(VFS::unlink("/myfifo"));
+}
@@ -13,28 +13,375 @@
  * General Public License version 2 for more details.
  */
 
+use m3::cfg;
 use m3::com::MemGate;
+use m3::errors::Code;
+use m3::io::{Read, Write};
 use m3::kif::Perm;
+use m3::math;
+use m3::session::{Advice, MapFlags, Pager, PagerMemKind, ShmFlags};
 use m3::test::WvTester;
 use m3::tiles::Activity;
-use m3::{wv_assert_ok, wv_run_test};
+use m3::vfs::{File, Map, OpenFlags, Seek, SeekMode, VFS};
+use m3::{wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, large_pages);
+    wv_run_test!(t, anon_pages);
+    wv_run_test!(t, swapping);
+    wv_run_test!(t, protect_advise);
+    wv_run_test!(t, shared_file);
+    wv_run_test!(t, shared_mem);
+    wv_run_test!(t, aspace_info);
 }
 
-fn large_pages(_t: &mut dyn WvTester) {
+fn large_pages(t: &mut dyn WvTester) {
     if let Some(pager) = Activity::own().pager() {
         const VIRT: u64 = 0x3000_0000;
         const MEM_SIZE: usize = 6 * 1024 * 1024;
+        let lpage = cfg::LPAGE_SIZE as u64;
+
+        // use the same offset within a large page as the physical memory to allow large pages
         let mem = wv_assert_ok!(MemGate::new(MEM_SIZE, Perm::RW));
-        wv_assert_ok!(pager.map_mem(VIRT, &mem, MEM_SIZE, Perm::RW));
+        let (phys, _) = wv_assert_ok!(mem.region());
+        let virt = VIRT + (phys.raw() & (lpage - 1));
+        wv_assert_ok!(pager.map_mem(virt, &mem, MEM_SIZE, Perm::RW));
 
-        let ptr = VIRT as *mut u64;
+        let before = wv_assert_ok!(pager.map_stats());
+        let ptr = virt as *mut u64;
         unsafe {
             ptr.write(0);
         }
 
+        // the whole memory is mapped at once, using large pages in between the boundaries
+        let large =
+            (math::round_dn(virt + MEM_SIZE as u64, lpage) - math::round_up(virt, lpage)) / lpage;
+        let stats = wv_assert_ok!(pager.map_stats());
+        wv_assert_eq!(t, stats.large_pages, before.large_pages + large as usize);
+        wv_assert_ok!(pager.unmap(virt));
+
+        // anonymous memory is faulted in as a whole large page, if possible
+        wv_assert_ok!(pager.map_anon(VIRT, MEM_SIZE, Perm::RW, MapFlags::empty()));
+        let ptr = VIRT as *mut u8;
+        unsafe {
+            ptr.add(cfg::LPAGE_SIZE + cfg::LPAGE_SIZE / 2).write(0xAB);
+            wv_assert_eq!(t, ptr.add(cfg::LPAGE_SIZE).read(), 0);
+            wv_assert_eq!(
+                t,
+                ptr.add(cfg::LPAGE_SIZE + cfg::LPAGE_SIZE / 2).read(),
+                0xAB
+            );
+        }
+        wv_assert_ok!(pager.unmap(VIRT));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn anon_pages(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT: u64 = 0x3000_0000;
+        const MEM_SIZE: usize = 8 * 1024 * 1024;
+        const WORDS_PER_PAGE: usize = cfg::PAGE_SIZE / 8;
+        wv_assert_ok!(pager.map_anon(VIRT, MEM_SIZE, Perm::RW, MapFlags::NOLPAGE));
+
+        // touch all pages first and check them afterwards, so that the pager has to bring back
+        // the pages it swapped out in between (if swapping is enabled)
+        let ptr = VIRT as *mut u64;
+        for p in 0..MEM_SIZE / cfg::PAGE_SIZE {
+            unsafe {
+                ptr.add(p * WORDS_PER_PAGE).write(p as u64);
+            }
+        }
+        for p in 0..MEM_SIZE / cfg::PAGE_SIZE {
+            let val = unsafe { ptr.add(p * WORDS_PER_PAGE).read() };
+            wv_assert_eq!(t, val, p as u64);
+        }
+
+        wv_assert_ok!(pager.unmap(VIRT));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn swapping(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        let before = match pager.swap_stats() {
+            Ok(stats) => stats,
+            Err(e) if e.code() == Code::NotSup => {
+                m3::println!("Skipping swap test without swap space");
+                return;
+            },
+            Err(e) => panic!("Unable to get swap stats: {:?}", e),
+        };
+
+        // more than our memory quota in the swap configuration (see boot/rust-swaptests.xml)
+        const VIRT: u64 = 0x3000_0000;
+        const MEM_SIZE: usize = 24 * 1024 * 1024;
+        const WORDS_PER_PAGE: usize = cfg::PAGE_SIZE / 8;
+        wv_assert_ok!(pager.map_anon(VIRT, MEM_SIZE, Perm::RW, MapFlags::NOLPAGE));
+
+        let ptr = VIRT as *mut u64;
+        for p in 0..MEM_SIZE / cfg::PAGE_SIZE {
+            unsafe {
+                ptr.add(p * WORDS_PER_PAGE).write(p as u64);
+            }
+        }
+        for p in 0..MEM_SIZE / cfg::PAGE_SIZE {
+            let val = unsafe { ptr.add(p * WORDS_PER_PAGE).read() };
+            wv_assert_eq!(t, val, p as u64);
+        }
+
+        // the pager had to write pages to the swap file and read them back
+        let after = wv_assert_ok!(pager.swap_stats());
+        wv_assert!(t, after.swap_outs > before.swap_outs);
+        wv_assert!(t, after.pages_out > before.pages_out);
+        wv_assert!(t, after.swap_ins > before.swap_ins);
+        wv_assert!(t, after.pages_in > before.pages_in);
+
+        wv_assert_ok!(pager.unmap(VIRT));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+/// Returns the memory that is charged to the address space with a mapping at `virt`
+fn charged_for(pager: &Pager, virt: u64) -> usize {
+    let num = wv_assert_ok!(pager.get_aspace_count());
+    for i in 0..num {
+        let aspace = wv_assert_ok!(pager.get_aspace_info(i));
+        for m in 0..aspace.mappings {
+            if wv_assert_ok!(pager.get_mapping_info(i, m)).virt == virt {
+                return aspace.charged;
+            }
+        }
+    }
+    panic!("No address space with a mapping at {:#x}", virt);
+}
+
+fn protect_advise(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT: u64 = 0x3000_0000;
+        const PAGES: usize = 16;
+        const WORDS_PER_PAGE: usize = cfg::PAGE_SIZE / 8;
+        let page_addr = |p: usize| VIRT + (p * cfg::PAGE_SIZE) as u64;
+        wv_assert_ok!(pager.map_anon(VIRT, PAGES * cfg::PAGE_SIZE, Perm::RW, MapFlags::NOLPAGE));
+
+        let ptr = VIRT as *mut u64;
+        for p in 0..PAGES {
+            unsafe {
+                ptr.add(p * WORDS_PER_PAGE).write(p as u64 + 1);
+            }
+        }
+
+        // the permissions cannot be extended and the range needs to be mapped
+        wv_assert_err!(
+            t,
+            pager.protect(page_addr(4), 4 * cfg::PAGE_SIZE, Perm::RWX),
+            Code::NoPerm
+        );
+        wv_assert_err!(
+            t,
+            pager.protect(page_addr(12), 8 * cfg::PAGE_SIZE, Perm::R),
+            Code::NotFound
+        );
+
+        // make a part read-only; the content stays the same and the parts share the memory
+        let charged = charged_for(pager, VIRT);
+        wv_assert_ok!(pager.protect(page_addr(4), 4 * cfg::PAGE_SIZE, Perm::R));
+        wv_assert_eq!(t, charged_for(pager, VIRT), charged);
+        for p in 4..8 {
+            let val = unsafe { ptr.add(p * WORDS_PER_PAGE).read() };
+            wv_assert_eq!(t, val, p as u64 + 1);
+        }
+
+        // drop some pages, which are zeroed afterwards
+        wv_assert_ok!(pager.advise(page_addr(6), 4 * cfg::PAGE_SIZE, Advice::DONTNEED));
+        wv_assert_ok!(pager.advise(VIRT, PAGES * cfg::PAGE_SIZE, Advice::WILLNEED));
+        wv_assert_ok!(pager.advise(VIRT, PAGES * cfg::PAGE_SIZE, Advice::SEQUENTIAL));
+        for p in 0..PAGES {
+            let val = unsafe { ptr.add(p * WORDS_PER_PAGE).read() };
+            let exp = if (6..10).contains(&p) {
+                0
+            }
+            else {
+                p as u64 + 1
+            };
+            wv_assert_eq!(t, val, exp);
+        }
+
+        // unmapping removes all parts of the mapping
+        wv_assert_ok!(pager.unmap(VIRT));
+        wv_assert_ok!(pager.map_anon(VIRT, PAGES * cfg::PAGE_SIZE, Perm::RW, MapFlags::NOLPAGE));
+        wv_assert_ok!(pager.unmap(VIRT));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn shared_file(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT: u64 = 0x3000_0000;
+        const PAGES: usize = 2;
+        const SIZE: usize = PAGES * cfg::PAGE_SIZE;
+
+        let mut file = wv_assert_ok!(VFS::open(
+            "/mapped.dat",
+            OpenFlags::RW | OpenFlags::CREATE | OpenFlags::TRUNC
+        ));
+        let mut buf = [0u8; cfg::PAGE_SIZE];
+        for _ in 0..PAGES {
+            wv_assert_ok!(file.write_all(&buf));
+        }
+        wv_assert_ok!(file.flush());
+
+        // the last page is behind the end of the file and extends it
+        let map_size = SIZE + cfg::PAGE_SIZE;
+        wv_assert_ok!(file.map(pager, VIRT, 0, map_size, Perm::RW, MapFlags::SHARED));
+        let ptr = VIRT as *mut u8;
+        for i in 0..map_size {
+            unsafe {
+                ptr.add(i).write(i as u8);
+            }
+        }
+        wv_assert_ok!(pager.sync(VIRT, map_size));
+        wv_assert_ok!(pager.unmap(VIRT));
+
+        // the changes are visible via the file interface
+        wv_assert!(t, wv_assert_ok!(file.stat()).size >= map_size);
+        wv_assert_ok!(file.seek(0, SeekMode::SET));
+        for p in 0..PAGES + 1 {
+            wv_assert_ok!(file.read_exact(&mut buf));
+            for (i, b) in buf.iter().enumerate() {
+                wv_assert_eq!(t, *b, (p * cfg::PAGE_SIZE + i) as u8);
+            }
+        }
+
+        drop(file);
+        wv_assert_ok!(VFS::unlink("/mapped.dat"));
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn shared_mem(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT1: u64 = 0x3000_0000;
+        const VIRT2: u64 = 0x3100_0000;
+        const SIZE: usize = 2 * cfg::PAGE_SIZE;
+
+        // only the segments in our config are accessible
+        wv_assert_err!(
+            t,
+            pager.open_shm("other-shm", ShmFlags::CREATE),
+            Code::NoPerm
+        );
+        wv_assert_err!(
+            t,
+            pager.open_shm("test-shm", ShmFlags::empty()),
+            Code::NoSuchFile
+        );
+        let (id1, size) = wv_assert_ok!(pager.open_shm("test-shm", ShmFlags::CREATE));
+        wv_assert_eq!(t, size, 0);
+        wv_assert_err!(
+            t,
+            pager.open_shm("test-shm", ShmFlags::CREATE | ShmFlags::EXCL),
+            Code::Exists
+        );
+
+        // the mapping cannot exceed the segment
+        wv_assert_err!(
+            t,
+            pager.map_shm(VIRT1, id1, 0, SIZE, Perm::RW, MapFlags::empty()),
+            Code::InvArgs
+        );
+        wv_assert_ok!(pager.resize_shm(id1, SIZE));
+        wv_assert_ok!(pager.map_shm(VIRT1, id1, 0, SIZE, Perm::RW, MapFlags::empty()));
+
+        let ptr1 = VIRT1 as *mut u64;
+        let words = SIZE / 8;
+        for i in 0..words {
+            unsafe { ptr1.add(i).write(i as u64) };
+        }
+
+        // a second handle refers to the same memory
+        let (id2, size) = wv_assert_ok!(pager.open_shm("test-shm", ShmFlags::CREATE));
+        wv_assert_eq!(t, size, SIZE);
+        wv_assert_ok!(pager.map_shm(
+            VIRT2,
+            id2,
+            cfg::PAGE_SIZE,
+            cfg::PAGE_SIZE,
+            Perm::R,
+            MapFlags::empty()
+        ));
+        let ptr2 = VIRT2 as *const u64;
+        for i in 0..cfg::PAGE_SIZE / 8 {
+            wv_assert_eq!(t, unsafe { ptr2.add(i).read() }, (words / 2 + i) as u64);
+        }
+
+        // shrinking keeps the content before the new end
+        wv_assert_ok!(pager.unmap(VIRT2));
+        wv_assert_ok!(pager.resize_shm(id2, cfg::PAGE_SIZE));
+        for i in 0..words / 2 {
+            wv_assert_eq!(t, unsafe { ptr1.add(i).read() }, i as u64);
+        }
+
+        // the segment is destroyed as soon as it is neither open nor mapped anymore
+        wv_assert_ok!(pager.close_shm(id1));
+        wv_assert_ok!(pager.close_shm(id2));
+        wv_assert_err!(t, pager.close_shm(id2), Code::InvArgs);
+        // the mapping keeps it alive
+        let (id3, _) = wv_assert_ok!(pager.open_shm("test-shm", ShmFlags::empty()));
+        wv_assert_ok!(pager.close_shm(id3));
+        wv_assert_ok!(pager.unmap(VIRT1));
+        wv_assert_err!(
+            t,
+            pager.open_shm("test-shm", ShmFlags::empty()),
+            Code::NoSuchFile
+        );
+    }
+    else {
+        m3::println!("Skipping paging test without pager");
+    }
+}
+
+fn aspace_info(t: &mut dyn WvTester) {
+    if let Some(pager) = Activity::own().pager() {
+        const VIRT: u64 = 0x3000_0000;
+        const PAGES: usize = 16;
+
+        wv_assert_ok!(pager.map_anon(VIRT, PAGES * cfg::PAGE_SIZE, Perm::RW, MapFlags::NOLPAGE));
+        unsafe {
+            (VIRT as *mut u8).write(0x12);
+        }
+
+        // find our mapping; no other address space has a mapping at that address
+        let mut found = 0;
+        let num = wv_assert_ok!(pager.get_aspace_count());
+        for i in 0..num {
+            let aspace = wv_assert_ok!(pager.get_aspace_info(i));
+            for m in 0..aspace.mappings {
+                let map = wv_assert_ok!(pager.get_mapping_info(i, m));
+                if map.virt == VIRT {
+                    wv_assert_eq!(t, map.size, PAGES * cfg::PAGE_SIZE);
+                    wv_assert_eq!(t, map.kind, PagerMemKind::Anon);
+                    wv_assert_eq!(t, map.prot, Perm::RW);
+                    wv_assert!(t, map.resident > 0 && map.resident < PAGES);
+                    wv_assert_eq!(t, map.cow, 0);
+                    wv_assert!(t, aspace.charged >= map.resident * cfg::PAGE_SIZE);
+                    found += 1;
+                }
+            }
+        }
+        wv_assert_eq!(t, found, 1);
+
+        wv_assert_err!(t, pager.get_aspace_info(num), Code::InvArgs);
         wv_assert_ok!(pager.unmap(VIRT));
     }
     else {
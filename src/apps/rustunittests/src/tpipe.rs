@@ -21,10 +21,10 @@ use m3::com::MemGate;
 use m3::errors::Code;
 use m3::io::{self, Read, Write};
 use m3::kif;
-use m3::session::Pipes;
+use m3::session::{PipeFlags, Pipes};
 use m3::test::{DefaultWvTester, WvTester};
-use m3::tiles::{ActivityArgs, ChildActivity, RunningActivity, Tile};
-use m3::vfs::{BufReader, IndirectPipe};
+use m3::tiles::{Activity, ActivityArgs, ChildActivity, RunningActivity, Tile};
+use m3::vfs::{BufReader, DuplexPipe, IndirectPipe, OpenFlags, VFS};
 use m3::{println, wv_assert_eq, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
@@ -34,6 +34,11 @@ pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, exec_child_to_child);
     wv_run_test!(t, writer_quit);
     wv_run_test!(t, reader_quit);
+    wv_run_test!(t, packet_boundaries);
+    wv_run_test!(t, duplex);
+    wv_run_test!(t, splice_file_to_pipe);
+    wv_run_test!(t, splice_pipe_to_file);
+    wv_run_test!(t, tee);
 }
 
 fn child_to_parent(t: &mut dyn WvTester) {
@@ -215,3 +220,177 @@ fn reader_quit(t: &mut dyn WvTester) {
 
     wv_assert_eq!(t, act.wait(), Ok(0));
 }
+
+fn packet_boundaries(t: &mut dyn WvTester) {
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let pipe_mem = wv_assert_ok!(MemGate::new(0x1000, kif::Perm::RW));
+    let pipe = wv_assert_ok!(IndirectPipe::new_with(
+        &pipeserv,
+        &pipe_mem,
+        0x1000,
+        PipeFlags::PACKET
+    ));
+
+    {
+        let mut output = pipe.writer().unwrap();
+        wv_assert_eq!(t, output.write(b"ab"), Ok(2));
+        wv_assert_eq!(t, output.write(b"cde"), Ok(3));
+        wv_assert_eq!(t, output.write(b"fghij"), Ok(5));
+        // packets need to fit into the write size of the pipe
+        let large = [0u8; 0x800];
+        wv_assert_eq!(
+            t,
+            output.write(&large).map_err(|e| e.code()),
+            Err(Code::InvArgs)
+        );
+    }
+    pipe.close_writer();
+
+    let mut input = pipe.reader().unwrap();
+    let mut buf = [0u8; 16];
+    wv_assert_eq!(t, input.read(&mut buf), Ok(2));
+    wv_assert_eq!(t, &buf[0..2], b"ab");
+    wv_assert_eq!(t, input.read(&mut buf), Ok(3));
+    wv_assert_eq!(t, &buf[0..3], b"cde");
+    // the rest of the packet is discarded
+    wv_assert_eq!(t, input.read(&mut buf[0..2]), Ok(2));
+    wv_assert_eq!(t, &buf[0..2], b"fg");
+    wv_assert_eq!(t, input.read(&mut buf), Ok(0));
+}
+
+fn duplex(t: &mut dyn WvTester) {
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let pipe_mem = wv_assert_ok!(MemGate::new(0x10000, kif::Perm::RW));
+    let pipe = wv_assert_ok!(DuplexPipe::new(&pipeserv, &pipe_mem, 0x10000));
+
+    let tile = wv_assert_ok!(Tile::get("clone|own"));
+    let mut act = wv_assert_ok!(ChildActivity::new_with(tile, ActivityArgs::new("echo")));
+    act.add_file(io::STDIN_FILENO, pipe.end(1).unwrap().fd());
+
+    let act = wv_assert_ok!(act.run(|| {
+        let mut t = DefaultWvTester::default();
+        let mut file = Activity::own().files().get(io::STDIN_FILENO).unwrap();
+        let mut buf = [0u8; 16];
+        // answer each request with its reversed content
+        loop {
+            let len = wv_assert_ok!(file.read(&mut buf));
+            if len == 0 {
+                break;
+            }
+            buf[0..len].reverse();
+            wv_assert_eq!(t, file.write(&buf[0..len]), Ok(len));
+        }
+        0
+    }));
+
+    pipe.close_end(1);
+
+    {
+        let mut end = pipe.end(0).unwrap();
+        let mut buf = [0u8; 16];
+        wv_assert_eq!(t, end.write(b"hello"), Ok(5));
+        wv_assert_eq!(t, end.write(b"world!"), Ok(6));
+        wv_assert_eq!(t, end.read(&mut buf), Ok(5));
+        wv_assert_eq!(t, &buf[0..5], b"olleh");
+        wv_assert_eq!(t, end.read(&mut buf), Ok(6));
+        wv_assert_eq!(t, &buf[0..6], b"!dlrow");
+    }
+    pipe.close_end(0);
+
+    wv_assert_eq!(t, act.wait(), Ok(0));
+}
+
+fn splice_file_to_pipe(t: &mut dyn WvTester) {
+    {
+        let mut file = wv_assert_ok!(VFS::open(
+            "/splice-src.txt",
+            OpenFlags::W | OpenFlags::CREATE | OpenFlags::TRUNC
+        ));
+        wv_assert_ok!(write!(file, "spliced from a file"));
+    }
+
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let pipe_mem = wv_assert_ok!(MemGate::new(0x10000, kif::Perm::RW));
+    let pipe = wv_assert_ok!(IndirectPipe::new(&pipeserv, &pipe_mem, 0x10000));
+
+    {
+        let file = wv_assert_ok!(VFS::open("/splice-src.txt", OpenFlags::R));
+        let output = pipe.writer().unwrap();
+        wv_assert_eq!(t, file.splice(&output, 100), Ok(19));
+        wv_assert_eq!(t, file.splice(&output, 100), Ok(0));
+    }
+    pipe.close_writer();
+
+    let mut input = pipe.reader().unwrap();
+    let s = wv_assert_ok!(input.read_to_string());
+    wv_assert_eq!(t, s, "spliced from a file");
+    pipe.close_reader();
+
+    wv_assert_ok!(VFS::unlink("/splice-src.txt"));
+}
+
+fn splice_pipe_to_file(t: &mut dyn WvTester) {
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let pipe_mem = wv_assert_ok!(MemGate::new(0x10000, kif::Perm::RW));
+    let pipe = wv_assert_ok!(IndirectPipe::new(&pipeserv, &pipe_mem, 0x10000));
+
+    {
+        let mut output = pipe.writer().unwrap();
+        wv_assert_ok!(write!(output, "spliced from a pipe"));
+    }
+    pipe.close_writer();
+
+    {
+        let file = wv_assert_ok!(VFS::open(
+            "/splice-dst.txt",
+            OpenFlags::W | OpenFlags::CREATE | OpenFlags::TRUNC
+        ));
+        let input = pipe.reader().unwrap();
+        let mut total = 0;
+        loop {
+            let amount = wv_assert_ok!(input.splice(&file, 100));
+            if amount == 0 {
+                break;
+            }
+            total += amount;
+        }
+        wv_assert_eq!(t, total, 19);
+    }
+    pipe.close_reader();
+
+    let mut file = wv_assert_ok!(VFS::open("/splice-dst.txt", OpenFlags::R));
+    let s = wv_assert_ok!(file.read_to_string());
+    wv_assert_eq!(t, s, "spliced from a pipe");
+
+    wv_assert_ok!(VFS::unlink("/splice-dst.txt"));
+}
+
+fn tee(t: &mut dyn WvTester) {
+    let pipeserv = wv_assert_ok!(Pipes::new("pipes"));
+    let pipe1_mem = wv_assert_ok!(MemGate::new(0x10000, kif::Perm::RW));
+    let pipe1 = wv_assert_ok!(IndirectPipe::new(&pipeserv, &pipe1_mem, 0x10000));
+    let pipe2_mem = wv_assert_ok!(MemGate::new(0x10000, kif::Perm::RW));
+    let pipe2 = wv_assert_ok!(IndirectPipe::new(&pipeserv, &pipe2_mem, 0x10000));
+
+    {
+        let mut output = pipe1.writer().unwrap();
+        wv_assert_ok!(write!(output, "duplicated"));
+    }
+    pipe1.close_writer();
+
+    {
+        let input = pipe1.reader().unwrap();
+        let output = pipe2.writer().unwrap();
+        wv_assert_eq!(t, input.tee(&output, 100), Ok(10));
+    }
+    pipe2.close_writer();
+
+    // the data is still available in the first pipe
+    let mut input1 = pipe1.reader().unwrap();
+    wv_assert_eq!(t, input1.read_to_string(), Ok(String::from("duplicated")));
+    pipe1.close_reader();
+
+    let mut input2 = pipe2.reader().unwrap();
+    wv_assert_eq!(t, input2.read_to_string(), Ok(String::from("duplicated")));
+    pipe2.close_reader();
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::errors::Code;
+use m3::io::Write;
+use m3::session::ResMngChildInfo;
+use m3::test::WvTester;
+use m3::tiles::Activity;
+use m3::time::TimeDuration;
+use m3::vfs::{OpenFlags, VFS};
+use m3::{wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
+
+// the domain of rustunittests, in which we start the childs
+const DOMAIN: &str = "tests";
+const DAEMON: &str = "/sbin/pipes";
+const DAEMON_CFG: &str = r#"<app args="/sbin/pipes" daemon="1" restart="always" retries="2" backoff="1ms">
+    <serv lname="pipes" gname="restart-pipes" />
+</app>"#;
+const PROBED_CFG: &str = r#"<app args="/sbin/pipes" daemon="1" restart="on-failure">
+    <serv lname="pipes" gname="probed-pipes" ready="probe" liveness="5ms" />
+</app>"#;
+const INCLUDE_FILE: &str = "/tresmng-inc.xml";
+const INCLUDE_CFG: &str = r#"<app args="/sbin/pipes" daemon="1">
+    <include file="/tresmng-inc.xml" />
+</app>"#;
+const RGATE_CFG: &str = r#"<app args="/sbin/pipes" daemon="1">
+    <serv lname="pipes" gname="rgate-pipes" />
+    <rgate name="tresmng" msgsize="64" slots="2" />
+</app>"#;
+
+pub fn run(t: &mut dyn WvTester) {
+    wv_run_test!(t, start_stop);
+    wv_run_test!(t, restart);
+    wv_run_test!(t, probes);
+    wv_run_test!(t, quotas);
+    wv_run_test!(t, includes);
+}
+
+fn find_child(name: &str) -> Option<ResMngChildInfo> {
+    let resmng = Activity::own().resmng().unwrap();
+    (0..)
+        .map(|i| resmng.get_child_info(i))
+        .take_while(|c| c.is_ok())
+        .map(|c| c.unwrap())
+        .find(|c| c.name == name)
+}
+
+fn wait_for<P: Fn(&Option<ResMngChildInfo>) -> bool>(name: &str, pred: P) -> bool {
+    for _ in 0..1000 {
+        if pred(&find_child(name)) {
+            return true;
+        }
+        wv_assert_ok!(Activity::own().sleep_for(TimeDuration::from_millis(1)));
+    }
+    false
+}
+
+fn wait_for_running(name: &str, restarts: u32) -> bool {
+    wait_for(
+        name,
+        |c| matches!(c, Some(c) if c.running && c.restarts == restarts),
+    )
+}
+
+fn start_stop(t: &mut dyn WvTester) {
+    let resmng = Activity::own().resmng().unwrap();
+
+    wv_assert_err!(t, resmng.start_child("invalid", RGATE_CFG), Code::NotFound);
+    wv_assert_err!(t, resmng.stop_child(DAEMON), Code::NotFound);
+
+    for _ in 0..2 {
+        wv_assert_ok!(resmng.start_child(DOMAIN, RGATE_CFG));
+        wv_assert!(t, wait_for_running(DAEMON, 0));
+
+        // the rgate exists until the child is stopped
+        let dup_cfg = RGATE_CFG.replace("rgate-pipes", "rgate-pipes2");
+        wv_assert_err!(t, resmng.start_child(DOMAIN, &dup_cfg), Code::Exists);
+
+        // stopping the child removes the rgate again, so that we can start it again
+        wv_assert_ok!(resmng.stop_child(DAEMON));
+        wv_assert!(t, wait_for(DAEMON, |c| c.is_none()));
+    }
+}
+
+fn restart(t: &mut dyn WvTester) {
+    let resmng = Activity::own().resmng().unwrap();
+
+    wv_assert_ok!(resmng.start_child(DOMAIN, DAEMON_CFG));
+    wv_assert!(t, wait_for_running(DAEMON, 0));
+
+    // the daemon is restarted after it has been killed
+    wv_assert_ok!(resmng.kill_child(DAEMON));
+    wv_assert!(t, wait_for_running(DAEMON, 1));
+
+    // but not after it has been stopped
+    wv_assert_ok!(resmng.stop_child(DAEMON));
+    wv_assert!(t, wait_for(DAEMON, |c| c.is_none()));
+    wv_assert_err!(t, resmng.kill_child(DAEMON), Code::NotFound);
+}
+
+fn probes(t: &mut dyn WvTester) {
+    let resmng = Activity::own().resmng().unwrap();
+
+    wv_assert_ok!(resmng.start_child(DOMAIN, PROBED_CFG));
+    wv_assert!(t, wait_for_running(DAEMON, 0));
+
+    // a responsive service survives many liveness probes
+    wv_assert_ok!(Activity::own().sleep_for(TimeDuration::from_millis(50)));
+    wv_assert!(t, wait_for_running(DAEMON, 0));
+
+    wv_assert_ok!(resmng.stop_child(DAEMON));
+    wv_assert!(t, wait_for(DAEMON, |c| c.is_none()));
+}
+
+fn quotas(t: &mut dyn WvTester) {
+    let resmng = Activity::own().resmng().unwrap();
+
+    let (num, _) = wv_assert_ok!(resmng.get_quota_count());
+    for i in 0..num {
+        wv_assert_ok!(resmng.get_quota_info(i));
+    }
+    wv_assert_err!(t, resmng.get_quota_info(num), Code::NotFound);
+
+    // the tree stays the same until we ask for the number of nodes again
+    wv_assert_ok!(resmng.start_child(DOMAIN, RGATE_CFG));
+    wv_assert!(t, wait_for_running(DAEMON, 0));
+    wv_assert_err!(t, resmng.get_quota_info(num), Code::NotFound);
+
+    let (new_num, _) = wv_assert_ok!(resmng.get_quota_count());
+    wv_assert_eq!(t, new_num, num + 1);
+    wv_assert!(
+        t,
+        (0..new_num).any(|i| matches!(resmng.get_quota_info(i), Ok(n) if n.name == DAEMON))
+    );
+
+    wv_assert_ok!(resmng.stop_child(DAEMON));
+    wv_assert!(t, wait_for(DAEMON, |c| c.is_none()));
+}
+
+fn includes(t: &mut dyn WvTester) {
+    let resmng = Activity::own().resmng().unwrap();
+
+    // the resource manager resolves includes with its file system
+    wv_assert_err!(t, resmng.start_child(DOMAIN, INCLUDE_CFG), Code::InvArgs);
+
+    {
+        let mut file = wv_assert_ok!(VFS::open(
+            INCLUDE_FILE,
+            OpenFlags::W | OpenFlags::CREATE | OpenFlags::TRUNC
+        ));
+        wv_assert_ok!(file.write_all(
+            b"<var name=\"gname\" value=\"inc-pipes\" />\n<serv lname=\"pipes\" gname=\"$gname\" />"
+        ));
+    }
+
+    wv_assert_ok!(resmng.start_child(DOMAIN, INCLUDE_CFG));
+    wv_assert!(t, wait_for_running(DAEMON, 0));
+
+    wv_assert_ok!(resmng.stop_child(DAEMON));
+    wv_assert!(t, wait_for(DAEMON, |c| c.is_none()));
+    wv_assert_ok!(VFS::unlink(INCLUDE_FILE));
+}
@@ -177,6 +177,7 @@ fn testcliexit(t: &mut dyn WvTester) {
                     crd: kif::CapRngDesc::new(kif::CapType::OBJECT, 0, 2),
                     args: kif::syscalls::ExchangeArgs::default(),
                     obtain: true,
+                    expiry: kif::syscalls::Expiry::default(),
                 }
             );
             wv_assert_ok!(syscalls::send_gate().send(&req_buf, RecvGate::syscall()));
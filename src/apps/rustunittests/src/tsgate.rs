@@ -19,6 +19,7 @@
 use m3::col::String;
 use m3::com::{recv_msg, recv_reply, RecvGate, SGateArgs, SendGate};
 use m3::errors::Code;
+use m3::kif;
 use m3::math;
 use m3::mem::MsgBuf;
 use m3::test::WvTester;
@@ -29,6 +30,8 @@ pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, send_errors);
     wv_run_test!(t, send_recv);
     wv_run_test!(t, send_reply);
+    wv_run_test!(t, derive);
+    wv_run_test!(t, derive_credits);
 }
 
 fn create(t: &mut dyn WvTester) {
@@ -38,6 +41,19 @@ fn create(t: &mut dyn WvTester) {
         SendGate::new_with(SGateArgs::new(&rgate).sel(1)),
         Code::InvArgs
     );
+
+    // the label bits for derived gates are reserved
+    wv_assert_err!(
+        t,
+        SendGate::new_with(SGateArgs::new(&rgate).label(kif::SGATE_DERIVE_LABEL_BITS)),
+        Code::InvArgs
+    );
+    let bit = 1 << kif::SGATE_DERIVE_LABEL_BITS.trailing_zeros();
+    wv_assert_err!(
+        t,
+        SendGate::new_with(SGateArgs::new(&rgate).label(0x1234 | bit)),
+        Code::InvArgs
+    );
 }
 
 fn send_errors(t: &mut dyn WvTester) {
@@ -126,3 +142,58 @@ fn send_reply(t: &mut dyn WvTester) {
         wv_assert_eq!(t, i2, 3);
     }
 }
+
+fn derive(t: &mut dyn WvTester) {
+    let mut rgate = wv_assert_ok!(RecvGate::new(math::next_log2(256), math::next_log2(256)));
+    let sgate = wv_assert_ok!(SendGate::new_with(
+        SGateArgs::new(&rgate).credits(2).label(0x1234)
+    ));
+    wv_assert_ok!(rgate.activate());
+
+    // no more credits than the parent
+    wv_assert_err!(t, sgate.derive(0, 0), Code::InvArgs);
+    wv_assert_err!(t, sgate.derive(0, 3), Code::InvArgs);
+    wv_assert_err!(t, sgate.derive(0, kif::UNLIM_CREDITS), Code::InvArgs);
+
+    // only the reserved label bits can be added
+    wv_assert_err!(t, sgate.derive(0x1, 1), Code::InvArgs);
+    wv_assert_err!(t, sgate.derive(0x4321, 1), Code::InvArgs);
+
+    let bit = 1 << kif::SGATE_DERIVE_LABEL_BITS.trailing_zeros();
+    let derived = wv_assert_ok!(sgate.derive(bit, 1));
+
+    // the bits of the parent cannot be changed or set again
+    wv_assert_err!(t, derived.derive(bit, 1), Code::InvArgs);
+    wv_assert_err!(t, derived.derive(0, 2), Code::InvArgs);
+
+    wv_assert_ok!(send_vmsg!(&derived, RecvGate::def(), 42));
+    let mut msg = wv_assert_ok!(recv_msg(&rgate));
+    wv_assert_eq!(t, msg.label(), 0x1234 | bit);
+    wv_assert_eq!(t, msg.pop(), Ok(42));
+}
+
+fn derive_credits(t: &mut dyn WvTester) {
+    let mut rgate = wv_assert_ok!(RecvGate::new(math::next_log2(256), math::next_log2(256)));
+    let sgate = wv_assert_ok!(SendGate::new_with(
+        SGateArgs::new(&rgate).credits(1).label(0x1234)
+    ));
+    wv_assert_ok!(rgate.activate());
+
+    // the credit is moved to the derived gate
+    let derived = wv_assert_ok!(sgate.derive(0, 1));
+    wv_assert_err!(t, sgate.derive(0, 1), Code::InvArgs);
+
+    // and given back as soon as the derived gate is revoked
+    drop(derived);
+    let derived = wv_assert_ok!(sgate.derive(0, 1));
+    wv_assert_ok!(send_vmsg!(&derived, RecvGate::def(), 42));
+    let mut msg = wv_assert_ok!(recv_msg(&rgate));
+    wv_assert_eq!(t, msg.pop(), Ok(42));
+
+    // activated gates have handed their credits to the EP already
+    let other = wv_assert_ok!(SendGate::new_with(
+        SGateArgs::new(&rgate).credits(2).label(0x1234)
+    ));
+    wv_assert_eq!(t, other.credits(), Ok(2));
+    wv_assert_err!(t, other.derive(0, 1), Code::InvState);
+}
@@ -22,7 +22,8 @@ use m3::com::{MemGate, RecvGate, SendGate};
 use m3::cpu;
 use m3::errors::{Code, Error};
 use m3::goff;
-use m3::kif::syscalls::{ActivityOp, SemOp};
+use m3::kif::syscalls::{ActivityOp, Expiry, SemOp, SyscBudget};
+use m3::kif::tilemux::SchedParams;
 use m3::kif::{CapRngDesc, CapType, Perm, INVALID_SEL, SEL_ACT, SEL_KMEM, SEL_TILE};
 use m3::math;
 use m3::server::{Handler, Server, SessId, SessionContainer};
@@ -31,6 +32,7 @@ use m3::syscalls;
 use m3::tcu::{AVAIL_EPS, FIRST_USER_EP, TOTAL_EPS};
 use m3::test::WvTester;
 use m3::tiles::{Activity, ActivityArgs, ChildActivity, Tile};
+use m3::time::{TimeDuration, TimeInstant};
 use m3::{wv_assert, wv_assert_eq, wv_assert_err, wv_assert_ok, wv_run_test};
 
 pub fn run(t: &mut dyn WvTester) {
@@ -41,12 +43,16 @@ pub fn run(t: &mut dyn WvTester) {
     wv_run_test!(t, create_sgate);
     wv_run_test!(t, create_map);
     wv_run_test!(t, create_activity);
+    wv_run_test!(t, sysc_budget);
     wv_run_test!(t, create_sem);
     wv_run_test!(t, alloc_ep);
 
     wv_run_test!(t, activate);
     wv_run_test!(t, activity_ctrl);
+    wv_run_test!(t, activity_crash);
+    wv_run_test!(t, activity_usage);
     wv_run_test!(t, derive_mem);
+    wv_run_test!(t, derive_mem_expiry);
     wv_run_test!(t, derive_kmem);
     wv_run_test!(t, derive_tile);
     wv_run_test!(t, derive_srv);
@@ -377,6 +383,30 @@ fn create_activity(t: &mut dyn WvTester) {
         Code::InvArgs
     );
 
+    // invalid budget
+    wv_assert_err!(
+        t,
+        syscalls::create_activity_with_budget(
+            sels,
+            "test",
+            tile.sel(),
+            kmem,
+            SyscBudget::new(0, 1000)
+        ),
+        Code::InvArgs
+    );
+    wv_assert_err!(
+        t,
+        syscalls::create_activity_with_budget(
+            sels,
+            "test",
+            tile.sel(),
+            kmem,
+            SyscBudget::new(1, 0)
+        ),
+        Code::InvArgs
+    );
+
     wv_assert_ok!(syscalls::create_activity(sels, "test", tile.sel(), kmem));
     if !tile.desc().has_virtmem() {
         let new_sels = Activity::own().alloc_sels(3);
@@ -389,6 +419,29 @@ fn create_activity(t: &mut dyn WvTester) {
     wv_assert_ok!(Activity::own().revoke(CapRngDesc::new(CapType::OBJECT, sels, 1), false));
 }
 
+fn sysc_budget(t: &mut dyn WvTester) {
+    let tile = wv_assert_ok!(Tile::get("clone|own"));
+    let act = wv_assert_ok!(ChildActivity::new_with(
+        tile,
+        ActivityArgs::new("test").sysc_budget(SyscBudget::new(
+            10,
+            TimeDuration::from_millis(20).as_nanos() as u64
+        ))
+    ));
+
+    let act = wv_assert_ok!(act.run(|| {
+        let start = TimeInstant::now();
+        for _ in 0..50 {
+            wv_assert_ok!(syscalls::noop());
+        }
+        (TimeInstant::now() - start).as_millis() as i32
+    }));
+
+    // 50 system calls with 10 per 20ms span at least five periods, three of them completely
+    let elapsed = wv_assert_ok!(act.wait());
+    wv_assert!(t, elapsed >= 3 * 20);
+}
+
 fn create_sem(t: &mut dyn WvTester) {
     let sel = Activity::own().alloc_sel();
 
@@ -635,6 +688,27 @@ fn derive_mem(t: &mut dyn WvTester) {
     // perms are arbitrary; will be ANDed
 }
 
+fn derive_mem_expiry(t: &mut dyn WvTester) {
+    let act = Activity::own().sel();
+    let sel = Activity::own().alloc_sel();
+    let mem = wv_assert_ok!(MemGate::new(0x1000, Perm::RW));
+
+    wv_assert_ok!(syscalls::derive_mem_with_expiry(
+        act,
+        sel,
+        mem.sel(),
+        0,
+        0x1000,
+        Perm::RW,
+        Expiry::new(TimeDuration::from_millis(10).as_nanos() as u64, 0),
+    ));
+    wv_assert_ok!(syscalls::mgate_region(sel));
+
+    // the kernel revokes the derived gate after the expiry
+    wv_assert_ok!(Activity::own().sleep_for(TimeDuration::from_millis(20)));
+    wv_assert_err!(t, syscalls::mgate_region(sel), Code::InvArgs);
+}
+
 fn derive_kmem(t: &mut dyn WvTester) {
     let sel = Activity::own().alloc_sel();
     let quota = wv_assert_ok!(Activity::own().kmem().quota()).left();
@@ -751,6 +825,52 @@ fn derive_tile(t: &mut dyn WvTester) {
         m3::println!("Skipping time transfer test due to insufficient time");
     }
 
+    // scheduling parameters
+    if tile.desc().supports_tilemux() {
+        // budget exceeds period
+        wv_assert_err!(
+            t,
+            syscalls::derive_tile_with_sched(
+                tile.sel(),
+                sel,
+                None,
+                None,
+                None,
+                SchedParams::new_edf(1000, 2000)
+            ),
+            Code::InvArgs
+        );
+        // without a root tile capability, we can neither reserve time nor raise our priority
+        wv_assert_err!(
+            t,
+            syscalls::derive_tile_with_sched(
+                tile.sel(),
+                sel,
+                None,
+                None,
+                None,
+                SchedParams::new_edf(1000, 800)
+            ),
+            Code::NoPerm
+        );
+        wv_assert_err!(
+            t,
+            syscalls::derive_tile_with_sched(
+                tile.sel(),
+                sel,
+                None,
+                None,
+                None,
+                SchedParams::new_prio(10)
+            ),
+            Code::NoPerm
+        );
+
+        // but keeping the class is fine
+        let tile2 = wv_assert_ok!(tile.derive_with_sched(None, None, None, SchedParams::new_rr()));
+        let _tile3 = wv_assert_ok!(tile2.derive(None, None, None));
+    }
+
     {
         let _act = wv_assert_ok!(ChildActivity::new(tile.clone(), "test"));
         // activity is still using the Tile
@@ -939,6 +1059,33 @@ fn activity_ctrl(t: &mut dyn WvTester) {
     );
 }
 
+fn activity_crash(t: &mut dyn WvTester) {
+    wv_assert_err!(t, syscalls::activity_crash(SEL_KMEM), Code::InvArgs);
+    wv_assert_err!(t, syscalls::activity_crash(INVALID_SEL), Code::InvArgs);
+    // we did not crash
+    wv_assert_err!(
+        t,
+        syscalls::activity_crash(Activity::own().sel()),
+        Code::NotFound
+    );
+}
+
+fn activity_usage(t: &mut dyn WvTester) {
+    wv_assert_err!(t, syscalls::activity_usage(SEL_KMEM), Code::InvArgs);
+    wv_assert_err!(t, syscalls::activity_usage(INVALID_SEL), Code::InvArgs);
+
+    if !Activity::own().tile_desc().supports_tilemux() {
+        wv_assert_err!(t, Activity::own().usage(), Code::NotSup);
+        return;
+    }
+
+    let before = wv_assert_ok!(Activity::own().usage());
+    wv_assert_ok!(Activity::own().sleep_for(TimeDuration::from_millis(1)));
+    let after = wv_assert_ok!(Activity::own().usage());
+    wv_assert!(t, after.cpu_time >= before.cpu_time);
+    wv_assert!(t, after.ctxsws > before.ctxsws);
+}
+
 fn exchange(t: &mut dyn WvTester) {
     let tile = wv_assert_ok!(Tile::get("clone|own"));
     let child = wv_assert_ok!(ChildActivity::new(tile, "test"));
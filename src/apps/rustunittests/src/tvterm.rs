@@ -0,0 +1,70 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::session::VTerm;
+use m3::test::WvTester;
+use m3::vfs::{File, TMode, WinSize};
+use m3::{wv_assert_eq, wv_assert_ok, wv_run_test};
+
+pub fn run(t: &mut dyn WvTester) {
+    wv_run_test!(t, tmode);
+    wv_run_test!(t, winsize);
+    wv_run_test!(t, echo);
+}
+
+fn tmode(t: &mut dyn WvTester) {
+    let vterm = wv_assert_ok!(VTerm::new("vterm"));
+    let mut input = wv_assert_ok!(vterm.create_channel(true));
+
+    wv_assert_eq!(t, input.get_tmode(), Ok(TMode::COOKED));
+    wv_assert_ok!(input.set_tmode(TMode::RAW));
+    wv_assert_eq!(t, input.get_tmode(), Ok(TMode::RAW));
+    wv_assert_ok!(input.set_tmode(TMode::COOKED));
+    wv_assert_eq!(t, input.get_tmode(), Ok(TMode::COOKED));
+}
+
+fn winsize(t: &mut dyn WvTester) {
+    let vterm = wv_assert_ok!(VTerm::new("vterm"));
+    let mut input = wv_assert_ok!(vterm.create_channel(true));
+    let output = wv_assert_ok!(vterm.create_channel(false));
+
+    // nobody uses the terminal interactively, so that it doesn't know its size
+    let def = WinSize { cols: 80, rows: 24 };
+    wv_assert_eq!(t, input.get_winsize(), Ok(def));
+
+    // the size is the same for all channels
+    let size = WinSize {
+        cols: 132,
+        rows: 43,
+    };
+    wv_assert_ok!(input.set_winsize(size));
+    wv_assert_eq!(t, input.get_winsize(), Ok(size));
+    wv_assert_eq!(t, output.get_winsize(), Ok(size));
+
+    wv_assert_ok!(input.set_winsize(def));
+    wv_assert_eq!(t, output.get_winsize(), Ok(def));
+}
+
+fn echo(t: &mut dyn WvTester) {
+    let vterm = wv_assert_ok!(VTerm::new("vterm"));
+    let mut input = wv_assert_ok!(vterm.create_channel(true));
+    let mut output = wv_assert_ok!(vterm.create_channel(false));
+
+    wv_assert_eq!(t, input.set_echo(false), Ok(()));
+    wv_assert_eq!(t, input.set_echo(true), Ok(()));
+    // the echo is a property of the terminal, not of the channel
+    wv_assert_eq!(t, output.set_echo(false), Ok(()));
+    wv_assert_eq!(t, output.set_echo(true), Ok(()));
+}
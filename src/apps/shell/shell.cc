@@ -31,6 +31,7 @@
 
 #include <algorithm>
 #include <memory>
+#include <signal.h>
 #include <stdlib.h>
 
 #include "Args.h"
@@ -83,6 +84,26 @@ static const char *get_pe_name(const std::unique_ptr<Parser::VarList> &vars, con
     return "core|own";
 }
 
+static bool forward_interrupt(std::unique_ptr<ChildActivity> *acts, size_t count) {
+    // only activities on TileMux can handle signals; otherwise, we stop all of them
+    for(size_t i = 0; i < count; ++i) {
+        if(acts[i] && !acts[i]->tile_desc().supports_tilemux())
+            return false;
+    }
+
+    for(size_t i = 0; i < count; ++i) {
+        if(acts[i]) {
+            try {
+                acts[i]->signal(SIGINT);
+            }
+            catch(const Exception &) {
+                // the activity might have exited in the meantime
+            }
+        }
+    }
+    return true;
+}
+
 static void execute_pipeline(Pipes &pipesrv, std::unique_ptr<Parser::CmdList> &cmds) {
     bool builtin[MAX_CMDS];
     std::unique_ptr<IndirectPipe> pipes[MAX_CMDS] = {nullptr};
@@ -274,6 +295,10 @@ static void execute_pipeline(Pipes &pipesrv, std::unique_ptr<Parser::CmdList> &c
                     break;
                 }
                 else if(have_vterm && cin.file()->fetch_signal()) {
+                    // let the activities decide what to do; we get notified if they exit
+                    if(forward_interrupt(acts, act_count))
+                        continue;
+
                     signal = true;
                     Syscalls::activity_wait(sels, 0, 1);
                     break;
@@ -0,0 +1,91 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+#![no_std]
+
+use m3::col::Vec;
+use m3::env;
+use m3::errors::{Code, Error};
+use m3::io::Read;
+use m3::println;
+use m3::tiles::Activity;
+use m3::vfs::{OpenFlags, VFS};
+
+fn usage() -> ! {
+    let name = env::args().next().unwrap();
+    println!("Usage: {} start <domain> <config>", name);
+    println!("       {} stop <name>", name);
+    println!("       {} kill <name>", name);
+    println!("       {} list", name);
+    println!();
+    println!("  start: starts the <app> in the XML file <config> in the given domain");
+    println!("  stop : stops all childs with given name");
+    println!(
+        "  kill : kills all childs with given name, which are restarted based on their policy"
+    );
+    println!("  list : lists all childs");
+    m3::exit(1);
+}
+
+fn start(domain: &str, path: &str) -> Result<(), Error> {
+    let cfg = VFS::open(path, OpenFlags::R)?.read_to_string()?;
+    Activity::own().resmng().unwrap().start_child(domain, &cfg)
+}
+
+fn stop(name: &str) -> Result<(), Error> {
+    Activity::own().resmng().unwrap().stop_child(name)
+}
+
+fn kill(name: &str) -> Result<(), Error> {
+    Activity::own().resmng().unwrap().kill_child(name)
+}
+
+fn list() -> Result<(), Error> {
+    println!(
+        "{:3} | {:10} | {:6} | {:7} | {:8} | Name",
+        "ID", "Domain", "Daemon", "Running", "Restarts"
+    );
+    for i in 0.. {
+        match Activity::own().resmng().unwrap().get_child_info(i) {
+            Ok(c) => println!(
+                "{:3} | {:10} | {:6} | {:7} | {:8} | {}",
+                c.id, c.domain, c.daemon, c.running, c.restarts, c.name
+            ),
+            Err(e) if e.code() == Code::NotFound => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let args: Vec<&str> = env::args().collect();
+    let res = match args.get(1) {
+        Some(&"start") if args.len() == 4 => start(args[2], args[3]),
+        Some(&"stop") if args.len() == 3 => stop(args[2]),
+        Some(&"kill") if args.len() == 3 => kill(args[2]),
+        Some(&"list") if args.len() == 2 => list(),
+        _ => usage(),
+    };
+
+    match res {
+        Ok(_) => 0,
+        Err(e) => {
+            println!("{} failed: {}", args[1], e);
+            1
+        },
+    }
+}
@@ -43,6 +43,12 @@ struct KIF {
      */
     static const uint UNLIM_CREDITS = TCU::UNLIM_CREDITS;
 
+    /**
+     * The label bits that can be added when deriving a send gate. The kernel refuses to create send
+     * gates with labels that use these bits, so that derived gates cannot impersonate others.
+     */
+    static const label_t SGATE_DERIVE_LABEL_BITS = 0xFF000000;
+
     /**
      * The maximum message length that can be used
      */
@@ -153,6 +159,26 @@ struct KIF {
         unsigned char data[64];
     } PACKED;
 
+    struct Expiry {
+        // ~0 means no expiry
+        xfer_t duration;
+        xfer_t event;
+    } PACKED;
+
+    struct SchedParams {
+        // class: 0 = inherit, 1 = round-robin, 2 = fixed priority, 3 = EDF
+        xfer_t sched_class;
+        xfer_t prio;
+        xfer_t period;
+        xfer_t budget;
+    } PACKED;
+
+    struct SyscBudget {
+        // ~0 means unlimited
+        xfer_t syscalls;
+        xfer_t period;
+    } PACKED;
+
     /**
      * System calls
      */
@@ -193,6 +219,11 @@ struct KIF {
             // misc
             RESET_STATS,
             NOOP,
+            MIGRATE_ACT,
+            DERIVE_SGATE,
+            SYSC_TRACE,
+            ACT_CRASH,
+            ACT_USAGE,
 
             COUNT
         };
@@ -201,6 +232,7 @@ struct KIF {
             VCTRL_INIT,
             VCTRL_START,
             VCTRL_STOP,
+            VCTRL_SIGNAL,
         };
 
         enum SemOp {
@@ -258,6 +290,7 @@ struct KIF {
             xfer_t dst_sel;
             xfer_t tile_sel;
             xfer_t kmem_sel;
+            SyscBudget budget;
             xfer_t namelen;
             char name[MAX_STR_SIZE];
         } PACKED;
@@ -320,6 +353,14 @@ struct KIF {
             xfer_t offset;
             xfer_t size;
             xfer_t perms;
+            Expiry expiry;
+        } PACKED;
+
+        struct DeriveSGate : public DefaultRequest {
+            xfer_t sgate_sel;
+            xfer_t dst_sel;
+            xfer_t label;
+            xfer_t credits;
         } PACKED;
 
         struct DeriveKMem : public DefaultRequest {
@@ -334,6 +375,7 @@ struct KIF {
             xfer_t eps;
             xfer_t time;
             xfer_t pts;
+            SchedParams sched;
         } PACKED;
 
         struct DeriveSrv : public DefaultRequest {
@@ -401,6 +443,7 @@ struct KIF {
             xfer_t own_caps[2];
             xfer_t other_sel;
             xfer_t obtain;
+            Expiry expiry;
         } PACKED;
 
         struct ExchangeSess : public DefaultRequest {
@@ -409,6 +452,7 @@ struct KIF {
             xfer_t caps[2];
             ExchangeArgs args;
             xfer_t obtain;
+            Expiry expiry;
         } PACKED;
 
         struct ExchangeSessReply : public DefaultReply {
@@ -426,6 +470,38 @@ struct KIF {
 
         struct Noop : public DefaultRequest {
         } PACKED;
+
+        struct SyscTrace : public DefaultRequest {
+            xfer_t dst_sel;
+        } PACKED;
+
+        struct MigrateAct : public DefaultRequest {
+            xfer_t act_sel;
+            xfer_t tile_sel;
+        } PACKED;
+
+        struct ActivityCrash : public DefaultRequest {
+            xfer_t act_sel;
+        } PACKED;
+
+        struct ActivityCrashReply : public DefaultReply {
+            xfer_t signal;
+            xfer_t cause;
+            xfer_t fault_addr;
+            xfer_t reg_count;
+            xfer_t regs[32];
+        } PACKED;
+
+        struct ActivityUsage : public DefaultRequest {
+            xfer_t act_sel;
+        } PACKED;
+
+        struct ActivityUsageReply : public DefaultReply {
+            xfer_t cpu_time;
+            xfer_t ctxsws;
+            xfer_t pagefaults;
+            xfer_t msgs;
+        } PACKED;
     };
 
     /**
@@ -472,6 +548,8 @@ struct KIF {
 
         struct ExchangeReply : public DefaultReply {
             ExchangeData data;
+            // the expiry of obtained capabilities, determined by the server
+            Expiry expiry;
         } PACKED;
 
         struct Close : public DefaultRequest {
@@ -489,6 +567,7 @@ struct KIF {
         enum Operation {
             DERIVE_SRV,
             ACTIVITY_WAIT,
+            CAP_EXPIRED,
         };
 
         struct DefaultUpcall : public DefaultRequest {
@@ -500,6 +579,10 @@ struct KIF {
             xfer_t act_sel;
             xfer_t exitcode;
         } PACKED;
+
+        struct CapExpired : public DefaultUpcall {
+            xfer_t error;
+        } PACKED;
     };
 };
 
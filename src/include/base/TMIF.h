@@ -36,6 +36,9 @@ enum Operation : word_t {
     TRANSL_FAULT,
     FLUSH_INV,
     NOOP,
+    SIG_ACTION,
+    SIG_RETURN,
+    ALARM,
 };
 
 }
@@ -78,8 +78,10 @@ public:
     static void activity_ctrl(capsel_t act, KIF::Syscall::ActivityOp op, xfer_t arg);
     static std::pair<int, capsel_t> activity_wait(const capsel_t *acts, size_t count,
                                                   event_t event);
+    static void migrate_activity(capsel_t act, capsel_t tile);
     static void derive_mem(capsel_t act, capsel_t dst, capsel_t src, goff_t offset, size_t size,
                            int perms);
+    static void derive_sgate(capsel_t sgate, capsel_t dst, label_t label, uint credits);
     static void derive_kmem(capsel_t kmem, capsel_t dst, size_t quota);
     static void derive_tile(capsel_t tile, capsel_t dst, uint eps = static_cast<uint>(-1),
                             uint64_t time = static_cast<uint64_t>(-1),
@@ -140,6 +140,21 @@ public:
         return SendGate(sel, ObjCap::KEEP_CAP, replygate);
     }
 
+    /**
+     * Derives a new send gate from this one that has <credits> credits and the label of this gate
+     * with the additional bits in <label> set. Only the bits in KIF::SGATE_DERIVE_LABEL_BITS that
+     * are not set in the label of this gate can be added. The credits are moved from this gate to
+     * the derived gate until the derived gate is revoked, which requires that this gate has not
+     * been activated yet (unless it has unlimited credits). The derived gate is revoked together
+     * with this gate.
+     *
+     * @param label the label bits to add
+     * @param credits the credits for the derived gate (at most the credits this gate has left)
+     * @param replygate the receive gate to which the replies should be sent
+     * @return the derived send gate
+     */
+    SendGate derive(label_t label, uint credits, RecvGate *replygate = nullptr);
+
     SendGate(SendGate &&g) noexcept : Gate(std::move(g)), _replygate(g._replygate) {
     }
 
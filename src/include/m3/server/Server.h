@@ -78,6 +78,14 @@ public:
         }
     }
 
+    /**
+     * Tells the resource manager that the service is ready to be used. This is only required if
+     * the service is configured with ready="signal".
+     */
+    void set_ready() {
+        Activity::own().resmng()->serv_ready(sel());
+    }
+
     void shutdown() {
         _handler->shutdown();
         _rgate.stop();
@@ -196,6 +204,8 @@ private:
         reply.error = _handler->obtain(sess, crt, xchg);
 
         reply.data.args.bytes = xchg.out_args().total();
+        reply.expiry.duration = static_cast<xfer_t>(-1);
+        reply.expiry.event = 0;
         is.reply(reply_buf);
     }
 
@@ -215,6 +225,8 @@ private:
         reply.error = _handler->delegate(sess, crt, xchg);
 
         reply.data.args.bytes = xchg.out_args().total();
+        reply.expiry.duration = static_cast<xfer_t>(-1);
+        reply.expiry.event = 0;
         is.reply(reply_buf);
     }
 
@@ -58,7 +58,9 @@ public:
         if(sess->sgate || xchg.in_caps() != 1)
             return Errors::INV_ARGS;
 
-        label_t label = ptr_to_label(sess);
+        // use the selector as the label, because pointers might use the bits that are reserved for
+        // derived send gates
+        label_t label = sess->sel();
         sess->sgate = std::make_unique<SendGate>(
             SendGate::create(&_rgate, SendGateArgs().label(label).credits(1)));
 
@@ -67,8 +69,9 @@ public:
     }
 
     virtual Errors::Code close(SimpleSession *sess, size_t) override {
+        label_t label = sess->sel();
         delete sess;
-        _rgate.drop_msgs_with(ptr_to_label(sess));
+        _rgate.drop_msgs_with(label);
         return Errors::NONE;
     }
 
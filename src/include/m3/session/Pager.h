@@ -46,6 +46,16 @@ public:
         MAP_MEM,
         UNMAP,
         CLOSE,
+        PROTECT,
+        ADVISE,
+        SYNC,
+        SHM_OPEN,
+        SHM_RESIZE,
+        SHM_CLOSE,
+        MAP_SHM,
+        MAP_STATS,
+        GET_INFO,
+        SWAP_STATS,
         COUNT,
     };
 
@@ -64,6 +74,18 @@ public:
         RWX = READ | WRITE | EXEC,
     };
 
+    enum Advice {
+        ADV_NORMAL = 0,
+        ADV_SEQUENTIAL = 2,
+        ADV_WILLNEED = 3,
+        ADV_DONTNEED = 4,
+    };
+
+    enum ShmFlags {
+        SHM_CREATE = 0x1,
+        SHM_EXCL = 0x2,
+    };
+
     explicit Pager(capsel_t sess, capsel_t sgate);
     ~Pager();
 
@@ -81,6 +103,14 @@ public:
                 size_t offset);
     void map_mem(goff_t *virt, MemGate &mem, size_t len, int prot);
     void unmap(goff_t virt);
+    void protect(goff_t virt, size_t len, int prot);
+    void advise(goff_t virt, size_t len, int advice);
+    void sync(goff_t virt, size_t len);
+    uint64_t open_shm(const char *name, int flags, size_t *size);
+    void resize_shm(uint64_t id, size_t size);
+    void close_shm(uint64_t id);
+    void map_shm(goff_t *virt, uint64_t id, size_t offset, size_t len, int prot, int flags);
+    void map_stats(size_t *large_pages, size_t *small_pages);
 
 private:
     capsel_t get_sgate();
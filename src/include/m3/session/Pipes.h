@@ -32,6 +32,10 @@ class Pipes : public ClientSession {
         OPEN_CHAN,
         SET_MEM,
         CLOSE_PIPE,
+        OPEN_NAMED,
+        GET_SPLICE_EP,
+        SPLICE,
+        AUTH_NAMED,
     };
 
 public:
@@ -74,7 +78,8 @@ public:
     Pipe create_pipe(MemGate &memory, size_t memsize) {
         KIF::ExchangeArgs args;
         ExchangeOStream os(args);
-        os << OPEN_PIPE << memsize;
+        // no flags: packet mode and duplex pipes are only supported by the Rust client
+        os << OPEN_PIPE << memsize << static_cast<uint64_t>(0);
         args.bytes = os.total();
         KIF::CapRngDesc desc = obtain(2, &args);
         return Pipe(desc.start(), memory);
@@ -51,6 +51,20 @@ public:
         USE_RGATE,
         USE_SGATE,
         USE_SEM,
+
+        GET_SERIAL,
+
+        GET_INFO,
+
+        SERV_READY,
+
+        ADM_START,
+        ADM_STOP,
+        ADM_LIST,
+
+        GET_QUOTAS,
+
+        ADM_KILL,
     };
 
     class ResMngException : public m3::Exception {
@@ -68,7 +82,9 @@ public:
             static const char *names[] = {
                 "REG_SERV",  "UNREG_SERV", "OPEN_SESS", "CLOSE_SESS", "ADD_CHILD",
                 "REM_CHILD", "ALLOC_MEM",  "FREE_MEM",  "ALLOC_TILE", "FREE_TILE",
-                "USE_RGATE", "USE_SGATE",  "USE_SEM",
+                "USE_RGATE", "USE_SGATE",  "USE_SEM",  "GET_SERIAL", "GET_INFO",
+                "SERV_READY", "ADM_START",  "ADM_STOP",  "ADM_LIST",  "GET_QUOTAS",
+                "ADM_KILL",
             };
 
             OStringStream os(msg_buf, sizeof(msg_buf));
@@ -116,6 +132,11 @@ public:
         retrieve_result(UNREG_SERV, reply);
     }
 
+    void serv_ready(capsel_t sel) {
+        GateIStream reply = send_receive_vmsg(_sgate, SERV_READY, sel);
+        retrieve_result(SERV_READY, reply);
+    }
+
     void open_sess(capsel_t dst, const std::string_view &name) {
         GateIStream reply = send_receive_vmsg(_sgate, OPEN_SESS, dst, name);
         retrieve_result(OPEN_SESS, reply);
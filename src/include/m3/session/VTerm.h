@@ -32,6 +32,12 @@ namespace m3 {
 
 class VTerm : public ClientSession {
 public:
+    enum Operation {
+        GET_WINSIZE = GenericFile::REQ_NOTIFY + 1,
+        SET_WINSIZE,
+        SET_ECHO,
+    };
+
     explicit VTerm(const std::string_view &name) : ClientSession(name) {
     }
 
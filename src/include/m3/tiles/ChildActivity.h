@@ -190,6 +190,22 @@ public:
      */
     void stop();
 
+    /**
+     * Sends the given signal to the activity. If the activity has not registered a handler for it,
+     * the activity is terminated.
+     *
+     * @param sig the signal number
+     */
+    void signal(uint sig);
+
+    /**
+     * Migrates the activity to the given tile, which has to be of the same type as the current
+     * tile. The activity continues to run on the new tile and uses its quotas from now on.
+     *
+     * @param tile the new tile
+     */
+    void migrate(Reference<class Tile> tile);
+
     /**
      * Waits until the currently executing program on this activity is finished
      *
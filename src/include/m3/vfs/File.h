@@ -55,6 +55,11 @@ public:
         COOKED = 1,
     };
 
+    struct WinSize {
+        size_t cols;
+        size_t rows;
+    };
+
     enum Event {
         INPUT = 1,
         OUTPUT = 2,
@@ -274,6 +279,28 @@ public:
         throw Exception(Errors::NOT_SUP);
     }
 
+    /**
+     * @return the window size in case the server is a terminal
+     */
+    virtual WinSize get_winsize() {
+        throw Exception(Errors::NOT_SUP);
+    }
+
+    /**
+     * Sets the window size in case the server is a terminal (e.g., if the terminal cannot report
+     * its size itself)
+     */
+    virtual void set_winsize(const WinSize &) {
+        throw Exception(Errors::NOT_SUP);
+    }
+
+    /**
+     * Sets whether the input is echoed in case the server is a terminal
+     */
+    virtual void set_echo(bool) {
+        throw Exception(Errors::NOT_SUP);
+    }
+
     /**
      * @return true if this file is operating in non-blocking mode (see set_blocking())
      */
@@ -62,6 +62,9 @@ public:
         GET_MEM,
         DEL_EP,
         OPEN_PRIV,
+        COMMIT_MEM,
+        MKFIFO,
+        OPEN_FIFO,
     };
 
     explicit FileSystem(size_t id) noexcept : RefCounted(), _id(id) {
@@ -88,6 +88,9 @@ public:
 
     virtual TMode get_tmode() override;
     virtual void set_tmode(TMode mode) override;
+    virtual WinSize get_winsize() override;
+    virtual void set_winsize(const WinSize &size) override;
+    virtual void set_echo(bool echo) override;
 
     virtual bool fetch_signal() override;
 
@@ -59,6 +59,7 @@ pub fn main() -> i32 {
 
     ktcu::init();
     platform::init(&args::get().free);
+    crate::systrace::init();
     crate::arch::childs::init();
     crate::arch::input::init();
     crate::com::init_queues();
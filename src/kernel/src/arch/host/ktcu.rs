@@ -24,6 +24,7 @@ use base::libc;
 use base::mem::{size_of, GlobAddr};
 use base::rc::Rc;
 use base::tcu::*;
+use base::time::TimeDuration;
 
 use crate::ktcu;
 use crate::tiles::{Activity, ActivityMng, State};
@@ -103,6 +104,12 @@ pub fn config_mem(
     regs[EpReg::MSGORDER.val as usize] = 0;
 }
 
+/// Sleeps for a short time, which is always shorter than `timeout`, because we need to check for
+/// child exits, network packets, etc. anyway
+pub fn sleep_for(_timeout: Option<TimeDuration>) -> Result<(), Error> {
+    TCU::sleep()
+}
+
 pub fn invalidate_ep_remote(tile: TileId, ep: EpId, _force: bool) -> Result<u32, Error> {
     #[allow(clippy::unnecessary_cast)]
     let regs = [0 as Reg; EP_REGS];
@@ -128,6 +128,8 @@ pub extern "C" fn env_run() {
 
     platform::init(&[]);
     create_rbufs();
+    // allocate the trace before the heap takes all remaining kernel memory
+    crate::systrace::init();
     extend_heap();
     thread::init();
     tiles::init();
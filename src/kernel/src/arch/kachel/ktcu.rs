@@ -19,6 +19,7 @@ use base::goff;
 use base::kif::{PageFlags, Perm};
 use base::mem::GlobAddr;
 use base::tcu::*;
+use base::time::TimeDuration;
 
 use crate::arch;
 use crate::ktcu;
@@ -79,6 +80,21 @@ pub fn config_mem(regs: &mut [Reg], act: ActId, tile: TileId, addr: goff, size:
     TCU::config_mem(regs, act, tile, addr, size, perm);
 }
 
+/// Puts the kernel tile to sleep until a message arrives or `timeout` has passed, if given
+pub fn sleep_for(timeout: Option<TimeDuration>) -> Result<(), Error> {
+    match timeout {
+        None => TCU::sleep(),
+        Some(t) => {
+            // the timer IRQ wakes us up if no message arrives in the meantime
+            TCU::set_timer(t.as_nanos().max(1) as u64)?;
+            let res = TCU::sleep();
+            TCU::set_timer(0)?;
+            TCU::clear_irq(IRQ::TIMER);
+            res
+        },
+    }
+}
+
 pub fn glob_to_phys_remote(tile: TileId, glob: GlobAddr, flags: PageFlags) -> Result<goff, Error> {
     glob.to_phys_with(flags, |ep| {
         let mut regs = [0; 3];
@@ -148,6 +164,24 @@ pub fn inv_reply_remote(
     Ok(())
 }
 
+pub fn has_missing_credits_remote(tile: TileId, ep: EpId) -> Result<bool, Error> {
+    let mut regs = [0; EP_REGS];
+    read_ep_remote(tile, ep, &mut regs)?;
+    Ok(TCU::has_missing_credits_regs(&regs))
+}
+
+pub fn retarget_send_remote(tile: TileId, ep: EpId, dst_tile: TileId) -> Result<(), Error> {
+    let mut regs = [0; EP_REGS];
+    read_ep_remote(tile, ep, &mut regs)?;
+    if TCU::ep_type(&regs) != EpType::SEND {
+        return Err(Error::new(Code::NoSEP));
+    }
+
+    // keep the credits and label and only change the destination tile
+    TCU::set_send_dest_tile(&mut regs, dst_tile);
+    write_ep_remote(tile, ep, &regs)
+}
+
 fn do_ext_cmd(tile: TileId, cmd: Reg) -> Result<Reg, Error> {
     let addr = TCU::ext_reg_addr(ExtReg::EXT_CMD) as goff;
     ktcu::try_write_slice(tile, addr, &[cmd])?;
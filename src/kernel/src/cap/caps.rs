@@ -25,7 +25,7 @@ use core::cmp;
 use core::fmt;
 use core::ptr::{NonNull, Unique};
 
-use crate::cap::{EPObject, GateEP, KObject};
+use crate::cap::{expiry, EPObject, GateEP, KObject};
 use crate::ktcu;
 use crate::tiles::{tilemng, Activity, ActivityMng};
 
@@ -116,6 +116,10 @@ impl CapTable {
         true
     }
 
+    pub fn for_each<F: FnMut(&Capability)>(&self, func: F) {
+        self.caps.for_each(func)
+    }
+
     pub fn get(&self, sel: CapSel) -> Option<&Capability> {
         self.caps.get(&SelRange::new(sel))
     }
@@ -190,6 +194,7 @@ impl CapTable {
         let mut nc: Capability = (*cap).clone();
         nc.sels = SelRange::new(sel);
         nc.derived = true;
+        nc.expiry = None;
 
         let nc = self.do_insert(nc);
         klog!(CAPS, "Cloning cap {:?}", nc);
@@ -259,6 +264,7 @@ pub struct Capability {
     next: Option<NonNull<Capability>>,
     prev: Option<NonNull<Capability>>,
     derived: bool,
+    expiry: Option<u64>,
 }
 
 impl Capability {
@@ -281,6 +287,7 @@ impl Capability {
             next: None,
             prev: None,
             derived: false,
+            expiry: None,
         }
     }
 
@@ -304,6 +311,11 @@ impl Capability {
         &mut self.obj
     }
 
+    /// Sets the expiry id and returns the previous one, if any
+    pub fn set_expiry(&mut self, id: u64) -> Option<u64> {
+        self.expiry.replace(id)
+    }
+
     pub fn has_parent(&self) -> bool {
         self.parent.is_some()
     }
@@ -456,6 +468,9 @@ impl Capability {
 
         let act = self.activity();
         let sel = self.sel();
+        if let Some(id) = self.expiry {
+            expiry::remove(id, sel);
+        }
         if !self.derived {
             // if it's not derived, we created the cap and thus will also free the kobject
             act.kmem()
@@ -508,6 +523,16 @@ impl Capability {
             KObject::SGate(ref mut o) => {
                 o.invalidate_reply_eps();
                 Self::invalidate_ep(o.gate_ep_mut(), foreign);
+
+                // see above; the credits of derived send gates go back to the parent
+                if !self.derived {
+                    if let Some(parent) = self.parent {
+                        let parent = unsafe { &(*parent.as_ptr()) };
+                        if let KObject::SGate(p) = parent.get() {
+                            p.revoke(o);
+                        }
+                    }
+                }
             },
 
             KObject::RGate(ref mut o) => {
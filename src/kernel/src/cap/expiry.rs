@@ -0,0 +1,186 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Time-limited capabilities.
+//!
+//! Capabilities that are created by exchanges or derivations can have an expiry. As soon as the
+//! expiry is due, the kernel revokes these capabilities including all their children and notifies
+//! the activity that requested the expiry via upcall.
+
+use base::cell::{StaticCell, StaticRefCell};
+use base::col::Vec;
+use base::errors::{Code, Error};
+use base::kif::{syscalls, CapRngDesc, CapSel, CapType};
+use base::tcu::ActId;
+use base::time::{TimeDuration, TimeInstant};
+
+use crate::tiles::{Activity, ActivityMng};
+
+struct Expiry {
+    id: u64,
+    end: TimeInstant,
+    act: ActId,
+    cap_type: CapType,
+    sels: Vec<CapSel>,
+    owner: ActId,
+    event: u64,
+}
+
+static LIST: StaticRefCell<Vec<Expiry>> = StaticRefCell::new(Vec::new());
+static NEXT_ID: StaticCell<u64> = StaticCell::new(1);
+
+/// Lets the capabilities in `crd` of `act` expire as specified by `expiry`. `owner` is the
+/// activity that requested the expiry and receives the upcall afterwards. A previous expiry of
+/// these capabilities is replaced.
+pub fn add(owner: &Activity, act: &Activity, crd: CapRngDesc, expiry: syscalls::Expiry) {
+    let duration = match expiry.duration {
+        Some(d) => d,
+        None => return,
+    };
+
+    let id = NEXT_ID.get();
+    let mut sels = Vec::new();
+    {
+        let mut caps = if crd.cap_type() == CapType::OBJECT {
+            act.obj_caps().borrow_mut()
+        }
+        else {
+            act.map_caps().borrow_mut()
+        };
+        let mut sel = crd.start();
+        while sel < crd.start() + crd.count() {
+            // obtains might have copied less capabilities than requested
+            if let Some(cap) = caps.get_mut(sel) {
+                // a new expiry replaces the previous one
+                if let Some(old) = cap.set_expiry(id) {
+                    remove(old, cap.sel());
+                }
+                sels.push(cap.sel());
+                sel = cap.sel() + cap.len();
+            }
+            else {
+                sel += 1;
+            }
+        }
+    }
+    if sels.is_empty() {
+        return;
+    }
+    NEXT_ID.set(id + 1);
+
+    let exp = Expiry {
+        id,
+        end: TimeInstant::from_nanos(TimeInstant::now().as_nanos().saturating_add(duration)),
+        act: act.id(),
+        cap_type: crd.cap_type(),
+        sels,
+        owner: owner.id(),
+        event: expiry.event,
+    };
+
+    klog!(
+        CAPS,
+        "Capabilities {:?} of Activity {} expire at {:?} (id={})",
+        exp.sels,
+        exp.act,
+        exp.end,
+        exp.id
+    );
+
+    // insert new expiry in descending order of expiries
+    let mut list = LIST.borrow_mut();
+    if let Some(idx) = list.iter().position(|e| e.end < exp.end) {
+        list.insert(idx, exp);
+    }
+    else {
+        list.push(exp);
+    }
+}
+
+/// Removes the capability `sel` from the expiry with given id, because it has been revoked.
+pub fn remove(id: u64, sel: CapSel) {
+    let mut list = LIST.borrow_mut();
+    if let Some(idx) = list.iter().position(|e| e.id == id) {
+        list[idx].sels.retain(|s| *s != sel);
+        // if all capabilities have been revoked manually, there is nothing left to do
+        if list[idx].sels.is_empty() {
+            list.remove(idx);
+        }
+    }
+}
+
+/// Returns the time until the next capabilities expire, if there are any
+pub fn next_timeout() -> Option<TimeDuration> {
+    LIST.borrow().last().map(|e| {
+        let now = TimeInstant::now();
+        if e.end > now {
+            e.end - now
+        }
+        else {
+            TimeDuration::from_nanos(1)
+        }
+    })
+}
+
+/// Revokes all capabilities whose expiry is due
+pub fn check_async() {
+    let now = TimeInstant::now();
+    loop {
+        // take the expiry out of the list first, because revoking might switch threads
+        let exp = {
+            let mut list = LIST.borrow_mut();
+            match list.last() {
+                Some(e) if now >= e.end => list.pop().unwrap(),
+                _ => break,
+            }
+        };
+        expire_async(exp);
+    }
+}
+
+fn expire_async(exp: Expiry) {
+    klog!(
+        CAPS,
+        "Capabilities {:?} of Activity {} expired (id={})",
+        exp.sels,
+        exp.act,
+        exp.id
+    );
+
+    let mut res = Ok(());
+    match ActivityMng::activity(exp.act) {
+        Some(act) => {
+            for sel in &exp.sels {
+                if let Err(e) = act.revoke_async(CapRngDesc::new(exp.cap_type, *sel, 1), true) {
+                    klog!(
+                        ERR,
+                        "Unable to revoke expired capability {} of Activity {}: {:?}",
+                        sel,
+                        exp.act,
+                        e.code()
+                    );
+                    res = Err(e);
+                }
+            }
+        },
+        None => res = Err(Error::new(Code::ActivityGone)),
+    }
+
+    if exp.event != 0 {
+        if let Some(owner) = ActivityMng::activity(exp.owner) {
+            owner.upcall_cap_expired(exp.event, res);
+        }
+    }
+}
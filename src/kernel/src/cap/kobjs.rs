@@ -250,7 +250,7 @@ pub struct SGateObject {
     gep: RefCell<GateEP>,
     rgate: SRc<RGateObject>,
     label: Label,
-    credits: u32,
+    credits: Cell<u32>,
 }
 
 impl SGateObject {
@@ -259,7 +259,7 @@ impl SGateObject {
             gep: RefCell::from(GateEP::new()),
             rgate: rgate.clone(),
             label,
-            credits,
+            credits: Cell::from(credits),
         })
     }
 
@@ -280,7 +280,22 @@ impl SGateObject {
     }
 
     pub fn credits(&self) -> u32 {
-        self.credits
+        self.credits.get()
+    }
+
+    /// Moves `credits` credits from this gate to a derived gate
+    pub fn take_credits(&self, credits: u32) {
+        if self.credits.get() != kif::UNLIM_CREDITS {
+            assert!(credits <= self.credits.get());
+            self.credits.set(self.credits.get() - credits);
+        }
+    }
+
+    /// Gives the credits of the derived gate `child` back to this gate
+    pub fn revoke(&self, child: &SGateObject) {
+        if self.credits.get() != kif::UNLIM_CREDITS {
+            self.credits.set(self.credits.get() + child.credits());
+        }
     }
 
     pub fn invalidate_reply_eps(&self) {
@@ -301,7 +316,7 @@ impl fmt::Debug for SGateObject {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "SGate[rgate=")?;
         self.rgate.print_loc(f)?;
-        write!(f, ", lbl={:#x}, crd={}]", self.label, self.credits)
+        write!(f, ", lbl={:#x}, crd={}]", self.label, self.credits.get())
     }
 }
 
@@ -684,7 +699,7 @@ pub struct EPObject {
     act: Weak<Activity>,
     ep: EpId,
     replies: u32,
-    tile: SRc<TileObject>,
+    tile: RefCell<SRc<TileObject>>,
 }
 
 impl EPObject {
@@ -702,7 +717,7 @@ impl EPObject {
             act,
             ep,
             replies,
-            tile: tile.clone(),
+            tile: RefCell::from(tile.clone()),
         });
         if let Some(v) = maybe_act {
             v.add_ep(ep.clone());
@@ -711,7 +726,15 @@ impl EPObject {
     }
 
     pub fn tile_id(&self) -> TileId {
-        self.tile.tile()
+        self.tile.borrow().tile()
+    }
+
+    pub fn tile(&self) -> SRc<TileObject> {
+        self.tile.borrow().clone()
+    }
+
+    pub fn set_tile(&self, tile: &SRc<TileObject>) {
+        self.tile.replace(tile.clone());
     }
 
     pub fn activity(&self) -> Option<Rc<Activity>> {
@@ -726,6 +749,14 @@ impl EPObject {
         self.replies
     }
 
+    pub fn is_std(&self) -> bool {
+        self.is_std
+    }
+
+    pub fn gate(&self) -> Ref<'_, Option<GateObject>> {
+        self.gate.borrow()
+    }
+
     pub fn is_rgate(&self) -> bool {
         matches!(self.gate.borrow().as_ref(), Some(GateObject::Recv(_)))
     }
@@ -791,9 +822,10 @@ impl EPObject {
 impl Drop for EPObject {
     fn drop(&mut self) {
         if !self.is_std {
-            tilemng::tilemux(self.tile.tile).free_eps(self.ep, 1 + self.replies);
+            let tile = self.tile.borrow();
+            tilemng::tilemux(tile.tile).free_eps(self.ep, 1 + self.replies);
 
-            self.tile.free(1 + self.replies);
+            tile.free(1 + self.replies);
         }
     }
 }
@@ -806,7 +838,7 @@ impl fmt::Debug for EPObject {
             self.activity().unwrap().id(),
             self.ep,
             self.replies,
-            self.tile
+            self.tile.borrow()
         )
     }
 }
@@ -19,5 +19,7 @@
 mod caps;
 mod kobjs;
 
+pub mod expiry;
+
 pub use self::caps::*;
 pub use self::kobjs::*;
@@ -145,6 +145,16 @@ impl SendQueue {
         self.queue.sender().id
     }
 
+    pub fn set_tile(&mut self, tile: tcu::TileId) {
+        klog!(
+            SQUEUE,
+            "SendQueue[{:?}]: moving to tile {}",
+            self.id(),
+            tile
+        );
+        self.queue.sender_mut().tile = tile;
+    }
+
     pub fn send(
         &mut self,
         rep: tcu::EpId,
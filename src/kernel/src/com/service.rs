@@ -51,6 +51,10 @@ impl Service {
         &self.name
     }
 
+    pub fn set_tile(&self, tile: tcu::TileId) {
+        self.queue.borrow_mut().set_tile(tile);
+    }
+
     pub fn send(&self, lbl: tcu::Label, msg: &MsgBuf) -> Result<thread::Event, Error> {
         let (_, rep) = self.rgate.location().unwrap();
         self.queue.borrow_mut().send(rep, lbl, msg)
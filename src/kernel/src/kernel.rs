@@ -34,5 +34,6 @@ mod mem;
 mod platform;
 mod slab;
 mod syscalls;
+mod systrace;
 mod tiles;
 mod workloop;
@@ -0,0 +1,188 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! System call budgets.
+//!
+//! Activities can be limited to a number of system calls per period. If an activity exceeds its
+//! budget, the kernel defers the handling of further system calls until the next period starts.
+//! Since the system call send gate has a single credit, the activity cannot send more system calls
+//! in the meantime and the kernel only keeps the already received message.
+//!
+//! Budgets are hierarchical: the system calls of an activity are charged to its own budget and to
+//! all budgets of its ancestors. Activities without an own budget share the budget of their parent.
+//! Thus, an activity and all its descendants together cannot exceed the budget of the activity.
+
+use base::cell::{Cell, StaticRefCell};
+use base::col::Vec;
+use base::errors::{Code, Error};
+use base::kif::syscalls::SyscBudget;
+use base::rc::Rc;
+use base::tcu::{ActId, Message};
+use base::time::{TimeDuration, TimeInstant};
+use core::cmp;
+
+use crate::tiles::Activity;
+
+#[derive(Debug)]
+pub struct Budget {
+    syscalls: u64,
+    period: u64,
+    start: Cell<TimeInstant>,
+    used: Cell<u64>,
+    parent: Option<Rc<Budget>>,
+}
+
+impl Budget {
+    fn new(syscalls: u64, period: u64, parent: Option<Rc<Budget>>) -> Self {
+        Self {
+            syscalls,
+            period,
+            start: Cell::new(TimeInstant::from_nanos(0)),
+            used: Cell::new(0),
+            parent,
+        }
+    }
+
+    /// Determines the budget of a new activity, created by an activity with budget `parent`, that
+    /// requested the budget `req` for the new activity.
+    pub fn derive(
+        parent: Option<Rc<Budget>>,
+        req: SyscBudget,
+    ) -> Result<Option<Rc<Budget>>, Error> {
+        let (syscalls, period) = match req.syscalls {
+            Some(n) if n > 0 && req.period > 0 => (n, req.period),
+            Some(_) => return Err(Error::new(Code::InvArgs)),
+            // share the budget with the parent
+            None => return Ok(parent),
+        };
+
+        if let Some(p) = &parent {
+            // the new activity cannot perform more system calls per time than its parent
+            if syscalls as u128 * p.period as u128 > p.syscalls as u128 * period as u128 {
+                return Err(Error::new(Code::NoPerm));
+            }
+        }
+        Ok(Some(Rc::new(Budget::new(syscalls, period, parent))))
+    }
+
+    /// Consumes one system call from this budget and all parent budgets. If any of these budgets
+    /// is exhausted, nothing is consumed and the point in time is returned at which all of these
+    /// budgets allow a system call again.
+    fn consume(&self, now: TimeInstant) -> Option<TimeInstant> {
+        let mut until = None;
+        let mut cur = Some(self);
+        while let Some(b) = cur {
+            if let Some(end) = b.exhausted(now) {
+                until = Some(until.map_or(end, |u| cmp::max(u, end)));
+            }
+            cur = b.parent.as_deref();
+        }
+
+        if until.is_none() {
+            let mut cur = Some(self);
+            while let Some(b) = cur {
+                b.used.set(b.used.get() + 1);
+                cur = b.parent.as_deref();
+            }
+        }
+        until
+    }
+
+    /// Starts a new period if the current one is over and returns the end of the current period
+    /// if the budget is exhausted.
+    fn exhausted(&self, now: TimeInstant) -> Option<TimeInstant> {
+        let end = self.start.get() + TimeDuration::from_nanos(self.period);
+        if now >= end {
+            self.start.set(now);
+            self.used.set(0);
+            None
+        }
+        else if self.used.get() >= self.syscalls {
+            Some(end)
+        }
+        else {
+            None
+        }
+    }
+}
+
+struct Deferred {
+    act: ActId,
+    until: TimeInstant,
+    msg: &'static Message,
+}
+
+static DEFERRED: StaticRefCell<Vec<Deferred>> = StaticRefCell::new(Vec::new());
+
+/// Charges the system call `msg` to the budget of `act`. Returns false if the budget is exhausted,
+/// in which case the system call has been deferred to the next period.
+pub fn charge(act: &Activity, msg: &'static Message) -> bool {
+    let budget = match act.sysc_budget() {
+        Some(b) => b,
+        None => return true,
+    };
+
+    match budget.consume(TimeInstant::now()) {
+        None => true,
+        Some(until) => {
+            sysc_log!(act, "budget exhausted; deferring syscall until {:?}", until);
+            DEFERRED.borrow_mut().push(Deferred {
+                act: act.id(),
+                until,
+                msg,
+            });
+            false
+        },
+    }
+}
+
+/// Returns the time until the next deferred system call can be handled, if there is any
+pub fn next_timeout() -> Option<TimeDuration> {
+    let now = TimeInstant::now();
+    DEFERRED
+        .borrow()
+        .iter()
+        .map(|d| d.until)
+        .min()
+        .map(|until| {
+            if until > now {
+                until - now
+            }
+            else {
+                TimeDuration::from_nanos(1)
+            }
+        })
+}
+
+/// Handles all deferred system calls whose period has started
+pub fn handle_deferred_async() {
+    let now = TimeInstant::now();
+    loop {
+        // take the message out of the list first, because handling it might switch threads
+        let msg = {
+            let mut list = DEFERRED.borrow_mut();
+            match list.iter().position(|d| now >= d.until) {
+                Some(idx) => list.remove(idx).msg,
+                None => break,
+            }
+        };
+        super::handle_async(msg);
+    }
+}
+
+/// Drops the deferred system calls of the given activity, because it has been stopped
+pub fn drop_deferred(act: ActId) {
+    DEFERRED.borrow_mut().retain(|d| d.act != act);
+}
@@ -18,7 +18,7 @@ use base::cfg;
 use base::col::ToString;
 use base::errors::{Code, VerboseError};
 use base::goff;
-use base::kif::{syscalls, CapRngDesc, CapSel, CapType, PageFlags, Perm};
+use base::kif::{self, syscalls, CapRngDesc, CapSel, CapType, PageFlags, Perm};
 use base::mem::{GlobAddr, MsgBuf};
 use base::rc::Rc;
 use base::tcu;
@@ -30,6 +30,7 @@ use crate::cap::{
 use crate::com::Service;
 use crate::mem;
 use crate::platform;
+use crate::syscalls::budget::Budget;
 use crate::syscalls::{get_request, reply_success, send_reply};
 use crate::tiles::{tilemng, Activity, ActivityFlags, ActivityMng};
 
@@ -159,6 +160,10 @@ pub fn create_sgate(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<()
     if !act_caps.unused(r.dst) {
         sysc_err!(Code::InvArgs, "Selector {} already in use", r.dst);
     }
+    // these bits are reserved for derived send gates (see derive_sgate)
+    if (r.label & kif::SGATE_DERIVE_LABEL_BITS) != 0 {
+        sysc_err!(Code::InvArgs, "Label {:#x} uses reserved bits", r.label);
+    }
 
     let cap = {
         let rgate = get_kobj_ref!(act_caps, r.rgate, RGate);
@@ -255,11 +260,12 @@ pub fn create_activity_async(
     let r: syscalls::CreateActivity<'_> = get_request(msg)?;
     sysc_log!(
         act,
-        "create_activity(dst={}, name={}, tile={}, kmem={})",
+        "create_activity(dst={}, name={}, tile={}, kmem={}, budget={:?})",
         r.dst,
         r.name,
         r.tile,
-        r.kmem
+        r.kmem,
+        r.budget
     );
 
     if !act
@@ -291,6 +297,11 @@ pub fn create_activity_async(
     let kmem = get_kobj!(act, r.kmem, KMem);
     // TODO kmem quota stuff
 
+    let budget = match Budget::derive(act.sysc_budget(), r.budget) {
+        Ok(b) => b,
+        Err(e) => sysc_err!(e.code(), "Invalid syscall budget {:?}", r.budget),
+    };
+
     // find contiguous space for standard EPs
     let tile_id = tile.tile();
     let tilemux = tilemng::tilemux(tile_id);
@@ -309,6 +320,7 @@ pub fn create_activity_async(
             Ok(nact) => nact,
             Err(e) => sysc_err!(e.code(), "Unable to create Activity"),
         };
+    nact.set_sysc_budget(budget);
 
     // give activity cap to the parent
     let cap = Capability::new(r.dst, KObject::Activity(Rc::downgrade(&nact)));
@@ -323,7 +335,7 @@ pub fn create_activity_async(
         {
             let scap = Capability::new(
                 r.dst + 1 + i as CapSel,
-                KObject::EP(EPObject::new(true, nact_rc.clone(), *ep, 0, nact.tile())),
+                KObject::EP(EPObject::new(true, nact_rc.clone(), *ep, 0, &nact.tile())),
             );
             try_kmem_quota!(act.obj_caps().borrow_mut().insert_as_child(scap, r.dst));
         }
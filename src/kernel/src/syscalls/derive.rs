@@ -16,14 +16,14 @@
 use base::build_vmsg;
 use base::col::ToString;
 use base::errors::{Code, Error, VerboseError};
-use base::kif::{self, syscalls};
+use base::kif::{self, syscalls, CapRngDesc, CapType};
 use base::mem::{GlobAddr, MsgBuf};
 use base::rc::Rc;
 use base::serialize::M3Deserializer;
 use base::tcu;
 
-use crate::cap::{Capability, KObject};
-use crate::cap::{EPQuota, KMemObject, MGateObject, ServObject, TileObject};
+use crate::cap::{expiry, Capability, KObject};
+use crate::cap::{EPQuota, KMemObject, MGateObject, SGateObject, ServObject, TileObject};
 use crate::com::Service;
 use crate::mem;
 use crate::syscalls::{get_request, reply_success};
@@ -37,12 +37,13 @@ pub fn derive_tile_async(
     let r: syscalls::DeriveTile = get_request(msg)?;
     sysc_log!(
         act,
-        "derive_tile(tile={}, dst={}, eps={:?}, time={:?}, pts={:?})",
+        "derive_tile(tile={}, dst={}, eps={:?}, time={:?}, pts={:?}, sched={:?})",
         r.tile,
         r.dst,
         r.eps,
         r.time,
         r.pts,
+        r.sched,
     );
 
     if !act.obj_caps().borrow().unused(r.dst) {
@@ -63,7 +64,10 @@ pub fn derive_tile_async(
         tile.ep_quota().clone()
     };
 
-    let (time_id, pt_id) = if r.time.is_some() || r.pts.is_some() {
+    let (time_id, pt_id) = if r.time.is_some()
+        || r.pts.is_some()
+        || r.sched.class != kif::tilemux::SchedClass::INHERIT
+    {
         let tilemux = tilemng::tilemux(tile.tile());
         match TileMux::derive_quota_async(
             tilemux,
@@ -71,6 +75,9 @@ pub fn derive_tile_async(
             tile.pt_quota_id(),
             r.time,
             r.pts,
+            r.sched,
+            // only root tile capabilities can raise the scheduling class or priority
+            !tile.derived(),
         ) {
             Err(e) => {
                 if let Some(eps) = r.eps {
@@ -129,13 +136,14 @@ pub fn derive_mem(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(),
     let r: syscalls::DeriveMem = get_request(msg)?;
     sysc_log!(
         act,
-        "derive_mem(act={}, src={}, dst={}, size={:#x}, offset={:#x}, perms={:?})",
+        "derive_mem(act={}, src={}, dst={}, size={:#x}, offset={:#x}, perms={:?}, expiry={:?})",
         r.act,
         r.src,
         r.dst,
         r.size,
         r.offset,
-        r.perms
+        r.perms,
+        r.expiry
     );
 
     let tact = get_kobj!(act, r.act, Activity).upgrade().unwrap();
@@ -158,6 +166,78 @@ pub fn derive_mem(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(),
     };
 
     try_kmem_quota!(tact.obj_caps().borrow_mut().insert_as_child(cap, r.src));
+    expiry::add(
+        act,
+        &tact,
+        CapRngDesc::new(CapType::OBJECT, r.dst, 1),
+        r.expiry,
+    );
+
+    reply_success(msg);
+    Ok(())
+}
+
+#[inline(never)]
+pub fn derive_sgate(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), VerboseError> {
+    let r: syscalls::DeriveSGate = get_request(msg)?;
+    sysc_log!(
+        act,
+        "derive_sgate(sgate={}, dst={}, label={:#x}, credits={})",
+        r.sgate,
+        r.dst,
+        r.label,
+        r.credits
+    );
+
+    let mut act_caps = act.obj_caps().borrow_mut();
+
+    if !act_caps.unused(r.dst) {
+        sysc_err!(Code::InvArgs, "Selector {} already in use", r.dst);
+    }
+
+    let (cap, parent) = {
+        let sgate = get_kobj_ref!(act_caps, r.sgate, SGate);
+        // the credits are moved from the parent to the derived gate, which is not possible anymore
+        // once the parent has been activated and thus handed its credits to the EP
+        if sgate.credits() != kif::UNLIM_CREDITS && sgate.gate_ep().get_ep().is_some() {
+            sysc_err!(Code::InvState, "Cannot derive from activated SendGate");
+        }
+
+        // the derived gate cannot have more credits than its parent has left
+        if r.credits == 0
+            || (sgate.credits() != kif::UNLIM_CREDITS
+                && (r.credits == kif::UNLIM_CREDITS || r.credits > sgate.credits()))
+        {
+            sysc_err!(
+                Code::InvArgs,
+                "Invalid credits {} (parent has {})",
+                r.credits,
+                sgate.credits()
+            );
+        }
+
+        // the derived gate can only add label bits from the reserved bits and not change the bits
+        // of the parent gate so that the receiver can still identify the sender
+        if (r.label & !kif::SGATE_DERIVE_LABEL_BITS) != 0 || (r.label & sgate.label()) != 0 {
+            sysc_err!(
+                Code::InvArgs,
+                "Invalid label {:#x} (parent has {:#x})",
+                r.label,
+                sgate.label()
+            );
+        }
+
+        let label = sgate.label() | r.label;
+        let cap = Capability::new(
+            r.dst,
+            KObject::SGate(SGateObject::new(sgate.rgate(), label, r.credits)),
+        );
+        (cap, sgate.clone())
+    };
+
+    try_kmem_quota!(act_caps.insert_as_child(cap, r.sgate));
+    // the credits are given back when the derived gate is revoked
+    parent.take_credits(r.credits);
 
     reply_success(msg);
     Ok(())
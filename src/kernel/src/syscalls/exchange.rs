@@ -23,17 +23,19 @@ use base::rc::Rc;
 use base::serialize::M3Deserializer;
 use base::tcu;
 
-use crate::cap::KObject;
+use crate::cap::{expiry, KObject};
 use crate::com::Service;
 use crate::syscalls::{get_request, reply_success, send_reply};
 use crate::tiles::Activity;
 
 fn do_exchange(
+    owner: &Activity,
     act1: &Rc<Activity>,
     act2: &Rc<Activity>,
     c1: &CapRngDesc,
     c2: &CapRngDesc,
     obtain: bool,
+    expiry: syscalls::Expiry,
 ) -> Result<(), VerboseError> {
     let src = if obtain { act2 } else { act1 };
     let dst = if obtain { act1 } else { act2 };
@@ -73,6 +75,8 @@ fn do_exchange(
         src_cap.map(|c| dst.obj_caps().borrow_mut().obtain(dst_sel, c, true));
     }
 
+    expiry::add(owner, dst, *dst_rng, expiry);
+
     Ok(())
 }
 
@@ -83,15 +87,16 @@ pub fn exchange(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), Ve
 
     sysc_log!(
         act,
-        "exchange(act={}, own={}, other={}, obtain={})",
+        "exchange(act={}, own={}, other={}, obtain={}, expiry={:?})",
         r.act,
         r.own,
         other_crd,
-        r.obtain
+        r.obtain,
+        r.expiry
     );
 
     let actcap = get_kobj!(act, r.act, Activity).upgrade().unwrap();
-    do_exchange(act, &actcap, &r.own, &other_crd, r.obtain)?;
+    do_exchange(act, act, &actcap, &r.own, &other_crd, r.obtain, r.expiry)?;
 
     reply_success(msg);
     Ok(())
@@ -106,13 +111,22 @@ pub fn exchange_over_sess_async(
     let name = if r.obtain { "obtain" } else { "delegate" };
     sysc_log!(
         act,
-        "{}(act={}, sess={}, crd={})",
+        "{}(act={}, sess={}, crd={}, expiry={:?})",
         name,
         r.act,
         r.sess,
-        r.crd
+        r.crd,
+        r.expiry
     );
 
+    // the delegator determines the expiry, which is the server in case of obtains
+    if r.obtain && r.expiry.duration.is_some() {
+        sysc_err!(
+            Code::InvArgs,
+            "Expiry of obtained capabilities is set by the server"
+        );
+    }
+
     let actcap = get_kobj!(act, r.act, Activity).upgrade().unwrap();
     let sess = get_kobj!(act, r.sess, Sess);
 
@@ -172,12 +186,23 @@ pub fn exchange_over_sess_async(
         reply.data.caps
     );
 
+    // the delegator receives the upcall when the capabilities expire
+    let srv_act = serv.service().activity();
+    let (owner, expiry) = if r.obtain {
+        (&srv_act, reply.expiry)
+    }
+    else {
+        (act, r.expiry)
+    };
+
     do_exchange(
+        owner,
         &actcap,
-        &serv.service().activity(),
+        &srv_act,
         &r.crd,
         &reply.data.caps,
         r.obtain,
+        expiry,
     )?;
 
     let mut kreply = MsgBuf::borrow_def();
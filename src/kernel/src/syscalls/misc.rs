@@ -22,14 +22,16 @@ use base::kif::{self, syscalls};
 use base::mem::MsgBuf;
 use base::rc::Rc;
 use base::tcu;
+use base::tmif;
 
 use crate::arch::loader;
 use crate::cap::{Capability, KObject};
-use crate::cap::{EPObject, SemObject};
+use crate::cap::{EPObject, MGateObject, SemObject};
 use crate::ktcu;
 use crate::platform;
 use crate::syscalls::{get_request, reply_success, send_reply};
-use crate::tiles::{tilemng, Activity, TileMux, INVAL_ID};
+use crate::systrace;
+use crate::tiles::{tilemng, Activity, State, TileMux, INVAL_ID};
 
 #[inline(never)]
 pub fn alloc_ep(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), VerboseError> {
@@ -95,7 +97,7 @@ pub fn alloc_ep(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), Ve
             Rc::downgrade(&dst_act),
             epid,
             r.replies,
-            dst_act.tile(),
+            &dst_act.tile(),
         )),
     );
     try_kmem_quota!(act.obj_caps().borrow_mut().insert_as_child(cap, r.act));
@@ -564,6 +566,23 @@ pub fn activity_ctrl_async(
             }
         },
 
+        kif::syscalls::ActivityOp::SIGNAL => {
+            if r.arg == 0 || r.arg >= tmif::SIG_COUNT as u64 {
+                sysc_err!(Code::InvArgs, "Invalid signal {}", r.arg);
+            }
+            if !platform::tile_desc(actcap.tile_id()).supports_tilemux() {
+                sysc_err!(Code::NotSup, "Activity does not run on TileMux");
+            }
+
+            if let Err(e) = TileMux::activity_signal_async(
+                tilemng::tilemux(actcap.tile_id()),
+                actcap.id(),
+                r.arg as u32,
+            ) {
+                sysc_err!(e.code(), "Unable to signal Activity");
+            }
+        },
+
         _ => sysc_err!(Code::InvArgs, "ActivityOp unsupported: {:?}", r.op),
     };
 
@@ -605,6 +624,124 @@ pub fn activity_wait_async(
     Ok(())
 }
 
+#[inline(never)]
+pub fn migrate_activity_async(
+    act: &Rc<Activity>,
+    msg: &'static tcu::Message,
+) -> Result<(), VerboseError> {
+    let r: syscalls::MigrateActivity = get_request(msg)?;
+    sysc_log!(act, "migrate_activity(act={}, tile={})", r.act, r.tile);
+
+    let actcap = get_kobj!(act, r.act, Activity).upgrade().unwrap();
+    let tile = get_kobj!(act, r.tile, Tile);
+
+    if Rc::ptr_eq(act, &actcap) {
+        sysc_err!(Code::InvArgs, "Activity can't migrate itself");
+    }
+    if actcap.is_root() {
+        sysc_err!(Code::InvArgs, "Root activity can't be migrated");
+    }
+    if actcap.state() != State::RUNNING {
+        sysc_err!(Code::InvState, "Activity is not running");
+    }
+    if tile.tile() == actcap.tile_id() {
+        sysc_err!(Code::InvArgs, "Activity is already on tile {}", tile.tile());
+    }
+
+    #[cfg(target_vendor = "host")]
+    sysc_err!(Code::NotSup, "Migration is not supported on host");
+
+    #[cfg(not(target_vendor = "host"))]
+    {
+        use crate::tiles::migration;
+
+        if !migration::compatible(actcap.tile_id(), tile.tile()) {
+            sysc_err!(
+                Code::InvArgs,
+                "Tiles {} and {} are not compatible",
+                actcap.tile_id(),
+                tile.tile()
+            );
+        }
+
+        if let Err(e) = migration::migrate_async(&actcap, tile) {
+            sysc_err!(e.code(), "Unable to migrate activity");
+        }
+
+        reply_success(msg);
+        Ok(())
+    }
+}
+
+#[inline(never)]
+pub fn activity_crash(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), VerboseError> {
+    let r: syscalls::ActivityCrash = get_request(msg)?;
+    sysc_log!(act, "activity_crash(act={})", r.act);
+
+    let actcap = get_kobj!(act, r.act, Activity).upgrade().unwrap();
+    let info = match actcap.crash() {
+        Some(info) => info,
+        None => sysc_err!(Code::NotFound, "Activity {} did not crash", actcap.id()),
+    };
+
+    let mut reply = MsgBuf::borrow_def();
+    build_vmsg!(reply, Code::None, kif::syscalls::ActivityCrashReply {
+        info
+    });
+    send_reply(msg, &reply);
+
+    Ok(())
+}
+
+#[inline(never)]
+pub fn activity_usage_async(
+    act: &Rc<Activity>,
+    msg: &'static tcu::Message,
+) -> Result<(), VerboseError> {
+    let r: syscalls::ActivityUsage = get_request(msg)?;
+    sysc_log!(act, "activity_usage(act={})", r.act);
+
+    let actcap = get_kobj!(act, r.act, Activity).upgrade().unwrap();
+    if !platform::tile_desc(actcap.tile_id()).supports_tilemux() {
+        sysc_err!(Code::NotSup, "Activity does not run on TileMux");
+    }
+
+    let tilemux = tilemng::tilemux(actcap.tile_id());
+    let usage = match TileMux::activity_usage_async(tilemux, actcap.id()) {
+        Ok(usage) => usage,
+        Err(e) => sysc_err!(e.code(), "Unable to get usage of Activity"),
+    };
+
+    let mut reply = MsgBuf::borrow_def();
+    build_vmsg!(reply, Code::None, kif::syscalls::ActivityUsageReply {
+        usage
+    });
+    send_reply(msg, &reply);
+
+    Ok(())
+}
+
+#[inline(never)]
+pub fn sysc_trace(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), VerboseError> {
+    let r: syscalls::SyscTrace = get_request(msg)?;
+    sysc_log!(act, "sysc_trace(dst={})", r.dst);
+
+    if !act.is_root() {
+        sysc_err!(Code::NoPerm, "Only root can access the syscall trace");
+    }
+    if !act.obj_caps().borrow().unused(r.dst) {
+        sysc_err!(Code::InvArgs, "Selector {} already in use", r.dst);
+    }
+
+    // the memory belongs to the kernel; thus, create a derived and read-only gate
+    let mgate = MGateObject::new(systrace::region(), kif::Perm::R, true);
+    let cap = Capability::new(r.dst, KObject::MGate(mgate));
+    try_kmem_quota!(act.obj_caps().borrow_mut().insert(cap));
+
+    reply_success(msg);
+    Ok(())
+}
+
 pub fn reset_stats(act: &Rc<Activity>, msg: &'static tcu::Message) -> Result<(), VerboseError> {
     sysc_log!(act, "reset_stats()",);
 
@@ -20,8 +20,10 @@ use base::mem;
 use base::rc::Rc;
 use base::serialize::{Deserialize, M3Deserializer};
 use base::tcu;
+use base::time::TimeInstant;
 
 use crate::ktcu;
+use crate::systrace;
 use crate::tiles::Activity;
 use crate::tiles::ActivityMng;
 
@@ -84,6 +86,7 @@ macro_rules! get_kobj_ref {
     }};
 }
 
+pub mod budget;
 mod create;
 mod derive;
 mod exchange;
@@ -112,7 +115,19 @@ fn get_request<R: Deserialize<'static>>(msg: &'static tcu::Message) -> Result<R,
 pub fn handle_async(msg: &'static tcu::Message) {
     let act: Rc<Activity> = ActivityMng::activity(msg.header.label as tcu::ActId).unwrap();
 
-    let opcode = msg.as_words()[0];
+    if !budget::charge(&act, msg) {
+        return;
+    }
+
+    let words = msg.as_words();
+    let opcode = words[0];
+    // fetch the arguments now, because the message is gone after the reply
+    let args = [
+        words.get(1).copied().unwrap_or(0),
+        words.get(2).copied().unwrap_or(0),
+    ];
+    let start = TimeInstant::now();
+
     let op = kif::syscalls::Operation::from(opcode);
     let res = match op {
         kif::syscalls::Operation::CREATE_MGATE => create::create_mgate(&act, msg),
@@ -127,6 +142,7 @@ pub fn handle_async(msg: &'static tcu::Message) {
         kif::syscalls::Operation::DERIVE_TILE => derive::derive_tile_async(&act, msg),
         kif::syscalls::Operation::DERIVE_MEM => derive::derive_mem(&act, msg),
         kif::syscalls::Operation::DERIVE_KMEM => derive::derive_kmem(&act, msg),
+        kif::syscalls::Operation::DERIVE_SGATE => derive::derive_sgate(&act, msg),
         kif::syscalls::Operation::DERIVE_SRV => derive::derive_srv_async(&act, msg),
 
         kif::syscalls::Operation::EXCHANGE => exchange::exchange(&act, msg),
@@ -144,13 +160,28 @@ pub fn handle_async(msg: &'static tcu::Message) {
         kif::syscalls::Operation::SEM_CTRL => misc::sem_ctrl_async(&act, msg),
         kif::syscalls::Operation::ACT_CTRL => misc::activity_ctrl_async(&act, msg),
         kif::syscalls::Operation::ACT_WAIT => misc::activity_wait_async(&act, msg),
+        kif::syscalls::Operation::MIGRATE_ACT => misc::migrate_activity_async(&act, msg),
+        kif::syscalls::Operation::ACT_CRASH => misc::activity_crash(&act, msg),
+        kif::syscalls::Operation::ACT_USAGE => misc::activity_usage_async(&act, msg),
 
+        kif::syscalls::Operation::SYSC_TRACE => misc::sysc_trace(&act, msg),
         kif::syscalls::Operation::RESET_STATS => misc::reset_stats(&act, msg),
         kif::syscalls::Operation::NOOP => misc::noop(&act, msg),
 
         _ => panic!("Unexpected operation: {}", opcode),
     };
 
+    systrace::record(
+        act.id(),
+        opcode,
+        args,
+        match &res {
+            Ok(_) => Code::None,
+            Err(e) => e.code(),
+        },
+        start,
+    );
+
     if let Err(e) = res {
         klog!(
             ERR,
@@ -0,0 +1,88 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Records all system calls into a ring buffer in kernel memory (see `kif::systrace`).
+
+use base::cell::{LazyStaticCell, StaticCell};
+use base::cfg;
+use base::errors::Code;
+use base::goff;
+use base::kif::systrace::{self, Entry, Header};
+use base::mem::{size_of, GlobAddr};
+use base::tcu::ActId;
+use base::time::TimeInstant;
+
+use crate::ktcu;
+use crate::mem;
+
+static BUF: LazyStaticCell<GlobAddr> = LazyStaticCell::default();
+static POS: StaticCell<u64> = StaticCell::new(0);
+
+pub fn init() {
+    let alloc = mem::borrow_mut()
+        .allocate(
+            mem::MemType::KERNEL,
+            systrace::SIZE as goff,
+            cfg::PAGE_SIZE as goff,
+        )
+        .expect("Unable to allocate memory for syscall trace");
+    BUF.set(alloc.global());
+
+    write(0, &Header {
+        entries: systrace::ENTRIES as u64,
+    });
+    // mark all entries as unused
+    for i in 0..systrace::ENTRIES {
+        write(
+            size_of::<Header>() + i * size_of::<Entry>(),
+            &Entry::default(),
+        );
+    }
+}
+
+/// Returns the memory region of the trace
+pub fn region() -> mem::Allocation {
+    mem::Allocation::new(BUF.get(), systrace::SIZE as goff)
+}
+
+pub fn record(act: ActId, op: u64, args: [u64; 2], error: Code, start: TimeInstant) {
+    let seq = POS.get() + 1;
+    POS.set(seq);
+
+    let entry = Entry {
+        seq,
+        act: act as u64,
+        op,
+        args,
+        error: error as u64,
+        start: start.as_nanos(),
+        duration: TimeInstant::now().as_nanos() - start.as_nanos(),
+    };
+
+    let idx = seq as usize % systrace::ENTRIES;
+    write(size_of::<Header>() + idx * size_of::<Entry>(), &entry);
+}
+
+fn write<T>(off: usize, obj: &T) {
+    let addr = BUF.get() + off as goff;
+    // tracing is best effort; never let it fail a system call
+    ktcu::try_write_mem(
+        addr.tile(),
+        addr.offset(),
+        obj as *const T as *const u8,
+        size_of::<T>(),
+    )
+    .ok();
+}
@@ -15,7 +15,7 @@
 
 use base::boxed::Box;
 use base::build_vmsg;
-use base::cell::{Cell, RefCell, StaticRefCell};
+use base::cell::{Cell, Ref, RefCell, StaticRefCell};
 use base::col::{String, ToString, Vec};
 use base::errors::{Code, Error};
 use base::goff;
@@ -32,6 +32,7 @@ use crate::cap::{CapTable, Capability, EPObject, KMemObject, KObject, TileObject
 use crate::com::{QueueId, SendQueue};
 use crate::ktcu;
 use crate::platform;
+use crate::syscalls::budget::{self, Budget};
 use crate::tiles::{tilemng, ActivityMng};
 use crate::workloop::thread_startup;
 
@@ -66,7 +67,7 @@ pub struct Activity {
     flags: ActivityFlags,
     eps_start: EpId,
 
-    tile: SRc<TileObject>,
+    tile: RefCell<SRc<TileObject>>,
     kmem: SRc<KMemObject>,
 
     state: Cell<State>,
@@ -80,6 +81,8 @@ pub struct Activity {
     eps: RefCell<Vec<Rc<EPObject>>>,
     rbuf_phys: Cell<goff>,
     upcalls: RefCell<Box<SendQueue>>,
+    sysc_budget: RefCell<Option<Rc<Budget>>>,
+    crash: RefCell<Option<Box<kif::tilemux::CrashInfo>>>,
 }
 
 impl Activity {
@@ -106,7 +109,9 @@ impl Activity {
             eps: RefCell::from(Vec::new()),
             rbuf_phys: Cell::from(0),
             upcalls: RefCell::from(SendQueue::new(QueueId::Activity(id), tile.tile())),
-            tile,
+            sysc_budget: RefCell::new(None),
+            crash: RefCell::from(None),
+            tile: RefCell::from(tile),
         });
 
         {
@@ -119,10 +124,9 @@ impl Activity {
                 KObject::KMem(act.kmem.clone()),
             ))?;
             // tile cap
-            act.obj_caps().borrow_mut().insert(Capability::new(
-                kif::SEL_TILE,
-                KObject::Tile(act.tile.clone()),
-            ))?;
+            act.obj_caps()
+                .borrow_mut()
+                .insert(Capability::new(kif::SEL_TILE, KObject::Tile(act.tile())))?;
             // cap for own activity
             act.obj_caps().borrow_mut().insert(Capability::new(
                 kif::SEL_ACT,
@@ -131,10 +135,10 @@ impl Activity {
 
             // alloc standard EPs
             tilemng::tilemux(act.tile_id()).alloc_eps(eps_start, STD_EPS_COUNT as u32);
-            act.tile.alloc(STD_EPS_COUNT as u32);
+            act.tile().alloc(STD_EPS_COUNT as u32);
 
             // add us to tile
-            act.tile.add_activity();
+            act.tile().add_activity();
         }
 
         // some system calls are blocking, leading to a thread switch in the kernel. there is just
@@ -244,12 +248,22 @@ impl Activity {
         self.id
     }
 
-    pub fn tile(&self) -> &SRc<TileObject> {
-        &self.tile
+    pub fn tile(&self) -> SRc<TileObject> {
+        self.tile.borrow().clone()
     }
 
     pub fn tile_id(&self) -> TileId {
-        self.tile.tile()
+        self.tile.borrow().tile()
+    }
+
+    pub fn set_tile(&self, tile: SRc<TileObject>, rbuf_phys: goff) {
+        self.rbuf_phys.set(rbuf_phys);
+        self.upcalls.borrow_mut().set_tile(tile.tile());
+        // update our own tile capability as well
+        if let Some(cap) = self.obj_caps.borrow_mut().get_mut(kif::SEL_TILE) {
+            *cap.get_mut() = KObject::Tile(tile.clone());
+        }
+        self.tile.replace(tile);
     }
 
     pub fn tile_desc(&self) -> TileDesc {
@@ -260,6 +274,22 @@ impl Activity {
         &self.kmem
     }
 
+    pub fn sysc_budget(&self) -> Option<Rc<Budget>> {
+        self.sysc_budget.borrow().clone()
+    }
+
+    pub fn set_sysc_budget(&self, budget: Option<Rc<Budget>>) {
+        self.sysc_budget.replace(budget);
+    }
+
+    pub fn crash(&self) -> Option<kif::tilemux::CrashInfo> {
+        self.crash.borrow().as_ref().map(|c| **c)
+    }
+
+    pub fn set_crash(&self, info: kif::tilemux::CrashInfo) {
+        self.crash.replace(Some(Box::new(info)));
+    }
+
     pub fn rbuf_addr(&self) -> goff {
         self.rbuf_phys.get()
     }
@@ -304,6 +334,10 @@ impl Activity {
         self.exit_code.replace(None)
     }
 
+    pub fn eps(&self) -> Ref<'_, Vec<Rc<EPObject>>> {
+        self.eps.borrow()
+    }
+
     pub fn add_ep(&self, ep: Rc<EPObject>) {
         self.eps.borrow_mut().push(ep);
     }
@@ -434,6 +468,20 @@ impl Activity {
         self.send_upcall::<kif::upcalls::DeriveSrv>(&msg);
     }
 
+    pub fn upcall_cap_expired(&self, event: u64, result: Result<(), Error>) {
+        let mut msg = MsgBuf::borrow_def();
+        build_vmsg!(
+            msg,
+            kif::upcalls::Operation::CAP_EXPIRED,
+            kif::upcalls::CapExpired {
+                event,
+                error: Code::from(result)
+            }
+        );
+
+        self.send_upcall::<kif::upcalls::CapExpired>(&msg);
+    }
+
     fn send_upcall<M: fmt::Debug>(&self, msg: &MsgBuf) {
         klog!(
             UPCALLS,
@@ -488,6 +536,7 @@ impl Activity {
             self.state.set(State::DEAD);
             ActivityMng::stop_activity_async(self, true, true).unwrap();
             ktcu::drop_msgs(ktcu::KSYS_EP, self.id() as Label);
+            budget::drop_deferred(self.id());
         }
     }
 
@@ -519,6 +568,7 @@ impl Activity {
 
         // make sure that we don't get further syscalls by this activity
         ktcu::drop_msgs(ktcu::KSYS_EP, self.id() as Label);
+        budget::drop_deferred(self.id());
 
         self.state.set(State::DEAD);
         self.exit_code.set(Some(exit_code));
@@ -563,10 +613,10 @@ impl Drop for Activity {
 
         // free standard EPs
         tilemng::tilemux(self.tile_id()).free_eps(self.eps_start, STD_EPS_COUNT as u32);
-        self.tile.free(STD_EPS_COUNT as u32);
+        self.tile().free(STD_EPS_COUNT as u32);
 
         // remove us from tile
-        self.tile.rem_activity();
+        self.tile().rem_activity();
 
         assert!(self.obj_caps.borrow().is_empty());
         assert!(self.map_caps.borrow().is_empty());
@@ -62,6 +62,16 @@ impl ActivityMng {
         INST.borrow().acts[id as usize].as_ref().cloned()
     }
 
+    pub fn for_each<F>(mut func: F)
+    where
+        F: FnMut(&Rc<Activity>),
+    {
+        let acts: Vec<Rc<Activity>> = INST.borrow().acts.iter().flatten().cloned().collect();
+        for act in &acts {
+            func(act);
+        }
+    }
+
     fn get_id() -> Result<tcu::ActId, Error> {
         let mut actmng = INST.borrow_mut();
         for id in actmng.next_id..cfg::MAX_ACTS as tcu::ActId {
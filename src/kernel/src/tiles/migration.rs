@@ -0,0 +1,433 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Live migration of activities between tiles.
+//!
+//! An activity is migrated by suspending it on its current tile, transferring its CPU state, its
+//! environment, its standard receive buffers, its mappings, and its endpoints to the new tile, and
+//! finally resuming it there. Activities are only migrated between identical tiles that use the
+//! same physical-memory protection EPs, so that all physical addresses stay valid. Furthermore,
+//! the activity must not wait for replies, because these would be sent to the old tile.
+
+use base::cfg;
+use base::col::Vec;
+use base::errors::{Code, Error};
+use base::goff;
+use base::kif::{self, PageFlags};
+use base::mem::GlobAddr;
+use base::rc::{Rc, SRc};
+use base::tcu::{self, EpId, Reg, TileId, TCU};
+use core::ptr;
+
+use crate::cap::{GateObject, KObject, RGateObject, TileObject};
+use crate::ktcu;
+use crate::platform;
+use crate::tiles::{tilemng, Activity, ActivityMng, TileMux};
+
+/// A mapping of the activity that needs to be re-established on the new tile
+struct Mapping {
+    virt: goff,
+    global: GlobAddr,
+    pages: usize,
+    flags: PageFlags,
+}
+
+/// Checks whether activities can be migrated from tile `src` to tile `dst`.
+pub fn compatible(src: TileId, dst: TileId) -> bool {
+    let desc = platform::tile_desc(src);
+    if desc.value() != platform::tile_desc(dst).value()
+        || !desc.supports_tilemux()
+        || !desc.has_virtmem()
+    {
+        return false;
+    }
+
+    // the physical addresses of receive buffers and memory EPs are only valid on the new tile, if
+    // both tiles have the same physical-memory protection EPs (except the one for TileMux)
+    for ep in 1..tcu::PMEM_PROT_EPS as EpId {
+        let mut src_regs = [0; tcu::EP_REGS];
+        let mut dst_regs = [0; tcu::EP_REGS];
+        if ktcu::read_ep_remote(src, ep, &mut src_regs).is_err()
+            || ktcu::read_ep_remote(dst, ep, &mut dst_regs).is_err()
+            || src_regs != dst_regs
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// Migrates `act` to the tile of `tile`, using `tile` for its quotas from now on.
+pub fn migrate_async(act: &Rc<Activity>, tile: SRc<TileObject>) -> Result<(), Error> {
+    let src = act.tile_id();
+    let dst = tile.tile();
+
+    klog!(
+        ACTIVITIES,
+        "Migrating Activity {} [id={}] from tile {} to tile {}",
+        act.name(),
+        act.id(),
+        src,
+        dst
+    );
+
+    // we keep the EP ids to not change the view of the activity
+    let ranges = ep_ranges(act);
+    let ep_count = ranges.iter().map(|(_, count)| count).sum();
+    {
+        let tilemux = tilemng::tilemux(dst);
+        if ranges
+            .iter()
+            .any(|(start, count)| !tilemux.eps_free(*start, *count))
+        {
+            return Err(Error::new(Code::NoSpace));
+        }
+    }
+    if !tile.has_quota(ep_count) {
+        return Err(Error::new(Code::NoSpace));
+    }
+
+    let src_state = TileMux::activity_suspend_async(tilemng::tilemux(src), act.id())?;
+
+    if let Err(e) = prepare_async(act, &tile, src_state) {
+        abort_async(act, dst);
+        return Err(e);
+    }
+
+    let moved = match move_eps_async(act, &tile, &ranges) {
+        Ok(moved) => moved,
+        Err(e) => {
+            abort_async(act, dst);
+            return Err(e);
+        },
+    };
+
+    // remove the activity from the old tile without notifying us about its exit
+    if let Err(e) = TileMux::activity_ctrl_async(
+        tilemng::tilemux(src),
+        act.id(),
+        kif::tilemux::ActivityOp::STOP,
+    ) {
+        moved.undo(src, dst);
+        abort_async(act, dst);
+        return Err(e);
+    }
+
+    commit(act, &tile, &ranges, ep_count, &moved);
+
+    TileMux::activity_resume_async(tilemng::tilemux(dst), act.id())
+}
+
+/// Removes the activity from the new tile again and lets it continue on the old tile
+fn abort_async(act: &Activity, dst: TileId) {
+    TileMux::activity_ctrl_async(
+        tilemng::tilemux(dst),
+        act.id(),
+        kif::tilemux::ActivityOp::STOP,
+    )
+    .ok();
+    TileMux::activity_resume_async(tilemng::tilemux(act.tile_id()), act.id()).ok();
+}
+
+fn ep_ranges(act: &Activity) -> Vec<(EpId, u32)> {
+    let mut ranges = Vec::new();
+    ranges.push((act.eps_start(), tcu::STD_EPS_COUNT as u32));
+    for ep in act.eps().iter().filter(|ep| !ep.is_std()) {
+        ranges.push((ep.ep(), 1 + ep.replies()));
+    }
+    ranges
+}
+
+fn prepare_async(
+    act: &Activity,
+    tile: &SRc<TileObject>,
+    (src_state, src_size): (GlobAddr, usize),
+) -> Result<(), Error> {
+    let src = act.tile_id();
+    let dst = tile.tile();
+
+    // replies to outstanding messages would be sent to the old tile
+    if ktcu::has_missing_credits_remote(src, act.eps_start() + tcu::SYSC_SEP_OFF)? {
+        return Err(Error::new(Code::InProgress));
+    }
+    for ep in act.eps().iter() {
+        if matches!(*ep.gate(), Some(GateObject::Send(_)))
+            && ktcu::has_missing_credits_remote(src, ep.ep())?
+        {
+            return Err(Error::new(Code::InProgress));
+        }
+    }
+
+    TileMux::activity_init_async(
+        tilemng::tilemux(dst),
+        act.id(),
+        tile.time_quota_id(),
+        tile.pt_quota_id(),
+        act.eps_start(),
+    )?;
+
+    // transfer the CPU state
+    let (dst_state, dst_size) = TileMux::activity_suspend_async(tilemng::tilemux(dst), act.id())?;
+    assert!(src_size == dst_size);
+    ktcu::copy(
+        dst_state.tile(),
+        dst_state.offset(),
+        src_state.tile(),
+        src_state.offset(),
+        src_size,
+    )?;
+
+    // transfer the environment
+    copy_pages_async(
+        act,
+        dst,
+        (cfg::ENV_START & !cfg::PAGE_MASK) as goff,
+        cfg::ENV_SIZE / cfg::PAGE_SIZE,
+    )?;
+
+    // re-establish all mappings on the new tile
+    let mut mappings = Vec::new();
+    act.map_caps().borrow().for_each(|c| {
+        if let KObject::Map(m) = c.get() {
+            if m.mapped() {
+                mappings.push(Mapping {
+                    virt: (c.sel() as goff) << cfg::PAGE_BITS,
+                    global: m.global(),
+                    pages: c.len() as usize,
+                    flags: m.flags(),
+                });
+            }
+        }
+    });
+    for m in mappings {
+        TileMux::map_async(
+            tilemng::tilemux(dst),
+            act.id(),
+            m.virt,
+            m.global,
+            m.pages,
+            m.flags,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn copy_pages_async(act: &Activity, dst: TileId, virt: goff, pages: usize) -> Result<(), Error> {
+    for i in 0..pages {
+        let page = virt + (i * cfg::PAGE_SIZE) as goff;
+        let src_glob = TileMux::translate_async(
+            tilemng::tilemux(act.tile_id()),
+            act.id(),
+            page,
+            PageFlags::R,
+        )?;
+        let dst_glob =
+            TileMux::translate_async(tilemng::tilemux(dst), act.id(), page, PageFlags::R)?;
+        ktcu::copy(
+            dst_glob.tile(),
+            dst_glob.offset(),
+            src_glob.tile(),
+            src_glob.offset(),
+            cfg::PAGE_SIZE,
+        )?;
+    }
+    Ok(())
+}
+
+/// The EPs that have been moved to the new tile, but are not yet accounted to it
+struct MovedEps {
+    // the original registers of the EPs on the old tile
+    eps: Vec<(EpId, [Reg; tcu::EP_REGS])>,
+    // the send EPs of other activities that have been retargeted to the new tile
+    senders: Vec<(TileId, EpId)>,
+    rgates: Vec<SRc<RGateObject>>,
+    old_rbuf: goff,
+    new_rbuf: goff,
+}
+
+impl MovedEps {
+    fn move_rbuf(&self, addr: goff) -> goff {
+        if addr >= self.old_rbuf && addr < self.old_rbuf + cfg::RBUF_STD_SIZE as goff {
+            self.new_rbuf + (addr - self.old_rbuf)
+        }
+        else {
+            addr
+        }
+    }
+
+    /// Restores the EPs on the old tile and lets all senders point to the old tile again
+    fn undo(&self, src: TileId, dst: TileId) {
+        for (ep, regs) in &self.eps {
+            ktcu::invalidate_ep_remote(dst, *ep, true).ok();
+            ktcu::write_ep_remote(src, *ep, regs).ok();
+        }
+        for (tile, ep) in &self.senders {
+            ktcu::retarget_send_remote(*tile, *ep, src).ok();
+        }
+    }
+}
+
+fn move_eps_async(
+    act: &Activity,
+    tile: &SRc<TileObject>,
+    ranges: &[(EpId, u32)],
+) -> Result<MovedEps, Error> {
+    let src = act.tile_id();
+    let dst = tile.tile();
+
+    let rbuf_virt = platform::tile_desc(dst).rbuf_std_space().0 as goff;
+    let new_rbuf = {
+        let glob =
+            TileMux::translate_async(tilemng::tilemux(dst), act.id(), rbuf_virt, PageFlags::RW)?;
+        ktcu::glob_to_phys_remote(dst, glob, PageFlags::RW)?
+    };
+
+    let mut moved = MovedEps {
+        eps: Vec::new(),
+        senders: Vec::new(),
+        rgates: Vec::new(),
+        old_rbuf: act.rbuf_addr(),
+        new_rbuf,
+    };
+    for ep in act.eps().iter() {
+        if let Some(GateObject::Recv(rg)) = &*ep.gate() {
+            moved.rgates.push(rg.clone());
+        }
+    }
+
+    let res = move_eps_to_async(act, dst, rbuf_virt, ranges, &mut moved);
+    if let Err(e) = res {
+        moved.undo(src, dst);
+        return Err(e);
+    }
+    Ok(moved)
+}
+
+fn move_eps_to_async(
+    act: &Activity,
+    dst: TileId,
+    rbuf_virt: goff,
+    ranges: &[(EpId, u32)],
+    moved: &mut MovedEps,
+) -> Result<(), Error> {
+    let src = act.tile_id();
+
+    // send EPs of this activity that refer to its own receive gates need to be retargeted as well
+    let mut own_senders = Vec::new();
+    for ep in act.eps().iter() {
+        if let Some(GateObject::Send(sg)) = &*ep.gate() {
+            if moved.rgates.iter().any(|rg| ptr::eq(&**rg, &**sg.rgate())) {
+                own_senders.push(ep.ep());
+            }
+        }
+    }
+
+    // move the EPs. note that we invalidate the EPs first and copy the standard receive buffers
+    // afterwards to not lose messages that arrive in between.
+    let mut eps = Vec::new();
+    for (start, count) in ranges {
+        for ep in *start..*start + *count as EpId {
+            let mut regs = [0; tcu::EP_REGS];
+            ktcu::read_ep_remote(src, ep, &mut regs)?;
+            ktcu::invalidate_ep_remote(src, ep, true)?;
+            moved.eps.push((ep, regs));
+
+            match TCU::ep_type(&regs) {
+                tcu::EpType::RECEIVE => regs[1] = moved.move_rbuf(regs[1]),
+                tcu::EpType::SEND if own_senders.contains(&ep) => {
+                    TCU::set_send_dest_tile(&mut regs, dst)
+                },
+                _ => {},
+            }
+            eps.push((ep, regs));
+        }
+    }
+    copy_pages_async(act, dst, rbuf_virt, cfg::RBUF_STD_SIZE / cfg::PAGE_SIZE)?;
+    for (ep, regs) in &eps {
+        ktcu::write_ep_remote(dst, *ep, regs)?;
+    }
+
+    // let all send EPs of other activities that refer to the moved receive gates point to the
+    // new tile
+    let mut senders = Vec::new();
+    ActivityMng::for_each(|other| {
+        if other.id() == act.id() {
+            return;
+        }
+        for ep in other.eps().iter() {
+            if let Some(GateObject::Send(sg)) = &*ep.gate() {
+                if moved.rgates.iter().any(|rg| ptr::eq(&**rg, &**sg.rgate())) {
+                    senders.push((ep.tile_id(), ep.ep()));
+                }
+            }
+        }
+    });
+    for (tile, ep) in senders {
+        ktcu::retarget_send_remote(tile, ep, dst)?;
+        moved.senders.push((tile, ep));
+    }
+
+    Ok(())
+}
+
+/// Accounts the moved activity and its EPs to the new tile
+fn commit(
+    act: &Activity,
+    tile: &SRc<TileObject>,
+    ranges: &[(EpId, u32)],
+    ep_count: u32,
+    moved: &MovedEps,
+) {
+    let src = act.tile_id();
+    let dst = tile.tile();
+
+    {
+        let mut src_mux = tilemng::tilemux(src);
+        for (start, count) in ranges {
+            src_mux.free_eps(*start, *count);
+        }
+        src_mux.rem_activity(act.id());
+    }
+    {
+        let mut dst_mux = tilemng::tilemux(dst);
+        for (start, count) in ranges {
+            dst_mux.alloc_eps(*start, *count);
+        }
+        dst_mux.add_activity(act.id());
+    }
+    let old_tile = act.tile();
+    old_tile.free(ep_count);
+    old_tile.rem_activity();
+    tile.alloc(ep_count);
+    tile.add_activity();
+
+    for ep in act.eps().iter() {
+        ep.set_tile(tile);
+        if let Some(GateObject::Recv(rg)) = &*ep.gate() {
+            rg.activate(dst, ep.ep(), moved.move_rbuf(rg.addr()));
+        }
+    }
+    act.set_tile(tile.clone(), moved.new_rbuf);
+
+    // the kernel sends requests to the services of this activity
+    act.obj_caps().borrow().for_each(|c| {
+        if let KObject::Serv(s) = c.get() {
+            if s.service().activity().id() == act.id() {
+                s.service().set_tile(dst);
+            }
+        }
+    });
+}
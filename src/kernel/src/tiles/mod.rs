@@ -18,6 +18,8 @@
 
 mod activities;
 mod actmng;
+#[cfg(not(target_vendor = "host"))]
+pub mod migration;
 pub mod tilemng;
 mod tilemux;
 
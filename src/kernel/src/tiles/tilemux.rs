@@ -391,6 +391,13 @@ impl TileMux {
 
         if has_act {
             let act = ActivityMng::activity(r.act_id).unwrap();
+            if r.crash != 0 {
+                let info = GlobAddr::new(r.crash);
+                match ktcu::try_read_obj::<kif::tilemux::CrashInfo>(info.tile(), info.offset()) {
+                    Ok(info) => act.set_crash(info),
+                    Err(e) => klog!(ERR, "Unable to read crash info of {}: {:?}", act.id(), e),
+                }
+            }
             act.stop_app_async(r.status, true);
         }
         Ok(())
@@ -437,12 +444,74 @@ impl TileMux {
             .map(|_| ())
     }
 
+    pub fn activity_suspend_async(
+        tilemux: RefMut<'_, Self>,
+        act: ActId,
+    ) -> Result<(GlobAddr, usize), Error> {
+        let mut msg = MsgBuf::borrow_def();
+        build_vmsg!(
+            msg,
+            kif::tilemux::Sidecalls::ACT_SUSPEND,
+            kif::tilemux::ActSuspend { act_id: act as u64 }
+        );
+
+        Self::send_receive_sidecall_async::<kif::tilemux::ActSuspend>(tilemux, None, msg)
+            .map(|r| (GlobAddr::new(r.val1), r.val2 as usize))
+    }
+
+    pub fn activity_resume_async(tilemux: RefMut<'_, Self>, act: ActId) -> Result<(), Error> {
+        let mut msg = MsgBuf::borrow_def();
+        build_vmsg!(
+            msg,
+            kif::tilemux::Sidecalls::ACT_RESUME,
+            kif::tilemux::ActResume { act_id: act as u64 }
+        );
+
+        Self::send_receive_sidecall_async::<kif::tilemux::ActResume>(tilemux, None, msg).map(|_| ())
+    }
+
+    pub fn activity_signal_async(
+        tilemux: RefMut<'_, Self>,
+        act: ActId,
+        signal: u32,
+    ) -> Result<(), Error> {
+        let mut msg = MsgBuf::borrow_def();
+        build_vmsg!(
+            msg,
+            kif::tilemux::Sidecalls::ACT_SIGNAL,
+            kif::tilemux::ActSignal {
+                act_id: act as u64,
+                signal,
+            }
+        );
+
+        Self::send_receive_sidecall_async::<kif::tilemux::ActSignal>(tilemux, None, msg).map(|_| ())
+    }
+
+    pub fn activity_usage_async(
+        tilemux: RefMut<'_, Self>,
+        act: ActId,
+    ) -> Result<kif::tilemux::Usage, Error> {
+        let mut msg = MsgBuf::borrow_def();
+        build_vmsg!(
+            msg,
+            kif::tilemux::Sidecalls::ACT_USAGE,
+            kif::tilemux::ActUsage { act_id: act as u64 }
+        );
+
+        let usage = Self::send_receive_sidecall_async::<kif::tilemux::ActUsage>(tilemux, None, msg)
+            .map(|r| GlobAddr::new(r.val1))?;
+        ktcu::try_read_obj(usage.tile(), usage.offset())
+    }
+
     pub fn derive_quota_async(
         tilemux: RefMut<'_, Self>,
         parent_time: quota::Id,
         parent_pts: quota::Id,
         time: Option<u64>,
         pts: Option<usize>,
+        sched: kif::tilemux::SchedParams,
+        privileged: bool,
     ) -> Result<(quota::Id, quota::Id), Error> {
         let mut msg = MsgBuf::borrow_def();
         build_vmsg!(
@@ -453,6 +522,8 @@ impl TileMux {
                 parent_pts,
                 time,
                 pts,
+                sched,
+                privileged,
             }
         );
 
@@ -670,12 +741,29 @@ impl TileMux {
         Ok(())
     }
 
+    pub fn activity_signal_async(
+        _tilemux: RefMut<'_, Self>,
+        _act: ActId,
+        _signal: u32,
+    ) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    pub fn activity_usage_async(
+        _tilemux: RefMut<'_, Self>,
+        _act: ActId,
+    ) -> Result<base::kif::tilemux::Usage, Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
     pub fn derive_quota_async(
         _tilemux: RefMut<'_, Self>,
         _parent_time: quota::Id,
         _parent_pts: quota::Id,
         _time: Option<u64>,
         _pts: Option<usize>,
+        _sched: base::kif::tilemux::SchedParams,
+        _privileged: bool,
     ) -> Result<(quota::Id, quota::Id), Error> {
         Ok((0, 0))
     }
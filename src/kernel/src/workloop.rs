@@ -15,7 +15,9 @@
 
 use base::envdata;
 use base::tcu;
+use core::cmp;
 
+use crate::cap;
 use crate::com;
 use crate::ktcu;
 use crate::syscalls;
@@ -31,10 +33,21 @@ pub fn workloop() -> ! {
     }
 
     while ActivityMng::count() > 0 {
+        // sleep at most until the next capabilities expire or deferred system calls can be handled
         if envdata::get().platform != envdata::Platform::HW.val {
-            tcu::TCU::sleep().unwrap();
+            let timeout = match (
+                cap::expiry::next_timeout(),
+                syscalls::budget::next_timeout(),
+            ) {
+                (Some(a), Some(b)) => Some(cmp::min(a, b)),
+                (a, b) => a.or(b),
+            };
+            ktcu::sleep_for(timeout).unwrap();
         }
 
+        cap::expiry::check_async();
+        syscalls::budget::handle_deferred_async();
+
         if let Some(msg) = ktcu::fetch_msg(ktcu::KSYS_EP) {
             syscalls::handle_async(msg);
         }
@@ -89,6 +89,8 @@ void Syscalls::create_mgate(capsel_t dst, capsel_t act, goff_t addr, size_t size
     req.addr = addr;
     req.size = size;
     req.perms = static_cast<xfer_t>(perms);
+    req.expiry.duration = static_cast<xfer_t>(-1);
+    req.expiry.event = 0;
     send_receive_throw(req_buf);
 }
 
@@ -135,6 +137,8 @@ std::pair<epid_t, actid_t> Syscalls::create_activity(capsel_t dst, const std::st
     req.dst_sel = dst;
     req.tile_sel = tile;
     req.kmem_sel = kmem;
+    req.budget.syscalls = static_cast<xfer_t>(-1);
+    req.budget.period = 0;
     req.namelen = Math::min(name.length(), sizeof(req.name));
     memcpy(req.name, name.data(), req.namelen);
 
@@ -231,6 +235,15 @@ std::pair<int, capsel_t> Syscalls::activity_wait(const capsel_t *acts, size_t co
     return std::make_pair(exitcode, act);
 }
 
+void Syscalls::migrate_activity(capsel_t act, capsel_t tile) {
+    MsgBuf req_buf;
+    auto &req = req_buf.cast<KIF::Syscall::MigrateAct>();
+    req.opcode = KIF::Syscall::MIGRATE_ACT;
+    req.act_sel = act;
+    req.tile_sel = tile;
+    send_receive_throw(req_buf);
+}
+
 void Syscalls::derive_mem(capsel_t act, capsel_t dst, capsel_t src, goff_t offset, size_t size,
                           int perms) {
     MsgBuf req_buf;
@@ -245,6 +258,17 @@ void Syscalls::derive_mem(capsel_t act, capsel_t dst, capsel_t src, goff_t offse
     send_receive_throw(req_buf);
 }
 
+void Syscalls::derive_sgate(capsel_t sgate, capsel_t dst, label_t label, uint credits) {
+    MsgBuf req_buf;
+    auto &req = req_buf.cast<KIF::Syscall::DeriveSGate>();
+    req.opcode = KIF::Syscall::DERIVE_SGATE;
+    req.sgate_sel = sgate;
+    req.dst_sel = dst;
+    req.label = label;
+    req.credits = credits;
+    send_receive_throw(req_buf);
+}
+
 void Syscalls::derive_kmem(capsel_t kmem, capsel_t dst, size_t quota) {
     MsgBuf req_buf;
     auto &req = req_buf.cast<KIF::Syscall::DeriveKMem>();
@@ -264,6 +288,8 @@ void Syscalls::derive_tile(capsel_t tile, capsel_t dst, uint eps, uint64_t time,
     req.eps = eps;
     req.time = time;
     req.pts = pts;
+    req.sched.sched_class = 0;
+    req.sched.prio = req.sched.period = req.sched.budget = 0;
     send_receive_throw(req_buf);
 }
 
@@ -361,6 +387,8 @@ void Syscalls::exchange(capsel_t act, const KIF::CapRngDesc &own, capsel_t other
     own.to_raw(req.own_caps);
     req.other_sel = other;
     req.obtain = obtain;
+    req.expiry.duration = static_cast<xfer_t>(-1);
+    req.expiry.event = 0;
     send_receive_throw(req_buf);
 }
 
@@ -372,6 +400,8 @@ void Syscalls::exchange_sess(capsel_t act, capsel_t sess, const KIF::CapRngDesc
     req.act_sel = act;
     req.sess_sel = sess;
     req.obtain = obtain;
+    req.expiry.duration = static_cast<xfer_t>(-1);
+    req.expiry.event = 0;
     crd.to_raw(req.caps);
     if(args)
         memcpy(&req.args, args, sizeof(*args));
@@ -42,6 +42,14 @@ SendGate SendGate::create_named(const char *name, RecvGate *replygate) {
     return SendGate(sel, 0, replygate);
 }
 
+SendGate SendGate::derive(label_t label, uint credits, RecvGate *replygate) {
+    if(replygate == nullptr)
+        replygate = &RecvGate::def();
+    auto sel = Activity::own().alloc_sel();
+    Syscalls::derive_sgate(this->sel(), sel, label, credits);
+    return SendGate(sel, 0, replygate);
+}
+
 uint SendGate::credits() {
     const EP &sep = activate();
     if(!TCU::get().is_valid(sep.id()))
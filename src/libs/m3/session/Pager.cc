@@ -103,6 +103,52 @@ void Pager::unmap(goff_t virt) {
     reply.pull_result();
 }
 
+void Pager::protect(goff_t virt, size_t len, int prot) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, PROTECT, virt, len, prot);
+    reply.pull_result();
+}
+
+void Pager::advise(goff_t virt, size_t len, int advice) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, ADVISE, virt, len, advice);
+    reply.pull_result();
+}
+
+void Pager::sync(goff_t virt, size_t len) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, SYNC, virt, len);
+    reply.pull_result();
+}
+
+uint64_t Pager::open_shm(const char *name, int flags, size_t *size) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, SHM_OPEN, name, flags);
+    reply.pull_result();
+    uint64_t id;
+    reply >> id >> *size;
+    return id;
+}
+
+void Pager::resize_shm(uint64_t id, size_t size) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, SHM_RESIZE, id, size);
+    reply.pull_result();
+}
+
+void Pager::close_shm(uint64_t id) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, SHM_CLOSE, id);
+    reply.pull_result();
+}
+
+void Pager::map_shm(goff_t *virt, uint64_t id, size_t offset, size_t len, int prot, int flags) {
+    GateIStream reply =
+        send_receive_vmsg(_req_sgate, MAP_SHM, *virt, id, offset, len, prot, flags);
+    reply.pull_result();
+    reply >> *virt;
+}
+
+void Pager::map_stats(size_t *large_pages, size_t *small_pages) {
+    GateIStream reply = send_receive_vmsg(_req_sgate, MAP_STATS);
+    reply.pull_result();
+    reply >> *large_pages >> *small_pages;
+}
+
 Reference<Pager> Pager::create_clone() {
     KIF::CapRngDesc caps;
     {
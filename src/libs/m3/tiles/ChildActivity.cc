@@ -120,6 +120,15 @@ void ChildActivity::stop() {
     Syscalls::activity_ctrl(sel(), KIF::Syscall::VCTRL_STOP, 0);
 }
 
+void ChildActivity::signal(uint sig) {
+    Syscalls::activity_ctrl(sel(), KIF::Syscall::VCTRL_SIGNAL, sig);
+}
+
+void ChildActivity::migrate(Reference<class Tile> tile) {
+    Syscalls::migrate_activity(sel(), tile->sel());
+    _tile = tile;
+}
+
 int ChildActivity::wait_async(event_t event) {
     const capsel_t sels[] = {sel()};
     return Syscalls::activity_wait(sels, 1, event).first;
@@ -21,6 +21,7 @@
 #include <m3/Syscalls.h>
 #include <m3/com/GateStream.h>
 #include <m3/session/M3FS.h>
+#include <m3/session/VTerm.h>
 #include <m3/vfs/FileTable.h>
 #include <m3/vfs/GenericFile.h>
 #include <m3/vfs/VFS.h>
@@ -318,6 +319,24 @@ void GenericFile::set_tmode(TMode mode) {
     reply.pull_result();
 }
 
+File::WinSize GenericFile::get_winsize() {
+    WinSize size;
+    GateIStream reply = send_receive_vmsg(*_sg, VTerm::GET_WINSIZE, _id);
+    reply.pull_result();
+    reply >> size.cols >> size.rows;
+    return size;
+}
+
+void GenericFile::set_winsize(const WinSize &size) {
+    GateIStream reply = send_receive_vmsg(*_sg, VTerm::SET_WINSIZE, _id, size.cols, size.rows);
+    reply.pull_result();
+}
+
+void GenericFile::set_echo(bool echo) {
+    GateIStream reply = send_receive_vmsg(*_sg, VTerm::SET_ECHO, _id, echo);
+    reply.pull_result();
+}
+
 NOINLINE void GenericFile::enable_notifications() {
     if(_notify_rgate)
         return;
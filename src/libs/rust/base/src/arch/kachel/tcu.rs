@@ -533,28 +533,46 @@ impl TCU {
     #[inline(always)]
     pub fn is_valid(ep: EpId) -> bool {
         let r0 = Self::read_ep_reg(ep, 0);
-        (r0 & 0x7) != EpType::INVALID.val
+        Self::ep_type(&[r0]) != EpType::INVALID
     }
 
     /// Returns the number of credits for the given endpoint
     pub fn credits(ep: EpId) -> Result<u32, Error> {
         let r0 = Self::read_ep_reg(ep, 0);
-        if (r0 & 0x7) != EpType::SEND.val {
-            return Err(Error::new(Code::NoSEP));
+        match Self::unpack_send_credits(&[r0]) {
+            Some((cur, _)) => Ok(cur),
+            None => Err(Error::new(Code::NoSEP)),
         }
-        let cur = (r0 >> 19) & 0x3F;
-        Ok(cur as u32)
     }
 
     /// Returns true if the given endpoint is a SEND EP and has missing credits
     pub fn has_missing_credits(ep: EpId) -> bool {
         let r0 = Self::read_ep_reg(ep, 0);
-        if (r0 & 0x7) != EpType::SEND.val {
-            return false;
+        Self::has_missing_credits_regs(&[r0])
+    }
+
+    /// Returns the type of the endpoint represented by the given registers
+    pub fn ep_type(regs: &[Reg]) -> EpType {
+        EpType::from(regs[0] & 0x7)
+    }
+
+    /// Unpacks the given send EP registers into the current and maximum number of credits.
+    ///
+    /// Returns `Some((<cur>, <max>))` if the given registers represent a send EP, or `None`
+    /// otherwise.
+    pub fn unpack_send_credits(regs: &[Reg]) -> Option<(u32, u32)> {
+        if Self::ep_type(regs) != EpType::SEND {
+            return None;
         }
-        let cur = (r0 >> 19) & 0x3F;
-        let max = (r0 >> 25) & 0x3F;
-        cur < max
+
+        let cur = (regs[0] >> 19) & 0x3F;
+        let max = (regs[0] >> 25) & 0x3F;
+        Some((cur as u32, max as u32))
+    }
+
+    /// Returns true if the given registers represent a SEND EP that has missing credits
+    pub fn has_missing_credits_regs(regs: &[Reg]) -> bool {
+        matches!(Self::unpack_send_credits(regs), Some((cur, max)) if cur < max)
     }
 
     /// Unpacks the given memory EP into the tile id, address, size, and permissions.
@@ -573,7 +591,7 @@ impl TCU {
     /// Returns `Some((<tile>, <address>, <size>, <perm>))` if the given registers represent a memory
     /// EP, or `None` otherwise.
     pub fn unpack_mem_regs(regs: &[Reg]) -> Option<(TileId, u64, u64, Perm)> {
-        if (regs[0] & 0x7) != EpType::MEMORY.val {
+        if Self::ep_type(regs) != EpType::MEMORY {
             return None;
         }
 
@@ -941,6 +959,12 @@ impl TCU {
         regs[2] = lbl as Reg;
     }
 
+    /// Lets the send EP represented by the given registers refer to `tile`, keeping the
+    /// destination EP, the credits, and the label.
+    pub fn set_send_dest_tile(regs: &mut [Reg], tile: TileId) {
+        regs[1] = ((Self::tileid_to_nocid(tile) as Reg) << 16) | (regs[1] & 0xFFFF);
+    }
+
     pub fn config_mem(
         regs: &mut [Reg],
         act: ActId,
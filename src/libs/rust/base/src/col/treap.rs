@@ -124,6 +124,25 @@ impl<K: Copy + Ord, V> Treap<K, V> {
         }
     }
 
+    /// Calls `func` for all values in the treap
+    pub fn for_each<F: FnMut(&V)>(&self, mut func: F) {
+        if let Some(r) = self.root {
+            Self::for_each_rec(r, &mut func);
+        }
+    }
+
+    fn for_each_rec<F: FnMut(&V)>(node: NonNull<Node<K, V>>, func: &mut F) {
+        unsafe {
+            func(&(*node.as_ptr()).value);
+            if let Some(l) = (*node.as_ptr()).left {
+                Self::for_each_rec(l, func);
+            }
+            if let Some(r) = (*node.as_ptr()).right {
+                Self::for_each_rec(r, func);
+            }
+        }
+    }
+
     fn get_node(&self, key: &K) -> Option<NonNull<Node<K, V>>> {
         let mut node = self.root;
         loop {
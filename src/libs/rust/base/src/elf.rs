@@ -24,11 +24,48 @@ use crate::kif;
 
 const EI_NIDENT: usize = 16;
 
+/// The ELF class of the current target
+#[cfg(target_pointer_width = "64")]
+pub const ELFCLASS: u8 = 2;
+/// The ELF class of the current target
+#[cfg(target_pointer_width = "32")]
+pub const ELFCLASS: u8 = 1;
+
+/// The machine of the current target
+#[cfg(target_arch = "x86_64")]
+pub const EM: u16 = 62;
+/// The machine of the current target
+#[cfg(target_arch = "arm")]
+pub const EM: u16 = 40;
+/// The machine of the current target
+#[cfg(target_arch = "riscv64")]
+pub const EM: u16 = 243;
+
+int_enum! {
+    /// The ELF file types
+    pub struct ET : u16 {
+        /// Core file
+        const CORE = 0x4;
+    }
+}
+
 int_enum! {
     /// The program header entry types
     pub struct PT : u32 {
         /// Load segment
         const LOAD = 0x1;
+        /// Note segment
+        const NOTE = 0x4;
+    }
+}
+
+int_enum! {
+    /// The note types in core files
+    pub struct NT : u32 {
+        /// The process status including the registers
+        const PRSTATUS = 0x1;
+        /// The signal information including the fault address
+        const SIGINFO  = 0x5349_4749;
     }
 }
 
@@ -64,6 +101,15 @@ pub struct Ehdr {
     pub shstrndx: u16,
 }
 
+/// Note header
+#[derive(Default)]
+#[repr(C, packed)]
+pub struct Nhdr {
+    pub namesz: u32,
+    pub descsz: u32,
+    pub ty: u32,
+}
+
 /// Program header for 32-bit ELF files
 #[derive(Default)]
 #[repr(C, packed)]
@@ -114,3 +160,19 @@ impl From<PF> for kif::Perm {
         prot
     }
 }
+
+impl From<kif::Perm> for PF {
+    fn from(prot: kif::Perm) -> Self {
+        let mut flags = PF::empty();
+        if prot.contains(kif::Perm::R) {
+            flags |= PF::R;
+        }
+        if prot.contains(kif::Perm::W) {
+            flags |= PF::W;
+        }
+        if prot.contains(kif::Perm::X) {
+            flags |= PF::X;
+        }
+        flags
+    }
+}
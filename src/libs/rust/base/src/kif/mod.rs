@@ -25,6 +25,7 @@ mod tiledesc;
 pub mod boot;
 pub mod service;
 pub mod syscalls;
+pub mod systrace;
 pub mod tilemux;
 pub mod upcalls;
 
@@ -42,6 +43,10 @@ pub const INVALID_SEL: CapSel = 0xFFFF;
 /// Represents unlimited credits for a SendGate
 pub const UNLIM_CREDITS: u32 = tcu::UNLIM_CREDITS;
 
+/// The label bits that can be added when deriving a SendGate. The kernel refuses to create
+/// SendGates with labels that use these bits, so that derived gates cannot impersonate others.
+pub const SGATE_DERIVE_LABEL_BITS: tcu::Label = 0xFF00_0000;
+
 /// The selector for the own tile capability
 pub const SEL_TILE: CapSel = 0;
 /// The selector for the own kernel memory capability
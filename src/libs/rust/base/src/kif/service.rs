@@ -18,7 +18,7 @@
 
 //! The service interface
 
-use super::syscalls::ExchangeArgs;
+use super::syscalls::{ExchangeArgs, Expiry};
 use crate::kif::{CapRngDesc, CapSel};
 use crate::serialize::{Deserialize, Serialize};
 
@@ -62,4 +62,6 @@ pub struct DeriveCreatorReply {
 #[repr(C)]
 pub struct ExchangeReply {
     pub data: ExchangeData,
+    /// The expiry of the obtained capabilities, which is determined by the server
+    pub expiry: Expiry,
 }
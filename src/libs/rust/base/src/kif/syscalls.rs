@@ -19,7 +19,8 @@
 //! The system call interface
 
 use crate::goff;
-use crate::kif::{tilemux::QuotaId, CapRngDesc, CapSel, Perm};
+use crate::kif::tilemux::{CrashInfo, QuotaId, SchedParams, Usage};
+use crate::kif::{CapRngDesc, CapSel, Perm};
 use crate::mem::GlobAddr;
 use crate::serialize::{Deserialize, Serialize};
 use crate::tcu::{ActId, EpId, Label};
@@ -68,6 +69,11 @@ int_enum! {
         // Misc
         const RESET_STATS = 26;
         const NOOP = 27;
+        const MIGRATE_ACT = 28;
+        const DERIVE_SGATE = 29;
+        const SYSC_TRACE = 30;
+        const ACT_CRASH = 31;
+        const ACT_USAGE = 32;
     }
 }
 
@@ -134,6 +140,7 @@ pub struct CreateActivity<'s> {
     pub dst: CapSel,
     pub tile: CapSel,
     pub kmem: CapSel,
+    pub budget: SyscBudget,
     pub name: &'s str,
 }
 
@@ -173,9 +180,10 @@ pub struct SetPMP {
 int_enum! {
     /// The operations for the `act_ctrl` system call
     pub struct ActivityOp : u64 {
-        const INIT  = 0x0;
-        const START = 0x1;
-        const STOP  = 0x2;
+        const INIT   = 0x0;
+        const START  = 0x1;
+        const STOP   = 0x2;
+        const SIGNAL = 0x3;
     }
 }
 
@@ -204,6 +212,7 @@ pub struct DeriveMem {
     pub offset: goff,
     pub size: goff,
     pub perms: Perm,
+    pub expiry: Expiry,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -222,6 +231,16 @@ pub struct DeriveTile {
     pub eps: Option<u32>,
     pub time: Option<u64>,
     pub pts: Option<usize>,
+    pub sched: SchedParams,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct DeriveSGate {
+    pub sgate: CapSel,
+    pub dst: CapSel,
+    pub label: Label,
+    pub credits: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -290,6 +309,49 @@ pub struct ExchangeArgs {
     pub data: [u64; 8],
 }
 
+/// The optional expiry of capabilities that are created by exchanges and derivations
+///
+/// If `duration` is not `None`, the kernel revokes the created capabilities after `duration`
+/// nanoseconds. Afterwards, the activity that requested the expiry receives an upcall with the
+/// given event, unless `event` is zero.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Expiry {
+    pub duration: Option<u64>,
+    pub event: u64,
+}
+
+impl Expiry {
+    /// Creates an expiry after `duration` nanoseconds with an upcall for `event`
+    pub fn new(duration: u64, event: u64) -> Self {
+        Self {
+            duration: Some(duration),
+            event,
+        }
+    }
+}
+
+/// The system call budget of an activity
+///
+/// If `syscalls` is not `None`, the activity can perform at most `syscalls` system calls within
+/// each period of `period` nanoseconds. Further system calls are delayed until the next period.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SyscBudget {
+    pub syscalls: Option<u64>,
+    pub period: u64,
+}
+
+impl SyscBudget {
+    /// Creates a budget of `syscalls` system calls per `period` nanoseconds
+    pub fn new(syscalls: u64, period: u64) -> Self {
+        Self {
+            syscalls: Some(syscalls),
+            period,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct ExchangeSess {
@@ -298,6 +360,7 @@ pub struct ExchangeSess {
     pub crd: CapRngDesc,
     pub args: ExchangeArgs,
     pub obtain: bool,
+    pub expiry: Expiry,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -307,6 +370,7 @@ pub struct Exchange {
     pub own: CapRngDesc,
     pub other: CapSel,
     pub obtain: bool,
+    pub expiry: Expiry,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -317,6 +381,31 @@ pub struct Revoke {
     pub own: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct SyscTrace {
+    pub dst: CapSel,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActivityCrash {
+    pub act: CapSel,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActivityUsage {
+    pub act: CapSel,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct MigrateActivity {
+    pub act: CapSel,
+    pub tile: CapSel,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct ResetStats {}
@@ -348,6 +437,20 @@ pub struct ActivityWaitReply {
     pub exitcode: i32,
 }
 
+/// The activity crash reply message
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActivityCrashReply {
+    pub info: CrashInfo,
+}
+
+/// The activity usage reply message
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActivityUsageReply {
+    pub usage: Usage,
+}
+
 /// The kernel gate region reply message
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
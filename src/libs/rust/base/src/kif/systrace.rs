@@ -0,0 +1,57 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! The layout of the kernel's system call trace
+//!
+//! The trace consists of a [`Header`], followed by [`ENTRIES`] times an [`Entry`]. The kernel
+//! records the n-th system call (starting at 1) into the entry at index `n % ENTRIES` with
+//! `Entry::seq` set to n. Thus, the trace always contains the last `min(n, ENTRIES)` system calls,
+//! which can be ordered by their sequence number, and each system call requires a single write.
+
+use crate::mem::size_of;
+
+/// The number of entries in the ring buffer
+pub const ENTRIES: usize = 1024;
+
+/// The total size of the trace in bytes
+pub const SIZE: usize = size_of::<Header>() + ENTRIES * size_of::<Entry>();
+
+/// The header of the trace
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C)]
+pub struct Header {
+    /// The number of entries in the ring buffer
+    pub entries: u64,
+}
+
+/// A recorded system call
+#[derive(Copy, Clone, Debug, Default)]
+#[repr(C)]
+pub struct Entry {
+    /// The sequence number of the system call (0 for unused entries)
+    pub seq: u64,
+    /// The id of the calling activity
+    pub act: u64,
+    /// The system call operation
+    pub op: u64,
+    /// The first two arguments of the system call
+    pub args: [u64; 2],
+    /// The resulting error code
+    pub error: u64,
+    /// The start time in nanoseconds
+    pub start: u64,
+    /// The duration in nanoseconds
+    pub duration: u64,
+}
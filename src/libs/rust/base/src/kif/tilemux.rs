@@ -47,6 +47,10 @@ int_enum! {
         const SET_QUOTA      = 0x8;
         const REMOVE_QUOTAS  = 0x9;
         const RESET_STATS    = 0xA;
+        const ACT_SUSPEND    = 0xB;
+        const ACT_RESUME     = 0xC;
+        const ACT_SIGNAL     = 0xD;
+        const ACT_USAGE      = 0xE;
     }
 }
 
@@ -58,6 +62,69 @@ int_enum! {
     }
 }
 
+int_enum! {
+    /// The scheduling classes of TileMux
+    pub struct SchedClass : u64 {
+        /// Keep the class of the parent quota (only valid for derivations)
+        const INHERIT = 0x0;
+        /// Round-robin scheduling with time slices
+        const RR      = 0x1;
+        /// Fixed priorities; higher priorities are preferred over lower ones and over `RR`
+        const PRIO    = 0x2;
+        /// Earliest deadline first with a reserved budget per period; preferred over all others
+        const EDF     = 0x3;
+    }
+}
+
+/// The maximum priority for [`SchedClass::PRIO`]
+pub const MAX_PRIO: u64 = 255;
+
+/// The scheduling parameters of a time quota
+///
+/// `prio` is only used by [`SchedClass::PRIO`], whereas `period` and `budget` (both in
+/// nanoseconds) are only used by [`SchedClass::EDF`]. Activities that have consumed their budget
+/// within the current period are scheduled like `RR` activities until the next period starts.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[repr(C)]
+pub struct SchedParams {
+    pub class: SchedClass,
+    pub prio: u64,
+    pub period: u64,
+    pub budget: u64,
+}
+
+impl SchedParams {
+    /// Creates parameters for round-robin scheduling
+    pub fn new_rr() -> Self {
+        Self::new(SchedClass::RR, 0, 0, 0)
+    }
+
+    /// Creates parameters for fixed-priority scheduling with given priority
+    pub fn new_prio(prio: u64) -> Self {
+        Self::new(SchedClass::PRIO, prio, 0, 0)
+    }
+
+    /// Creates parameters for EDF scheduling with `budget` nanoseconds every `period` nanoseconds
+    pub fn new_edf(period: u64, budget: u64) -> Self {
+        Self::new(SchedClass::EDF, 0, period, budget)
+    }
+
+    fn new(class: SchedClass, prio: u64, period: u64, budget: u64) -> Self {
+        Self {
+            class,
+            prio,
+            period,
+            budget,
+        }
+    }
+}
+
+impl Default for SchedParams {
+    fn default() -> Self {
+        Self::new(SchedClass::INHERIT, 0, 0, 0)
+    }
+}
+
 /// The activity init sidecall
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
@@ -76,6 +143,35 @@ pub struct ActivityCtrl {
     pub act_op: ActivityOp,
 }
 
+/// The activity suspend sidecall
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActSuspend {
+    pub act_id: u64,
+}
+
+/// The activity resume sidecall
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActResume {
+    pub act_id: u64,
+}
+
+/// The activity signal sidecall
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActSignal {
+    pub act_id: u64,
+    pub signal: u32,
+}
+
+/// The activity usage sidecall
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct ActUsage {
+    pub act_id: u64,
+}
+
 /// The map sidecall
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
@@ -120,6 +216,9 @@ pub struct DeriveQuota {
     pub parent_pts: QuotaId,
     pub time: Option<u64>,
     pub pts: Option<usize>,
+    pub sched: SchedParams,
+    /// whether the derivation is done via a root tile capability
+    pub privileged: bool,
 }
 
 /// The get quota sidecall
@@ -168,9 +267,56 @@ int_enum! {
 }
 
 /// The exit call
+///
+/// If the activity crashed, `crash` holds the global address of the [`CrashInfo`] within TileMux'
+/// memory, which stays valid until the activity is initialized again. Otherwise, `crash` is 0.
 #[derive(Debug, Serialize, Deserialize)]
 #[repr(C)]
 pub struct Exit {
     pub act_id: ActId,
     pub status: i32,
+    pub crash: u64,
+}
+
+/// The maximum number of registers in [`CrashInfo`]
+pub const CRASH_REGS: usize = 32;
+
+/// The signal number for crashes due to illegal instructions
+pub const SIGILL: u64 = 4;
+/// The signal number for crashes due to breakpoints
+pub const SIGTRAP: u64 = 5;
+/// The signal number for crashes due to other exceptions
+pub const SIGBUS: u64 = 7;
+/// The signal number for crashes due to arithmetic errors
+pub const SIGFPE: u64 = 8;
+/// The signal number for crashes due to page faults that could not be resolved
+pub const SIGSEGV: u64 = 11;
+
+/// The resources an activity has used so far
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[repr(C)]
+pub struct Usage {
+    /// The consumed CPU time in nanoseconds
+    pub cpu_time: u64,
+    /// The number of times the activity has been switched away from
+    pub ctxsws: u64,
+    /// The number of page faults that have been handled for the activity
+    pub pagefaults: u64,
+    /// The number of messages the activity has received
+    pub msgs: u64,
+}
+
+/// The state of an activity at the time it crashed
+///
+/// The registers are stored in the order that debuggers expect in the `NT_PRSTATUS` note of ELF
+/// core files for the respective architecture. The signal uses the POSIX numbers (see
+/// [`SIGSEGV`] etc.) to describe the crash to debuggers.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[repr(C)]
+pub struct CrashInfo {
+    pub signal: u64,
+    pub cause: u64,
+    pub fault_addr: u64,
+    pub reg_count: u64,
+    pub regs: [u64; CRASH_REGS],
 }
@@ -30,6 +30,9 @@ int_enum! {
 
         /// waits for activity exits
         const ACT_WAIT          = 1;
+
+        /// expiries of exchanged or derived capabilities
+        const CAP_EXPIRED       = 2;
     }
 }
 
@@ -50,3 +53,11 @@ pub struct DeriveSrv {
     pub event: u64,
     pub error: Code,
 }
+
+/// The cap-expired upcall that is sent after the kernel revoked expired capabilities
+#[derive(Debug, Serialize, Deserialize)]
+#[repr(C)]
+pub struct CapExpired {
+    pub event: u64,
+    pub error: Code,
+}
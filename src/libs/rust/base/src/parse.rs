@@ -38,7 +38,7 @@ pub fn addr(s: &str) -> Result<goff, Error> {
 /// respectively.
 pub fn size(s: &str) -> Result<usize, Error> {
     let mul = match s.chars().last() {
-        Some(c) if c >= '0' && c <= '9' => 1,
+        Some(c) if c.is_ascii_digit() => 1,
         Some('k') | Some('K') => 1024,
         Some('m') | Some('M') => 1024 * 1024,
         Some('g') | Some('G') => 1024 * 1024 * 1024,
@@ -76,6 +76,19 @@ pub fn time(s: &str) -> Result<u64, Error> {
     })
 }
 
+/// Parses a rate in the form `<count>/<time>` from the given string
+///
+/// The time is parsed via [`time`], but the number can be omitted to denote one unit (e.g.,
+/// "1000/ms"). Returns the count and the time in nanoseconds.
+pub fn rate(s: &str) -> Result<(u64, u64), Error> {
+    let (count, per) = s.split_once('/').ok_or_else(|| Error::new(Code::InvArgs))?;
+    let per = match per.chars().next() {
+        Some(c) if c.is_ascii_digit() => time(per)?,
+        _ => time(&crate::format!("1{}", per))?,
+    };
+    Ok((int(count)?, per))
+}
+
 /// Parses a u64 from the given string
 pub fn int(s: &str) -> Result<u64, Error> {
     s.parse::<u64>().map_err(|_| Error::new(Code::InvArgs))
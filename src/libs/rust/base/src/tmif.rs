@@ -45,6 +45,37 @@ int_enum! {
         const FLUSH_INV     = 0x6;
         /// Noop operation for testing purposes
         const NOOP          = 0x7;
+        /// Register the signal handler entry point and the handled signals
+        const SIG_ACTION    = 0x8;
+        /// Return from a signal handler to the interrupted context
+        const SIG_RETURN    = 0x9;
+        /// Deliver `Signal::ALRM` after a given time
+        const ALARM         = 0xA;
+    }
+}
+
+/// The number of supported signals
+pub const SIG_COUNT: u32 = 32;
+
+int_enum! {
+    /// The asynchronous signals that can be delivered to activities
+    ///
+    /// If an activity has not registered a handler for a signal, it is terminated with exit code
+    /// 128 + signal on its delivery. The only exception is [`Signal::SERV_RESTART`], which is
+    /// ignored in this case.
+    pub struct Signal : u32 {
+        /// Interrupt (e.g., Ctrl+C in vterm)
+        const INT           = 2;
+        /// User-defined signal 1
+        const USR1          = 10;
+        /// User-defined signal 2
+        const USR2          = 12;
+        /// Timer alarm, requested via [`alarm`]
+        const ALRM          = 14;
+        /// Termination request
+        const TERM          = 15;
+        /// A service the activity had a session with has been restarted and the session is gone
+        const SERV_RESTART  = 16;
     }
 }
 
@@ -103,6 +134,28 @@ pub fn switch_activity() -> Result<(), Error> {
     tmabi::call1(Operation::YIELD, 0).map(|_| ())
 }
 
+/// Registers `entry` as the entry point for signals in `mask` (bit n for signal n).
+///
+/// TileMux calls `entry` with the signal number as the only argument on the interrupted stack.
+/// The entry point has to call [`sig_return`] afterwards to continue the interrupted code.
+pub fn sig_action(entry: usize, mask: u32) -> Result<(), Error> {
+    tmabi::call2(Operation::SIG_ACTION, entry, mask as usize).map(|_| ())
+}
+
+/// Returns from a signal handler to the interrupted context. Only returns on errors.
+pub fn sig_return() -> Result<(), Error> {
+    tmabi::call1(Operation::SIG_RETURN, 0).map(|_| ())
+}
+
+/// Delivers `Signal::ALRM` after `duration` or cancels a pending alarm if `duration` is `None`.
+pub fn alarm(duration: Option<TimeDuration>) -> Result<(), Error> {
+    tmabi::call1(Operation::ALARM, match duration {
+        Some(d) => d.as_nanos() as usize,
+        None => usize::MAX,
+    })
+    .map(|_| ())
+}
+
 #[inline(always)]
 pub fn noop() -> Result<(), Error> {
     tmabi::call1(Operation::NOOP, 0).map(|_| ())
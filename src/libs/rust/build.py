@@ -6,6 +6,7 @@ dirs = [
     'paging',
     'pci',
     'resmng',
+    'resmngcfg',
     'thread',
 ]
 
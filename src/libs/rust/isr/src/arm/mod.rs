@@ -35,7 +35,7 @@ pub const TMC_ARG2: usize = 2; // r2
 pub const TMC_ARG3: usize = 3; // r3
 pub const TMC_ARG4: usize = 4; // r4
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 // for some reason, we need to specify the alignment here. actually, this struct needs to be packed,
 // but unfortunately, we cannot specify both packed and align. but without packed seems to be fine,
 // because there are no holes between the fields.
@@ -32,7 +32,7 @@ pub const TMC_ARG2: usize = 11; // a2 = x12
 pub const TMC_ARG3: usize = 12; // a3 = x13
 pub const TMC_ARG4: usize = 13; // a4 = x14
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 // see comment in ARM code
 #[repr(C, align(8))]
 pub struct State {
@@ -57,7 +57,7 @@ int_enum! {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 // see comment in ARM code
 #[repr(C, align(16))]
 pub struct State {
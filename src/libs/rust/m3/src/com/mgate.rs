@@ -27,7 +27,7 @@ use crate::com::ep::EP;
 use crate::com::gate::Gate;
 use crate::errors::Error;
 use crate::goff;
-use crate::kif::INVALID_SEL;
+use crate::kif::{syscalls::Expiry, INVALID_SEL};
 use crate::mem::{self, MaybeUninit};
 use crate::syscalls;
 use crate::tcu;
@@ -167,7 +167,21 @@ impl MemGate {
         size: usize,
         perm: Perm,
     ) -> Result<Self, Error> {
-        syscalls::derive_mem(act, sel, self.sel(), offset, size as goff, perm)?;
+        self.derive_for_with_expiry(act, sel, offset, size, perm, Expiry::default())
+    }
+
+    /// Like [`MemGate::derive_for`], but lets the kernel revoke the derived `MemGate` according to
+    /// `expiry`.
+    pub fn derive_for_with_expiry(
+        &self,
+        act: Selector,
+        sel: Selector,
+        offset: goff,
+        size: usize,
+        perm: Perm,
+        expiry: Expiry,
+    ) -> Result<Self, Error> {
+        syscalls::derive_mem_with_expiry(act, sel, self.sel(), offset, size as goff, perm, expiry)?;
         Ok(MemGate {
             gate: Gate::new(sel, CapFlags::empty()),
             resmng: false,
@@ -125,6 +125,22 @@ impl SendGate {
         }
     }
 
+    /// Derives a new `SendGate` from `self` that has `credits` credits and the label of `self`
+    /// with the additional bits in `label` set.
+    ///
+    /// Only the bits in [`kif::SGATE_DERIVE_LABEL_BITS`](crate::kif::SGATE_DERIVE_LABEL_BITS) that
+    /// are not set in the label of `self` can be added. The credits are moved from `self` to the
+    /// derived `SendGate` until it is revoked. Therefore, `self` needs to have enough credits left
+    /// and cannot be activated yet (unless it has unlimited credits).
+    /// The derived `SendGate` is a child of `self` and is therefore revoked together with `self`.
+    pub fn derive(&self, label: tcu::Label, credits: u32) -> Result<Self, Error> {
+        let sel = Activity::own().alloc_sel();
+        syscalls::derive_sgate(self.sel(), sel, label, credits)?;
+        Ok(SendGate {
+            gate: Gate::new(sel, CapFlags::empty()),
+        })
+    }
+
     /// Returns the capability selector.
     pub fn sel(&self) -> Selector {
         self.gate.sel()
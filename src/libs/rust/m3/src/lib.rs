@@ -47,6 +47,7 @@ pub mod cap;
 pub mod crypto;
 pub mod server;
 pub mod session;
+pub mod signal;
 pub mod syscalls;
 #[macro_use]
 pub mod test;
@@ -23,6 +23,7 @@ use crate::com::{GateIStream, RecvGate};
 use crate::errors::{Code, Error};
 use crate::kif::{
     service::{DeriveCreatorReply, ExchangeData, ExchangeReply, OpenReply, Request},
+    syscalls::Expiry,
     CapRngDesc,
 };
 use crate::llog;
@@ -45,6 +46,7 @@ pub struct CapExchange<'d> {
     sink: M3Serializer<SliceSink<'d>>,
     input: &'d ExchangeData,
     out_crd: CapRngDesc,
+    out_expiry: Expiry,
 }
 
 impl<'d> CapExchange<'d> {
@@ -57,6 +59,7 @@ impl<'d> CapExchange<'d> {
             sink: M3Serializer::new(SliceSink::new(&mut output.args.data)),
             input,
             out_crd: CapRngDesc::default(),
+            out_expiry: Expiry::default(),
         }
     }
 
@@ -79,6 +82,12 @@ impl<'d> CapExchange<'d> {
     pub fn out_caps(&mut self, crd: CapRngDesc) {
         self.out_crd = crd;
     }
+
+    /// Lets the kernel revoke the output capabilities according to `expiry`. This is only
+    /// supported for obtains, because the client determines the expiry of delegated capabilities.
+    pub fn out_expiry(&mut self, expiry: Expiry) {
+        self.out_expiry = expiry;
+    }
 }
 
 impl<'d> fmt::Debug for CapExchange<'d> {
@@ -183,6 +192,19 @@ impl Server {
         &self.rgate
     }
 
+    /// Tells the resource manager that the service is ready to be used.
+    ///
+    /// This is only required if the service is configured with `ready="signal"`, in which case the
+    /// resource manager starts the service's dependents not before this call.
+    pub fn set_ready(&self) -> Result<(), Error> {
+        if self.public {
+            Activity::own().resmng().unwrap().serv_ready(self.sel())
+        }
+        else {
+            Ok(())
+        }
+    }
+
     /// Fetches a message from the control channel and handles it if so.
     pub fn handle_ctrl_chan<S>(&self, hdl: &mut dyn Handler<S>) -> Result<(), Error> {
         if let Some(msg) = self.rgate.fetch() {
@@ -285,7 +307,7 @@ impl Server {
 
         let mut reply = ExchangeReply::default();
 
-        let (res, args_size, crd) = {
+        let (res, args_size, crd, expiry) = {
             let mut xchg = CapExchange::new(data, &mut reply.data);
 
             let res = hdl.obtain(crt, sid, &mut xchg);
@@ -299,12 +321,13 @@ impl Server {
                 res
             );
 
-            (res, xchg.out_args().size(), xchg.out_crd)
+            (res, xchg.out_args().size(), xchg.out_crd, xchg.out_expiry)
         };
 
         let res = res.err().map(|e| e.code()).unwrap_or(Code::None);
         reply.data.args.bytes = args_size;
         reply.data.caps = crd;
+        reply.expiry = expiry;
         reply_vmsg!(is, res, reply)
     }
 
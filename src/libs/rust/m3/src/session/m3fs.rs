@@ -24,12 +24,12 @@ use crate::cap::Selector;
 use crate::cell::RefCell;
 use crate::col::Vec;
 use crate::com::{recv_result, RecvGate, SendGate, EP};
-use crate::errors::Error;
+use crate::errors::{Code, Error};
 use crate::goff;
 use crate::kif;
 use crate::rc::Rc;
 use crate::serialize::{M3Deserializer, M3Serializer, VecSink};
-use crate::session::ClientSession;
+use crate::session::{ClientSession, Pipes};
 use crate::tiles::{Activity, ChildActivity};
 use crate::vfs::{
     FSHandle, FSOperation, File, FileInfo, FileMode, FileSystem, GenericFile, OpenFlags,
@@ -47,6 +47,7 @@ pub struct M3FS {
     sess: ClientSession,
     sgate: Rc<SendGate>,
     eps: Vec<CachedEP>,
+    pipes: Option<Pipes>,
 }
 
 impl M3FS {
@@ -56,6 +57,7 @@ impl M3FS {
             sess,
             sgate: Rc::new(sgate),
             eps: Vec::new(),
+            pipes: None,
         }))
     }
 
@@ -107,6 +109,21 @@ impl M3FS {
         )?;
         Ok((offset, len, crd.start()))
     }
+
+    /// Writes back the `len` bytes at `off` that have been changed via memory capabilities
+    /// obtained by [`M3FS::get_mem`].
+    pub fn commit_mem(sess: &ClientSession, off: goff, len: goff) -> Result<(), Error> {
+        sess.obtain(
+            0,
+            |os| {
+                os.push(FSOperation::COMMIT_MEM);
+                os.push(off);
+                os.push(len);
+            },
+            |_| Ok(()),
+        )
+        .map(|_| ())
+    }
 }
 
 impl FileSystem for M3FS {
@@ -119,46 +136,10 @@ impl FileSystem for M3FS {
     }
 
     fn open(&mut self, path: &str, flags: OpenFlags) -> Result<Box<dyn File>, Error> {
-        if !flags.contains(OpenFlags::NEW_SESS) {
-            let ep_idx = self.get_ep()?;
-
-            let mut reply = send_recv_res!(
-                &self.sgate,
-                RecvGate::def(),
-                FSOperation::OPEN_PRIV,
-                path,
-                u64::from(flags.bits()),
-                self.eps[ep_idx].id
-            )?;
-            let file_id: usize = reply.pop()?;
-
-            // mark ep as in-use
-            self.eps[ep_idx].file = Some(file_id);
-
-            Ok(Box::new(GenericFile::new_without_sess(
-                flags,
-                self.sess.sel(),
-                file_id,
-                self.id(),
-                self.eps[ep_idx].ep.id(),
-                self.sgate.clone(),
-            )))
-        }
-        else {
-            let crd = self.sess.obtain(
-                2,
-                |os| {
-                    os.push(FSOperation::OPEN);
-                    os.push(flags);
-                    os.push(path);
-                },
-                |_| Ok(()),
-            )?;
-            Ok(Box::new(GenericFile::new(
-                flags,
-                crd.start(),
-                Some(self.id()),
-            )))
+        match self.open_file(path, flags) {
+            // m3fs refuses to open FIFOs, because their data is exchanged via the pipes server
+            Err(e) if e.code() == Code::NotSup => self.open_fifo(path, flags),
+            res => res,
         }
     }
 
@@ -188,6 +169,17 @@ impl FileSystem for M3FS {
         .map(|_| ())
     }
 
+    fn mkfifo(&self, path: &str, mode: FileMode) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            FSOperation::MKFIFO,
+            path,
+            mode.bits()
+        )
+        .map(|_| ())
+    }
+
     fn rmdir(&self, path: &str) -> Result<(), Error> {
         send_recv_res!(&self.sgate, RecvGate::def(), FSOperation::RMDIR, path).map(|_| ())
     }
@@ -243,6 +235,74 @@ impl FileSystem for M3FS {
 }
 
 impl M3FS {
+    fn open_file(&mut self, path: &str, flags: OpenFlags) -> Result<Box<dyn File>, Error> {
+        if !flags.contains(OpenFlags::NEW_SESS) {
+            let ep_idx = self.get_ep()?;
+
+            let mut reply = send_recv_res!(
+                &self.sgate,
+                RecvGate::def(),
+                FSOperation::OPEN_PRIV,
+                path,
+                u64::from(flags.bits()),
+                self.eps[ep_idx].id
+            )?;
+            let file_id: usize = reply.pop()?;
+
+            // mark ep as in-use
+            self.eps[ep_idx].file = Some(file_id);
+
+            Ok(Box::new(GenericFile::new_without_sess(
+                flags,
+                self.sess.sel(),
+                file_id,
+                self.id(),
+                self.eps[ep_idx].ep.id(),
+                self.sgate.clone(),
+            )))
+        }
+        else {
+            let crd = self.sess.obtain(
+                2,
+                |os| {
+                    os.push(FSOperation::OPEN);
+                    os.push(flags);
+                    os.push(path);
+                },
+                |_| Ok(()),
+            )?;
+            Ok(Box::new(GenericFile::new(
+                flags,
+                crd.start(),
+                Some(self.id()),
+            )))
+        }
+    }
+
+    fn open_fifo(&mut self, path: &str, flags: OpenFlags) -> Result<Box<dyn File>, Error> {
+        // FIFOs are uni-directional, so that we need to decide for one side
+        let read = match flags & OpenFlags::RW {
+            OpenFlags::R => true,
+            OpenFlags::W => false,
+            _ => return Err(Error::new(Code::InvArgs)),
+        };
+
+        // m3fs checks our permissions and lets the pipes server accept the open
+        let mut reply = send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            FSOperation::OPEN_FIFO,
+            path,
+            u64::from(flags.bits())
+        )?;
+        let token: u64 = reply.pop()?;
+
+        if self.pipes.is_none() {
+            self.pipes = Some(Pipes::new("pipes")?);
+        }
+        self.pipes.as_ref().unwrap().open_named(token, read)
+    }
+
     fn get_ep(&mut self) -> Result<usize, Error> {
         for (i, ep) in self.eps.iter_mut().enumerate() {
             if ep.file.is_none() {
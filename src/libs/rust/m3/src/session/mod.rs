@@ -27,13 +27,21 @@ mod pager;
 mod pipe;
 mod resmng;
 mod srvsession;
+mod vterm;
 
 pub use self::clisession::ClientSession;
 pub use self::disk::{BlockNo, BlockRange, Disk, DiskOperation};
 pub use self::hash::{HashInput, HashOp, HashOutput, HashSession};
 pub use self::m3fs::M3FS;
 pub use self::netmng::{NetworkManager, NetworkOp};
-pub use self::pager::{MapFlags, Pager, PagerOp};
-pub use self::pipe::{Pipe, PipeOperation, Pipes};
-pub use self::resmng::{ResMng, ResMngActInfo, ResMngActInfoResult, ResMngOperation};
+pub use self::pager::{
+    Advice, MapFlags, MapStats, Pager, PagerAddrSpaceInfo, PagerInfoResult, PagerMappingInfo,
+    PagerMemKind, PagerOp, ShmFlags, SwapStats,
+};
+pub use self::pipe::{Pipe, PipeFlags, PipeOperation, Pipes};
+pub use self::resmng::{
+    ResMng, ResMngActInfo, ResMngActInfoResult, ResMngChildInfo, ResMngOperation, ResMngQuotaInfo,
+    ResMngQuotaKind, ResMngQuotaResult,
+};
 pub use self::srvsession::ServerSession;
+pub use self::vterm::{VTerm, VTermOperation};
@@ -20,6 +20,7 @@ use bitflags::bitflags;
 use core::fmt;
 
 use crate::cap;
+use crate::col::String;
 use crate::com::{MemGate, RGateArgs, RecvGate, SendGate};
 use crate::errors::Error;
 use crate::goff;
@@ -46,25 +47,59 @@ int_enum! {
     /// The pager's operations
     pub struct PagerOp : u32 {
         /// A page fault
-        const PAGEFAULT = 0x0;
+        const PAGEFAULT  = 0x0;
         /// Initializes the pager session
-        const INIT      = 0x1;
+        const INIT       = 0x1;
         /// Adds a child activity to the pager session
-        const ADD_CHILD = 0x2;
+        const ADD_CHILD  = 0x2;
         /// Adds a new send gate to the pager session
-        const ADD_SGATE = 0x3;
+        const ADD_SGATE  = 0x3;
         /// Clone the address space of a child activity (see `ADD_CHILD`) from the parent
-        const CLONE     = 0x4;
+        const CLONE      = 0x4;
         /// Add a new mapping with anonymous memory
-        const MAP_ANON  = 0x5;
+        const MAP_ANON   = 0x5;
         /// Add a new data space mapping (e.g., a file)
-        const MAP_DS    = 0x6;
+        const MAP_DS     = 0x6;
         /// Add a new mapping for a given memory capability
-        const MAP_MEM   = 0x7;
+        const MAP_MEM    = 0x7;
         /// Remove an existing mapping
-        const UNMAP     = 0x8;
+        const UNMAP      = 0x8;
         /// Close the pager session
-        const CLOSE     = 0x9;
+        const CLOSE      = 0x9;
+        /// Change the permissions of an existing range
+        const PROTECT    = 0xA;
+        /// Give a hint about the usage of an existing range
+        const ADVISE     = 0xB;
+        /// Write back the changes in a shared file mapping
+        const SYNC       = 0xC;
+        /// Open or create a named shared-memory segment
+        const SHM_OPEN   = 0xD;
+        /// Change the size of a shared-memory segment
+        const SHM_RESIZE = 0xE;
+        /// Close a shared-memory segment
+        const SHM_CLOSE  = 0xF;
+        /// Add a new mapping for a shared-memory segment
+        const MAP_SHM    = 0x10;
+        /// Get the number of mapped large and small pages
+        const MAP_STATS  = 0x11;
+        /// Get information about the address spaces and their mappings
+        const GET_INFO   = 0x12;
+        /// Get the statistics about swapping
+        const SWAP_STATS = 0x13;
+    }
+}
+
+int_enum! {
+    /// The hints for `Pager::advise`
+    pub struct Advice : u32 {
+        /// No special treatment
+        const NORMAL     = 0x0;
+        /// The range will be accessed sequentially; read ahead on page faults
+        const SEQUENTIAL = 0x2;
+        /// The range will be accessed soon; fault it in right away
+        const WILLNEED   = 0x3;
+        /// The range is not needed anymore; drop the pages and zero them on the next access
+        const DONTNEED   = 0x4;
     }
 }
 
@@ -84,6 +119,106 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// The flags for `Pager::open_shm`
+    pub struct ShmFlags : u32 {
+        /// Create the segment, if it does not exist
+        const CREATE = 0x1;
+        /// Fail if the segment exists already (only together with `CREATE`)
+        const EXCL   = 0x2;
+    }
+}
+
+/// The number of pages that are currently mapped in an address space
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct MapStats {
+    /// The number of large pages
+    pub large_pages: usize,
+    /// The number of small pages
+    pub small_pages: usize,
+}
+
+/// The statistics about swapping, which cover all address spaces of the pager
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct SwapStats {
+    /// The number of regions that have been swapped out
+    pub swap_outs: u64,
+    /// The number of regions that have been swapped in
+    pub swap_ins: u64,
+    /// The number of pages written to the swap file
+    pub pages_out: u64,
+    /// The number of swapped out pages that were clean and therefore not written
+    pub pages_clean: u64,
+    /// The number of pages read from the swap file
+    pub pages_in: u64,
+    /// The number of regions that got a second chance during eviction
+    pub second_chances: u64,
+}
+
+/// The kind of memory behind a mapping
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub enum PagerMemKind {
+    /// Anonymous memory (see `Pager::map_anon`)
+    Anon,
+    /// A memory capability (see `Pager::map_mem`)
+    Mem,
+    /// A file or other dataspace (see `Pager::map_ds`)
+    File,
+    /// A shared-memory segment (see `Pager::map_shm`)
+    Shm,
+}
+
+/// Information about an address space of the pager
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct PagerAddrSpaceInfo {
+    /// The id of the address space
+    pub id: usize,
+    /// The id of the address space it is cloned from, if any
+    pub parent: Option<usize>,
+    /// The name of the child the address space belongs to
+    pub name: String,
+    /// The number of mappings
+    pub mappings: usize,
+    /// The number of bytes of physical memory that is accounted to the address space
+    pub charged: usize,
+}
+
+/// Information about a mapping in an address space of the pager
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct PagerMappingInfo {
+    pub virt: goff,
+    pub size: usize,
+    /// The permissions the mapping has been created with
+    pub perm: kif::Perm,
+    /// The current permissions (see `Pager::protect`)
+    pub prot: kif::Perm,
+    pub flags: MapFlags,
+    pub kind: PagerMemKind,
+    /// The offset within the file or segment
+    pub offset: goff,
+    /// The name of the segment (empty for other kinds)
+    pub name: String,
+    /// The number of pages that are in memory
+    pub resident: usize,
+    /// The number of pages that are swapped out
+    pub swapped: usize,
+    /// The number of resident pages that are shared copy-on-write with other address spaces
+    pub cow: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub enum PagerInfoResult {
+    Count(usize),
+    AddrSpace(PagerAddrSpaceInfo),
+    Mapping(PagerMappingInfo),
+}
+
 impl Pager {
     fn get_sgate(sess: &ClientSession) -> Result<cap::Selector, Error> {
         sess.obtain(1, |os| os.push(PagerOp::ADD_SGATE), |_| Ok(()))
@@ -217,6 +352,9 @@ impl Pager {
 
     /// Maps a dataspace of `len` bytes handled by given session to virtual address `addr` with
     /// permissions `prot`.
+    ///
+    /// Accesses behind the end of a file that has been opened for writing extend the file by
+    /// whole blocks.
     pub fn map_ds(
         &self,
         addr: goff,
@@ -247,6 +385,10 @@ impl Pager {
         Ok(res)
     }
 
+    /// Maps the first `len` bytes of `mem` at virtual address `addr` with permissions `prot`.
+    ///
+    /// The memory is mapped with large pages as far as possible, which requires that `addr` has
+    /// the same offset within a large page as the physical address of `mem`.
     pub fn map_mem(
         &self,
         addr: goff,
@@ -277,6 +419,175 @@ impl Pager {
     pub fn unmap(&self, addr: goff) -> Result<(), Error> {
         send_recv_res!(&self.req_sgate, RecvGate::def(), PagerOp::UNMAP, addr).map(|_| ())
     }
+
+    /// Changes the permissions of the `len` bytes at virtual address `addr` to `prot`.
+    ///
+    /// The range has to be page aligned and fully mapped. The permissions cannot exceed the ones
+    /// the mappings have been created with. Empty permissions turn the range into guard pages.
+    pub fn protect(&self, addr: goff, len: usize, prot: kif::Perm) -> Result<(), Error> {
+        send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::PROTECT,
+            addr,
+            len,
+            prot.bits()
+        )
+        .map(|_| ())
+    }
+
+    /// Writes back the changes to shared file mappings in the `len` bytes at virtual address
+    /// `addr` to the file.
+    ///
+    /// The range has to be page aligned and fully mapped. Note that changes are also written back
+    /// if the mapping is removed.
+    pub fn sync(&self, addr: goff, len: usize) -> Result<(), Error> {
+        send_recv_res!(&self.req_sgate, RecvGate::def(), PagerOp::SYNC, addr, len).map(|_| ())
+    }
+
+    /// Opens the shared-memory segment with given name and returns its id and its current size.
+    ///
+    /// The name has to be granted via `<shm>` in the configuration of the activity, which maps it
+    /// to the global name of the segment. With `ShmFlags::CREATE`, the segment is created with a
+    /// size of 0, if it does not exist. The segment is available to all activities of this pager
+    /// that have access to it, is charged to the creator, and is destroyed as soon as it is neither
+    /// open nor mapped anymore.
+    pub fn open_shm(&self, name: &str, flags: ShmFlags) -> Result<(u64, usize), Error> {
+        let mut reply = send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::SHM_OPEN,
+            name,
+            flags.bits()
+        )?;
+        Ok((reply.pop()?, reply.pop()?))
+    }
+
+    /// Changes the size of the shared-memory segment with given id to `size` bytes.
+    ///
+    /// Shrinking the segment drops its content behind the new end and accesses to existing
+    /// mappings behind the new end fail. Growing the segment adds zeroed memory.
+    pub fn resize_shm(&self, id: u64, size: usize) -> Result<(), Error> {
+        send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::SHM_RESIZE,
+            id,
+            size
+        )
+        .map(|_| ())
+    }
+
+    /// Closes the shared-memory segment with given id. Existing mappings stay valid.
+    pub fn close_shm(&self, id: u64) -> Result<(), Error> {
+        send_recv_res!(&self.req_sgate, RecvGate::def(), PagerOp::SHM_CLOSE, id).map(|_| ())
+    }
+
+    /// Maps `len` bytes at offset `off` of the shared-memory segment with given id to virtual
+    /// address `addr` with permissions `prot`.
+    ///
+    /// The segment has to be opened before and the range has to be within the segment. Segments
+    /// are always mapped shared.
+    pub fn map_shm(
+        &self,
+        addr: goff,
+        id: u64,
+        off: usize,
+        len: usize,
+        prot: kif::Perm,
+        flags: MapFlags,
+    ) -> Result<goff, Error> {
+        let mut reply = send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::MAP_SHM,
+            addr,
+            id,
+            off,
+            len,
+            prot.bits(),
+            flags.bits()
+        )?;
+        reply.pop()
+    }
+
+    /// Returns the number of large and small pages that are currently mapped in this address
+    /// space.
+    ///
+    /// The pager uses large pages for suitably aligned memory. This is the case for anonymous
+    /// memory if the large page around a page fault is not mapped yet and for memory mapped via
+    /// [`Pager::map_mem`] if the virtual and the physical address have the same offset within a
+    /// large page.
+    pub fn map_stats(&self) -> Result<MapStats, Error> {
+        let mut reply = send_recv_res!(&self.req_sgate, RecvGate::def(), PagerOp::MAP_STATS)?;
+        reply.pop()
+    }
+
+    /// Returns the statistics about swapping of this pager.
+    ///
+    /// Fails with `Code::NotSup` if the pager has been started without swap space.
+    pub fn swap_stats(&self) -> Result<SwapStats, Error> {
+        let mut reply = send_recv_res!(&self.req_sgate, RecvGate::def(), PagerOp::SWAP_STATS)?;
+        reply.pop()
+    }
+
+    /// Returns the number of address spaces of this pager for `get_aspace_info`.
+    pub fn get_aspace_count(&self) -> Result<usize, Error> {
+        match self.info(usize::MAX, usize::MAX) {
+            Ok(PagerInfoResult::Count(num)) => Ok(num),
+            Err(e) => Err(e),
+            _ => panic!("unexpected info type"),
+        }
+    }
+
+    /// Returns information about the address space with index `idx`.
+    pub fn get_aspace_info(&self, idx: usize) -> Result<PagerAddrSpaceInfo, Error> {
+        match self.info(idx, usize::MAX) {
+            Ok(PagerInfoResult::AddrSpace(i)) => Ok(i),
+            Err(e) => Err(e),
+            _ => panic!("unexpected info type"),
+        }
+    }
+
+    /// Returns information about the mapping with index `map_idx` in the address space with index
+    /// `aspace_idx`. The number of mappings is available via `get_aspace_info`.
+    pub fn get_mapping_info(
+        &self,
+        aspace_idx: usize,
+        map_idx: usize,
+    ) -> Result<PagerMappingInfo, Error> {
+        match self.info(aspace_idx, map_idx) {
+            Ok(PagerInfoResult::Mapping(i)) => Ok(i),
+            Err(e) => Err(e),
+            _ => panic!("unexpected info type"),
+        }
+    }
+
+    fn info(&self, aspace_idx: usize, map_idx: usize) -> Result<PagerInfoResult, Error> {
+        send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::GET_INFO,
+            aspace_idx,
+            map_idx
+        )
+        .and_then(|mut is| is.pop())
+    }
+
+    /// Gives the pager the hint `advice` about the `len` bytes at virtual address `addr`.
+    ///
+    /// The range has to be page aligned and fully mapped.
+    pub fn advise(&self, addr: goff, len: usize, advice: Advice) -> Result<(), Error> {
+        send_recv_res!(
+            &self.req_sgate,
+            RecvGate::def(),
+            PagerOp::ADVISE,
+            addr,
+            len,
+            advice
+        )
+        .map(|_| ())
+    }
 }
 
 impl Drop for Pager {
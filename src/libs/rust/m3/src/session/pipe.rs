@@ -16,14 +16,16 @@
  * General Public License version 2 for more details.
  */
 
+use bitflags::bitflags;
+
 use crate::boxed::Box;
 use crate::cap::Selector;
 use crate::com::{MemGate, RecvGate, SendGate};
-use crate::errors::Error;
+use crate::errors::{Code, Error};
 use crate::int_enum;
 use crate::kif::{CapRngDesc, CapType};
 use crate::session::ClientSession;
-use crate::vfs::{File, GenFileOp, GenericFile, OpenFlags};
+use crate::vfs::{File, GenFileOp, GenericFile, INodeId, OpenFlags};
 
 /// Represents a session at the pipes server.
 pub struct Pipes {
@@ -37,6 +39,20 @@ int_enum! {
         const OPEN_CHAN     = Self::OPEN_PIPE.val + 1;
         const SET_MEM       = Self::OPEN_CHAN.val + 1;
         const CLOSE_PIPE    = Self::SET_MEM.val + 1;
+        const OPEN_NAMED    = Self::CLOSE_PIPE.val + 1;
+        const GET_SPLICE_EP = Self::OPEN_NAMED.val + 1;
+        const SPLICE        = Self::GET_SPLICE_EP.val + 1;
+        const AUTH_NAMED    = Self::SPLICE.val + 1;
+    }
+}
+
+bitflags! {
+    /// The flags for `Pipes::create_pipe_with`
+    pub struct PipeFlags : u64 {
+        /// Preserve the boundaries of writes: each read receives at most one write
+        const PACKET = 0x1;
+        /// Create a bidirectional pipe with two ends (implies `PACKET`)
+        const DUPLEX = 0x2;
     }
 }
 
@@ -49,15 +65,80 @@ impl Pipes {
 
     /// Creates a new pipe using `mem` of `mem_size` bytes as shared memory for the data exchange.
     pub fn create_pipe(&self, mem: &MemGate, mem_size: usize) -> Result<Pipe, Error> {
+        self.create_pipe_with(mem, mem_size, PipeFlags::empty())
+    }
+
+    /// Creates a new pipe with given flags using `mem` of `mem_size` bytes as shared memory for
+    /// the data exchange.
+    ///
+    /// With `PipeFlags::DUPLEX`, each direction uses one half of the memory.
+    pub fn create_pipe_with(
+        &self,
+        mem: &MemGate,
+        mem_size: usize,
+        mut flags: PipeFlags,
+    ) -> Result<Pipe, Error> {
+        if flags.contains(PipeFlags::DUPLEX) {
+            flags |= PipeFlags::PACKET;
+        }
+
         let crd = self.sess.obtain(
             2,
             |os| {
                 os.push(PipeOperation::OPEN_PIPE);
                 os.push(mem_size);
+                os.push(flags.bits());
             },
             |_| Ok(()),
         )?;
-        Pipe::new(mem, crd.start())
+        Pipe::new(mem, crd.start(), flags)
+    }
+
+    /// Allows one open of the named pipe that belongs to the FIFO with inode `ino` of the calling
+    /// file system and returns the token for [`Pipes::open_named`]. If `read` is true, the open
+    /// is allowed for the read-end, otherwise for the write-end.
+    ///
+    /// This is only allowed for file systems, that is, for sessions that have been configured with
+    /// `args="fs"`. The token can be used once.
+    pub fn auth_named(&self, ino: INodeId, read: bool) -> Result<u64, Error> {
+        let mut token = 0;
+        self.sess.obtain(
+            0,
+            |os| {
+                os.push(PipeOperation::AUTH_NAMED);
+                os.push(ino);
+                os.push(read);
+            },
+            |is| {
+                token = is.pop()?;
+                Ok(())
+            },
+        )?;
+        Ok(token)
+    }
+
+    /// Creates a new channel for the named pipe that has been authorized with `token` (see
+    /// [`Pipes::auth_named`]). If `read` is true, it is a read-end, otherwise a write-end.
+    ///
+    /// The named pipe is created on the first open and uses memory of the pipes server. It is
+    /// destroyed as soon as all of its channels have been closed.
+    pub fn open_named(&self, token: u64, read: bool) -> Result<Box<dyn File>, Error> {
+        let crd = self.sess.obtain(
+            2,
+            |os| {
+                os.push(PipeOperation::OPEN_NAMED);
+                os.push(token);
+                os.push(read);
+            },
+            |_| Ok(()),
+        )?;
+        let flags = if read {
+            OpenFlags::R | OpenFlags::NEW_SESS
+        }
+        else {
+            OpenFlags::W | OpenFlags::NEW_SESS
+        };
+        Ok(Box::new(GenericFile::new(flags, crd.start(), None)))
     }
 }
 
@@ -65,10 +146,11 @@ impl Pipes {
 pub struct Pipe {
     sess: ClientSession,
     sgate: SendGate,
+    flags: PipeFlags,
 }
 
 impl Pipe {
-    fn new(mem: &MemGate, sel: Selector) -> Result<Self, Error> {
+    fn new(mem: &MemGate, sel: Selector, flags: PipeFlags) -> Result<Self, Error> {
         let sess = ClientSession::new_bind(sel);
         sess.delegate(
             CapRngDesc::new(CapType::OBJECT, mem.sel(), 1),
@@ -80,6 +162,7 @@ impl Pipe {
         Ok(Pipe {
             sess,
             sgate: SendGate::new_bind(sel + 1),
+            flags,
         })
     }
 
@@ -88,9 +171,20 @@ impl Pipe {
         self.sess.sel()
     }
 
+    /// Returns the flags the pipe has been created with.
+    pub fn flags(&self) -> PipeFlags {
+        self.flags
+    }
+
     /// Creates a new channel for this pipe. If `read` is true, it is a read-end, otherwise a
     /// write-end.
+    ///
+    /// Fails for duplex pipes, which only have the ends created by `create_end`.
     pub fn create_chan(&self, read: bool) -> Result<Box<dyn File>, Error> {
+        if self.flags.contains(PipeFlags::DUPLEX) {
+            return Err(Error::new(Code::NotSup));
+        }
+
         let crd = self.sess.obtain(
             2,
             |os| {
@@ -99,12 +193,34 @@ impl Pipe {
             },
             |_| Ok(()),
         )?;
-        let flags = if read {
+        let mut flags = if read {
             OpenFlags::R | OpenFlags::NEW_SESS
         }
         else {
             OpenFlags::W | OpenFlags::NEW_SESS
         };
+        if self.flags.contains(PipeFlags::PACKET) {
+            flags |= OpenFlags::PACKET;
+        }
+        Ok(Box::new(GenericFile::new(flags, crd.start(), None)))
+    }
+
+    /// Creates the end with index `idx` (0 or 1) of this duplex pipe. Each end receives the
+    /// packets written to the other end.
+    pub fn create_end(&self, idx: usize) -> Result<Box<dyn File>, Error> {
+        if !self.flags.contains(PipeFlags::DUPLEX) || idx > 1 {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let crd = self.sess.obtain(
+            2,
+            |os| {
+                os.push(PipeOperation::OPEN_CHAN);
+                os.push(idx);
+            },
+            |_| Ok(()),
+        )?;
+        let flags = OpenFlags::RW | OpenFlags::PACKET | OpenFlags::NEW_SESS;
         Ok(Box::new(GenericFile::new(flags, crd.start(), None)))
     }
 }
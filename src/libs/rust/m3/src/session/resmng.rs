@@ -18,7 +18,7 @@ use base::serialize::{Deserialize, Serialize};
 use crate::cap::Selector;
 use crate::cfg;
 use crate::col::String;
-use crate::com::{GateIStream, RecvGate, SendGate};
+use crate::com::{GateIStream, MemGate, RecvGate, SendGate};
 use crate::errors::Error;
 use crate::goff;
 use crate::int_enum;
@@ -53,6 +53,16 @@ int_enum! {
         const GET_SERIAL    = 0xD;
 
         const GET_INFO      = 0xE;
+
+        const SERV_READY    = 0xF;
+
+        const ADM_START     = 0x10;
+        const ADM_STOP      = 0x11;
+        const ADM_LIST      = 0x12;
+
+        const GET_QUOTAS    = 0x13;
+
+        const ADM_KILL      = 0x14;
     }
 }
 
@@ -68,9 +78,52 @@ pub struct ResMngActInfo {
     pub eps: Quota<u32>,
     pub time: Quota<u64>,
     pub pts: Quota<usize>,
+    pub usage: kif::tilemux::Usage,
     pub tile: TileId,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct ResMngChildInfo {
+    pub id: u32,
+    pub name: String,
+    pub domain: String,
+    pub daemon: bool,
+    pub running: bool,
+    pub restarts: u32,
+}
+
+/// The kind of node in the quota tree
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub enum ResMngQuotaKind {
+    /// A domain, whose quotas are shared by the activities within
+    Domain,
+    /// An activity, including resource managers
+    Activity,
+}
+
+/// A node in the quota tree, which lists the assigned (total) and remaining (left) quotas
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub struct ResMngQuotaInfo {
+    pub layer: u32,
+    pub kind: ResMngQuotaKind,
+    pub name: String,
+    pub umem: Quota<usize>,
+    pub kmem: Quota<usize>,
+    pub eps: Quota<u32>,
+    pub time: Quota<u64>,
+    pub pts: Quota<usize>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "base::serde")]
+pub enum ResMngQuotaResult {
+    Info(ResMngQuotaInfo),
+    Count((usize, u32)),
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "base::serde")]
 pub enum ResMngActInfoResult {
@@ -156,6 +209,17 @@ impl ResMng {
         .map(|_| ())
     }
 
+    /// Tells the resource manager that the service with given selector is ready to be used.
+    pub fn serv_ready(&self, sel: Selector) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            ResMngOperation::SERV_READY,
+            sel
+        )
+        .map(|_| ())
+    }
+
     /// Opens a session at service `name` using selector `dst`.
     pub fn open_sess(&self, dst: Selector, name: &str) -> Result<(), Error> {
         send_recv_res!(
@@ -282,6 +346,84 @@ impl ResMng {
         }
     }
 
+    /// Gets the number of nodes in the quota tree for `get_quota_info` and the layer of the caller.
+    pub fn get_quota_count(&self) -> Result<(usize, u32), Error> {
+        match self.quota_info(None) {
+            Ok(ResMngQuotaResult::Count((num, layer))) => Ok((num, layer)),
+            Err(e) => Err(e),
+            _ => panic!("unexpected info type"),
+        }
+    }
+
+    /// Retrieves the node with given index of the quota tree.
+    ///
+    /// The nodes are ordered depth-first, starting with the root resource manager, and each node
+    /// is followed by its domains or childs with the next higher layer. The tree is determined by
+    /// the preceding call to [`ResMng::get_quota_count`], so that it stays consistent while the
+    /// nodes are retrieved. Fails with `Code::InvState` if there was no such call before.
+    pub fn get_quota_info(&self, idx: usize) -> Result<ResMngQuotaInfo, Error> {
+        match self.quota_info(Some(idx)) {
+            Ok(ResMngQuotaResult::Info(i)) => Ok(i),
+            Err(e) => Err(e),
+            _ => panic!("unexpected info type"),
+        }
+    }
+
+    /// Starts a new child in the domain with given name, using the given `<app>` configuration.
+    ///
+    /// This requires the admin permission.
+    pub fn start_child(&self, domain: &str, cfg: &str) -> Result<(), Error> {
+        // the configuration does not necessarily fit into a message, so that we pass a memory gate
+        let mgate = MemGate::new(cfg.len(), kif::Perm::R)?;
+        mgate.write(cfg.as_bytes(), 0)?;
+
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            ResMngOperation::ADM_START,
+            mgate.sel(),
+            cfg.len(),
+            domain
+        )
+        .map(|_| ())
+    }
+
+    /// Stops all childs with given name.
+    ///
+    /// This requires the admin permission.
+    pub fn stop_child(&self, name: &str) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            ResMngOperation::ADM_STOP,
+            name
+        )
+        .map(|_| ())
+    }
+
+    /// Kills all childs with given name. In contrast to [`ResMng::stop_child`], the childs are
+    /// restarted according to their restart policy.
+    ///
+    /// This requires the admin permission.
+    pub fn kill_child(&self, name: &str) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            ResMngOperation::ADM_KILL,
+            name
+        )
+        .map(|_| ())
+    }
+
+    /// Retrieves information about the child with given index. Fails with `Code::NotFound` if there
+    /// is no child with that index.
+    ///
+    /// This requires the admin permission.
+    pub fn get_child_info(&self, idx: usize) -> Result<ResMngChildInfo, Error> {
+        send_recv_res!(&self.sgate, RecvGate::def(), ResMngOperation::ADM_LIST, idx)
+            .and_then(|mut is| is.pop())
+    }
+
     fn activity_info(&self, act_idx: Option<usize>) -> Result<ResMngActInfoResult, Error> {
         send_recv_res!(
             &self.sgate,
@@ -292,6 +434,16 @@ impl ResMng {
         .and_then(|mut is| is.pop())
     }
 
+    fn quota_info(&self, idx: Option<usize>) -> Result<ResMngQuotaResult, Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            ResMngOperation::GET_QUOTAS,
+            idx.unwrap_or(usize::MAX)
+        )
+        .and_then(|mut is| is.pop())
+    }
+
     fn use_op(
         &self,
         op: ResMngOperation,
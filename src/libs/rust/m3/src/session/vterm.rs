@@ -0,0 +1,62 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use crate::boxed::Box;
+use crate::errors::Error;
+use crate::int_enum;
+use crate::session::ClientSession;
+use crate::vfs::{File, GenFileOp, GenericFile, OpenFlags};
+
+/// Represents a session at the virtual terminal server.
+pub struct VTerm {
+    sess: ClientSession,
+}
+
+int_enum! {
+    /// The virtual terminal operations.
+    pub struct VTermOperation : u64 {
+        const GET_WINSIZE   = GenFileOp::REQ_NOTIFY.val + 1;
+        const SET_WINSIZE   = Self::GET_WINSIZE.val + 1;
+        const SET_ECHO      = Self::SET_WINSIZE.val + 1;
+    }
+}
+
+impl VTerm {
+    /// Creates a new `VTerm` session at service with given name.
+    pub fn new(name: &str) -> Result<Self, Error> {
+        let sess = ClientSession::new(name)?;
+        Ok(VTerm { sess })
+    }
+
+    /// Creates a new channel to the terminal. If `read` is true, it is used for reading,
+    /// otherwise for writing.
+    pub fn create_channel(&self, read: bool) -> Result<Box<dyn File>, Error> {
+        let crd = self.sess.obtain(
+            2,
+            |os| {
+                os.push(GenFileOp::CLONE);
+                os.push(if read { 0i32 } else { 1i32 });
+            },
+            |_| Ok(()),
+        )?;
+        let flags = if read {
+            OpenFlags::R | OpenFlags::NEW_SESS
+        }
+        else {
+            OpenFlags::W | OpenFlags::NEW_SESS
+        };
+        Ok(Box::new(GenericFile::new(flags, crd.start(), None)))
+    }
+}
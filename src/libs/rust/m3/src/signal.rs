@@ -0,0 +1,80 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Contains the handling of asynchronous signals
+//!
+//! Signals are sent by other activities (see
+//! [`RunningActivity::signal`](crate::tiles::RunningActivity::signal)) or by TileMux itself (see
+//! [`alarm`]). TileMux interrupts the activity at an arbitrary point and calls the registered
+//! handler on the current stack. Afterwards, the interrupted code continues. Therefore, handlers
+//! should only touch state that can be accessed at any point, like [`StaticCell`]s. Signals without
+//! handler terminate the activity.
+
+use base::envdata;
+
+use crate::cell::StaticCell;
+use crate::errors::{Code, Error};
+use crate::time::TimeDuration;
+use crate::tmif;
+
+pub use crate::tmif::Signal;
+
+/// A signal handler
+pub type Handler = fn(Signal);
+
+static HANDLERS: StaticCell<[Option<Handler>; tmif::SIG_COUNT as usize]> =
+    StaticCell::new([None; tmif::SIG_COUNT as usize]);
+
+extern "C" fn signal_entry(sig: usize) -> ! {
+    if let Some(handler) = HANDLERS.get()[sig] {
+        handler(Signal::from(sig as u32));
+    }
+    tmif::sig_return().unwrap();
+    unreachable!();
+}
+
+fn check_support() -> Result<(), Error> {
+    if envdata::get().platform == envdata::Platform::HOST.val {
+        Err(Error::new(Code::NotSup))
+    }
+    else {
+        Ok(())
+    }
+}
+
+/// Sets the handler for the given signal. If `handler` is `None`, the default action (termination)
+/// is restored.
+pub fn set_handler(sig: Signal, handler: Option<Handler>) -> Result<(), Error> {
+    check_support()?;
+
+    let mut handlers = HANDLERS.get();
+    handlers[sig.val as usize] = handler;
+    HANDLERS.set(handlers);
+
+    let mask = handlers
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| h.is_some())
+        .fold(0, |mask, (i, _)| mask | 1 << i);
+    tmif::sig_action(signal_entry as usize, mask)
+}
+
+/// Requests [`Signal::ALRM`] after the given duration. If `duration` is `None`, a pending alarm is
+/// cancelled.
+pub fn alarm(duration: Option<TimeDuration>) -> Result<(), Error> {
+    check_support()?;
+
+    tmif::alarm(duration)
+}
@@ -18,6 +18,7 @@
 
 //! Contains the system call wrapper functions
 
+use base::kif::tilemux::{CrashInfo, SchedParams, Usage};
 use base::kif::{self, syscalls, CapRngDesc, Perm, INVALID_SEL};
 
 use core::mem::MaybeUninit;
@@ -229,6 +230,21 @@ pub fn create_activity(
     name: &str,
     tile: Selector,
     kmem: Selector,
+) -> Result<(ActId, EpId), Error> {
+    create_activity_with_budget(dst, name, tile, kmem, syscalls::SyscBudget::default())
+}
+
+/// Creates a new activity like [`create_activity`], but limits the system calls the activity can
+/// perform to the given budget.
+///
+/// If the own activity has a system call budget, the budget of the new activity cannot exceed it
+/// and defaults to it.
+pub fn create_activity_with_budget(
+    dst: Selector,
+    name: &str,
+    tile: Selector,
+    kmem: Selector,
+    budget: syscalls::SyscBudget,
 ) -> Result<(ActId, EpId), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
     build_vmsg!(
@@ -238,7 +254,8 @@ pub fn create_activity(
             dst,
             name,
             tile,
-            kmem
+            kmem,
+            budget
         }
     );
 
@@ -297,6 +314,27 @@ pub fn derive_mem(
     offset: goff,
     size: goff,
     perms: Perm,
+) -> Result<(), Error> {
+    derive_mem_with_expiry(
+        act,
+        dst,
+        src,
+        offset,
+        size,
+        perms,
+        syscalls::Expiry::default(),
+    )
+}
+
+/// Like [`derive_mem`], but lets the kernel revoke the derived memory gate according to `expiry`.
+pub fn derive_mem_with_expiry(
+    act: Selector,
+    dst: Selector,
+    src: Selector,
+    offset: goff,
+    size: goff,
+    perms: Perm,
+    expiry: syscalls::Expiry,
 ) -> Result<(), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
     build_vmsg!(buf, syscalls::Operation::DERIVE_MEM, syscalls::DeriveMem {
@@ -306,10 +344,35 @@ pub fn derive_mem(
         offset,
         size,
         perms,
+        expiry,
     });
     send_receive_result(&buf)
 }
 
+/// Derives a new send gate at selector `dst` from the send gate `sgate`.
+///
+/// The new send gate uses the label of `sgate` with the additional bits in `label` set and has
+/// `credits` credits, which cannot exceed the credits of `sgate`.
+pub fn derive_sgate(
+    sgate: Selector,
+    dst: Selector,
+    label: Label,
+    credits: u32,
+) -> Result<(), Error> {
+    let mut buf = SYSC_BUF.borrow_mut();
+    build_vmsg!(
+        buf,
+        syscalls::Operation::DERIVE_SGATE,
+        syscalls::DeriveSGate {
+            sgate,
+            dst,
+            label,
+            credits,
+        }
+    );
+    send_receive_result(&buf)
+}
+
 /// Derives a new kernel memory object at `dst` from `kmem`, transferring `quota` bytes to the new
 /// kernel memory object.
 pub fn derive_kmem(kmem: Selector, dst: Selector, quota: usize) -> Result<(), Error> {
@@ -334,6 +397,26 @@ pub fn derive_tile(
     eps: Option<u32>,
     time: Option<u64>,
     pts: Option<usize>,
+) -> Result<(), Error> {
+    derive_tile_with_sched(tile, dst, eps, time, pts, SchedParams::default())
+}
+
+/// Derives a new tile object like [`derive_tile`], but additionally sets the scheduling parameters
+/// for the time quota of the new tile object.
+///
+/// Unless `sched` uses [`SchedClass::INHERIT`](kif::tilemux::SchedClass::INHERIT), the new tile
+/// object receives its own time quota, even if `time` is `None`.
+///
+/// Without a root tile capability, the new tile object can only keep or lower the scheduling class
+/// and priority of `tile`. In particular, EDF is only possible with the parameters of `tile`, in
+/// which case both share the same reservation.
+pub fn derive_tile_with_sched(
+    tile: Selector,
+    dst: Selector,
+    eps: Option<u32>,
+    time: Option<u64>,
+    pts: Option<usize>,
+    sched: SchedParams,
 ) -> Result<(), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
     build_vmsg!(
@@ -345,6 +428,7 @@ pub fn derive_tile(
             eps,
             time,
             pts,
+            sched,
         }
     );
     send_receive_result(&buf)
@@ -432,6 +516,19 @@ pub fn tile_set_quota(tile: Selector, time: u64, pts: usize) -> Result<(), Error
     send_receive_result(&buf)
 }
 
+/// Migrates the activity `act` to the tile `tile`.
+///
+/// The activity continues to run on the new tile and uses the quotas of `tile` from now on.
+pub fn migrate_activity(act: Selector, tile: Selector) -> Result<(), Error> {
+    let mut buf = SYSC_BUF.borrow_mut();
+    build_vmsg!(
+        buf,
+        syscalls::Operation::MIGRATE_ACT,
+        syscalls::MigrateActivity { act, tile }
+    );
+    send_receive_result(&buf)
+}
+
 /// Performs the activity operation `op` with the given activity.
 pub fn activity_ctrl(act: Selector, op: syscalls::ActivityOp, arg: u64) -> Result<(), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
@@ -480,6 +577,38 @@ pub fn activity_wait(sels: &[Selector], event: u64) -> Result<(Selector, i32), E
     }
 }
 
+/// Returns the state of the activity `act` at the time it crashed.
+///
+/// The information is available as soon as the activity has exited (see [`activity_wait`]). If the
+/// activity did not crash, the error [`Code::NotFound`] is returned.
+pub fn activity_crash(act: Selector) -> Result<CrashInfo, Error> {
+    let mut buf = SYSC_BUF.borrow_mut();
+    build_vmsg!(
+        buf,
+        syscalls::Operation::ACT_CRASH,
+        syscalls::ActivityCrash { act }
+    );
+
+    let reply: Reply<syscalls::ActivityCrashReply> = send_receive(&buf)?;
+    Ok(reply.data.info)
+}
+
+/// Returns the resource usage of the activity `act`.
+///
+/// The usage contains the consumed CPU time, the number of context switches, the number of handled
+/// page faults and the number of received messages since the activity was created.
+pub fn activity_usage(act: Selector) -> Result<Usage, Error> {
+    let mut buf = SYSC_BUF.borrow_mut();
+    build_vmsg!(
+        buf,
+        syscalls::Operation::ACT_USAGE,
+        syscalls::ActivityUsage { act }
+    );
+
+    let reply: Reply<syscalls::ActivityUsageReply> = send_receive(&buf)?;
+    Ok(reply.data.usage)
+}
+
 /// Performs the semaphore operation `op` with the given semaphore.
 pub fn sem_ctrl(sem: Selector, op: syscalls::SemOp) -> Result<(), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
@@ -499,6 +628,17 @@ pub fn exchange(
     own: CapRngDesc,
     other: Selector,
     obtain: bool,
+) -> Result<(), Error> {
+    exchange_with_expiry(act, own, other, obtain, syscalls::Expiry::default())
+}
+
+/// Like [`exchange`], but lets the kernel revoke the copied capabilities according to `expiry`.
+pub fn exchange_with_expiry(
+    act: Selector,
+    own: CapRngDesc,
+    other: Selector,
+    obtain: bool,
+    expiry: syscalls::Expiry,
 ) -> Result<(), Error> {
     let mut buf = SYSC_BUF.borrow_mut();
     build_vmsg!(buf, syscalls::Operation::EXCHANGE, syscalls::Exchange {
@@ -506,6 +646,7 @@ pub fn exchange(
         own,
         other,
         obtain,
+        expiry,
     });
     send_receive_result(&buf)
 }
@@ -527,7 +668,31 @@ where
     PRE: Fn(&mut M3Serializer<SliceSink<'_>>),
     POST: FnMut(&mut M3Deserializer<'_>) -> Result<(), Error>,
 {
-    exchange_sess(act, false, sess, crd, pre, post)
+    exchange_sess(
+        act,
+        false,
+        sess,
+        crd,
+        syscalls::Expiry::default(),
+        pre,
+        post,
+    )
+}
+
+/// Like [`delegate`], but lets the kernel revoke the delegated capabilities according to `expiry`.
+pub fn delegate_with_expiry<PRE, POST>(
+    act: Selector,
+    sess: Selector,
+    crd: CapRngDesc,
+    expiry: syscalls::Expiry,
+    pre: PRE,
+    post: POST,
+) -> Result<(), Error>
+where
+    PRE: Fn(&mut M3Serializer<SliceSink<'_>>),
+    POST: FnMut(&mut M3Deserializer<'_>) -> Result<(), Error>,
+{
+    exchange_sess(act, false, sess, crd, expiry, pre, post)
 }
 
 /// Obtains `crd.count` capabilities via the session `sess` from the server managing the session
@@ -547,7 +712,7 @@ where
     PRE: Fn(&mut M3Serializer<SliceSink<'_>>),
     POST: FnMut(&mut M3Deserializer<'_>) -> Result<(), Error>,
 {
-    exchange_sess(act, true, sess, crd, pre, post)
+    exchange_sess(act, true, sess, crd, syscalls::Expiry::default(), pre, post)
 }
 
 fn exchange_sess<PRE, POST>(
@@ -555,6 +720,7 @@ fn exchange_sess<PRE, POST>(
     obtain: bool,
     sess: Selector,
     crd: CapRngDesc,
+    expiry: syscalls::Expiry,
     pre: PRE,
     mut post: POST,
 ) -> Result<(), Error>
@@ -580,6 +746,7 @@ where
             crd,
             args,
             obtain,
+            expiry,
         }
     );
 
@@ -628,6 +795,18 @@ pub fn revoke(act: Selector, crd: CapRngDesc, own: bool) -> Result<(), Error> {
     send_receive_result(&buf)
 }
 
+/// Creates a read-only memory gate at selector `dst` for the kernel's system call trace.
+///
+/// The layout of the trace is described in [`kif::systrace`]. Only the root activity is allowed to
+/// use this system call.
+pub fn sysc_trace(dst: Selector) -> Result<(), Error> {
+    let mut buf = SYSC_BUF.borrow_mut();
+    build_vmsg!(buf, syscalls::Operation::SYSC_TRACE, syscalls::SyscTrace {
+        dst
+    });
+    send_receive_result(&buf)
+}
+
 /// The reset stats system call for benchmarking
 ///
 /// Resets the statistics for all activities in the system
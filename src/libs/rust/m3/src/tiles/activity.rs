@@ -120,6 +120,14 @@ impl Activity {
     pub fn get_mem(&self, addr: goff, size: goff, perms: kif::Perm) -> Result<MemGate, Error> {
         MemGate::new_foreign(self.sel(), addr, size, perms)
     }
+
+    /// Returns the resource usage of this activity (CPU time, context switches, page faults, and
+    /// received messages).
+    ///
+    /// The usage is only available for activities that run on TileMux.
+    pub fn usage(&self) -> Result<kif::tilemux::Usage, Error> {
+        syscalls::activity_usage(self.sel())
+    }
 }
 
 impl fmt::Debug for Activity {
@@ -53,6 +53,7 @@ pub struct ActivityArgs<'n> {
     pager: Option<Pager>,
     kmem: Option<Rc<KMem>>,
     rmng: Option<ResMng>,
+    budget: kif::syscalls::SyscBudget,
 }
 
 impl<'n> ActivityArgs<'n> {
@@ -63,6 +64,7 @@ impl<'n> ActivityArgs<'n> {
             pager: None,
             kmem: None,
             rmng: None,
+            budget: kif::syscalls::SyscBudget::default(),
         }
     }
 
@@ -85,6 +87,13 @@ impl<'n> ActivityArgs<'n> {
         self.kmem = Some(kmem);
         self
     }
+
+    /// Sets the system call budget of the activity. By default, the activity inherits the budget
+    /// of the own activity (if any).
+    pub fn sysc_budget(mut self, budget: kif::syscalls::SyscBudget) -> Self {
+        self.budget = budget;
+        self
+    }
 }
 
 impl ChildActivity {
@@ -127,8 +136,13 @@ impl ChildActivity {
 
         act.pager = if let Some(mut pg) = pager {
             // now create activity, which implicitly obtains the gate cap from us
-            let (id, eps_start) =
-                syscalls::create_activity(sel, args.name, tile.sel(), act.kmem().sel())?;
+            let (id, eps_start) = syscalls::create_activity_with_budget(
+                sel,
+                args.name,
+                tile.sel(),
+                act.kmem().sel(),
+                args.budget,
+            )?;
             act.id = id;
             act.eps_start = eps_start;
 
@@ -137,8 +151,13 @@ impl ChildActivity {
             Some(pg)
         }
         else {
-            let (id, eps_start) =
-                syscalls::create_activity(sel, args.name, tile.sel(), act.kmem().sel())?;
+            let (id, eps_start) = syscalls::create_activity_with_budget(
+                sel,
+                args.name,
+                tile.sel(),
+                act.kmem().sel(),
+                args.budget,
+            )?;
             act.id = id;
             act.eps_start = eps_start;
             None
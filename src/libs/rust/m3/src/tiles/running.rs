@@ -17,8 +17,10 @@
 
 use crate::errors::Error;
 use crate::kif;
+use crate::rc::Rc;
 use crate::syscalls;
-use crate::tiles::ChildActivity;
+use crate::tiles::{ChildActivity, Tile};
+use crate::tmif::Signal;
 use crate::vfs::{BufReader, File, FileRef};
 
 /// Represents an activity that is run on a [`ChildActivity`].
@@ -40,6 +42,25 @@ pub trait RunningActivity {
             .map(|_| ())
     }
 
+    /// Sends the given signal to the activity. The activity is terminated if it has not registered
+    /// a handler for this signal (see [`signal`](crate::signal)).
+    fn signal(&self, sig: Signal) -> Result<(), Error> {
+        syscalls::activity_ctrl(
+            self.activity().sel(),
+            kif::syscalls::ActivityOp::SIGNAL,
+            sig.val as u64,
+        )
+        .map(|_| ())
+    }
+
+    /// Migrates the activity to the given tile, which has to be of the same type as the current
+    /// tile. The activity continues to run on the new tile and uses its quotas from now on.
+    fn migrate(&mut self, tile: Rc<Tile>) -> Result<(), Error> {
+        syscalls::migrate_activity(self.activity().sel(), tile.sel())?;
+        self.activity_mut().tile = tile;
+        Ok(())
+    }
+
     /// Waits until the activity exits and returns the error code.
     fn wait(&self) -> Result<i32, Error> {
         syscalls::activity_wait(&[self.activity().sel()], 0).map(|r| r.1)
@@ -20,7 +20,7 @@ use core::fmt;
 
 use crate::cap::{CapFlags, Capability, Selector};
 use crate::errors::{Code, Error};
-use crate::kif::TileDesc;
+use crate::kif::{tilemux::SchedParams, TileDesc};
 use crate::quota::Quota;
 use crate::rc::Rc;
 use crate::syscalls;
@@ -149,9 +149,21 @@ impl Tile {
         eps: Option<u32>,
         time: Option<u64>,
         pts: Option<usize>,
+    ) -> Result<Rc<Self>, Error> {
+        self.derive_with_sched(eps, time, pts, SchedParams::default())
+    }
+
+    /// Derives a new tile object like [`derive`](Self::derive), but additionally sets the
+    /// scheduling parameters for the activities on the new tile object.
+    pub fn derive_with_sched(
+        &self,
+        eps: Option<u32>,
+        time: Option<u64>,
+        pts: Option<usize>,
+        sched: SchedParams,
     ) -> Result<Rc<Self>, Error> {
         let sel = Activity::own().alloc_sel();
-        syscalls::derive_tile(self.sel(), sel, eps, time, pts)?;
+        syscalls::derive_tile_with_sched(self.sel(), sel, eps, time, pts, sched)?;
         Ok(Rc::new(Tile {
             cap: Capability::new(sel, CapFlags::empty()),
             desc: self.desc(),
@@ -42,6 +42,23 @@ int_enum! {
     }
 }
 
+int_enum! {
+    /// The modes of terminals.
+    pub struct TMode : u64 {
+        /// Passes all input unmodified to the application.
+        const RAW       = 0x0;
+        /// Provides line editing and passes the input line by line to the application.
+        const COOKED    = 0x1;
+    }
+}
+
+/// The size of a terminal window.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct WinSize {
+    pub cols: usize,
+    pub rows: usize,
+}
+
 bitflags! {
     /// The flags to open files.
     #[derive(Serialize, Deserialize)]
@@ -63,6 +80,9 @@ bitflags! {
         const NODATA    = 0b0100_0000;
         /// Create a new file session
         const NEW_SESS  = 0b1000_0000;
+        /// Preserve message boundaries: each write is one packet and each read receives at most
+        /// one packet (only supported by pipes).
+        const PACKET    = 0b1_0000_0000;
 
         /// Opens the file for reading and writing.
         const RW        = Self::R.bits | Self::W.bits;
@@ -206,6 +226,32 @@ pub trait File: Read + Write + Seek + Map + Debug + HashInput + HashOutput {
         Err(Error::new(Code::NotSup))
     }
 
+    /// Returns the current terminal mode in case the server is a terminal.
+    fn get_tmode(&self) -> Result<TMode, Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    /// Sets the terminal mode in case the server is a terminal.
+    fn set_tmode(&mut self, _mode: TMode) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    /// Returns the window size in case the server is a terminal.
+    fn get_winsize(&self) -> Result<WinSize, Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    /// Sets the window size in case the server is a terminal (e.g., if the terminal cannot report
+    /// its size itself).
+    fn set_winsize(&mut self, _size: WinSize) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
+    /// Sets whether the input is echoed in case the server is a terminal.
+    fn set_echo(&mut self, _echo: bool) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+
     /// Returns the type of the file implementation used for serialization.
     fn file_type(&self) -> u8;
     /// Delegates this file to `act`.
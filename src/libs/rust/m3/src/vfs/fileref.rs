@@ -22,7 +22,7 @@ use core::marker::PhantomData;
 
 use crate::cap::Selector;
 use crate::cell::RefMut;
-use crate::errors::Error;
+use crate::errors::{Code, Error};
 use crate::goff;
 use crate::io::{Read, Write};
 use crate::kif;
@@ -30,7 +30,9 @@ use crate::net::{DGramSocket, StreamSocket};
 use crate::serialize::{M3Serializer, VecSink};
 use crate::session::{HashInput, HashOutput, HashSession, MapFlags, Pager};
 use crate::tiles::{Activity, ChildActivity};
-use crate::vfs::{Fd, File, FileEvent, FileTable, Map, Seek, SeekMode};
+use crate::vfs::{
+    Fd, File, FileEvent, FileTable, GenericFile, Map, Seek, SeekMode, TMode, WinSize,
+};
 
 /// A file reference provides access to a file of type `T`.
 ///
@@ -89,6 +91,38 @@ impl<T: 'static> FileRef<T> {
     }
 }
 
+impl FileRef<GenericFile> {
+    /// Moves up to `len` bytes from this file to `dst` (see [`GenericFile::splice`]).
+    pub fn splice(&self, dst: &FileRef<GenericFile>, len: usize) -> Result<usize, Error> {
+        let (mut src, mut dst) = self.borrow_pair(dst)?;
+        src.splice(&mut dst, len)
+    }
+
+    /// Duplicates up to `len` bytes from this file to `dst` (see [`GenericFile::tee`]).
+    ///
+    /// Only the data of the current input extent is duplicated, because the following data can
+    /// only be obtained by consuming the current data. Therefore, the returned number of bytes can
+    /// be less than `len` even before EOF. Call it again after consuming the data to duplicate
+    /// more.
+    pub fn tee(&self, dst: &FileRef<GenericFile>, len: usize) -> Result<usize, Error> {
+        let (mut src, mut dst) = self.borrow_pair(dst)?;
+        src.tee(&mut dst, len)
+    }
+
+    fn borrow_pair(
+        &self,
+        other: &FileRef<GenericFile>,
+    ) -> Result<(RefMut<'_, GenericFile>, RefMut<'_, GenericFile>), Error> {
+        let files = Activity::own().files();
+        let (a, b) = FileTable::get_raw_pair(files, self.fd, other.fd)
+            .ok_or_else(|| Error::new(Code::InvArgs))?;
+        Ok((
+            RefMut::map(a, |f| f.as_any_mut().downcast_mut().unwrap()),
+            RefMut::map(b, |f| f.as_any_mut().downcast_mut().unwrap()),
+        ))
+    }
+}
+
 impl<T: ?Sized> Drop for FileRef<T> {
     fn drop(&mut self) {
         if self.close {
@@ -130,6 +164,26 @@ impl<T: ?Sized + 'static> File for FileRef<T> {
         self.borrow().stat()
     }
 
+    fn get_tmode(&self) -> Result<TMode, Error> {
+        self.borrow().get_tmode()
+    }
+
+    fn set_tmode(&mut self, mode: TMode) -> Result<(), Error> {
+        self.borrow().set_tmode(mode)
+    }
+
+    fn get_winsize(&self) -> Result<WinSize, Error> {
+        self.borrow().get_winsize()
+    }
+
+    fn set_winsize(&mut self, size: WinSize) -> Result<(), Error> {
+        self.borrow().set_winsize(size)
+    }
+
+    fn set_echo(&mut self, echo: bool) -> Result<(), Error> {
+        self.borrow().set_echo(echo)
+    }
+
     fn delegate(&self, act: &ChildActivity) -> Result<Selector, Error> {
         self.borrow().delegate(act)
     }
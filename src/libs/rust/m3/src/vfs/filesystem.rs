@@ -41,6 +41,9 @@ int_enum! {
         const GET_MEM       = 23;
         const DEL_EP        = 24;
         const OPEN_PRIV     = 25;
+        const COMMIT_MEM    = 26;
+        const MKFIFO        = 27;
+        const OPEN_FIFO     = 28;
     }
 }
 
@@ -66,6 +69,9 @@ pub trait FileSystem: fmt::Debug {
     /// Removes the directory at `path`, if it is empty.
     fn rmdir(&self, path: &str) -> Result<(), Error>;
 
+    /// Creates a new FIFO with given permissions at `path`.
+    fn mkfifo(&self, path: &str, mode: FileMode) -> Result<(), Error>;
+
     /// Links `new_path` to `old_path`.
     fn link(&self, old_path: &str, new_path: &str) -> Result<(), Error>;
     /// Removes the file at `path`.
@@ -16,7 +16,7 @@
  * General Public License version 2 for more details.
  */
 
-use core::{fmt, mem};
+use core::{cmp, fmt, mem};
 
 use crate::boxed::Box;
 use crate::cap::Selector;
@@ -89,6 +89,33 @@ impl FileTable {
         }
     }
 
+    /// Returns the two different files with given file descriptors at the same time.
+    pub(crate) fn get_raw_pair(
+        ftable: RefMut<'static, Self>,
+        a: Fd,
+        b: Fd,
+    ) -> Option<(
+        RefMut<'static, (dyn File + 'static)>,
+        RefMut<'static, (dyn File + 'static)>,
+    )> {
+        if a != b && ftable.exists(a) && ftable.exists(b) {
+            Some(RefMut::map_split(ftable, |ft| {
+                let (lo, hi) = ft.files.split_at_mut(cmp::max(a, b));
+                let first = lo[cmp::min(a, b)].as_mut().unwrap().as_mut();
+                let second = hi[0].as_mut().unwrap().as_mut();
+                if a < b {
+                    (first, second)
+                }
+                else {
+                    (second, first)
+                }
+            }))
+        }
+        else {
+            None
+        }
+    }
+
     /// Adds the given file to the table using the file descriptor `fd`, assuming that the file
     /// descriptor is not yet in use.
     pub(crate) fn set_raw(&mut self, fd: Fd, mut file: Box<dyn File>) {
@@ -34,10 +34,15 @@ use crate::kif::{CapRngDesc, CapType, Perm, INVALID_SEL};
 use crate::math;
 use crate::rc::Rc;
 use crate::serialize::{M3Deserializer, M3Serializer, VecSink};
-use crate::session::{ClientSession, HashInput, HashOutput, HashSession, MapFlags, Pager};
+use crate::session::{
+    ClientSession, HashInput, HashOutput, HashSession, MapFlags, Pager, PipeOperation,
+    VTermOperation,
+};
 use crate::tcu::EpId;
 use crate::tiles::{Activity, ChildActivity};
-use crate::vfs::{filetable, Fd, File, FileEvent, FileInfo, Map, OpenFlags, Seek, SeekMode};
+use crate::vfs::{
+    filetable, Fd, File, FileEvent, FileInfo, Map, OpenFlags, Seek, SeekMode, TMode, WinSize,
+};
 
 int_enum! {
     /// The operations for [`GenericFile`].
@@ -89,6 +94,7 @@ pub struct GenericFile {
     pos: usize,
     len: usize,
     writing: bool,
+    splice_ep: Option<Selector>,
 }
 
 impl GenericFile {
@@ -109,6 +115,7 @@ impl GenericFile {
             pos: 0,
             len: 0,
             writing: false,
+            splice_ep: None,
         }
     }
 
@@ -138,6 +145,7 @@ impl GenericFile {
             pos: 0,
             len: 0,
             writing: false,
+            splice_ep: None,
         }
     }
 
@@ -227,12 +235,24 @@ impl GenericFile {
                 return Err(Error::new(Code::WouldBlock));
             }
 
-            let mut reply = send_recv_res!(
-                &self.sgate,
-                RecvGate::def(),
-                GenFileOp::NEXT_OUT,
-                self.file_id()
-            )?;
+            let mut reply = if self.flags.contains(OpenFlags::PACKET) {
+                // the server needs to know the packet size to check whether it fits
+                send_recv_res!(
+                    &self.sgate,
+                    RecvGate::def(),
+                    GenFileOp::NEXT_OUT,
+                    self.file_id(),
+                    len
+                )?
+            }
+            else {
+                send_recv_res!(
+                    &self.sgate,
+                    RecvGate::def(),
+                    GenFileOp::NEXT_OUT,
+                    self.file_id()
+                )?
+            };
             self.goff += self.len;
             self.off = reply.pop()?;
             self.len = reply.pop()?;
@@ -242,6 +262,101 @@ impl GenericFile {
         Ok(cmp::min(len, self.len - self.pos))
     }
 
+    /// Moves up to `len` bytes from this file to `dst` and returns the number of moved bytes
+    ///
+    /// The data is not copied through this activity, but by the pipes server, which accesses the
+    /// memory of the other file via an EP of its own. Therefore, at least one of both files has to
+    /// be a pipe. A return value of 0 denotes EOF.
+    pub fn splice(&mut self, dst: &mut GenericFile, len: usize) -> Result<usize, Error> {
+        self.transfer(dst, len, true)
+    }
+
+    /// Duplicates up to `len` bytes from this file to `dst` without consuming them
+    ///
+    /// In contrast to [`splice`](GenericFile::splice), the data stays in this file and can
+    /// therefore still be read. The data is taken from the current input extent only, so that
+    /// less than `len` bytes might be duplicated. A return value of 0 denotes EOF.
+    pub fn tee(&mut self, dst: &mut GenericFile, len: usize) -> Result<usize, Error> {
+        self.transfer(dst, len, false)
+    }
+
+    fn get_splice_ep(&mut self) -> Result<Selector, Error> {
+        if let Some(ep) = self.splice_ep {
+            return Ok(ep);
+        }
+
+        let crd = self
+            .sess
+            .obtain(1, |os| os.push(PipeOperation::GET_SPLICE_EP), |_| Ok(()))?;
+        self.splice_ep = Some(crd.start());
+        Ok(crd.start())
+    }
+
+    fn transfer(
+        &mut self,
+        dst: &mut GenericFile,
+        len: usize,
+        consume: bool,
+    ) -> Result<usize, Error> {
+        // packets would need to be committed one by one
+        if self.flags.contains(OpenFlags::PACKET) || dst.flags.contains(OpenFlags::PACKET) {
+            return Err(Error::new(Code::NotSup));
+        }
+
+        // let the server of the destination perform the copy, if it's a pipe, and otherwise the
+        // server of the source. other servers refuse to hand out a splice EP.
+        let to_pipe = match dst.get_splice_ep() {
+            Ok(ep) => {
+                self.delegate_ep(ep)?;
+                true
+            },
+            Err(e) if e.code() == Code::InvArgs || e.code() == Code::NotSup => {
+                let ep = self.get_splice_ep()?;
+                dst.delegate_ep(ep)?;
+                false
+            },
+            Err(e) => return Err(e),
+        };
+
+        let mut remaining = len;
+        while remaining > 0 {
+            let amount = self.next_in(remaining)?;
+            if amount == 0 {
+                break;
+            }
+            let amount = dst.next_out(amount)?;
+
+            let (pipe, other_off) = if to_pipe {
+                (&*dst, self.off + self.pos)
+            }
+            else {
+                (&*self, dst.off + dst.pos)
+            };
+            send_recv_res!(
+                &pipe.sgate,
+                RecvGate::def(),
+                PipeOperation::SPLICE,
+                pipe.file_id(),
+                to_pipe,
+                pipe.off + pipe.pos,
+                other_off as goff,
+                amount
+            )?;
+
+            dst.pos += amount;
+            remaining -= amount;
+            // without consuming, we would get the same data again
+            if !consume {
+                break;
+            }
+            self.pos += amount;
+        }
+
+        self.writing = false;
+        dst.writing = true;
+        Ok(len - remaining)
+    }
+
     #[inline(never)]
     fn enable_notifications(&mut self) -> Result<(), Error> {
         if self.nb_state.is_some() {
@@ -429,6 +544,63 @@ impl File for GenericFile {
         Ok(())
     }
 
+    fn get_tmode(&self) -> Result<TMode, Error> {
+        let mut reply = send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            GenFileOp::GET_TMODE,
+            self.file_id()
+        )?;
+        reply.pop()
+    }
+
+    fn set_tmode(&mut self, mode: TMode) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            GenFileOp::SET_TMODE,
+            self.file_id(),
+            mode
+        )
+        .map(|_| ())
+    }
+
+    fn get_winsize(&self) -> Result<WinSize, Error> {
+        let mut reply = send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            VTermOperation::GET_WINSIZE,
+            self.file_id()
+        )?;
+        Ok(WinSize {
+            cols: reply.pop()?,
+            rows: reply.pop()?,
+        })
+    }
+
+    fn set_winsize(&mut self, size: WinSize) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            VTermOperation::SET_WINSIZE,
+            self.file_id(),
+            size.cols,
+            size.rows
+        )
+        .map(|_| ())
+    }
+
+    fn set_echo(&mut self, echo: bool) -> Result<(), Error> {
+        send_recv_res!(
+            &self.sgate,
+            RecvGate::def(),
+            VTermOperation::SET_ECHO,
+            self.file_id(),
+            echo
+        )
+        .map(|_| ())
+    }
+
     fn file_type(&self) -> u8 {
         b'F'
     }
@@ -520,6 +692,10 @@ impl Read for GenericFile {
             self.pos += amount;
         }
         self.writing = false;
+        // in packet mode, the rest of the packet is discarded
+        if self.flags.contains(OpenFlags::PACKET) {
+            self.pos = self.len;
+        }
         Ok(amount)
     }
 }
@@ -551,6 +727,10 @@ impl Write for GenericFile {
             self.pos += amount;
         }
         self.writing = true;
+        // in packet mode, every write is a packet on its own
+        if self.flags.contains(OpenFlags::PACKET) {
+            self.submit(false)?;
+        }
         Ok(amount)
     }
 }
@@ -19,7 +19,7 @@
 use crate::com::MemGate;
 use crate::errors::Error;
 use crate::rc::Rc;
-use crate::session::{Pipe, Pipes};
+use crate::session::{Pipe, PipeFlags, Pipes};
 use crate::tiles::Activity;
 use crate::vfs::{Fd, FileRef, GenericFile};
 
@@ -34,7 +34,18 @@ impl IndirectPipe {
     /// Creates a new pipe at pipe service `pipes` using `mem` as the shared memory of `mem_size`
     /// bytes.
     pub fn new(pipes: &Pipes, mem: &MemGate, mem_size: usize) -> Result<Self, Error> {
-        let pipe = Rc::new(pipes.create_pipe(mem, mem_size)?);
+        Self::new_with(pipes, mem, mem_size, PipeFlags::empty())
+    }
+
+    /// Creates a new pipe with given flags at pipe service `pipes` using `mem` as the shared memory
+    /// of `mem_size` bytes.
+    pub fn new_with(
+        pipes: &Pipes,
+        mem: &MemGate,
+        mem_size: usize,
+        flags: PipeFlags,
+    ) -> Result<Self, Error> {
+        let pipe = Rc::new(pipes.create_pipe_with(mem, mem_size, flags)?);
         let mut files = Activity::own().files();
         let rd_fd = files.add(pipe.create_chan(true)?)?;
         let wr_fd = files.add(pipe.create_chan(false)?)?;
@@ -72,3 +83,41 @@ impl Drop for IndirectPipe {
         self.close_writer();
     }
 }
+
+/// A bi-directional channel between two ends that exchange packets.
+pub struct DuplexPipe {
+    _pipe: Rc<Pipe>,
+    fds: [Fd; 2],
+}
+
+impl DuplexPipe {
+    /// Creates a new duplex pipe at pipe service `pipes` using `mem` as the shared memory of
+    /// `mem_size` bytes. Each direction uses one half of the memory.
+    pub fn new(pipes: &Pipes, mem: &MemGate, mem_size: usize) -> Result<Self, Error> {
+        let pipe = Rc::new(pipes.create_pipe_with(mem, mem_size, PipeFlags::DUPLEX)?);
+        let mut files = Activity::own().files();
+        let fd0 = files.add(pipe.create_end(0)?)?;
+        let fd1 = files.add(pipe.create_end(1)?)?;
+        Ok(DuplexPipe {
+            fds: [fd0, fd1],
+            _pipe: pipe,
+        })
+    }
+
+    /// Returns the file for the end with index `idx` (0 or 1).
+    pub fn end(&self, idx: usize) -> Option<FileRef<GenericFile>> {
+        Activity::own().files().get_as(self.fds[idx])
+    }
+
+    /// Closes the end with index `idx` (0 or 1).
+    pub fn close_end(&self, idx: usize) {
+        Activity::own().files().remove(self.fds[idx]);
+    }
+}
+
+impl Drop for DuplexPipe {
+    fn drop(&mut self) {
+        self.close_end(0);
+        self.close_end(1);
+    }
+}
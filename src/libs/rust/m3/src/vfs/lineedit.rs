@@ -0,0 +1,345 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use core::mem;
+use core::str;
+
+use crate::col::Vec;
+use crate::format;
+
+/// The maximum number of lines in the history
+const MAX_HISTORY: usize = 32;
+/// The maximum number of parameter bytes in an escape sequence
+const MAX_PARAMS: usize = 16;
+
+/// The result of feeding a byte into the [`LineEditor`]
+#[derive(Debug, Eq, PartialEq)]
+pub enum EditAction {
+    None,
+    /// The line is complete and can be taken via [`LineEditor::take_line`]
+    Line,
+    /// ^D was pressed
+    Eof,
+    /// ^C was pressed
+    Signal,
+    /// The terminal reported its size (columns and rows)
+    WinSize(usize, usize),
+}
+
+#[derive(Debug)]
+enum EscState {
+    None,
+    // received ESC
+    Esc,
+    // received ESC [
+    Csi,
+    // received ESC O
+    Ss3,
+}
+
+/// The line discipline for the cooked mode
+///
+/// The editor receives the input byte by byte, maintains the current line including the cursor
+/// position and a history of previous lines, and produces the ANSI escape sequences to update the
+/// terminal accordingly.
+#[derive(Debug)]
+pub struct LineEditor {
+    line: Vec<u8>,
+    cursor: usize,
+    history: Vec<Vec<u8>>,
+    // the position in the history while browsing through it and the line we started with
+    hist_pos: Option<usize>,
+    saved: Vec<u8>,
+    esc: EscState,
+    params: Vec<u8>,
+}
+
+impl LineEditor {
+    pub const fn new() -> Self {
+        LineEditor {
+            line: Vec::new(),
+            cursor: 0,
+            history: Vec::new(),
+            hist_pos: None,
+            saved: Vec::new(),
+            esc: EscState::None,
+            params: Vec::new(),
+        }
+    }
+
+    /// Returns the current line including the terminating newline and adds it to the history
+    pub fn take_line(&mut self) -> Vec<u8> {
+        let mut line = mem::take(&mut self.line);
+        if !line.is_empty() && self.history.last() != Some(&line) {
+            if self.history.len() == MAX_HISTORY {
+                self.history.remove(0);
+            }
+            self.history.push(line.clone());
+        }
+        self.cursor = 0;
+        self.hist_pos = None;
+        line.push(b'\n');
+        line
+    }
+
+    /// Returns the current line without terminating it (e.g., on EOF)
+    pub fn take_partial(&mut self) -> Vec<u8> {
+        self.cursor = 0;
+        self.hist_pos = None;
+        mem::take(&mut self.line)
+    }
+
+    /// Returns true if the editor holds back the bytes of an unfinished escape sequence
+    pub fn in_escape(&self) -> bool {
+        !matches!(self.esc, EscState::None)
+    }
+
+    /// Feeds `b`, received in raw mode, into the editor and appends it to `out`, unless it belongs
+    /// to a cursor position report
+    ///
+    /// The reported size (columns and rows) is returned instead. The bytes of other escape
+    /// sequences are held back until it is clear that they do not form such a report.
+    pub fn filter_report(&mut self, b: u8, out: &mut Vec<u8>) -> Option<(usize, usize)> {
+        match self.esc {
+            EscState::Esc if b == b'[' => {
+                self.esc = EscState::Csi;
+                self.params.clear();
+                return None;
+            },
+            EscState::Csi
+                if (b.is_ascii_digit() || b == b';') && self.params.len() < MAX_PARAMS =>
+            {
+                self.params.push(b);
+                return None;
+            },
+            EscState::Csi if b == b'R' => {
+                if let (Some(rows), Some(cols)) = (self.param(0), self.param(1)) {
+                    self.esc = EscState::None;
+                    return Some((cols, rows));
+                }
+            },
+            _ => {},
+        }
+
+        self.flush_esc(out);
+        if b == 0x1b {
+            self.esc = EscState::Esc;
+        }
+        else {
+            out.push(b);
+        }
+        None
+    }
+
+    /// Feeds `b` into the editor and appends the output for the terminal to `out`
+    pub fn input(&mut self, b: u8, out: &mut Vec<u8>) -> EditAction {
+        match self.esc {
+            EscState::Esc => {
+                self.esc = match b {
+                    b'[' => EscState::Csi,
+                    b'O' => EscState::Ss3,
+                    _ => EscState::None,
+                };
+                self.params.clear();
+                return EditAction::None;
+            },
+            EscState::Csi => {
+                // parameter and intermediate bytes; the final byte ends the sequence
+                if (0x20..0x40).contains(&b) {
+                    if self.params.len() < MAX_PARAMS {
+                        self.params.push(b);
+                    }
+                    return EditAction::None;
+                }
+                self.esc = EscState::None;
+                return self.handle_csi(b, out);
+            },
+            EscState::Ss3 => {
+                self.esc = EscState::None;
+                return self.handle_csi(b, out);
+            },
+            EscState::None => {},
+        }
+
+        match b {
+            // ^A
+            0x01 => self.move_to(0, out),
+            // ^B
+            0x02 => self.move_to(self.cursor.saturating_sub(1), out),
+            // ^C
+            0x03 => return EditAction::Signal,
+            // ^D
+            0x04 => return EditAction::Eof,
+            // ^E
+            0x05 => self.move_to(self.line.len(), out),
+            // ^F
+            0x06 => self.move_to(self.cursor + 1, out),
+            // backspace
+            0x08 | 0x7f => {
+                if self.cursor > 0 {
+                    self.move_to(self.cursor - 1, out);
+                    self.line.remove(self.cursor);
+                    self.redraw_from(self.cursor, out);
+                }
+            },
+            b'\n' => {
+                self.move_to(self.line.len(), out);
+                out.push(b'\n');
+                return EditAction::Line;
+            },
+            // ^K
+            0x0b => {
+                self.line.truncate(self.cursor);
+                out.extend_from_slice(b"\x1b[K");
+            },
+            // ^U
+            0x15 => {
+                let cur = self.cursor;
+                self.move_to(0, out);
+                self.line.drain(0..cur);
+                self.redraw_from(0, out);
+            },
+            // ESC
+            0x1b => self.esc = EscState::Esc,
+            b if !b.is_ascii_control() => {
+                self.line.insert(self.cursor, b);
+                self.cursor += 1;
+                self.redraw_from(self.cursor - 1, out);
+            },
+            _ => {},
+        }
+        EditAction::None
+    }
+
+    fn handle_csi(&mut self, b: u8, out: &mut Vec<u8>) -> EditAction {
+        match b {
+            b'A' => self.history_prev(out),
+            b'B' => self.history_next(out),
+            b'C' => self.move_to(self.cursor + 1, out),
+            b'D' => self.move_to(self.cursor.saturating_sub(1), out),
+            b'H' => self.move_to(0, out),
+            b'F' => self.move_to(self.line.len(), out),
+            b'~' => match self.param(0) {
+                Some(1) | Some(7) => self.move_to(0, out),
+                Some(4) | Some(8) => self.move_to(self.line.len(), out),
+                // delete
+                Some(3) => {
+                    if self.cursor < self.line.len() {
+                        self.line.remove(self.cursor);
+                        self.redraw_from(self.cursor, out);
+                    }
+                },
+                _ => {},
+            },
+            // cursor position report, which we request to determine the window size
+            b'R' => {
+                if let (Some(rows), Some(cols)) = (self.param(0), self.param(1)) {
+                    return EditAction::WinSize(cols, rows);
+                }
+            },
+            _ => {},
+        }
+        EditAction::None
+    }
+
+    /// Appends the held back bytes of an unfinished escape sequence to `out`
+    fn flush_esc(&mut self, out: &mut Vec<u8>) {
+        match self.esc {
+            EscState::None => {},
+            EscState::Esc => out.push(0x1b),
+            EscState::Csi => {
+                out.extend_from_slice(b"\x1b[");
+                out.extend_from_slice(&self.params);
+            },
+            EscState::Ss3 => out.extend_from_slice(b"\x1bO"),
+        }
+        self.esc = EscState::None;
+    }
+
+    fn param(&self, idx: usize) -> Option<usize> {
+        str::from_utf8(&self.params)
+            .ok()?
+            .split(';')
+            .nth(idx)?
+            .parse()
+            .ok()
+    }
+
+    fn history_prev(&mut self, out: &mut Vec<u8>) {
+        let pos = match self.hist_pos {
+            Some(0) => return,
+            Some(p) => p - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.saved = self.line.clone();
+                self.history.len() - 1
+            },
+        };
+        self.hist_pos = Some(pos);
+        let line = self.history[pos].clone();
+        self.replace_line(line, out);
+    }
+
+    fn history_next(&mut self, out: &mut Vec<u8>) {
+        let line = match self.hist_pos {
+            None => return,
+            Some(p) if p + 1 < self.history.len() => {
+                self.hist_pos = Some(p + 1);
+                self.history[p + 1].clone()
+            },
+            Some(_) => {
+                self.hist_pos = None;
+                mem::take(&mut self.saved)
+            },
+        };
+        self.replace_line(line, out);
+    }
+
+    fn replace_line(&mut self, line: Vec<u8>, out: &mut Vec<u8>) {
+        self.move_to(0, out);
+        self.line = line;
+        self.cursor = self.line.len();
+        self.redraw_from(0, out);
+    }
+
+    /// Moves the cursor to `pos` (limited to the end of the line)
+    fn move_to(&mut self, pos: usize, out: &mut Vec<u8>) {
+        let pos = pos.min(self.line.len());
+        if pos < self.cursor {
+            out.extend_from_slice(format!("\x1b[{}D", self.cursor - pos).as_bytes());
+        }
+        else if pos > self.cursor {
+            out.extend_from_slice(format!("\x1b[{}C", pos - self.cursor).as_bytes());
+        }
+        self.cursor = pos;
+    }
+
+    /// Writes the line from `start` on, assuming that the terminal cursor is at `start`, and moves
+    /// the terminal cursor back to the current position afterwards
+    fn redraw_from(&mut self, start: usize, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.line[start..]);
+        out.extend_from_slice(b"\x1b[K");
+        let back = self.line.len() - self.cursor;
+        if back > 0 {
+            out.extend_from_slice(format!("\x1b[{}D", back).as_bytes());
+        }
+    }
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
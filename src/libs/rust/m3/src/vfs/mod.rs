@@ -26,6 +26,7 @@ mod filesystem;
 mod filetable;
 mod genericfile;
 mod indirpipe;
+mod lineedit;
 mod mounttable;
 #[allow(clippy::module_inception)]
 mod vfs;
@@ -40,13 +41,16 @@ pub type BlockId = u32;
 
 pub use self::bufio::{BufReader, BufWriter};
 pub use self::dir::{read_dir, DirEntry, ReadDir};
-pub use self::file::{File, FileEvent, FileInfo, FileMode, Map, OpenFlags, Seek, SeekMode};
+pub use self::file::{
+    File, FileEvent, FileInfo, FileMode, Map, OpenFlags, Seek, SeekMode, TMode, WinSize,
+};
 pub use self::fileref::FileRef;
 pub use self::filesystem::{FSOperation, FileSystem};
 pub(crate) use self::filetable::INV_FD;
 pub use self::filetable::{Fd, FileTable};
 pub use self::genericfile::{GenFileOp, GenericFile};
-pub use self::indirpipe::IndirectPipe;
+pub use self::indirpipe::{DuplexPipe, IndirectPipe};
+pub use self::lineedit::{EditAction, LineEditor};
 pub use self::mounttable::{FSHandle, MountTable};
 pub use self::waiter::FileWaiter;
 
@@ -140,6 +140,14 @@ pub fn mkdir(path: &str, mode: FileMode) -> Result<(), Error> {
     with_path(path, |fs, fs_path| fs.borrow().mkdir(fs_path, mode))
 }
 
+/// Creates a FIFO with permissions `mode` at `path`.
+///
+/// Opening the FIFO connects to a pipe at the pipes server that is shared by everyone that opened
+/// the same FIFO.
+pub fn mkfifo(path: &str, mode: FileMode) -> Result<(), Error> {
+    with_path(path, |fs, fs_path| fs.borrow().mkfifo(fs_path, mode))
+}
+
 /// Removes the directory at `path`, if it is empty.
 pub fn rmdir(path: &str) -> Result<(), Error> {
     with_path(path, |fs, fs_path| fs.borrow().rmdir(fs_path))
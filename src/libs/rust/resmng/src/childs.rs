@@ -14,6 +14,7 @@
  */
 
 use bitflags::bitflags;
+use core::cmp;
 use core::fmt;
 use m3::boxed::Box;
 use m3::cap::Selector;
@@ -32,15 +33,20 @@ use m3::println;
 use m3::quota::{Id as QuotaId, Quota};
 use m3::rc::Rc;
 use m3::serialize::M3Deserializer;
-use m3::session::{ResMngActInfo, ResMngActInfoResult};
+use m3::session::{
+    ResMngActInfo, ResMngActInfoResult, ResMngChildInfo, ResMngQuotaInfo, ResMngQuotaKind,
+    ResMngQuotaResult,
+};
 use m3::syscalls;
 use m3::tcu;
 use m3::tiles::{
     Activity, ChildActivity, KMem, Mapper, RunningActivity, RunningProgramActivity, TileQuota,
 };
+use m3::time::{TimeDuration, TimeInstant};
+use m3::tmif::Signal;
 use m3::vfs::{File, FileRef};
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, RestartPolicy};
 use crate::gates;
 use crate::memory::{self, Allocation, MemPool};
 use crate::sems;
@@ -51,6 +57,12 @@ use crate::{events, subsys};
 
 pub type Id = u32;
 
+/// The maximum size of configurations for childs that are started at runtime
+const MAX_CFG_SIZE: usize = 4096;
+
+/// The exit code of killed childs, which corresponds to a termination via [`Signal::TERM`]
+const KILL_EXITCODE: i32 = 128 + Signal::TERM.val as i32;
+
 pub struct ChildMem {
     id: Id,
     pool: Rc<RefCell<MemPool>>,
@@ -88,6 +100,48 @@ impl ChildMem {
     pub(crate) fn free_mem(&self, size: goff) {
         self.quota.replace(self.quota.get() + size);
     }
+
+    /// Allocates `size` bytes from the pool and charges them to this memory
+    ///
+    /// In contrast to [`Child::alloc_local`], the memory does not belong to a child and is
+    /// therefore not given back if the child exits. Instead, it needs to be given back via
+    /// [`ChildMem::free_detached`].
+    pub fn alloc_detached(&self, size: goff, perm: Perm) -> Result<(MemGate, Allocation), Error> {
+        log!(
+            crate::LOG_MEM,
+            "{:?}: allocate_detached(size={:#x}, perm={:?})",
+            self,
+            size,
+            perm
+        );
+
+        if !self.have_quota(size) {
+            return Err(Error::new(Code::NoSpace));
+        }
+
+        let alloc = self.pool.borrow_mut().allocate(size)?;
+        let mem_sel = self.pool.borrow().mem_cap(alloc.slice_id());
+        match MemGate::new_bind(mem_sel).derive(alloc.addr(), alloc.size() as usize, perm) {
+            Ok(mgate) => {
+                self.alloc_mem(alloc.size());
+                Ok((mgate, alloc))
+            },
+            Err(e) => {
+                self.pool.borrow_mut().free(alloc);
+                Err(e)
+            },
+        }
+    }
+
+    /// Revokes the access to the given memory, allocated via [`ChildMem::alloc_detached`], and
+    /// gives it back
+    pub fn free_detached(&self, mgate: MemGate, alloc: Allocation) {
+        log!(crate::LOG_MEM, "{:?}: free_detached({:?})", self, alloc);
+
+        drop(mgate);
+        self.pool.borrow_mut().free(alloc);
+        self.free_mem(alloc.size());
+    }
 }
 
 impl fmt::Debug for ChildMem {
@@ -102,6 +156,8 @@ pub struct Resources {
     services: Vec<(Id, Selector)>,
     sessions: Vec<(usize, Session)>,
     mem: Vec<(Option<Selector>, Allocation)>,
+    // the memory allocated via alloc_local with the selector of the MemGate
+    local_mem: Vec<(Selector, Allocation)>,
     tiles: Vec<(tiles::TileUsage, usize, Selector)>,
     sgates: Vec<SendGate>,
 }
@@ -126,6 +182,27 @@ pub trait Child {
     fn res_mut(&mut self) -> &mut Resources;
     fn kmem(&self) -> Option<Rc<KMem>>;
 
+    /// Creates a new child with given id that is started with the same configuration and
+    /// resources. Returns `None` if the child cannot be restarted (anymore).
+    fn restart(&self, _id: Id) -> Option<Box<OwnChild>> {
+        None
+    }
+
+    /// Returns the number of times this child has been restarted
+    fn restarts(&self) -> u32 {
+        0
+    }
+
+    /// Returns whether the child has been added at runtime instead of via the boot config
+    fn is_dynamic(&self) -> bool {
+        false
+    }
+
+    /// Returns the name of the domain the child runs in (empty for unnamed domains)
+    fn domain(&self) -> &str {
+        ""
+    }
+
     fn delegate(&self, src: Selector, dst: Selector) -> Result<(), Error> {
         let crd = CapRngDesc::new(CapType::OBJECT, src, 1);
         syscalls::exchange(self.activity_sel(), crd, dst, false)
@@ -176,6 +253,7 @@ pub trait Child {
             sessions,
             true,
         )?;
+        services::get_mut_by_id(id).unwrap().set_checks(sdesc);
 
         sdesc.mark_used();
         self.res_mut().services.push((id, srv_sel));
@@ -183,6 +261,20 @@ pub trait Child {
         Ok(())
     }
 
+    fn serv_ready(&mut self, sel: Selector) -> Result<(), Error> {
+        log!(crate::LOG_SERV, "{}: serv_ready(sel={})", self.name(), sel);
+
+        let sid = self
+            .res()
+            .services
+            .iter()
+            .find(|t| t.1 == sel)
+            .ok_or_else(|| Error::new(Code::InvArgs))?
+            .0;
+        services::set_ready(sid);
+        Ok(())
+    }
+
     fn unreg_service(&mut self, sel: Selector) -> Result<(), Error> {
         log!(crate::LOG_SERV, "{}: unreg_serv(sel={})", self.name(), sel);
 
@@ -214,11 +306,40 @@ pub trait Child {
         let alloc = self.mem().pool.borrow_mut().allocate(size)?;
         let mem_sel = self.mem().pool.borrow().mem_cap(alloc.slice_id());
         let mgate = MemGate::new_bind(mem_sel).derive(alloc.addr(), alloc.size() as usize, perm)?;
-        // TODO this memory is currently only free'd on child exit
         self.add_mem(alloc, None);
+        self.res_mut().local_mem.push((mgate.sel(), alloc));
         Ok(mgate)
     }
 
+    fn free_local(&mut self, mgate: MemGate) -> Result<(), Error> {
+        log!(
+            crate::LOG_MEM,
+            "{}: free_local(sel={})",
+            self.name(),
+            mgate.sel()
+        );
+
+        let local = &mut self.res_mut().local_mem;
+        let alloc = local
+            .iter()
+            .position(|(s, _)| *s == mgate.sel())
+            .map(|idx| local.remove(idx).1)
+            .ok_or_else(|| Error::new(Code::InvArgs))?;
+        // revoke the access to the memory before we hand it out again
+        drop(mgate);
+
+        let idx = self
+            .res()
+            .mem
+            .iter()
+            .position(|(s, a)| {
+                s.is_none() && a.slice_id() == alloc.slice_id() && a.addr() == alloc.addr()
+            })
+            .unwrap();
+        self.remove_mem_by_idx(idx);
+        Ok(())
+    }
+
     fn alloc_mem(&mut self, dst_sel: Selector, size: goff, perm: Perm) -> Result<(), Error> {
         log!(
             crate::LOG_MEM,
@@ -499,6 +620,7 @@ pub trait Child {
             self.cfg().unreg_service(serv.name());
         }
 
+        self.res_mut().local_mem.clear();
         while !self.res().mem.is_empty() {
             self.remove_mem_by_idx(0);
         }
@@ -727,6 +849,7 @@ pub fn get_info(id: Id, idx: Option<usize>) -> Result<ResMngActInfoResult, Error
                     eps: *tile_quota.endpoints(),
                     time: *tile_quota.time(),
                     pts: *tile_quota.page_tables(),
+                    usage: Activity::own().usage().unwrap_or_default(),
                     tile: Activity::own().tile_id(),
                 }));
             }
@@ -765,6 +888,7 @@ pub fn get_info(id: Id, idx: Option<usize>) -> Result<ResMngActInfoResult, Error
                 eps: *tile_quota.endpoints(),
                 time: *tile_quota.time(),
                 pts: *tile_quota.page_tables(),
+                usage: syscalls::activity_usage(act.activity_sel()).unwrap_or_default(),
                 tile: act.our_tile().tile_id(),
             }))
         }
@@ -775,6 +899,271 @@ pub fn get_info(id: Id, idx: Option<usize>) -> Result<ResMngActInfoResult, Error
     }
 }
 
+fn child_quota_info(child: &dyn Child, layer: u32) -> Result<ResMngQuotaInfo, Error> {
+    let kmem = child
+        .kmem()
+        .map(|km| km.quota())
+        .unwrap_or_else(|| Ok(Quota::default()))?;
+    let tile_quota = child
+        .child_tile()
+        .map(|tile| tile.tile_obj().quota())
+        .unwrap_or_else(|| Ok(TileQuota::default()))?;
+    Ok(ResMngQuotaInfo {
+        layer,
+        kind: ResMngQuotaKind::Activity,
+        name: child.name().to_string(),
+        umem: Quota::new(
+            child.mem().id as QuotaId,
+            child.mem().total as usize,
+            child.mem().quota.get() as usize,
+        ),
+        kmem,
+        eps: *tile_quota.endpoints(),
+        time: *tile_quota.time(),
+        pts: *tile_quota.page_tables(),
+    })
+}
+
+fn add_child_quotas(
+    childs: &ChildManager,
+    child: &dyn Child,
+    layer: u32,
+    caller: Id,
+    tree: &mut Vec<ResMngQuotaInfo>,
+    caller_layer: &mut Option<u32>,
+) -> Result<(), Error> {
+    if child.id() == caller {
+        *caller_layer = Some(layer);
+    }
+    tree.push(child_quota_info(child, layer)?);
+    for (cid, _) in &child.res().childs {
+        let c = childs.child_by_id(*cid).unwrap();
+        add_child_quotas(childs, c, layer + 1, caller, tree, caller_layer)?;
+    }
+    Ok(())
+}
+
+/// Builds our part of the quota tree starting at layer `base` and returns it together with the
+/// layer of the child with id `caller`.
+fn own_quota_tree(caller: Id, base: u32) -> Result<(Vec<ResMngQuotaInfo>, u32), Error> {
+    let mut tree = Vec::new();
+    let mut caller_layer = None;
+
+    let umem = {
+        let mem = memory::container();
+        Quota::new(0, mem.capacity() as usize, mem.available() as usize)
+    };
+    let kmem = Activity::own().kmem().quota()?;
+    let tile_quota = Activity::own().tile().quota()?;
+    tree.push(ResMngQuotaInfo {
+        layer: base,
+        kind: ResMngQuotaKind::Activity,
+        name: env::args().next().unwrap().to_string(),
+        umem,
+        kmem,
+        eps: *tile_quota.endpoints(),
+        time: *tile_quota.time(),
+        pts: *tile_quota.page_tables(),
+    });
+
+    let mut childs = borrow_mut();
+    let caller_tile = childs.child_by_id(caller).unwrap().our_tile().tile_id();
+    // childs that are resource managers report their subtree themselves. to keep the tree in
+    // depth-first order, the domain of the caller is therefore listed last.
+    let mut cidx = Vec::new();
+    for id in childs.ids.clone() {
+        let c = childs.child_by_id_mut(id).unwrap();
+        if !c.foreign() && !(c.id() == caller && c.subsys().is_some()) {
+            cidx.push((c.our_tile().tile_id(), id));
+        }
+    }
+
+    let doms = subsys::domains();
+    let order = doms
+        .iter()
+        .filter(|d| d.tile.tile_id() != caller_tile)
+        .chain(doms.iter().filter(|d| d.tile.tile_id() == caller_tile));
+    for d in order {
+        // the domain lists the quotas that are shared by its childs; child-specific kernel memory
+        // and tile quotas are derived from them, whereas child-specific user memory is not.
+        let tile_quota = d.base.as_ref().unwrap_or(&d.tile).tile_obj().quota()?;
+        tree.push(ResMngQuotaInfo {
+            layer: base + 1,
+            kind: ResMngQuotaKind::Domain,
+            name: d.name.clone(),
+            umem: Quota::new(
+                d.umem.id as QuotaId,
+                d.umem.total as usize,
+                d.umem.quota.get() as usize,
+            ),
+            kmem: d.kmem.quota()?,
+            eps: *tile_quota.endpoints(),
+            time: *tile_quota.time(),
+            pts: *tile_quota.page_tables(),
+        });
+
+        for (_, id) in cidx.iter().filter(|(t, _)| *t == d.tile.tile_id()) {
+            let c = childs.child_by_id(*id).unwrap();
+            add_child_quotas(&childs, c, base + 2, caller, &mut tree, &mut caller_layer)?;
+        }
+    }
+
+    // the caller is not part of the tree if it's a resource manager
+    Ok((tree, caller_layer.unwrap_or(base + 2)))
+}
+
+pub fn get_quota_info(id: Id, idx: Option<usize>) -> Result<ResMngQuotaResult, Error> {
+    if !borrow_mut().child_by_id(id).unwrap().cfg().can_get_info() {
+        return Err(Error::new(Code::NoPerm));
+    }
+
+    match idx {
+        Some(idx) => {
+            {
+                let childs = borrow_mut();
+                let tree = childs
+                    .quota_trees
+                    .iter()
+                    .find(|t| t.caller == id)
+                    .ok_or_else(|| Error::new(Code::InvState))?;
+                if idx >= tree.parent_num {
+                    return match tree.nodes.get(idx - tree.parent_num) {
+                        Some(node) => Ok(ResMngQuotaResult::Info(node.clone())),
+                        None => Err(Error::new(Code::NotFound)),
+                    };
+                }
+            }
+
+            // our parent has kept its part of the tree for us as well
+            Ok(ResMngQuotaResult::Info(
+                Activity::own().resmng().unwrap().get_quota_info(idx)?,
+            ))
+        },
+
+        None => {
+            // the part above us is provided by our parent, if we are allowed to ask for it
+            let (parent_num, parent_layer) = if let Some(presmng) = Activity::own().resmng() {
+                match presmng.get_quota_count() {
+                    Err(e) if e.code() == Code::NoPerm => (0, 0),
+                    Err(e) => return Err(e),
+                    Ok(res) => res,
+                }
+            }
+            else {
+                (0, 0)
+            };
+
+            // build the tree once and keep it for the following requests for the single nodes
+            let (nodes, layer) = own_quota_tree(id, parent_layer)?;
+            let total = parent_num + nodes.len();
+
+            let mut childs = borrow_mut();
+            childs.quota_trees.retain(|t| t.caller != id);
+            childs.quota_trees.push(QuotaTree {
+                caller: id,
+                parent_num,
+                nodes,
+            });
+            Ok(ResMngQuotaResult::Count((total, layer)))
+        },
+    }
+}
+
+pub fn start_child(id: Id, mgate_sel: Selector, size: usize, domain: &str) -> Result<(), Error> {
+    let mgate = {
+        let mut childs = borrow_mut();
+        let child = childs.child_by_id_mut(id).unwrap();
+        log!(
+            crate::LOG_CHILD,
+            "{}: start_child(mgate={}, size={}, domain={})",
+            child.name(),
+            mgate_sel,
+            size,
+            domain
+        );
+
+        if !child.cfg().can_admin() {
+            return Err(Error::new(Code::NoPerm));
+        }
+        if size > MAX_CFG_SIZE {
+            return Err(Error::new(Code::InvArgs));
+        }
+        MemGate::new_owned_bind(child.obtain(mgate_sel)?)
+    };
+
+    let xml = mgate.read_into_vec::<u8>(size, 0)?;
+    let xml = String::from_utf8(xml).map_err(|_| Error::new(Code::InvArgs))?;
+    let cfg = AppConfig::parse(&xml).map_err(|e| {
+        println!("Invalid configuration for child: {}", e);
+        Error::new(e.code())
+    })?;
+    subsys::add_child(domain, cfg)
+}
+
+pub fn stop_child_async(id: Id, name: &str) -> Result<(), Error> {
+    {
+        let childs = borrow_mut();
+        let child = childs.child_by_id(id).unwrap();
+        log!(
+            crate::LOG_CHILD,
+            "{}: stop_child(name={})",
+            child.name(),
+            name
+        );
+
+        if !child.cfg().can_admin() {
+            return Err(Error::new(Code::NoPerm));
+        }
+    }
+
+    ChildManager::stop_childs_async(name)
+}
+
+pub fn kill_child(id: Id, name: &str) -> Result<(), Error> {
+    let childs = borrow_mut();
+    let child = childs.child_by_id(id).unwrap();
+    log!(
+        crate::LOG_CHILD,
+        "{}: kill_child(name={})",
+        child.name(),
+        name
+    );
+
+    if !child.cfg().can_admin() {
+        return Err(Error::new(Code::NoPerm));
+    }
+
+    childs.kill_childs(name)
+}
+
+pub fn get_child_info(id: Id, idx: usize) -> Result<ResMngChildInfo, Error> {
+    let childs = borrow_mut();
+    if !childs.child_by_id(id).unwrap().cfg().can_admin() {
+        return Err(Error::new(Code::NoPerm));
+    }
+
+    // foreign childs are managed by their parent
+    let own = childs
+        .ids
+        .iter()
+        .map(|id| childs.child_by_id(*id).unwrap())
+        .filter(|c| !c.foreign())
+        .collect::<Vec<_>>();
+
+    match own.get(idx) {
+        Some(c) => Ok(ResMngChildInfo {
+            id: c.id(),
+            name: c.name().clone(),
+            domain: c.domain().to_string(),
+            daemon: c.daemon(),
+            running: true,
+            restarts: c.restarts(),
+        }),
+        // afterwards, list the childs that have not been started yet
+        None => subsys::delayed_info(idx - own.len()).ok_or_else(|| Error::new(Code::NotFound)),
+    }
+}
+
 pub struct OwnChild {
     id: Id,
     // the activity has to be dropped before we drop the tile
@@ -790,6 +1179,10 @@ pub struct OwnChild {
     sub: Option<SubsystemBuilder>,
     daemon: bool,
     kmem: Rc<KMem>,
+    domain: String,
+    dynamic: bool,
+    restarts: u32,
+    restart_at: Option<TimeInstant>,
 }
 
 impl OwnChild {
@@ -805,6 +1198,8 @@ impl OwnChild {
         mem: Rc<ChildMem>,
         cfg: Rc<AppConfig>,
         sub: Option<SubsystemBuilder>,
+        domain: String,
+        dynamic: bool,
     ) -> Self {
         OwnChild {
             id,
@@ -820,9 +1215,18 @@ impl OwnChild {
             daemon,
             activity: None,
             kmem,
+            domain,
+            dynamic,
+            restarts: 0,
+            restart_at: None,
         }
     }
 
+    /// Returns the point in time at which this child should be restarted, if any
+    pub fn restart_at(&self) -> Option<TimeInstant> {
+        self.restart_at
+    }
+
     pub fn start(
         &mut self,
         act: ChildActivity,
@@ -843,13 +1247,18 @@ impl OwnChild {
     }
 
     pub fn has_unmet_reqs(&self) -> bool {
+        if let Some(at) = self.restart_at {
+            if TimeInstant::now() < at {
+                return true;
+            }
+        }
         for sess in self.cfg().sessions() {
-            if sess.is_dep() && services::get_mut_by_name(sess.name().global()).is_err() {
+            if sess.is_dep() && !services::is_ready(sess.name().global()) {
                 return true;
             }
         }
         for scrt in self.cfg().sess_creators() {
-            if services::get_mut_by_name(scrt.serv_name()).is_err() {
+            if !services::is_ready(scrt.serv_name()) {
                 return true;
             }
         }
@@ -926,6 +1335,47 @@ impl Child for OwnChild {
     fn kmem(&self) -> Option<Rc<KMem>> {
         Some(self.kmem.clone())
     }
+
+    fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    fn is_dynamic(&self) -> bool {
+        self.dynamic
+    }
+
+    fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    fn restart(&self, id: Id) -> Option<Box<OwnChild>> {
+        // subsystems have passed down their resources, so that we cannot start them again
+        if self.sub.is_some() || self.restarts >= self.cfg.restart_retries() {
+            return None;
+        }
+
+        // double the delay with every restart
+        let backoff = self.cfg.restart_backoff() << cmp::min(self.restarts, 16);
+        Some(Box::new(OwnChild {
+            id,
+            activity: None,
+            our_tile: self.our_tile.clone(),
+            _domain_tile: self._domain_tile.clone(),
+            child_tile: self.child_tile.clone(),
+            name: self.name.clone(),
+            args: self.args.clone(),
+            cfg: self.cfg.clone(),
+            mem: self.mem.clone(),
+            res: Resources::default(),
+            sub: None,
+            daemon: self.daemon,
+            kmem: self.kmem.clone(),
+            domain: self.domain.clone(),
+            dynamic: self.dynamic,
+            restarts: self.restarts + 1,
+            restart_at: Some(TimeInstant::now() + TimeDuration::from_nanos(backoff)),
+        }))
+    }
 }
 
 impl fmt::Debug for OwnChild {
@@ -1063,13 +1513,25 @@ bitflags! {
     }
 }
 
+/// The function that is called for crashed childs before their resources are removed
+pub type CrashHandler = fn(Id, &str, &kif::tilemux::CrashInfo);
+
+/// The quota tree as it was when the child asked for the number of nodes
+struct QuotaTree {
+    caller: Id,
+    parent_num: usize,
+    nodes: Vec<ResMngQuotaInfo>,
+}
+
 pub struct ChildManager {
     flags: Flags,
     childs: Treap<Id, Box<dyn Child>>,
     ids: Vec<Id>,
+    quota_trees: Vec<QuotaTree>,
     next_id: Id,
     daemons: usize,
     foreigns: usize,
+    crash_handler: Option<CrashHandler>,
 }
 
 static MNG: StaticRefCell<ChildManager> = StaticRefCell::new(ChildManager::new());
@@ -1091,12 +1553,22 @@ impl ChildManager {
             flags: Flags::STARTING,
             childs: Treap::new(),
             ids: Vec::new(),
+            quota_trees: Vec::new(),
             next_id: 0,
             daemons: 0,
             foreigns: 0,
+            crash_handler: None,
         }
     }
 
+    pub fn set_crash_handler(&mut self, handler: CrashHandler) {
+        self.crash_handler = Some(handler);
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.flags.contains(Flags::SHUTDOWN)
+    }
+
     pub fn should_stop(&self) -> bool {
         // don't stop if we didn't have a child yet. this is necessary, because we use derive_srv
         // asynchronously and thus switch to a different thread while starting a subsystem. thus, if
@@ -1183,7 +1655,11 @@ impl ChildManager {
 
         Self::kill_child_async(upcall.act_sel, upcall.exitcode);
 
-        // wait for the next
+        Self::wait_for_next_async();
+    }
+
+    /// Shuts down if only daemons are left or waits for the next exiting child otherwise
+    fn wait_for_next_async() {
         {
             let mut childs = borrow_mut();
             let no_wait_childs = childs.daemons() + childs.foreigns();
@@ -1214,11 +1690,218 @@ impl ChildManager {
         };
 
         if let Some(id) = maybe_id {
+            // fetch the crash information before the child's activity and resources are gone
+            if exitcode != 0 {
+                if let Ok(info) = syscalls::activity_crash(sel) {
+                    Self::handle_crash(id, &info);
+                }
+            }
+
+            // remember the child's services to notify their clients in case of a restart
+            let servs = {
+                let childs = borrow_mut();
+                let child = childs.child_by_id(id).unwrap();
+                child.res().services.iter().map(|s| s.0).collect::<Vec<_>>()
+            };
+
             let child = Self::remove_rec_async(id).unwrap();
 
             if exitcode != 0 {
                 println!("Child '{}' exited with exitcode {}", child.name(), exitcode);
             }
+
+            if !Self::restart_child(child.as_ref(), &servs, exitcode) && child.is_dynamic() {
+                subsys::remove_rgates(&child.cfg());
+            }
+        }
+    }
+
+    /// Stops all own childs with given name without restarting them.
+    pub fn stop_childs_async(name: &str) -> Result<(), Error> {
+        let ids = {
+            let childs = borrow_mut();
+            childs
+                .ids
+                .iter()
+                .copied()
+                .filter(|&id| {
+                    let child = childs.child_by_id(id).unwrap();
+                    !child.foreign() && child.name() == name
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // childs that wait for their start or restart are simply dropped
+        let delayed = subsys::remove_delayed(name);
+        if ids.is_empty() && delayed == 0 {
+            return Err(Error::new(Code::NotFound));
+        }
+
+        for id in ids {
+            println!("Stopping child '{}'", name);
+            if let Some(child) = Self::remove_rec_async(id) {
+                if child.is_dynamic() {
+                    subsys::remove_rgates(&child.cfg());
+                }
+            }
+        }
+
+        // in contrast to exits, stopping childs never shuts us down, because that is not what the
+        // admin asked for
+        let mut childs = borrow_mut();
+        if !childs.should_stop() {
+            childs.start_waiting(1);
+        }
+        Ok(())
+    }
+
+    /// Kills all own childs with given name. As for crashed childs, the exit triggers a restart
+    /// according to the child's restart policy.
+    pub fn kill_childs(&self, name: &str) -> Result<(), Error> {
+        let mut found = false;
+        for id in &self.ids {
+            let child = self.child_by_id(*id).unwrap();
+            if !child.foreign() && child.name() == name {
+                println!("Killing child '{}'", name);
+                // the kernel notifies us about the exit, which triggers the restart
+                syscalls::activity_ctrl(
+                    child.activity_sel(),
+                    kif::syscalls::ActivityOp::STOP,
+                    KILL_EXITCODE as u64,
+                )?;
+                found = true;
+            }
+        }
+
+        if found {
+            Ok(())
+        }
+        else {
+            Err(Error::new(Code::NotFound))
+        }
+    }
+
+    /// Handles a service of the given child that does not respond to liveness probes anymore. If
+    /// the child has a restart policy, it is stopped to restart it.
+    pub fn service_dead(id: Id, serv: &str) {
+        let childs = borrow_mut();
+        let child = match childs.child_by_id(id) {
+            Some(c) => c,
+            None => return,
+        };
+
+        if child.cfg().restart_policy() == RestartPolicy::Never {
+            println!(
+                "Service '{}' of child '{}' does not respond",
+                serv,
+                child.name()
+            );
+        }
+        else {
+            println!(
+                "Service '{}' of child '{}' does not respond; stopping child",
+                serv,
+                child.name()
+            );
+            // the kernel notifies us about the exit, which triggers the restart
+            syscalls::activity_ctrl(
+                child.activity_sel(),
+                kif::syscalls::ActivityOp::STOP,
+                KILL_EXITCODE as u64,
+            )
+            .ok();
+        }
+    }
+
+    /// Restarts the given child according to its restart policy. Returns whether the child will be
+    /// restarted.
+    fn restart_child(child: &dyn Child, servs: &[services::Id], exitcode: i32) -> bool {
+        let cfg = child.cfg();
+        let restart = match cfg.restart_policy() {
+            RestartPolicy::Never => false,
+            RestartPolicy::Always => true,
+            RestartPolicy::OnFailure => exitcode != 0,
+        };
+        if !restart || borrow_mut().is_shutdown() {
+            return false;
+        }
+
+        Self::release_sessions(servs);
+        cfg.release_sgates();
+
+        let id = borrow_mut().alloc_id();
+        match child.restart(id) {
+            Some(nchild) => {
+                println!(
+                    "Restarting child '{}' (attempt {} of {})",
+                    nchild.name(),
+                    nchild.restarts(),
+                    cfg.restart_retries()
+                );
+                subsys::delay_child(nchild);
+                true
+            },
+            None => {
+                println!("Giving up on restarting child '{}'", child.name());
+                false
+            },
+        }
+    }
+
+    fn release_sessions(servs: &[services::Id]) {
+        let mut childs = borrow_mut();
+        for id in childs.ids.clone() {
+            let child = childs.child_by_id_mut(id).unwrap();
+
+            let mut notify = false;
+            let mut i = 0;
+            while i < child.res().sessions.len() {
+                if !servs.contains(&child.res().sessions[i].1.serv()) {
+                    i += 1;
+                    continue;
+                }
+
+                // the service is gone, so that we just drop the session and allow the child to
+                // open a new one at the same selector
+                let (idx, sess) = child.res_mut().sessions.remove(i);
+                log!(
+                    crate::LOG_SERV,
+                    "{}: releasing session (sel={})",
+                    child.name(),
+                    sess.sel()
+                );
+                child.cfg().close_session(idx);
+                let crd = CapRngDesc::new(CapType::OBJECT, sess.sel(), 1);
+                syscalls::revoke(child.activity_sel(), crd, true).ok();
+                notify = true;
+            }
+
+            if notify {
+                // ignore failures; not all activities support signals
+                syscalls::activity_ctrl(
+                    child.activity_sel(),
+                    kif::syscalls::ActivityOp::SIGNAL,
+                    Signal::SERV_RESTART.val as u64,
+                )
+                .ok();
+            }
+        }
+    }
+
+    fn handle_crash(id: Id, info: &kif::tilemux::CrashInfo) {
+        let (name, handler) = {
+            let childs = borrow_mut();
+            let child = childs.child_by_id(id).unwrap();
+            (child.name().to_string(), childs.crash_handler)
+        };
+
+        println!(
+            "Child '{}' crashed with signal {} (cause {:#x}) at {:#x}",
+            name, info.signal, info.cause, info.fault_addr
+        );
+
+        if let Some(handler) = handler {
+            handler(id, &name, info);
         }
     }
 
@@ -1274,6 +1957,7 @@ impl ChildManager {
 
             let mut childs = borrow_mut();
             childs.ids.retain(|&i| i != id);
+            childs.quota_trees.retain(|t| t.caller != id);
             if child.daemon() {
                 childs.daemons -= 1;
             }
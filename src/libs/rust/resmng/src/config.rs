@@ -15,16 +15,23 @@
 
 use core::fmt;
 use m3::cell::Cell;
-use m3::col::{BTreeMap, BTreeSet, String, Vec};
-use m3::errors::{Code, Error};
+use m3::col::{String, ToString, Vec};
+use m3::errors::{Code, Error, VerboseError};
+use m3::format;
 use m3::goff;
-use m3::kif;
+use m3::kif::{self, syscalls::SyscBudget, tilemux::SchedParams};
 use m3::rc::Rc;
 use m3::tcu::Label;
 
+use resmngcfg::check;
+use resmngcfg::xml::Element;
+
 use crate::parser;
 use crate::tiles;
 
+const DEF_RESTART_RETRIES: u32 = 3;
+const DEF_RESTART_BACKOFF: u64 = 10_000_000; // 10ms
+
 #[derive(Default)]
 pub struct DualName {
     pub(crate) local: String,
@@ -92,16 +99,44 @@ impl MountDesc {
     }
 }
 
+/// Determines when a service is considered ready, so that its dependents can be started
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Readiness {
+    /// The service is ready as soon as it is registered
+    Register,
+    /// The service tells us explicitly that it is ready
+    Signal,
+    /// The service is ready as soon as we could open a session with the probe arguments
+    Probe,
+}
+
+impl Default for Readiness {
+    fn default() -> Self {
+        Self::Register
+    }
+}
+
 #[derive(Default)]
 pub struct ServiceDesc {
     name: DualName,
+    ready: Readiness,
+    probe: String,
+    liveness: Option<u64>,
     used: Cell<bool>,
 }
 
 impl ServiceDesc {
-    pub(crate) fn new(name: DualName) -> Self {
+    pub(crate) fn new(
+        name: DualName,
+        ready: Readiness,
+        probe: String,
+        liveness: Option<u64>,
+    ) -> Self {
         Self {
             name,
+            ready,
+            probe,
+            liveness,
             used: Cell::new(false),
         }
     }
@@ -110,6 +145,20 @@ impl ServiceDesc {
         &self.name
     }
 
+    pub fn readiness(&self) -> Readiness {
+        self.ready
+    }
+
+    /// Returns the session arguments that are used to probe the service
+    pub fn probe_args(&self) -> &String {
+        &self.probe
+    }
+
+    /// Returns the interval in nanoseconds for liveness probes, if any
+    pub fn liveness(&self) -> Option<u64> {
+        self.liveness
+    }
+
     pub fn is_used(&self) -> bool {
         self.used.get()
     }
@@ -339,6 +388,35 @@ impl SemDesc {
     }
 }
 
+/// A named shared-memory segment of the pager that the child may use
+pub struct ShmDesc {
+    name: DualName,
+}
+
+impl ShmDesc {
+    pub(crate) fn new(name: DualName) -> Self {
+        ShmDesc { name }
+    }
+
+    pub fn name(&self) -> &DualName {
+        &self.name
+    }
+}
+
+/// Determines whether a daemon is restarted after it exited
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RestartPolicy {
+    Never,
+    Always,
+    OnFailure,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self::Never
+    }
+}
+
 #[derive(Default)]
 pub struct SerialDesc {
     used: Cell<bool>,
@@ -346,12 +424,17 @@ pub struct SerialDesc {
 
 #[derive(Default)]
 pub struct Domain {
+    pub(crate) name: String,
     pub(crate) pseudo: bool,
     pub(crate) tile: TileType,
     pub(crate) apps: Vec<Rc<AppConfig>>,
 }
 
 impl Domain {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn apps(&self) -> &Vec<Rc<AppConfig>> {
         &self.apps
     }
@@ -365,13 +448,20 @@ impl Domain {
 pub struct AppConfig {
     pub(crate) name: String,
     pub(crate) args: Vec<String>,
-    pub(crate) cfg_range: (usize, usize),
+    // our part of the config, if we have domains
+    pub(crate) xml: Option<Element>,
     pub(crate) daemon: bool,
+    pub(crate) restart: RestartPolicy,
+    pub(crate) retries: Option<u32>,
+    pub(crate) backoff: Option<u64>,
     pub(crate) getinfo: bool,
+    pub(crate) admin: bool,
     pub(crate) eps: Option<u32>,
     pub(crate) user_mem: Option<usize>,
     pub(crate) kern_mem: Option<usize>,
     pub(crate) time: Option<u64>,
+    pub(crate) syscalls: Option<(u64, u64)>,
+    pub(crate) sched: SchedParams,
     pub(crate) pts: Option<usize>,
     pub(crate) serial: Option<SerialDesc>,
     pub(crate) domains: Vec<Domain>,
@@ -383,11 +473,14 @@ pub struct AppConfig {
     pub(crate) rgates: Vec<RGateDesc>,
     pub(crate) sgates: Vec<SGateDesc>,
     pub(crate) sems: Vec<SemDesc>,
+    pub(crate) shms: Vec<ShmDesc>,
     pub(crate) tiles: Vec<TileDesc>,
 }
 
 impl AppConfig {
-    pub fn parse(xml: &str) -> Result<Self, Error> {
+    /// Parses the given config, including the resolution of includes and the expansion of
+    /// variables and templates
+    pub fn parse(xml: &str) -> Result<Self, VerboseError> {
         parser::parse(xml)
     }
 
@@ -400,18 +493,37 @@ impl AppConfig {
         }
     }
 
-    pub fn cfg_range(&self) -> (usize, usize) {
-        self.cfg_range
+    pub fn xml(&self) -> Option<&Element> {
+        self.xml.as_ref()
     }
 
     pub fn daemon(&self) -> bool {
         self.daemon
     }
 
+    pub fn restart_policy(&self) -> RestartPolicy {
+        self.restart
+    }
+
+    /// Returns the maximum number of restarts
+    pub fn restart_retries(&self) -> u32 {
+        self.retries.unwrap_or(DEF_RESTART_RETRIES)
+    }
+
+    /// Returns the delay in nanoseconds before the first restart, which doubles with every restart
+    pub fn restart_backoff(&self) -> u64 {
+        self.backoff.unwrap_or(DEF_RESTART_BACKOFF)
+    }
+
     pub fn can_get_info(&self) -> bool {
         self.getinfo
     }
 
+    /// Returns whether the application is allowed to start and stop childs at runtime
+    pub fn can_admin(&self) -> bool {
+        self.admin
+    }
+
     pub fn eps(&self) -> Option<u32> {
         self.eps
     }
@@ -424,6 +536,17 @@ impl AppConfig {
         self.kern_mem
     }
 
+    pub fn sched(&self) -> SchedParams {
+        self.sched
+    }
+
+    pub fn sysc_budget(&self) -> SyscBudget {
+        match self.syscalls {
+            Some((n, period)) => SyscBudget::new(n, period),
+            None => SyscBudget::default(),
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -476,10 +599,21 @@ impl AppConfig {
         self.sgates.iter().find(|s| s.name().local() == lname)
     }
 
+    /// Marks all send gates as unused again, so that a restarted child can obtain them
+    pub fn release_sgates(&self) {
+        for s in &self.sgates {
+            s.used.replace(false);
+        }
+    }
+
     pub fn get_sem(&self, lname: &str) -> Option<&SemDesc> {
         self.sems.iter().find(|s| s.name().local() == lname)
     }
 
+    pub fn get_shm(&self, lname: &str) -> Option<&ShmDesc> {
+        self.shms.iter().find(|s| s.name().local() == lname)
+    }
+
     pub fn get_service(&self, lname: &str) -> Option<&ServiceDesc> {
         self.services.iter().find(|s| s.name().local() == lname)
     }
@@ -541,10 +675,14 @@ impl AppConfig {
         self.domains.iter().fold(0, |total, d| total + d.apps.len())
     }
 
-    pub fn check(&self) {
-        self.check_services(&BTreeSet::new());
-        self.check_gates();
-        self.check_tiles();
+    /// Checks the configuration for conflicts and unsatisfiable requirements
+    pub fn check(&self) -> Result<(), VerboseError> {
+        if let Some(xml) = &self.xml {
+            check::check_app(xml, true)
+                .and_then(|app| app.check())
+                .map_err(|e| VerboseError::new(Code::InvArgs, e.to_string()))?;
+        }
+        self.check_tiles()
     }
 
     fn count_tiles(tile: &TileDesc) -> u32 {
@@ -557,10 +695,10 @@ impl AppConfig {
         count
     }
 
-    fn check_tiles(&self) {
+    fn check_tiles(&self) -> Result<(), VerboseError> {
         for d in &self.domains {
             for a in &d.apps {
-                a.check_tiles();
+                a.check_tiles()?;
             }
         }
 
@@ -568,100 +706,20 @@ impl AppConfig {
             if !tile.optional {
                 let available = Self::count_tiles(tile);
                 if available < tile.count.get() {
-                    panic!(
-                        "AppConfig '{}' needs tile type '{}' {} times, but {} are available",
-                        self.name(),
-                        tile.tile_type().0,
-                        tile.count.get(),
-                        available
-                    );
-                }
-            }
-        }
-    }
-
-    fn check_services(&self, parent_set: &BTreeSet<String>) {
-        let mut set = BTreeSet::new();
-        for d in &self.domains {
-            for a in &d.apps {
-                for serv in a.services() {
-                    if set.contains(serv.name().global()) {
-                        panic!(
-                            "config '{}': service '{}' does already exist",
-                            a.name(),
-                            serv.name().global()
-                        );
-                    }
-                    set.insert(serv.name().global().clone());
-                }
-            }
-        }
-
-        let mut subset = set.clone();
-        for s in parent_set.iter() {
-            if !subset.contains(s) {
-                subset.insert(s.clone());
-            }
-        }
-        for d in &self.domains {
-            for a in &d.apps {
-                a.check_services(&subset);
-            }
-        }
-
-        for sess in self.sessions() {
-            if !set.contains(sess.name().global()) && !parent_set.contains(sess.name().global()) {
-                panic!(
-                    "config '{}': service '{}' does not exist",
-                    self.name(),
-                    sess.name().global()
-                );
-            }
-        }
-    }
-
-    fn check_gates(&self) {
-        let mut map = BTreeMap::new();
-        for d in &self.domains {
-            for a in &d.apps {
-                for rgate in a.rgates() {
-                    if map.contains_key(rgate.name().global()) {
-                        panic!(
-                            "config '{}': rgate '{}' does already exist",
-                            a.name(),
-                            rgate.name().global()
-                        );
-                    }
-                    map.insert(rgate.name().global().clone(), rgate.slots());
-                }
-            }
-        }
-
-        for d in &self.domains {
-            for a in &d.apps {
-                a.check_gates();
-
-                for sgate in a.sgates() {
-                    match map.get_mut(sgate.name().global()) {
-                        Some(s) => {
-                            if *s == 0 {
-                                panic!(
-                                    "config '{}': not enough slots in rgate '{}'",
-                                    a.name(),
-                                    sgate.name().global()
-                                );
-                            }
-                            *s -= 1;
-                        },
-                        None => panic!(
-                            "config '{}': rgate '{}' does not exist",
-                            a.name(),
-                            sgate.name().global()
+                    return Err(VerboseError::new(
+                        Code::NotFound,
+                        format!(
+                            "config '{}': needs tile type '{}' {} times, but {} are available",
+                            self.name(),
+                            tile.tile_type().0,
+                            tile.count.get(),
+                            available
                         ),
-                    }
+                    ));
                 }
             }
         }
+        Ok(())
     }
 
     fn print_rec(&self, f: &mut fmt::Formatter<'_>, layer: usize) -> Result<(), fmt::Error> {
@@ -673,12 +731,47 @@ impl AppConfig {
         if self.daemon {
             writeln!(f, "{:0w$}Daemon,", "", w = layer + 2)?;
         }
+        if self.restart != RestartPolicy::Never {
+            writeln!(
+                f,
+                "{:0w$}Restart[policy={:?}, retries={}, backoff={} ns],",
+                "",
+                self.restart,
+                self.restart_retries(),
+                self.restart_backoff(),
+                w = layer + 2
+            )?;
+        }
         if let Some(eps) = self.eps {
             writeln!(f, "{:0w$}Endpoints[count={}],", "", eps, w = layer + 2)?;
         }
         if let Some(t) = self.time {
             writeln!(f, "{:0w$}TimeSlice[{} ns],", "", t, w = layer + 2)?;
         }
+        match self.sched.class {
+            kif::tilemux::SchedClass::PRIO => {
+                writeln!(f, "{:0w$}Priority[{}],", "", self.sched.prio, w = layer + 2)?
+            },
+            kif::tilemux::SchedClass::EDF => writeln!(
+                f,
+                "{:0w$}Reservation[{} ns per {} ns],",
+                "",
+                self.sched.budget,
+                self.sched.period,
+                w = layer + 2
+            )?,
+            _ => {},
+        }
+        if let Some((n, period)) = self.syscalls {
+            writeln!(
+                f,
+                "{:0w$}Syscalls[{} per {} ns],",
+                "",
+                n,
+                period,
+                w = layer + 2
+            )?;
+        }
         if let Some(n) = self.pts {
             writeln!(f, "{:0w$}PageTables[{}],", "", n, w = layer + 2)?;
         }
@@ -712,7 +805,17 @@ impl AppConfig {
             )?;
         }
         for s in &self.services {
-            writeln!(f, "{:0w$}Service[{:?}],", "", s.name, w = layer + 2)?;
+            write!(f, "{:0w$}Service[{:?}", "", s.name, w = layer + 2)?;
+            if s.ready != Readiness::Register {
+                write!(f, ", ready={:?}", s.ready)?;
+            }
+            if s.ready == Readiness::Probe || s.liveness.is_some() {
+                write!(f, ", probe='{}'", s.probe)?;
+            }
+            if let Some(l) = s.liveness {
+                write!(f, ", liveness={} ns", l)?;
+            }
+            writeln!(f, "],")?;
         }
         for s in &self.sesscrt {
             writeln!(
@@ -784,9 +887,23 @@ impl AppConfig {
         if self.can_get_info() {
             writeln!(f, "{:0w$}GetInfo[],", "", w = layer + 2)?;
         }
+        if self.can_admin() {
+            writeln!(f, "{:0w$}Admin[],", "", w = layer + 2)?;
+        }
         for d in &self.domains {
             let mut sub_layer = layer;
-            if !d.pseudo {
+            if !d.pseudo && !d.name.is_empty() {
+                writeln!(
+                    f,
+                    "{:0w$}Domain '{}' on {} [",
+                    "",
+                    d.name,
+                    d.tile.0,
+                    w = layer + 2
+                )?;
+                sub_layer += 2;
+            }
+            else if !d.pseudo {
                 writeln!(f, "{:0w$}Domain on {} [", "", d.tile.0, w = layer + 2)?;
                 sub_layer += 2;
             }
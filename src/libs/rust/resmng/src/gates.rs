@@ -45,6 +45,13 @@ impl GateManager {
         Ok(())
     }
 
+    pub fn remove_rgate(&mut self, name: &str) {
+        if let Some(idx) = self.gates.iter().position(|(gname, _gate)| gname == name) {
+            log!(crate::LOG_GATE, "Removing rgate {}", name);
+            self.gates.remove(idx);
+        }
+    }
+
     pub fn get(&self, name: &str) -> Option<&RecvGate> {
         self.gates
             .iter()
@@ -13,219 +13,195 @@
  * General Public License version 2 for more details.
  */
 
+//! Converts configurations into [`config::AppConfig`]
+//!
+//! The XML parsing, the expansion of variables and templates, and the validation of tags,
+//! attributes, and values is done by the resmngcfg crate, which is shared with cfgcheck. Thus, we
+//! only need to resolve includes and convert the values here.
+
 use m3::col::{String, ToString, Vec};
-use m3::errors::{Code, Error};
+use m3::errors::{Code, Error, VerboseError};
 use m3::format;
 use m3::goff;
-use m3::kif;
+use m3::io::Read;
+use m3::kif::{self, tilemux::SchedParams};
 use m3::parse;
 use m3::rc::Rc;
 use m3::tcu::Label;
+use m3::vfs::{OpenFlags, VFS};
+
+use resmngcfg::xml::{self, Attr, Element, Includer};
+use resmngcfg::{check, expand};
 
 use crate::config;
 
-struct ConfigParser {
-    chars: Vec<char>,
-    pos: usize,
+fn error(loc: &xml::Loc, msg: String) -> VerboseError {
+    resmng_error(resmngcfg::Error::new(loc.clone(), msg))
 }
 
-impl ConfigParser {
-    fn new(xml: &str) -> Self {
-        ConfigParser {
-            chars: xml.chars().collect(),
-            pos: 0,
-        }
-    }
-
-    fn get(&mut self) -> Result<char, Error> {
-        if self.pos < self.chars.len() {
-            let idx = self.pos;
-            self.pos += 1;
-            Ok(self.chars[idx])
-        }
-        else {
-            Err(Error::new(Code::InvArgs))
-        }
-    }
-
-    fn put(&mut self) -> Option<char> {
-        if self.pos > 0 {
-            self.pos -= 1;
-            Some(self.chars[self.pos])
-        }
-        else {
-            None
-        }
-    }
-
-    fn get_no_ws(&mut self) -> Result<char, Error> {
-        loop {
-            let c = self.get()?;
-            if c.is_whitespace() {
-                continue;
-            }
-            break Ok(c);
-        }
-    }
-
-    fn consume(&mut self, c: char) -> Result<(), Error> {
-        let nc = self.get_no_ws()?;
-        if nc != c {
-            Err(Error::new(Code::InvArgs))
-        }
-        else {
-            Ok(())
-        }
-    }
-
-    fn parse_ident(&mut self, delim: char) -> Result<String, Error> {
-        let mut name_buf = String::new();
-        let first = self.get_no_ws()?;
-        name_buf.push(first);
-
-        while let Ok(c) = self.get() {
-            if c == delim || c.is_whitespace() {
-                break;
-            }
-
-            name_buf.push(c);
-        }
-        Ok(name_buf)
-    }
-
-    fn parse_arg(&mut self) -> Result<Option<(String, String)>, Error> {
-        let first = self.get_no_ws()?;
-        self.put();
-        if first == '>' || first == '/' {
-            return Ok(None);
-        }
-
-        let name = self.parse_ident('=')?;
-        self.consume('"')?;
-
-        let mut val_buf = String::new();
-        while let Ok(c) = self.get() {
-            if c == '"' {
-                break;
-            }
-
-            val_buf.push(c);
-        }
-        Ok(Some((name, val_buf)))
-    }
-
-    fn parse_tag_name(&mut self) -> Result<Option<String>, Error> {
-        self.consume('<')?;
-
-        let mut name_buf = String::new();
-        let first = self.get_no_ws()?;
-
-        if first == '/' {
-            while let Some(n) = self.put() {
-                if n == '<' {
-                    return Ok(None);
-                }
-            }
-        }
-        name_buf.push(first);
+fn resmng_error(e: resmngcfg::Error) -> VerboseError {
+    VerboseError::new(Code::InvArgs, e.to_string())
+}
 
-        while let Ok(c) = self.get() {
-            if c.is_whitespace() {
-                break;
-            }
-            if c == '>' || c == '/' {
-                self.put();
-                break;
-            }
+/// Converts the value of the given attribute via `func`
+fn value<T, F>(attr: &Attr, func: F) -> Result<T, VerboseError>
+where
+    F: FnOnce(&str) -> Result<T, Error>,
+{
+    func(&attr.value).map_err(|_| {
+        error(
+            &attr.loc,
+            format!(
+                "invalid value '{}' for attribute '{}'",
+                attr.value, attr.name
+            ),
+        )
+    })
+}
 
-            name_buf.push(c);
-        }
+/// Reads included files from our file system, relative to the including file
+struct VfsIncluder;
 
-        Ok(Some(name_buf))
+impl Includer for VfsIncluder {
+    fn read(&self, from: &str, file: &str) -> Result<(String, String), String> {
+        let path = match from.rfind('/') {
+            Some(pos) if !file.starts_with('/') => format!("{}/{}", &from[0..pos], file),
+            _ => file.to_string(),
+        };
+        let xml = VFS::open(&path, OpenFlags::R)
+            .and_then(|mut f| f.read_to_string())
+            .map_err(|e| format!("{:?}", e.code()))?;
+        Ok((VFS::abs_path(&path), xml))
     }
 }
 
-pub(crate) fn parse(xml: &str) -> Result<config::AppConfig, Error> {
-    let mut p = ConfigParser::new(xml);
-
-    match p.parse_tag_name()? {
-        Some(tag) if tag == "app" => parse_app(&mut p, 0),
-        _ => Err(Error::new(Code::InvArgs)),
+pub(crate) fn parse(xml: &str) -> Result<config::AppConfig, VerboseError> {
+    let root = xml::parse("", xml, Some(&VfsIncluder)).map_err(resmng_error)?;
+    if root.name != "app" {
+        return Err(error(
+            &root.loc,
+            format!("expected <app>, found <{}>", root.name),
+        ));
     }
+
+    let root = expand::expand_app(&root, &[]).map_err(resmng_error)?;
+    check::check_app(&root, true).map_err(resmng_error)?;
+    parse_app(&root, true)
 }
 
-fn parse_app(p: &mut ConfigParser, start: usize) -> Result<config::AppConfig, Error> {
+fn parse_app(elem: &Element, root: bool) -> Result<config::AppConfig, VerboseError> {
     let mut app = config::AppConfig::default();
-
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "args" => {
-                    for (i, a) in v.split_whitespace().enumerate() {
-                        if i == 0 {
-                            app.name = a.to_string();
-                        }
-                        app.args.push(a.to_string());
+    let (mut prio, mut period, mut budget) = (None, None, None);
+
+    for a in &elem.attrs {
+        match a.name.as_ref() {
+            "args" => {
+                for (i, arg) in a.value.split_whitespace().enumerate() {
+                    if i == 0 {
+                        app.name = arg.to_string();
                     }
-                },
-                "usermem" => app.user_mem = Some(parse::size(&v)?),
-                "kernmem" => app.kern_mem = Some(parse::size(&v)?),
-                "time" => app.time = Some(parse::time(&v)?),
-                "pagetables" => app.pts = Some(parse::int(&v)? as usize),
-                "eps" => app.eps = Some(parse::int(&v)? as u32),
-                "daemon" => app.daemon = parse::bool(&v)?,
-                "getinfo" => app.getinfo = parse::bool(&v)?,
-                _ => return Err(Error::new(Code::InvArgs)),
+                    app.args.push(arg.to_string());
+                }
+            },
+            "usermem" => app.user_mem = Some(value(a, parse::size)?),
+            "kernmem" => app.kern_mem = Some(value(a, parse::size)?),
+            "time" => app.time = Some(value(a, parse::time)?),
+            "syscalls" => app.syscalls = Some(value(a, parse::rate)?),
+            "prio" => prio = Some((a, value(a, parse::int)?)),
+            "period" => period = Some(value(a, parse::time)?),
+            "budget" => budget = Some(value(a, parse::time)?),
+            "pagetables" => app.pts = Some(value(a, parse::int)? as usize),
+            "eps" => app.eps = Some(value(a, parse::int)? as u32),
+            "daemon" => app.daemon = value(a, parse::bool)?,
+            "restart" => {
+                app.restart = match a.value.as_ref() {
+                    "always" => config::RestartPolicy::Always,
+                    "on-failure" => config::RestartPolicy::OnFailure,
+                    _ => config::RestartPolicy::Never,
+                }
             },
+            "retries" => app.retries = Some(value(a, parse::int)? as u32),
+            "backoff" => app.backoff = Some(value(a, parse::time)?),
+            "getinfo" => app.getinfo = value(a, parse::bool)?,
+            "admin" => app.admin = value(a, parse::bool)?,
+            // all other attributes have been refused by check_app
+            _ => {},
         }
     }
 
-    let nc = p.get_no_ws()?;
-    if nc == '/' {
-        p.consume('>')?;
-        Ok(app)
+    if app.args.is_empty() {
+        return Err(error(
+            &elem.loc,
+            "<app> requires a non-empty attribute 'args'".to_string(),
+        ));
     }
-    else if nc == '>' {
-        // put all apps that belong to the same domain as `app` into a pseudo domain
-        let mut pseudo_dom = config::Domain {
-            pseudo: true,
-            ..Default::default()
-        };
 
-        let mut app_start = p.pos;
-        while let Some(tag) = p.parse_tag_name()? {
-            match tag.as_ref() {
-                "app" => pseudo_dom.apps.push(Rc::new(parse_app(p, app_start)?)),
-                "dom" => app.domains.push(parse_domain(p)?),
-                "mount" => app.mounts.push(parse_mount(p)?),
-                "sess" => app.sessions.push(parse_session(p)?),
-                "sesscrt" => app.sesscrt.push(parse_sesscrt(p)?),
-                "serv" => app.services.push(parse_service(p)?),
-                "physmem" => app.phys_mems.push(parse_physmem(p)?),
-                "tiles" => app.tiles.push(parse_tile(p)?),
-                "rgate" => app.rgates.push(parse_rgate(p)?),
-                "sgate" => app.sgates.push(parse_sgate(p)?),
-                "sem" => app.sems.push(parse_sem(p)?),
-                "serial" => app.serial = Some(config::SerialDesc::default()),
-                _ => return Err(Error::new(Code::InvArgs)),
+    // check_app makes sure that we either have a priority or both period and budget
+    app.sched = match (prio, period, budget) {
+        (Some((attr, prio)), _, _) => {
+            if prio > kif::tilemux::MAX_PRIO {
+                return Err(error(
+                    &attr.loc,
+                    format!(
+                        "app '{}' has priority {}, but the maximum is {}",
+                        app.name,
+                        prio,
+                        kif::tilemux::MAX_PRIO
+                    ),
+                ));
             }
-
-            if tag != "dom" && tag != "app" {
-                p.consume('/')?;
-                p.consume('>')?;
+            SchedParams::new_prio(prio)
+        },
+        (None, Some(period), Some(budget)) => {
+            if budget == 0 || budget > period {
+                return Err(error(
+                    &elem.loc,
+                    format!(
+                        "app '{}' has budget {}ns, which needs to be in (0, period={}ns]",
+                        app.name, budget, period
+                    ),
+                ));
             }
-            app_start = p.pos;
+            SchedParams::new_edf(period, budget)
+        },
+        _ => SchedParams::default(),
+    };
+
+    // put all apps that belong to the same domain as `app` into a pseudo domain
+    let mut pseudo_dom = config::Domain {
+        pseudo: true,
+        ..Default::default()
+    };
+
+    for c in &elem.childs {
+        match c.name.as_ref() {
+            "app" => pseudo_dom.apps.push(Rc::new(parse_app(c, false)?)),
+            "dom" => app.domains.push(parse_domain(c)?),
+            "mount" => app.mounts.push(parse_mount(c)),
+            "sess" => app.sessions.push(parse_session(c)?),
+            "sesscrt" => app.sesscrt.push(parse_sesscrt(c)?),
+            "serv" => app.services.push(parse_service(c)?),
+            "physmem" => app.phys_mems.push(parse_physmem(c)?),
+            "tiles" => app.tiles.push(parse_tile(c)?),
+            "rgate" => app.rgates.push(parse_rgate(c)?),
+            "sgate" => app.sgates.push(parse_sgate(c)?),
+            "sem" => app.sems.push(config::SemDesc::new(parse_dual_name(c))),
+            "shm" => app.shms.push(config::ShmDesc::new(parse_dual_name(c))),
+            "serial" => app.serial = Some(config::SerialDesc::default()),
+            _ => {},
         }
-        parse_close_tag(p, "app")?;
+    }
 
-        if !pseudo_dom.apps.is_empty() {
-            app.domains.insert(0, pseudo_dom);
-        }
+    if !pseudo_dom.apps.is_empty() {
+        app.domains.insert(0, pseudo_dom);
+    }
+
+    if !app.domains.is_empty() {
+        // keep our part of the config to pass it to our resource manager
+        app.xml = Some(elem.clone());
 
-        app.cfg_range = (start, p.pos);
         // don't collect session creators for root
-        if start != 0 {
+        if !root {
             let mut crts = Vec::new();
             collect_sess_crts(&app, &mut crts);
 
@@ -236,12 +212,9 @@ fn parse_app(p: &mut ConfigParser, start: usize) -> Result<config::AppConfig, Er
                 }
             }
         }
-
-        Ok(app)
-    }
-    else {
-        Err(Error::new(Code::InvArgs))
     }
+
+    Ok(app)
 }
 
 fn hosts_service(app: &config::AppConfig, name: &str) -> bool {
@@ -268,212 +241,151 @@ fn collect_sess_crts(app: &config::AppConfig, crts: &mut Vec<config::SessCrtDesc
     }
 }
 
-fn parse_dual_name(dual: &mut config::DualName, n: String, v: String) -> Result<(), Error> {
-    match n.as_ref() {
-        "name" => {
-            dual.local = v.clone();
-            dual.global = v
-        },
-        "lname" => dual.local = v,
-        "gname" => dual.global = v,
-        _ => return Err(Error::new(Code::InvArgs)),
+fn parse_dual_name(elem: &Element) -> config::DualName {
+    let mut dual = config::DualName::default();
+    if let Some(n) = elem.attr("name") {
+        dual.local = n.value.clone();
+        dual.global = n.value.clone();
     }
-    Ok(())
-}
-
-fn parse_domain(p: &mut ConfigParser) -> Result<config::Domain, Error> {
-    let mut dom = config::Domain::default();
-
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "tile" => dom.tile = config::TileType(v),
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
-        }
+    if let Some(n) = elem.attr("lname") {
+        dual.local = n.value.clone();
     }
-
-    if dom.tile.0.is_empty() {
-        dom.tile = config::TileType("core".to_string());
+    if let Some(n) = elem.attr("gname") {
+        dual.global = n.value.clone();
     }
+    dual
+}
 
-    p.consume('>')?;
-
-    let mut app_start = p.pos;
-    while let Some(tag) = p.parse_tag_name()? {
-        if tag != "app" {
-            return Err(Error::new(Code::InvArgs));
-        }
-
-        dom.apps.push(Rc::new(parse_app(p, app_start)?));
-        app_start = p.pos;
+fn parse_domain(elem: &Element) -> Result<config::Domain, VerboseError> {
+    let mut dom = config::Domain {
+        name: elem.attr("name").map_or(String::new(), |n| n.value.clone()),
+        tile: config::TileType(match elem.attr("tile") {
+            Some(t) if !t.value.is_empty() => t.value.clone(),
+            _ => "core".to_string(),
+        }),
+        ..Default::default()
+    };
+
+    for c in &elem.childs {
+        dom.apps.push(Rc::new(parse_app(c, false)?));
     }
-
-    parse_close_tag(p, "dom")?;
     Ok(dom)
 }
 
-fn parse_mount(p: &mut ConfigParser) -> Result<config::MountDesc, Error> {
-    let mut fs = String::new();
-    let mut path = String::new();
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "fs" => fs = v.clone(),
-                "path" => {
-                    if v.ends_with('/') {
-                        path = v.clone();
-                    }
-                    else {
-                        path = format!("{}/", v);
-                    }
-                },
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
-        }
+fn parse_mount(elem: &Element) -> config::MountDesc {
+    let fs = elem.attr("fs").unwrap().value.clone();
+    let path = &elem.attr("path").unwrap().value;
+    if path.ends_with('/') {
+        config::MountDesc::new(fs, path.clone())
+    }
+    else {
+        config::MountDesc::new(fs, format!("{}/", path))
     }
-    Ok(config::MountDesc::new(fs, path))
 }
 
-fn parse_physmem(p: &mut ConfigParser) -> Result<config::PhysMemDesc, Error> {
+fn parse_physmem(elem: &Element) -> Result<config::PhysMemDesc, VerboseError> {
     let mut phys = 0;
     let mut size = 0;
     let mut perm = kif::Perm::RWX;
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "addr" => phys = parse::addr(&v)?,
-                "size" => size = parse::size(&v)? as goff,
-                "perm" => perm = parse::perm(&v)?,
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
+    for a in &elem.attrs {
+        match a.name.as_ref() {
+            "addr" => phys = value(a, parse::addr)?,
+            "size" => size = value(a, parse::size)? as goff,
+            "perm" => perm = value(a, parse::perm)?,
+            _ => {},
         }
     }
     Ok(config::PhysMemDesc::new(phys, size, perm))
 }
 
-fn parse_service(p: &mut ConfigParser) -> Result<config::ServiceDesc, Error> {
-    let mut name = config::DualName::default();
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => parse_dual_name(&mut name, n, v)?,
+fn parse_service(elem: &Element) -> Result<config::ServiceDesc, VerboseError> {
+    let mut ready = config::Readiness::Register;
+    let mut probe = String::new();
+    let mut liveness = None;
+    for a in &elem.attrs {
+        match a.name.as_ref() {
+            "ready" => {
+                ready = match a.value.as_ref() {
+                    "signal" => config::Readiness::Signal,
+                    "probe" => config::Readiness::Probe,
+                    _ => config::Readiness::Register,
+                }
+            },
+            "probe" => probe = a.value.clone(),
+            "liveness" => liveness = Some(value(a, parse::time)?),
+            _ => {},
         }
     }
-    Ok(config::ServiceDesc::new(name))
+    Ok(config::ServiceDesc::new(
+        parse_dual_name(elem),
+        ready,
+        probe,
+        liveness,
+    ))
 }
 
-fn parse_sesscrt(p: &mut ConfigParser) -> Result<config::SessCrtDesc, Error> {
-    let mut name = String::new();
-    let mut count = None;
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "name" => name = v,
-                "count" => count = Some(parse::int(&v)? as u32),
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
-        }
-    }
+fn parse_sesscrt(elem: &Element) -> Result<config::SessCrtDesc, VerboseError> {
+    let name = elem.attr("name").unwrap().value.clone();
+    let count = match elem.attr("count") {
+        Some(c) => Some(value(c, parse::int)? as u32),
+        None => None,
+    };
     Ok(config::SessCrtDesc::new(name, count))
 }
 
-fn parse_session(p: &mut ConfigParser) -> Result<config::SessionDesc, Error> {
-    let mut name = config::DualName::default();
-    let mut arg = String::new();
-    let mut dep = true;
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "name" | "lname" | "gname" => parse_dual_name(&mut name, n, v)?,
-                "args" => arg = v,
-                "dep" => dep = parse::bool(&v)?,
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
-        }
-    }
-    Ok(config::SessionDesc::new(name, arg, dep))
+fn parse_session(elem: &Element) -> Result<config::SessionDesc, VerboseError> {
+    let arg = elem.attr("args").map_or(String::new(), |a| a.value.clone());
+    let dep = match elem.attr("dep") {
+        Some(d) => value(d, parse::bool)?,
+        None => true,
+    };
+    Ok(config::SessionDesc::new(parse_dual_name(elem), arg, dep))
 }
 
-fn parse_tile(p: &mut ConfigParser) -> Result<config::TileDesc, Error> {
-    let mut ty = String::new();
+fn parse_tile(elem: &Element) -> Result<config::TileDesc, VerboseError> {
     let mut count = 1;
     let mut optional = false;
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "type" => ty = v,
-                "count" => count = parse::int(&v)? as u32,
-                "optional" => optional = parse::bool(&v)?,
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
+    for a in &elem.attrs {
+        match a.name.as_ref() {
+            "count" => count = value(a, parse::int)? as u32,
+            "optional" => optional = value(a, parse::bool)?,
+            _ => {},
         }
     }
+    let ty = elem.attr("type").unwrap().value.clone();
     Ok(config::TileDesc::new(ty, count, optional))
 }
 
-fn parse_rgate(p: &mut ConfigParser) -> Result<config::RGateDesc, Error> {
-    let mut name = config::DualName::default();
+fn parse_rgate(elem: &Element) -> Result<config::RGateDesc, VerboseError> {
     let mut msg_size = 64;
     let mut slots = 1;
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "name" | "lname" | "gname" => parse_dual_name(&mut name, n, v)?,
-                "msgsize" => msg_size = parse::size(&v)?,
-                "slots" => slots = parse::int(&v)? as usize,
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
+    for a in &elem.attrs {
+        match a.name.as_ref() {
+            "msgsize" => msg_size = value(a, parse::size)?,
+            "slots" => slots = value(a, parse::int)? as usize,
+            _ => {},
         }
     }
-    Ok(config::RGateDesc::new(name, msg_size, slots))
+    Ok(config::RGateDesc::new(
+        parse_dual_name(elem),
+        msg_size,
+        slots,
+    ))
 }
 
-fn parse_sgate(p: &mut ConfigParser) -> Result<config::SGateDesc, Error> {
-    let mut name = config::DualName::default();
+fn parse_sgate(elem: &Element) -> Result<config::SGateDesc, VerboseError> {
     let mut credits = 1;
     let mut label = 0;
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => match n.as_ref() {
-                "name" | "lname" | "gname" => parse_dual_name(&mut name, n, v)?,
-                "credits" => credits = parse::int(&v)? as u32,
-                "label" => label = parse::int(&v)? as Label,
-                _ => return Err(Error::new(Code::InvArgs)),
-            },
-        }
-    }
-    Ok(config::SGateDesc::new(name, credits, label))
-}
-
-fn parse_sem(p: &mut ConfigParser) -> Result<config::SemDesc, Error> {
-    let mut name = config::DualName::default();
-    loop {
-        match p.parse_arg()? {
-            None => break,
-            Some((n, v)) => parse_dual_name(&mut name, n, v)?,
+    for a in &elem.attrs {
+        match a.name.as_ref() {
+            "credits" => credits = value(a, parse::int)? as u32,
+            "label" => label = value(a, parse::int)? as Label,
+            _ => {},
         }
     }
-    Ok(config::SemDesc::new(name))
-}
-
-fn parse_close_tag(p: &mut ConfigParser, name: &str) -> Result<(), Error> {
-    p.consume('<')?;
-    p.consume('/')?;
-
-    let tname = p.parse_ident('>')?;
-    if tname != name {
-        Err(Error::new(Code::InvArgs))
-    }
-    else {
-        Ok(())
-    }
+    Ok(config::SGateDesc::new(
+        parse_dual_name(elem),
+        credits,
+        label,
+    ))
 }
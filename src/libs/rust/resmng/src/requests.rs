@@ -28,6 +28,7 @@ use m3::tiles::Activity;
 
 use crate::childs::{self, Id};
 use crate::sendqueue;
+use crate::services;
 use crate::subsys;
 
 static RGATE: LazyStaticRefCell<RecvGate> = LazyStaticRefCell::default();
@@ -53,7 +54,6 @@ where
             if let Some(msg) = rgate.fetch() {
                 let is = GateIStream::new(msg, &rgate);
                 handle_request_async(is);
-                subsys::start_delayed_async(&mut spawn)?;
             }
         }
 
@@ -63,6 +63,11 @@ where
 
         sendqueue::check_replies();
 
+        services::check_probes_async();
+
+        // start delayed childs whose services are ready and whose restart backoff has elapsed
+        subsys::start_delayed_async(&mut spawn)?;
+
         func();
 
         if thread::ready_count() > 0 {
@@ -73,7 +78,16 @@ where
             break;
         }
 
-        Activity::own().sleep().ok();
+        // wake up for the next restart or probe, whatever comes first
+        let timeout = match (subsys::next_restart(), services::next_probe()) {
+            (Some(r), Some(p)) => Some(r.min(p)),
+            (r, p) => r.or(p),
+        };
+        match timeout {
+            Some(delay) => Activity::own().sleep_for(delay),
+            None => Activity::own().sleep(),
+        }
+        .ok();
     }
 
     if !thread::cur().is_main() {
@@ -91,6 +105,7 @@ fn handle_request_async(mut is: GateIStream<'_>) {
     let res = match op {
         Ok(ResMngOperation::REG_SERV) => reg_serv(&mut is, id),
         Ok(ResMngOperation::UNREG_SERV) => unreg_serv(&mut is, id),
+        Ok(ResMngOperation::SERV_READY) => serv_ready(&mut is, id),
 
         Ok(ResMngOperation::OPEN_SESS) => open_session_async(&mut is, id),
         Ok(ResMngOperation::CLOSE_SESS) => close_session_async(&mut is, id),
@@ -121,6 +136,22 @@ fn handle_request_async(mut is: GateIStream<'_>) {
 
         Ok(ResMngOperation::GET_INFO) => get_info(&mut is, id),
 
+        Ok(ResMngOperation::ADM_START) => adm_start(&mut is, id),
+        Ok(ResMngOperation::ADM_STOP) => adm_stop_async(&mut is, id),
+        Ok(ResMngOperation::ADM_LIST) => match adm_list(&mut is, id) {
+            // reply already done
+            Ok(_) => return,
+            Err(e) => Err(e),
+        },
+
+        Ok(ResMngOperation::GET_QUOTAS) => match get_quotas(&mut is, id) {
+            // reply already done
+            Ok(_) => return,
+            Err(e) => Err(e),
+        },
+
+        Ok(ResMngOperation::ADM_KILL) => adm_kill(&mut is, id),
+
         _ => Err(Error::new(Code::InvArgs)),
     };
 
@@ -155,6 +186,14 @@ fn unreg_serv(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
     child.unreg_service(sel)
 }
 
+fn serv_ready(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
+    let sel: Selector = is.pop()?;
+
+    let mut childs = childs::borrow_mut();
+    let child = childs.child_by_id_mut(id).unwrap();
+    child.serv_ready(sel)
+}
+
 fn open_session_async(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
     let dst_sel: Selector = is.pop()?;
     let name: String = is.pop()?;
@@ -275,3 +314,37 @@ fn get_info(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
 
     childs::get_info(id, idx).and_then(|info| reply_vmsg!(is, Code::None as u32, info))
 }
+
+fn get_quotas(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
+    let idx: usize = is.pop()?;
+
+    let idx = if idx == usize::MAX { None } else { Some(idx) };
+
+    childs::get_quota_info(id, idx).and_then(|info| reply_vmsg!(is, Code::None as u32, info))
+}
+
+fn adm_start(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
+    let mgate_sel: Selector = is.pop()?;
+    let size: usize = is.pop()?;
+    let domain: String = is.pop()?;
+
+    childs::start_child(id, mgate_sel, size, &domain)
+}
+
+fn adm_stop_async(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
+    let name: String = is.pop()?;
+
+    childs::stop_child_async(id, &name)
+}
+
+fn adm_kill(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
+    let name: String = is.pop()?;
+
+    childs::kill_child(id, &name)
+}
+
+fn adm_list(is: &mut GateIStream<'_>, id: Id) -> Result<(), Error> {
+    let idx: usize = is.pop()?;
+
+    childs::get_child_info(id, idx).and_then(|info| reply_vmsg!(is, Code::None as u32, info))
+}
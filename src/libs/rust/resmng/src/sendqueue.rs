@@ -63,7 +63,7 @@ pub fn check_replies() {
     let rgate = RGATE.borrow();
     if let Some(msg) = rgate.fetch() {
         if let Ok(mut serv) = services::get_mut_by_id(msg.header.label as Id) {
-            serv.queue().received_reply(&rgate, msg);
+            serv.received_reply(&rgate, msg);
         }
         else {
             rgate.ack_msg(msg).unwrap();
@@ -102,7 +102,11 @@ impl SendQueue {
         Ok(event)
     }
 
-    fn received_reply(&mut self, rg: &RecvGate, msg: &'static tcu::Message) {
+    pub fn cur_event(&self) -> Option<thread::Event> {
+        self.queue.sender().cur_event
+    }
+
+    pub fn received_reply(&mut self, rg: &RecvGate, msg: &'static tcu::Message) {
         log!(crate::LOG_SQUEUE, "{}:squeue: received reply", self.sid());
 
         let event = self.queue.sender_mut().cur_event.take().unwrap();
@@ -16,23 +16,59 @@
 use m3::cap::{CapFlags, Capability, Selector};
 use m3::cell::{Ref, RefMut, StaticRefCell};
 use m3::col::{String, Vec};
-use m3::com::SendGate;
+use m3::com::{RecvGate, SendGate};
 use m3::errors::{Code, Error};
 use m3::log;
 use m3::mem::MsgBuf;
 use m3::serialize::M3Deserializer;
 use m3::syscalls;
+use m3::tcu;
 use m3::tiles::Activity;
+use m3::time::{TimeDuration, TimeInstant};
 use m3::{build_vmsg, kif};
 
 use core::cmp::Reverse;
 
 use crate::childs;
+use crate::config::{Readiness, ServiceDesc};
 use crate::events;
 use crate::sendqueue::SendQueue;
 
 pub type Id = u32;
 
+/// The delay before a failed readiness probe is repeated
+const READY_PROBE_DELAY: TimeDuration = TimeDuration::from_millis(10);
+/// The time after which a pending readiness probe fails if the service does not respond
+const READY_PROBE_TIMEOUT: TimeDuration = TimeDuration::from_millis(100);
+/// The number of consecutive failed liveness probes after which a service is considered dead
+const MAX_PROBE_FAILURES: u32 = 3;
+
+struct Probe {
+    args: String,
+    interval: Option<TimeDuration>,
+    next: TimeInstant,
+    // the start of the probe that is currently in progress
+    pending: Option<TimeInstant>,
+    // the event the probe waits for, which is used to abort it
+    event: Option<thread::Event>,
+    // the event of the open request of the probe, if it has not been answered yet
+    open: Option<thread::Event>,
+    // the open requests of aborted probes, whose sessions need to be closed on a late reply
+    late_opens: Vec<thread::Event>,
+    failures: u32,
+}
+
+impl Probe {
+    fn timeout(&self) -> TimeDuration {
+        self.interval.unwrap_or(READY_PROBE_TIMEOUT)
+    }
+
+    fn failed(&mut self) -> bool {
+        self.failures += 1;
+        self.failures == MAX_PROBE_FAILURES
+    }
+}
+
 pub struct Service {
     id: Id,
     child: childs::Id,
@@ -41,6 +77,9 @@ pub struct Service {
     name: String,
     sessions: u32,
     owned: bool,
+    readiness: Readiness,
+    ready: bool,
+    probe: Option<Probe>,
 }
 
 impl Service {
@@ -63,6 +102,57 @@ impl Service {
             name,
             sessions,
             owned,
+            readiness: Readiness::Register,
+            ready: true,
+            probe: None,
+        }
+    }
+
+    /// Configures the readiness and liveness checks according to the given description
+    pub fn set_checks(&mut self, desc: &ServiceDesc) {
+        self.readiness = desc.readiness();
+        self.ready = desc.readiness() == Readiness::Register;
+        if desc.readiness() == Readiness::Probe || desc.liveness().is_some() {
+            let interval = desc.liveness().map(TimeDuration::from_nanos);
+            self.probe = Some(Probe {
+                args: desc.probe_args().clone(),
+                interval,
+                next: TimeInstant::now() + interval.filter(|_| self.ready).unwrap_or_default(),
+                pending: None,
+                event: None,
+                open: None,
+                late_opens: Vec::new(),
+                failures: 0,
+            });
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    fn set_ready(&mut self) {
+        log!(
+            crate::LOG_SERV,
+            "Service {}:{} is ready",
+            self.id,
+            self.name
+        );
+
+        self.ready = true;
+        if let Some(p) = self.probe.as_mut() {
+            p.next = TimeInstant::now() + p.interval.unwrap_or_default();
+        }
+    }
+
+    fn next_probe(&self) -> Option<TimeInstant> {
+        let probe = self.probe.as_ref()?;
+        match probe.pending {
+            // pending probes fail if the service does not respond in time
+            Some(start) => Some(start + probe.timeout()),
+            None if self.ready => probe.interval.map(|_| probe.next),
+            None if self.readiness == Readiness::Probe => Some(probe.next),
+            None => None,
         }
     }
 
@@ -82,6 +172,44 @@ impl Service {
         &mut self.queue
     }
 
+    /// Passes the given reply to the waiting probe or closes the session if the probe was aborted
+    pub fn received_reply(&mut self, rg: &RecvGate, msg: &'static tcu::Message) {
+        let late_open = match (self.probe.as_mut(), self.queue.cur_event()) {
+            (Some(p), Some(ev)) => p
+                .late_opens
+                .iter()
+                .position(|e| *e == ev)
+                .map(|idx| p.late_opens.remove(idx)),
+            _ => None,
+        };
+
+        // fetch the session id before the message is marked as read
+        let sid = late_open.and_then(|_| {
+            let mut de = M3Deserializer::new(msg.as_words());
+            match de.pop::<Code>() {
+                Ok(Code::None) => de.pop::<kif::service::OpenReply>().ok().map(|r| r.ident),
+                _ => None,
+            }
+        });
+
+        self.queue.received_reply(rg, msg);
+
+        if let Some(sid) = sid {
+            log!(
+                crate::LOG_SERV,
+                "Closing session {} of aborted probe for service {}:{}",
+                sid,
+                self.id,
+                self.name
+            );
+
+            // nobody waits for the reply; the send queue drops it when it arrives
+            let mut smsg_buf = MsgBuf::borrow_def();
+            build_vmsg!(smsg_buf, kif::service::Request::Close { sid });
+            self.queue.send(&smsg_buf).ok();
+        }
+    }
+
     pub fn sessions(&self) -> u32 {
         self.sessions
     }
@@ -181,6 +309,10 @@ impl Session {
         self.ident
     }
 
+    pub fn serv(&self) -> Id {
+        self.serv
+    }
+
     pub fn close_async(self, child: childs::Id) -> Result<(), Error> {
         let event = {
             let mut serv = get_mut_by_id(self.serv)?;
@@ -296,6 +428,158 @@ pub fn remove_service(id: Id) -> Service {
     serv
 }
 
+pub fn is_ready(name: &str) -> bool {
+    get_by_name(name).map(|s| s.is_ready()).unwrap_or(false)
+}
+
+pub fn set_ready(id: Id) {
+    if let Ok(mut serv) = get_mut_by_id(id) {
+        if !serv.ready {
+            serv.set_ready();
+        }
+    }
+}
+
+/// Returns the time until the next probe of a service is due
+pub fn next_probe() -> Option<TimeDuration> {
+    let now = TimeInstant::now();
+    mng()
+        .servs
+        .iter()
+        .filter_map(|s| s.next_probe())
+        .map(|t| t.checked_duration_since(now).unwrap_or_default())
+        .min()
+}
+
+/// Starts all probes that are due and aborts pending probes that timed out
+pub fn check_probes_async() {
+    let now = TimeInstant::now();
+    let mut due = Vec::new();
+    let mut aborted = Vec::new();
+    for serv in mng_mut().servs.iter_mut() {
+        if !serv.next_probe().map_or(false, |t| t <= now) {
+            continue;
+        }
+
+        let probe = serv.probe.as_mut().unwrap();
+        if probe.pending.is_some() {
+            // the service did not respond in time; wake up the probe to let it fail
+            aborted.extend(probe.event.take());
+            // the service might still open the session, which we need to close then
+            probe.late_opens.extend(probe.open.take());
+        }
+        else {
+            due.push(serv.id);
+        }
+        // start the next timeout period or the probe
+        probe.pending = Some(now);
+    }
+
+    for event in aborted {
+        thread::notify(event, None);
+    }
+
+    // probing switches threads, so that we cannot hold the borrow of the services
+    for id in due {
+        run_probe_async(id);
+    }
+}
+
+fn run_probe_async(id: Id) {
+    let res = probe_async(id);
+
+    let mut dead = None;
+    if let Ok(mut serv) = get_mut_by_id(id) {
+        let serv = &mut *serv;
+        let now = TimeInstant::now();
+        let probe = serv.probe.as_mut().unwrap();
+        probe.pending = None;
+        probe.event = None;
+        match res {
+            Ok(_) => {
+                probe.failures = 0;
+                if !serv.ready {
+                    serv.set_ready();
+                }
+                else {
+                    probe.next = now + probe.interval.unwrap_or_default();
+                }
+            },
+            Err(e) => {
+                log!(
+                    crate::LOG_SERV,
+                    "Probing service {}:{} failed: {}",
+                    serv.id,
+                    serv.name,
+                    e
+                );
+
+                if !serv.ready {
+                    probe.next = now + READY_PROBE_DELAY;
+                }
+                else {
+                    probe.next = now + probe.interval.unwrap_or_default();
+                    if probe.failed() {
+                        dead = Some((serv.child, serv.name.clone()));
+                    }
+                }
+            },
+        }
+    }
+
+    if let Some((child, name)) = dead {
+        childs::ChildManager::service_dead(child, &name);
+    }
+}
+
+fn probe_async(id: Id) -> Result<(), Error> {
+    // open a session with the probe arguments and close it again
+    let reply = send_receive_async(id, |serv| {
+        let args = serv.probe.as_ref().unwrap().args.clone();
+        let mut smsg_buf = MsgBuf::borrow_def();
+        build_vmsg!(smsg_buf, kif::service::Request::Open { arg: &args });
+        let event = serv.queue.send(&smsg_buf)?;
+        serv.probe.as_mut().unwrap().open = Some(event);
+        Ok(event)
+    });
+    if let Ok(mut serv) = get_mut_by_id(id) {
+        serv.probe.as_mut().unwrap().open = None;
+    }
+    let reply = reply?;
+
+    let mut de = M3Deserializer::new(reply.as_words());
+    let res: Code = de.pop()?;
+    if res != Code::None {
+        return Err(Error::new(res));
+    }
+    let reply: kif::service::OpenReply = de.pop()?;
+
+    send_receive_async(id, |serv| {
+        let mut smsg_buf = MsgBuf::borrow_def();
+        build_vmsg!(smsg_buf, kif::service::Request::Close { sid: reply.ident });
+        serv.queue.send(&smsg_buf)
+    })
+    .map(|_| ())
+}
+
+fn send_receive_async<F>(id: Id, send: F) -> Result<&'static tcu::Message, Error>
+where
+    F: FnOnce(&mut Service) -> Result<thread::Event, Error>,
+{
+    let event = {
+        let mut serv = get_mut_by_id(id)?;
+        let event = send(&mut serv)?;
+        serv.probe.as_mut().unwrap().event = Some(event);
+        event
+    };
+
+    // don't use the child's event here, because the child might wait for a different event. If the
+    // service is removed in the meantime, the send queue delivers an empty message and if it does
+    // not respond in time, check_probes_async does so.
+    thread::wait_for(event);
+    thread::fetch_msg().ok_or_else(|| Error::new(Code::RecvGone))
+}
+
 pub fn shutdown_async() {
     // first collect the ids
     let mut ids = Vec::new();
@@ -15,23 +15,27 @@
 
 use m3::boxed::Box;
 use m3::cap::Selector;
-use m3::cell::{RefCell, StaticRefCell};
+use m3::cell::{Ref, RefCell, StaticRefCell};
 use m3::cfg::PAGE_SIZE;
 use m3::col::{String, ToString, Vec};
 use m3::com::MemGate;
 use m3::errors::{Code, Error, VerboseError};
 use m3::format;
 use m3::goff;
+use m3::kif::tilemux::{SchedClass, SchedParams};
 use m3::kif::{boot, CapRngDesc, CapType, Perm, FIRST_FREE_SEL};
 use m3::log;
 use m3::math;
 use m3::mem::{size_of, GlobAddr};
+use m3::println;
 use m3::rc::Rc;
 use m3::server::DEF_MAX_CLIENTS;
+use m3::session::ResMngChildInfo;
 use m3::tcu::TileId;
-use m3::tiles::{Activity, ChildActivity, Tile};
+use m3::tiles::{Activity, ChildActivity, KMem, Tile};
+use m3::time::{TimeDuration, TimeInstant};
 
-use crate::childs;
+use crate::childs::{self, Child};
 use crate::config;
 use crate::gates;
 use crate::memory;
@@ -57,9 +61,23 @@ static OUR_TILE: StaticRefCell<Option<Rc<tiles::TileUsage>>> = StaticRefCell::ne
 // use Box here, because we also store them in the ChildManager, which expects them to be boxed
 #[allow(clippy::vec_box)]
 static DELAYED: StaticRefCell<Vec<Box<childs::OwnChild>>> = StaticRefCell::new(Vec::new());
+// the resources of all domains to report their quotas and to start further childs in named domains
+// at runtime
+static DOMAINS: StaticRefCell<Vec<DomainRes>> = StaticRefCell::new(Vec::new());
+
+/// The resources that are shared by all childs of a domain
+pub(crate) struct DomainRes {
+    pub(crate) name: String,
+    pub(crate) tile: Rc<tiles::TileUsage>,
+    // the tile object the childs derive from (not available for subsystems)
+    pub(crate) base: Option<Rc<tiles::TileUsage>>,
+    pub(crate) kmem: Rc<KMem>,
+    pub(crate) umem: Rc<childs::ChildMem>,
+}
 
 pub struct Arguments {
     pub max_clients: usize,
+    pub systrace: bool,
     sems: Vec<String>,
 }
 
@@ -67,6 +85,7 @@ impl Default for Arguments {
     fn default() -> Self {
         Self {
             max_clients: DEF_MAX_CLIENTS,
+            systrace: false,
             sems: Vec::new(),
         }
     }
@@ -78,12 +97,11 @@ pub struct Subsystem {
     tiles: Vec<boot::Tile>,
     mems: Vec<boot::Mem>,
     servs: Vec<boot::Service>,
-    cfg_str: String,
     cfg: config::AppConfig,
 }
 
 impl Subsystem {
-    pub fn new() -> Result<Self, Error> {
+    pub fn new() -> Result<Self, VerboseError> {
         let mgate = MemGate::new_bind(SUBSYS_SELS);
         let mut off: goff = 0;
 
@@ -103,7 +121,7 @@ impl Subsystem {
 
         let cfg = Self::parse_config(&mods)?;
 
-        Self::create_rgates(&cfg.1)?;
+        Self::create_rgates(&cfg)?;
 
         let sub = Self {
             info,
@@ -111,8 +129,7 @@ impl Subsystem {
             tiles,
             mems,
             servs,
-            cfg_str: cfg.0,
-            cfg: cfg.1,
+            cfg,
         };
         sub.init();
         Ok(sub)
@@ -171,7 +188,7 @@ impl Subsystem {
         }
     }
 
-    fn parse_config(mods: &[boot::Mod]) -> Result<(String, config::AppConfig), Error> {
+    fn parse_config(mods: &[boot::Mod]) -> Result<config::AppConfig, VerboseError> {
         let mut cfg_mem: Option<(usize, goff)> = None;
 
         // find boot config
@@ -189,8 +206,7 @@ impl Subsystem {
 
         // parse boot config
         let xml_str = String::from_utf8(xml).map_err(|_| Error::new(Code::InvArgs))?;
-        let cfg = config::AppConfig::parse(&xml_str)?;
-        Ok((xml_str, cfg))
+        config::AppConfig::parse(&xml_str)
     }
 
     fn create_rgates(cfg: &config::AppConfig) -> Result<(), Error> {
@@ -219,14 +235,13 @@ impl Subsystem {
             else if let Some(sem) = arg.strip_prefix("sem=") {
                 args.sems.push(sem.to_string());
             }
+            else if arg == "systrace" {
+                args.systrace = true;
+            }
         }
         args
     }
 
-    pub fn cfg_str(&self) -> &String {
-        &self.cfg_str
-    }
-
     pub fn cfg(&self) -> &config::AppConfig {
         &self.cfg
     }
@@ -279,7 +294,7 @@ impl Subsystem {
     {
         let root = self.cfg();
         if Activity::own().resmng().is_none() {
-            root.check();
+            root.check()?;
         }
 
         let args = self.parse_args();
@@ -488,7 +503,7 @@ impl Subsystem {
 
                 Some(Rc::new(
                     tile_usage
-                        .derive(domain_eps, domain_time, domain_pts)
+                        .derive(domain_eps, domain_time, domain_pts, SchedParams::default())
                         .map_err(|e| {
                             VerboseError::new(
                                 e.code(),
@@ -504,27 +519,42 @@ impl Subsystem {
                 None
             };
 
+            DOMAINS.borrow_mut().push(DomainRes {
+                name: d.name().to_string(),
+                tile: tile_usage.clone(),
+                base: domain_pe_usage.clone(),
+                kmem: domain_kmem.clone(),
+                umem: domain_umem.clone(),
+            });
+
             for cfg in d.apps() {
                 // determine tile object with potentially reduced number of EPs
                 let (domain_tile_usage, child_pe_usage) = if !cfg.domains().is_empty() {
                     // a resource manager has to be able to set PMPs and thus needs the root tile
                     (None, tile_usage.clone())
                 }
-                else if cfg.eps.is_some() || cfg.time.is_some() || cfg.pts.is_some() {
+                else if cfg.eps.is_some()
+                    || cfg.time.is_some()
+                    || cfg.pts.is_some()
+                    || cfg.sched.class != SchedClass::INHERIT
+                {
                     // if the child wants any specific quota, derive from the base tile object
                     let base = domain_pe_usage.as_ref().unwrap();
                     (
                         // keep the base object around in case there are no other children using it
                         Some(base.clone()),
-                        Rc::new(base.derive(cfg.eps, cfg.time, cfg.pts).map_err(|e| {
-                            VerboseError::new(
-                                e.code(),
-                                format!(
-                                    "Unable to derive new tile with {:?} EPs, {:?} time, {:?} pts",
-                                    cfg.eps, cfg.time, cfg.pts,
-                                ),
-                            )
-                        })?),
+                        Rc::new(
+                            base.derive(cfg.eps, cfg.time, cfg.pts, cfg.sched)
+                                .map_err(|e| {
+                                    VerboseError::new(
+                                        e.code(),
+                                        format!(
+                                            "Unable to derive new tile with {:?} EPs, {:?} time, {:?} pts, {:?}",
+                                            cfg.eps, cfg.time, cfg.pts, cfg.sched,
+                                        ),
+                                    )
+                                })?,
+                        ),
                     )
                 }
                 else {
@@ -564,9 +594,9 @@ impl Subsystem {
                             && d.apps().len() == 1
                     );
 
-                    // create MemGate for config substring
-                    let cfg_range = cfg.cfg_range();
-                    let cfg_len = cfg_range.1 - cfg_range.0;
+                    // create MemGate for our part of the config
+                    let cfg_str = cfg.xml().unwrap().to_string();
+                    let cfg_len = cfg_str.len();
                     let cfg_slice =
                         memory::container()
                             .alloc_mem(cfg_len as goff)
@@ -577,7 +607,7 @@ impl Subsystem {
                                 )
                             })?;
                     let cfg_mem = cfg_slice.derive()?;
-                    cfg_mem.write(self.cfg_str()[cfg_range.0..cfg_range.1].as_bytes(), 0)?;
+                    cfg_mem.write(cfg_str.as_bytes(), 0)?;
 
                     let mut sub = SubsystemBuilder::new((cfg_mem, cfg_slice.addr(), cfg_len));
 
@@ -634,6 +664,8 @@ impl Subsystem {
                     child_mem,
                     cfg.clone(),
                     sub,
+                    d.name().to_string(),
+                    false,
                 ));
                 log!(crate::LOG_CHILD, "Created {:?}", child);
 
@@ -811,6 +843,20 @@ impl SubsystemBuilder {
     }
 }
 
+pub(crate) fn delay_child(child: Box<childs::OwnChild>) {
+    DELAYED.borrow_mut().push(child);
+}
+
+/// Returns the time until the next delayed child should be restarted
+pub(crate) fn next_restart() -> Option<TimeDuration> {
+    let now = TimeInstant::now();
+    DELAYED
+        .borrow()
+        .iter()
+        .filter_map(|c| c.restart_at()?.checked_duration_since(now))
+        .min()
+}
+
 pub(crate) fn start_delayed_async<S>(mut spawn_async: S) -> Result<(), VerboseError>
 where
     S: FnMut(&mut childs::OwnChild) -> Result<(), VerboseError>,
@@ -818,15 +864,29 @@ where
     let mut new_wait = false;
     let mut idx = 0;
     while idx < DELAYED.borrow().len() {
+        // don't restart childs anymore if we're shutting down
+        if DELAYED.borrow()[idx].restarts() > 0 && childs::borrow_mut().is_shutdown() {
+            DELAYED.borrow_mut().remove(idx);
+            continue;
+        }
+
         if DELAYED.borrow()[idx].has_unmet_reqs() {
             idx += 1;
             continue;
         }
 
         let mut child = DELAYED.borrow_mut().remove(idx);
-        spawn_async(&mut child)?;
-        childs::borrow_mut().add(child);
-        new_wait = true;
+        match spawn_async(&mut child) {
+            Ok(_) => {
+                childs::borrow_mut().add(child);
+                new_wait = true;
+            },
+            // childs that have been added at runtime should not take us down
+            Err(e) if child.is_dynamic() => {
+                println!("Unable to start child '{}': {}", child.name(), e)
+            },
+            Err(e) => return Err(e),
+        }
     }
 
     if new_wait {
@@ -835,6 +895,124 @@ where
     Ok(())
 }
 
+/// Creates a new child with given configuration in the domain with given name. The child is
+/// started by `start_delayed_async` as soon as its requirements are met.
+pub(crate) fn add_child(domain: &str, cfg: config::AppConfig) -> Result<(), Error> {
+    // the memory and subsystems of a domain are determined at boot and cannot be extended
+    if !cfg.domains().is_empty() || !cfg.phys_mems().is_empty() || cfg.user_mem().is_some() {
+        return Err(Error::new(Code::NotSup));
+    }
+    if childs::borrow_mut().is_shutdown() {
+        return Err(Error::new(Code::InvState));
+    }
+    for s in cfg.services() {
+        if services::get_by_name(s.name().global()).is_ok() {
+            return Err(Error::new(Code::Exists));
+        }
+    }
+    for rgate in cfg.rgates() {
+        if gates::get().get(rgate.name().global()).is_some() {
+            return Err(Error::new(Code::Exists));
+        }
+    }
+
+    let doms = DOMAINS.borrow();
+    let (dom, base) = doms
+        .iter()
+        .filter(|d| !d.name.is_empty() && d.name == domain)
+        .find_map(|d| d.base.as_ref().map(|b| (d, b)))
+        .ok_or_else(|| Error::new(Code::NotFound))?;
+
+    let (domain_tile, child_tile) = if cfg.eps.is_some()
+        || cfg.time.is_some()
+        || cfg.pts.is_some()
+        || cfg.sched.class != SchedClass::INHERIT
+    {
+        let child_tile = base.derive(cfg.eps, cfg.time, cfg.pts, cfg.sched)?;
+        (Some(base.clone()), Rc::new(child_tile))
+    }
+    else {
+        (None, base.clone())
+    };
+
+    let kmem = match cfg.kernel_mem() {
+        Some(bytes) => dom.kmem.derive(bytes)?,
+        None => dom.kmem.clone(),
+    };
+
+    for rgate in cfg.rgates() {
+        let res = gates::get().add_rgate(
+            rgate.name().global().clone(),
+            rgate.msg_size(),
+            rgate.slots(),
+        );
+        if let Err(e) = res {
+            remove_rgates(&cfg);
+            return Err(e);
+        }
+    }
+
+    let child_id = childs::borrow_mut().alloc_id();
+    let child = Box::new(childs::OwnChild::new(
+        child_id,
+        dom.tile.clone(),
+        domain_tile,
+        child_tile,
+        cfg.args().clone(),
+        cfg.daemon(),
+        kmem,
+        dom.umem.clone(),
+        Rc::new(cfg),
+        None,
+        domain.to_string(),
+        true,
+    ));
+    log!(crate::LOG_CHILD, "Created {:?}", child);
+
+    delay_child(child);
+    Ok(())
+}
+
+/// Returns the resources of all domains in the order of the configuration
+pub(crate) fn domains() -> Ref<'static, Vec<DomainRes>> {
+    DOMAINS.borrow()
+}
+
+/// Removes all delayed childs with given name and returns their number
+pub(crate) fn remove_delayed(name: &str) -> usize {
+    let mut delayed = DELAYED.borrow_mut();
+    let count = delayed.len();
+    delayed.retain(|c| {
+        if c.name() != name {
+            return true;
+        }
+        if c.is_dynamic() {
+            remove_rgates(&c.cfg());
+        }
+        false
+    });
+    count - delayed.len()
+}
+
+/// Removes the receive gates that have been created by `add_child` for the given configuration
+pub(crate) fn remove_rgates(cfg: &config::AppConfig) {
+    for rgate in cfg.rgates() {
+        gates::get().remove_rgate(rgate.name().global());
+    }
+}
+
+/// Returns information about the delayed child with given index
+pub(crate) fn delayed_info(idx: usize) -> Option<ResMngChildInfo> {
+    DELAYED.borrow().get(idx).map(|c| ResMngChildInfo {
+        id: c.id(),
+        name: c.name().clone(),
+        domain: c.domain().to_string(),
+        daemon: c.daemon(),
+        running: false,
+        restarts: c.restarts(),
+    })
+}
+
 fn pass_down_tiles(sub: &mut SubsystemBuilder, app: &config::AppConfig) {
     for d in app.domains() {
         for child in d.apps() {
@@ -17,7 +17,7 @@ use m3::cell::{Cell, LazyReadOnlyCell, RefCell};
 use m3::col::Vec;
 use m3::com::MemGate;
 use m3::errors::{Code, Error};
-use m3::kif::{Perm, TileDesc};
+use m3::kif::{tilemux::SchedParams, Perm, TileDesc};
 use m3::log;
 use m3::rc::Rc;
 use m3::syscalls;
@@ -109,8 +109,9 @@ impl TileUsage {
         eps: Option<u32>,
         time: Option<u64>,
         pts: Option<usize>,
+        sched: SchedParams,
     ) -> Result<TileUsage, Error> {
-        let tile = self.tile_obj().derive(eps, time, pts)?;
+        let tile = self.tile_obj().derive_with_sched(eps, time, pts, sched)?;
         if let Some(idx) = self.idx {
             get().tiles[idx].add_user();
         }
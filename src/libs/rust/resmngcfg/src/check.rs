@@ -0,0 +1,593 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Validates app configurations against the rules of the resource manager
+//!
+//! [`check_app`] checks the tags, attributes, and values of a single app and its subtree, so that
+//! the resource manager only needs to convert the values afterwards. [`App::check`] checks the
+//! references between the apps, which is only possible if the configuration is complete. Checks
+//! that depend on the platform (e.g., the number of available tiles) are left to the resource
+//! manager.
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::error::Error;
+use crate::xml::{Attr, Element, Loc};
+
+/// The type of an attribute value
+#[derive(Copy, Clone)]
+pub enum Ty {
+    Str,
+    Addr,
+    Size,
+    Time,
+    Rate,
+    Int,
+    Bool,
+    Perm,
+    Enum(&'static [&'static str]),
+}
+
+const DUAL_NAME: [(&str, Ty); 3] = [("name", Ty::Str), ("lname", Ty::Str), ("gname", Ty::Str)];
+
+const APP_ATTRS: &[(&str, Ty)] = &[
+    ("args", Ty::Str),
+    ("usermem", Ty::Size),
+    ("kernmem", Ty::Size),
+    ("time", Ty::Time),
+    ("syscalls", Ty::Rate),
+    ("prio", Ty::Int),
+    ("period", Ty::Time),
+    ("budget", Ty::Time),
+    ("pagetables", Ty::Int),
+    ("eps", Ty::Int),
+    ("daemon", Ty::Bool),
+    ("restart", Ty::Enum(&["never", "always", "on-failure"])),
+    ("retries", Ty::Int),
+    ("backoff", Ty::Time),
+    ("getinfo", Ty::Bool),
+    ("admin", Ty::Bool),
+];
+
+/// The maximum priority of an app, which needs to match `kif::tilemux::MAX_PRIO`
+pub const MAX_PRIO: u64 = 255;
+
+const DOM_ATTRS: &[(&str, Ty)] = &[("name", Ty::Str), ("tile", Ty::Str)];
+
+const CHILD_TAGS: &[(&str, &[(&str, Ty)])] = &[
+    ("mount", &[("fs", Ty::Str), ("path", Ty::Str)]),
+    ("physmem", &[
+        ("addr", Ty::Addr),
+        ("size", Ty::Size),
+        ("perm", Ty::Perm),
+    ]),
+    ("serv", &[
+        DUAL_NAME[0],
+        DUAL_NAME[1],
+        DUAL_NAME[2],
+        ("ready", Ty::Enum(&["register", "signal", "probe"])),
+        ("probe", Ty::Str),
+        ("liveness", Ty::Time),
+    ]),
+    ("sesscrt", &[("name", Ty::Str), ("count", Ty::Int)]),
+    ("sess", &[
+        DUAL_NAME[0],
+        DUAL_NAME[1],
+        DUAL_NAME[2],
+        ("args", Ty::Str),
+        ("dep", Ty::Bool),
+    ]),
+    ("tiles", &[
+        ("type", Ty::Str),
+        ("count", Ty::Int),
+        ("optional", Ty::Bool),
+    ]),
+    ("rgate", &[
+        DUAL_NAME[0],
+        DUAL_NAME[1],
+        DUAL_NAME[2],
+        ("msgsize", Ty::Size),
+        ("slots", Ty::Int),
+    ]),
+    ("sgate", &[
+        DUAL_NAME[0],
+        DUAL_NAME[1],
+        DUAL_NAME[2],
+        ("credits", Ty::Int),
+        ("label", Ty::Int),
+    ]),
+    ("sem", &DUAL_NAME),
+    ("shm", &DUAL_NAME),
+    ("serial", &[]),
+];
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum DepState {
+    Unvisited,
+    Visiting,
+    Done,
+}
+
+fn error<T>(loc: Loc, msg: String) -> Result<T, Error> {
+    Err(Error::new(loc, msg))
+}
+
+fn is_int(s: &str) -> bool {
+    s.parse::<u64>().is_ok()
+}
+
+fn is_time(s: &str) -> bool {
+    ["ns", "µs", "ms", "s"]
+        .iter()
+        .any(|suffix| s.strip_suffix(suffix).map_or(false, is_int))
+}
+
+fn valid(ty: Ty, v: &str) -> bool {
+    // variables are replaced before the boot, so that we can't say anything about them
+    if v.contains('$') {
+        return true;
+    }
+
+    match ty {
+        Ty::Str => true,
+        Ty::Addr => match v.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).is_ok(),
+            None => is_int(v),
+        },
+        Ty::Size => match v.chars().last() {
+            Some('k') | Some('K') | Some('m') | Some('M') | Some('g') | Some('G') => {
+                is_int(&v[0..v.len() - 1])
+            },
+            _ => is_int(v),
+        },
+        Ty::Time => is_time(v),
+        Ty::Rate => match v.split_once('/') {
+            Some((count, per)) => is_int(count) && (is_time(per) || is_time(&format!("1{}", per))),
+            None => false,
+        },
+        Ty::Int => is_int(v),
+        Ty::Bool => v == "true" || v == "false" || is_int(v),
+        Ty::Perm => v.chars().all(|c| c == 'r' || c == 'w' || c == 'x'),
+        Ty::Enum(vals) => vals.contains(&v),
+    }
+}
+
+fn is_true(attr: Option<&Attr>) -> bool {
+    match attr {
+        Some(a) => a.value == "true" || a.value.parse::<u64>() == Ok(1),
+        None => false,
+    }
+}
+
+/// Checks that all attributes of `elem` are contained in `schema` and have valid values
+pub fn check_attrs(elem: &Element, schema: &[(&str, Ty)]) -> Result<(), Error> {
+    for a in &elem.attrs {
+        match schema.iter().find(|(n, _)| *n == a.name) {
+            None => {
+                return error(
+                    a.loc.clone(),
+                    format!("unknown attribute '{}' in <{}>", a.name, elem.name),
+                )
+            },
+            Some((_, ty)) if !valid(*ty, &a.value) => {
+                return error(
+                    a.loc.clone(),
+                    format!(
+                        "invalid value '{}' for attribute '{}' in <{}>",
+                        a.value, a.name, elem.name
+                    ),
+                )
+            },
+            Some(_) => {},
+        }
+    }
+    Ok(())
+}
+
+fn require(elem: &Element, attrs: &[&str]) -> Result<(), Error> {
+    if attrs.iter().any(|a| elem.attr(a).is_none()) {
+        let list = attrs
+            .iter()
+            .map(|a| format!("'{}'", a))
+            .collect::<Vec<_>>()
+            .join(" and ");
+        let what = if attrs.len() == 1 {
+            "attribute"
+        }
+        else {
+            "attributes"
+        };
+        return error(
+            elem.loc.clone(),
+            format!("<{}> requires the {} {}", elem.name, what, list),
+        );
+    }
+    Ok(())
+}
+
+/// Returns the global name of the given service, session, or gate
+fn global_name(elem: &Element) -> Result<String, Error> {
+    match (elem.attr("name"), elem.attr("lname"), elem.attr("gname")) {
+        (Some(n), _, None) => Ok(n.value.clone()),
+        (Some(_), _, Some(g)) | (_, Some(_), Some(g)) => Ok(g.value.clone()),
+        _ => error(
+            elem.loc.clone(),
+            format!(
+                "<{}> requires the attribute 'name' or both 'lname' and 'gname'",
+                elem.name
+            ),
+        ),
+    }
+}
+
+/// The information about an app that is required for the checks across apps
+#[derive(Default)]
+pub struct App {
+    name: String,
+    loc: Loc,
+    services: Vec<(String, Loc)>,
+    // global name, loc, and whether it's a dependency
+    sessions: Vec<(String, Loc, bool)>,
+    sesscrt: Vec<String>,
+    // global name, loc, and the number of slots (if known)
+    rgates: Vec<(String, Loc, Option<u64>)>,
+    sgates: Vec<(String, Loc)>,
+    // the apps in all domains, including the pseudo domain
+    childs: Vec<App>,
+}
+
+fn parse_domain(dom: &Element) -> Result<Vec<App>, Error> {
+    check_attrs(dom, DOM_ATTRS)?;
+
+    let mut apps = Vec::new();
+    for c in &dom.childs {
+        if c.name != "app" {
+            return error(
+                c.loc.clone(),
+                format!("expected <app> in <dom>, found <{}>", c.name),
+            );
+        }
+        apps.push(check_app(c, false)?);
+    }
+
+    if apps.is_empty() {
+        return error(
+            dom.loc.clone(),
+            "<dom> requires at least one <app>".to_string(),
+        );
+    }
+    Ok(apps)
+}
+
+/// Checks the given `<app>` and its subtree and returns the information for [`App::check`]
+///
+/// For the `root` app, the session creators for the dependencies of its childs are not collected,
+/// because there is nobody to create the sessions.
+pub fn check_app(elem: &Element, root: bool) -> Result<App, Error> {
+    check_attrs(elem, APP_ATTRS)?;
+    require(elem, &["args"])?;
+
+    let mut app = App {
+        name: elem
+            .attr("args")
+            .unwrap()
+            .value
+            .split_whitespace()
+            .next()
+            .unwrap_or("")
+            .to_string(),
+        loc: elem.loc.clone(),
+        ..Default::default()
+    };
+
+    let period = elem.attr("period");
+    let budget = elem.attr("budget");
+    if let (Some(_), Some(p)) = (elem.attr("prio"), period.or(budget)) {
+        return error(
+            p.loc.clone(),
+            format!(
+                "app '{}' cannot use '{}' together with 'prio'",
+                app.name, p.name
+            ),
+        );
+    }
+    // like in valid, values with variables cannot be checked yet
+    if let Some(p) = elem.attr("prio") {
+        match p.value.parse::<u64>() {
+            Ok(prio) if prio > MAX_PRIO => {
+                return error(
+                    p.loc.clone(),
+                    format!(
+                        "app '{}' has priority {}, but the maximum is {}",
+                        app.name, prio, MAX_PRIO
+                    ),
+                );
+            },
+            _ => {},
+        }
+    }
+    // EDF scheduling requires both period and budget
+    if period.is_some() != budget.is_some() {
+        return error(
+            elem.loc.clone(),
+            format!(
+                "app '{}' needs both 'period' and 'budget' for EDF scheduling",
+                app.name
+            ),
+        );
+    }
+
+    // only daemons can be restarted, because the others determine when we shut down
+    let restart = elem.attr("restart").map_or(false, |r| r.value != "never");
+    if restart && !is_true(elem.attr("daemon")) {
+        return error(
+            elem.loc.clone(),
+            format!("app '{}' can only be restarted if it is a daemon", app.name),
+        );
+    }
+
+    let mut doms = BTreeSet::new();
+    for c in &elem.childs {
+        match c.name.as_ref() {
+            "app" => app.childs.push(check_app(c, false)?),
+            "dom" => {
+                if let Some(n) = c.attr("name") {
+                    if !doms.insert(n.value.clone()) {
+                        return error(
+                            c.loc.clone(),
+                            format!(
+                                "app '{}' contains multiple domains named '{}'",
+                                app.name, n.value
+                            ),
+                        );
+                    }
+                }
+                app.childs.extend(parse_domain(c)?);
+            },
+            tag => match CHILD_TAGS.iter().find(|(n, _)| *n == tag) {
+                Some((_, schema)) => {
+                    check_attrs(c, schema)?;
+                    if !c.childs.is_empty() {
+                        return error(
+                            c.childs[0].loc.clone(),
+                            format!("<{}> cannot have childs", tag),
+                        );
+                    }
+                    parse_resource(&mut app, c)?;
+                },
+                None => return error(c.loc.clone(), format!("unknown tag <{}> in <app>", tag)),
+            },
+        }
+    }
+
+    // we can't restart subsystems, because their resources have been passed down
+    if restart && !app.childs.is_empty() {
+        return error(
+            elem.loc.clone(),
+            format!(
+                "app '{}' cannot be restarted, because it has domains",
+                app.name
+            ),
+        );
+    }
+
+    // the resource manager creates the sessions for the dependencies of our childs that are not
+    // provided within our subtree (except for root)
+    if !root {
+        let mut crts = Vec::new();
+        collect_sess_crts(&app, &mut crts);
+        for c in crts {
+            if !app.sesscrt.contains(&c) && !app.childs.iter().any(|a| a.provides(&c)) {
+                app.sesscrt.push(c);
+            }
+        }
+    }
+
+    Ok(app)
+}
+
+fn parse_resource(app: &mut App, elem: &Element) -> Result<(), Error> {
+    match elem.name.as_ref() {
+        "mount" => require(elem, &["fs", "path"]),
+        "tiles" => require(elem, &["type"]),
+        "sesscrt" => {
+            require(elem, &["name"])?;
+            app.sesscrt.push(elem.attr("name").unwrap().value.clone());
+            Ok(())
+        },
+        "serv" => {
+            app.services.push((global_name(elem)?, elem.loc.clone()));
+            Ok(())
+        },
+        "sess" => {
+            let dep = elem.attr("dep").map_or(true, |_| is_true(elem.attr("dep")));
+            app.sessions
+                .push((global_name(elem)?, elem.loc.clone(), dep));
+            Ok(())
+        },
+        "rgate" => {
+            let slots = match elem.attr("slots") {
+                Some(s) => s.value.parse::<u64>().ok(),
+                None => Some(1),
+            };
+            app.rgates
+                .push((global_name(elem)?, elem.loc.clone(), slots));
+            Ok(())
+        },
+        "sgate" => {
+            app.sgates.push((global_name(elem)?, elem.loc.clone()));
+            Ok(())
+        },
+        "sem" | "shm" => global_name(elem).map(|_| ()),
+        _ => Ok(()),
+    }
+}
+
+fn collect_sess_crts(app: &App, crts: &mut Vec<String>) {
+    for a in &app.childs {
+        for (name, _, dep) in &a.sessions {
+            if *dep {
+                crts.push(name.clone());
+            }
+        }
+        collect_sess_crts(a, crts);
+    }
+}
+
+impl App {
+    fn provides(&self, serv: &str) -> bool {
+        self.services.iter().any(|(s, _)| s == serv) || self.childs.iter().any(|a| a.provides(serv))
+    }
+
+    /// Checks that the services, dependencies, and gates of all apps within this app are
+    /// satisfiable
+    pub fn check(&self) -> Result<(), Error> {
+        self.check_services(&BTreeSet::new())?;
+        self.check_deps()?;
+        self.check_gates()
+    }
+
+    fn check_services(&self, parent_set: &BTreeSet<String>) -> Result<(), Error> {
+        let mut set = BTreeSet::new();
+        for a in &self.childs {
+            for (serv, loc) in &a.services {
+                if set.contains(serv) || parent_set.contains(serv) {
+                    return error(
+                        loc.clone(),
+                        format!("config '{}': service '{}' does already exist", a.name, serv),
+                    );
+                }
+                set.insert(serv.clone());
+            }
+        }
+
+        let subset = set.union(parent_set).cloned().collect::<BTreeSet<_>>();
+        for a in &self.childs {
+            a.check_services(&subset)?;
+        }
+
+        for (sess, loc, _) in &self.sessions {
+            if !set.contains(sess) && !parent_set.contains(sess) {
+                return error(
+                    loc.clone(),
+                    format!("config '{}': service '{}' does not exist", self.name, sess),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn check_deps(&self) -> Result<(), Error> {
+        // childs are only started if all services they depend on are available. thus, a cycle
+        // between the childs on the same level lets all of them wait forever.
+        let mut state = vec![DepState::Unvisited; self.childs.len()];
+        let mut path = Vec::new();
+        for i in 0..self.childs.len() {
+            self.visit_deps(i, &mut state, &mut path)?;
+        }
+
+        for a in &self.childs {
+            a.check_deps()?;
+        }
+        Ok(())
+    }
+
+    fn visit_deps(
+        &self,
+        idx: usize,
+        state: &mut [DepState],
+        path: &mut Vec<usize>,
+    ) -> Result<(), Error> {
+        match state[idx] {
+            DepState::Done => return Ok(()),
+            DepState::Visiting => {
+                let start = path.iter().position(|i| *i == idx).unwrap();
+                let mut cycle = String::new();
+                for i in &path[start..] {
+                    cycle.push_str(&format!("'{}' -> ", self.childs[*i].name));
+                }
+                return error(
+                    self.childs[idx].loc.clone(),
+                    format!(
+                        "config: dependency cycle {}'{}'",
+                        cycle, self.childs[idx].name
+                    ),
+                );
+            },
+            DepState::Unvisited => {},
+        }
+
+        state[idx] = DepState::Visiting;
+        path.push(idx);
+        let app = &self.childs[idx];
+        let deps = app
+            .sessions
+            .iter()
+            .filter(|(_, _, dep)| *dep)
+            .map(|(name, ..)| name)
+            .chain(app.sesscrt.iter());
+        for dep in deps {
+            if let Some(provider) = self.childs.iter().position(|a| a.provides(dep)) {
+                self.visit_deps(provider, state, path)?;
+            }
+        }
+        path.pop();
+        state[idx] = DepState::Done;
+        Ok(())
+    }
+
+    fn check_gates(&self) -> Result<(), Error> {
+        let mut slots = Vec::<(&String, Option<u64>)>::new();
+        for a in &self.childs {
+            for (rgate, loc, count) in &a.rgates {
+                if slots.iter().any(|(n, _)| *n == rgate) {
+                    return error(
+                        loc.clone(),
+                        format!("config '{}': rgate '{}' does already exist", a.name, rgate),
+                    );
+                }
+                slots.push((rgate, *count));
+            }
+        }
+
+        for a in &self.childs {
+            a.check_gates()?;
+
+            for (sgate, loc) in &a.sgates {
+                match slots.iter_mut().find(|(n, _)| *n == sgate) {
+                    Some((_, Some(0))) => {
+                        return error(
+                            loc.clone(),
+                            format!("config '{}': not enough slots in rgate '{}'", a.name, sgate),
+                        )
+                    },
+                    Some((_, Some(s))) => *s -= 1,
+                    Some((_, None)) => {},
+                    None => {
+                        return error(
+                            loc.clone(),
+                            format!("config '{}': rgate '{}' does not exist", a.name, sgate),
+                        )
+                    },
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use alloc::string::String;
+use core::fmt;
+
+use crate::xml::Loc;
+
+/// An error in a configuration, referring to the tag or attribute that caused it
+#[derive(Debug)]
+pub struct Error {
+    pub loc: Loc,
+    pub msg: String,
+}
+
+impl Error {
+    pub fn new(loc: Loc, msg: String) -> Self {
+        Self { loc, msg }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.loc.file.is_empty() {
+            write!(f, "{}:", self.loc.file)?;
+        }
+        write!(f, "{}:{}: {}", self.loc.line, self.loc.col, self.msg)
+    }
+}
@@ -0,0 +1,202 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Expands variables and templates within apps
+//!
+//! Variables are defined via `<var name="..." value="..."/>` and referred to via `$name` in
+//! attribute values (`$$` produces a single `$`). Templates are defined via
+//! `<template name="...">...</template>` and instantiated via `<use template="..." .../>`, whereas
+//! all other attributes of `<use>` are available as variables within the template. Both are visible
+//! from their definition to the end of the enclosing element.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::slice;
+
+use crate::error::Error;
+use crate::xml::{Attr, Element, Loc};
+
+/// The maximum nesting level of templates
+const MAX_DEPTH: usize = 16;
+
+#[derive(Default)]
+struct Scope {
+    vars: Vec<(String, String)>,
+    templates: Vec<(String, Vec<Element>)>,
+}
+
+struct Expander {
+    scopes: Vec<Scope>,
+}
+
+fn error<T>(loc: &Loc, msg: String) -> Result<T, Error> {
+    Err(Error::new(loc.clone(), msg))
+}
+
+impl Expander {
+    fn var(&self, name: &str) -> Option<&String> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|s| s.vars.iter())
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+    }
+
+    fn template(&self, name: &str) -> Option<&Vec<Element>> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|s| s.templates.iter())
+            .find(|(n, _)| n == name)
+            .map(|(_, t)| t)
+    }
+
+    fn subst(&self, attr: &Attr) -> Result<String, Error> {
+        let mut res = String::new();
+        let mut chars = attr.value.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '$' {
+                res.push(c);
+                continue;
+            }
+            if chars.peek() == Some(&'$') {
+                chars.next();
+                res.push('$');
+                continue;
+            }
+
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !c.is_alphanumeric() && c != '_' && c != '.' && c != '-' {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+
+            match self.var(&name) {
+                Some(v) => res.push_str(v),
+                None => return error(&attr.loc, format!("unknown variable '${}'", name)),
+            }
+        }
+        Ok(res)
+    }
+
+    fn expand_childs(&mut self, childs: &[Element], depth: usize) -> Result<Vec<Element>, Error> {
+        let mut res = Vec::new();
+        for c in childs {
+            match c.name.as_ref() {
+                "var" => match (c.attrs.as_slice(), c.childs.is_empty()) {
+                    ([n, v], true) | ([v, n], true) if n.name == "name" && v.name == "value" => {
+                        let value = self.subst(v)?;
+                        let scope = self.scopes.last_mut().unwrap();
+                        scope.vars.push((n.value.clone(), value));
+                    },
+                    _ => {
+                        return error(
+                            &c.loc,
+                            "<var> expects the attributes 'name' and 'value'".to_string(),
+                        )
+                    },
+                },
+
+                "template" => match (c.attrs.as_slice(), c.childs.is_empty()) {
+                    ([n], false) if n.name == "name" => {
+                        let scope = self.scopes.last_mut().unwrap();
+                        scope.templates.push((n.value.clone(), c.childs.clone()));
+                    },
+                    _ => {
+                        return error(
+                            &c.loc,
+                            "<template> expects the attribute 'name' and a body".to_string(),
+                        )
+                    },
+                },
+
+                "use" => {
+                    let name = match c.attr("template") {
+                        Some(t) if c.childs.is_empty() => self.subst(t)?,
+                        _ => {
+                            return error(
+                                &c.loc,
+                                "<use> expects the attribute 'template'".to_string(),
+                            )
+                        },
+                    };
+                    let body = match self.template(&name) {
+                        Some(b) => b.clone(),
+                        None => return error(&c.loc, format!("unknown template '{}'", name)),
+                    };
+                    if depth == MAX_DEPTH {
+                        return error(&c.loc, format!("templates nested too deeply at '{}'", name));
+                    }
+
+                    // the parameters are only visible within the template
+                    let mut scope = Scope::default();
+                    for a in c.attrs.iter().filter(|a| a.name != "template") {
+                        scope.vars.push((a.name.clone(), self.subst(a)?));
+                    }
+                    self.scopes.push(scope);
+                    let elems = self.expand_childs(&body, depth + 1);
+                    self.scopes.pop();
+                    res.extend(elems?);
+                },
+
+                _ => {
+                    let mut elem = Element {
+                        name: c.name.clone(),
+                        attrs: Vec::new(),
+                        childs: Vec::new(),
+                        loc: c.loc.clone(),
+                    };
+                    for a in &c.attrs {
+                        elem.attrs.push(Attr {
+                            name: a.name.clone(),
+                            value: self.subst(a)?,
+                            loc: a.loc.clone(),
+                        });
+                    }
+
+                    self.scopes.push(Scope::default());
+                    let childs = self.expand_childs(&c.childs, depth);
+                    self.scopes.pop();
+                    elem.childs = childs?;
+                    res.push(elem);
+                },
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// Expands all variables and templates within the given app
+///
+/// The variables in `vars` are predefined with the given values.
+pub fn expand_app(app: &Element, vars: &[(&str, &str)]) -> Result<Element, Error> {
+    let mut exp = Expander {
+        scopes: vec![Scope {
+            vars: vars
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+            templates: Vec::new(),
+        }],
+    };
+    let mut res = exp.expand_childs(slice::from_ref(app), 0)?;
+    Ok(res.pop().unwrap())
+}
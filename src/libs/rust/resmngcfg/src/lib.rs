@@ -0,0 +1,30 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Parsing and validation of the configurations of the resource manager
+//!
+//! This crate is shared between the resource manager and the host tool cfgcheck, which checks the
+//! boot configurations before they are used. Therefore, it only depends on `core` and `alloc`.
+
+#![no_std]
+
+extern crate alloc;
+
+pub mod check;
+mod error;
+pub mod expand;
+pub mod xml;
+
+pub use error::Error;
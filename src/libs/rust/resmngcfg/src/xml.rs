@@ -0,0 +1,365 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! A minimal XML parser for the configurations that remembers the location of all tags and
+//! attributes
+//!
+//! Elements of the form `<include file="..."/>` are replaced by the elements in the given file,
+//! which is obtained from the [`Includer`].
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::error::Error;
+
+/// The maximum nesting level of includes
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// The location within a configuration; `file` is empty if the configuration is not stored in a
+/// file
+#[derive(Clone, Debug, Default)]
+pub struct Loc {
+    pub file: Rc<String>,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Attr {
+    pub name: String,
+    pub value: String,
+    pub loc: Loc,
+}
+
+#[derive(Clone, Debug)]
+pub struct Element {
+    pub name: String,
+    pub attrs: Vec<Attr>,
+    pub childs: Vec<Element>,
+    pub loc: Loc,
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&Attr> {
+        self.attrs.iter().find(|a| a.name == name)
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        write!(f, "{:indent$}<{}", "", self.name, indent = indent)?;
+        for a in &self.attrs {
+            write!(f, " {}=\"{}\"", a.name, a.value)?;
+        }
+        if self.childs.is_empty() {
+            return writeln!(f, " />");
+        }
+
+        writeln!(f, ">")?;
+        for c in &self.childs {
+            c.write(f, indent + 4)?;
+        }
+        writeln!(f, "{:indent$}</{}>", "", self.name, indent = indent)
+    }
+}
+
+impl fmt::Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+/// Provides the files for `<include>`
+pub trait Includer {
+    /// Returns the name and the content of `file`, which is included by the file `from`
+    fn read(&self, from: &str, file: &str) -> Result<(String, String), String>;
+}
+
+struct Parser<'i> {
+    file: Rc<String>,
+    includer: Option<&'i dyn Includer>,
+    depth: usize,
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    col: usize,
+}
+
+impl<'i> Parser<'i> {
+    fn new(file: String, xml: &str, includer: Option<&'i dyn Includer>, depth: usize) -> Self {
+        Parser {
+            file: Rc::new(file),
+            includer,
+            depth,
+            chars: xml.chars().collect(),
+            pos: 0,
+            line: 1,
+            col: 1,
+        }
+    }
+
+    fn loc(&self) -> Loc {
+        Loc {
+            file: self.file.clone(),
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    fn error<T>(&self, msg: String) -> Result<T, Error> {
+        Err(Error::new(self.loc(), msg))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn get(&mut self) -> Result<char, Error> {
+        match self.peek() {
+            Some(c) => {
+                self.pos += 1;
+                if c == '\n' {
+                    self.line += 1;
+                    self.col = 1;
+                }
+                else {
+                    self.col += 1;
+                }
+                Ok(c)
+            },
+            None => self.error("unexpected end of input".to_string()),
+        }
+    }
+
+    fn skip(&mut self, n: usize) -> Result<(), Error> {
+        for _ in 0..n {
+            self.get()?;
+        }
+        Ok(())
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().map_or(false, char::is_whitespace) {
+            self.get().ok();
+        }
+    }
+
+    /// Skips whitespace, comments, and the XML declaration
+    fn skip_misc(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip_ws();
+            let end = if self.starts_with("<!--") {
+                "-->"
+            }
+            else if self.starts_with("<?") {
+                "?>"
+            }
+            else {
+                break Ok(());
+            };
+            while !self.starts_with(end) {
+                self.get()?;
+            }
+            self.skip(end.len())?;
+        }
+    }
+
+    fn consume(&mut self, c: char) -> Result<(), Error> {
+        self.skip_ws();
+        match self.peek() {
+            Some(nc) if nc == c => self.skip(1),
+            Some(nc) => self.error(format!("expected '{}', found '{}'", c, nc)),
+            None => self.error(format!("expected '{}', found end of input", c)),
+        }
+    }
+
+    fn parse_ident(&mut self) -> Result<String, Error> {
+        self.skip_ws();
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if !c.is_alphanumeric() && c != '_' && c != '-' && c != ':' {
+                break;
+            }
+            name.push(self.get()?);
+        }
+        if name.is_empty() {
+            match self.peek() {
+                Some(c) => self.error(format!("expected name, found '{}'", c)),
+                None => self.error("expected name, found end of input".to_string()),
+            }
+        }
+        else {
+            Ok(name)
+        }
+    }
+
+    fn parse_attr(&mut self) -> Result<Attr, Error> {
+        self.skip_ws();
+        let loc = self.loc();
+        let name = self.parse_ident()?;
+        self.consume('=')?;
+        self.consume('"')?;
+
+        let mut value = String::new();
+        loop {
+            match self.get()? {
+                '"' => break,
+                c => value.push(c),
+            }
+        }
+        Ok(Attr { name, value, loc })
+    }
+
+    fn parse_element(&mut self) -> Result<Element, Error> {
+        self.skip_misc()?;
+        let loc = self.loc();
+        self.consume('<')?;
+        let name = self.parse_ident()?;
+
+        let mut elem = Element {
+            name,
+            attrs: Vec::new(),
+            childs: Vec::new(),
+            loc,
+        };
+
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                Some('/') => {
+                    self.skip(1)?;
+                    self.consume('>')?;
+                    return Ok(elem);
+                },
+                Some('>') => {
+                    self.skip(1)?;
+                    break;
+                },
+                _ => {
+                    let attr = self.parse_attr()?;
+                    if elem.attr(&attr.name).is_some() {
+                        return Err(Error::new(
+                            attr.loc,
+                            format!("duplicate attribute '{}' in <{}>", attr.name, elem.name),
+                        ));
+                    }
+                    elem.attrs.push(attr);
+                },
+            }
+        }
+
+        loop {
+            self.skip_misc()?;
+            if self.starts_with("</") {
+                let close = self.loc();
+                self.skip(2)?;
+                let name = self.parse_ident()?;
+                if name != elem.name {
+                    return Err(Error::new(
+                        close,
+                        format!("expected </{}>, found </{}>", elem.name, name),
+                    ));
+                }
+                self.consume('>')?;
+                return Ok(elem);
+            }
+            if self.peek() != Some('<') {
+                return self.error(format!("unexpected text in <{}>", elem.name));
+            }
+
+            let child = self.parse_element()?;
+            if child.name == "include" {
+                elem.childs.extend(self.include(&child)?);
+            }
+            else {
+                elem.childs.push(child);
+            }
+        }
+    }
+
+    fn include(&self, elem: &Element) -> Result<Vec<Element>, Error> {
+        let file = match (elem.attrs.as_slice(), elem.childs.is_empty()) {
+            ([a], true) if a.name == "file" => &a.value,
+            _ => {
+                return Err(Error::new(
+                    elem.loc.clone(),
+                    "<include> expects the attribute 'file' and no childs".to_string(),
+                ))
+            },
+        };
+        let includer = match self.includer {
+            Some(i) => i,
+            None => {
+                return Err(Error::new(
+                    elem.loc.clone(),
+                    format!("unable to include '{}': includes are not supported", file),
+                ))
+            },
+        };
+        if self.depth == MAX_INCLUDE_DEPTH {
+            return Err(Error::new(
+                elem.loc.clone(),
+                format!("includes nested too deeply at '{}'", file),
+            ));
+        }
+
+        let (name, xml) = includer.read(&self.file, file).map_err(|e| {
+            Error::new(
+                elem.loc.clone(),
+                format!("unable to include '{}': {}", file, e),
+            )
+        })?;
+        let mut p = Parser::new(name, &xml, self.includer, self.depth + 1);
+
+        // an included file contains an arbitrary number of elements
+        let mut elems = Vec::new();
+        loop {
+            p.skip_misc()?;
+            if p.peek().is_none() {
+                break Ok(elems);
+            }
+
+            let child = p.parse_element()?;
+            if child.name == "include" {
+                elems.extend(p.include(&child)?);
+            }
+            else {
+                elems.push(child);
+            }
+        }
+    }
+}
+
+/// Parses the given XML document, resolves all includes, and returns the root element
+///
+/// The name of the file is used for the locations in errors and passed to the includer. Without
+/// includer, `<include>` is refused.
+pub fn parse(file: &str, xml: &str, includer: Option<&dyn Includer>) -> Result<Element, Error> {
+    let mut p = Parser::new(file.to_string(), xml, includer, 0);
+
+    let root = p.parse_element()?;
+    p.skip_misc()?;
+    if p.peek().is_some() {
+        return p.error("unexpected content after root element".to_string());
+    }
+    Ok(root)
+}
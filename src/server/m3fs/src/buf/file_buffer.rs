@@ -65,22 +65,28 @@ impl FileBufferEntry {
 
     fn flush(&mut self) -> Result<(), Error> {
         if self.dirty {
-            self.locked = true;
-            log!(
-                crate::LOG_BUFFER,
-                "filebuffer: writing back blocks <{:?}>",
-                self.blocks,
-            );
+            self.store()?;
 
-            // write data of block to backend
-            crate::backend_mut().store_data(self.blocks, self.unlock)?;
-
-            // reset dirty and unlock
+            // reset dirty
             self.dirty = false;
-            self.locked = false;
         }
         Ok(())
     }
+
+    fn store(&mut self) -> Result<(), Error> {
+        self.locked = true;
+        log!(
+            crate::LOG_BUFFER,
+            "filebuffer: writing back blocks <{:?}>",
+            self.blocks,
+        );
+
+        // write data of block to backend
+        crate::backend_mut().store_data(self.blocks, self.unlock)?;
+
+        self.locked = false;
+        Ok(())
+    }
 }
 
 pub struct LoadLimit {
@@ -293,6 +299,33 @@ impl FileBuffer {
         Ok(load_size * self.block_size)
     }
 
+    /// Writes back the entries that contain any of the given blocks
+    ///
+    /// The blocks have been changed via memory capabilities and the change has been reported to us
+    /// explicitly. Therefore, they are written back independent of the dirty state of the entries,
+    /// which stays as it is for the changes of other clients.
+    pub fn write_back(&mut self, blocks: BlockRange) -> Result<(), Error> {
+        let end = blocks.start + blocks.count;
+        let mut bno = blocks.start;
+        while bno < end {
+            // workaround for borrow-checker: see get_extent
+            let entry = self
+                .entries
+                .get_mut(&BlockRange::new(bno))
+                .map(|b| unsafe { &mut *b.as_mut() });
+
+            match entry {
+                Some(e) if e.locked => thread::wait_for(e.unlock),
+                Some(e) => {
+                    e.store()?;
+                    bno = e.blocks.start + e.blocks.count;
+                },
+                None => bno += 1,
+            }
+        }
+        Ok(())
+    }
+
     pub fn flush(&mut self) -> Result<(), Error> {
         while let Some(mut b) = self.lru.pop_front() {
             self.entries.remove(&b.blocks);
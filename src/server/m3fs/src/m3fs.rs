@@ -27,7 +27,7 @@ mod sess;
 
 use crate::backend::{Backend, DiskBackend, MemBackend};
 use crate::buf::{FileBuffer, MetaBuffer};
-use crate::data::{Allocator, SuperBlock};
+use crate::data::{Allocator, InodeNo, SuperBlock};
 use crate::sess::{FSSession, M3FSSession, MetaSession, OpenFiles};
 
 use base::cell::LazyStaticUnsafeCell;
@@ -44,6 +44,7 @@ use m3::{
         server_loop, CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer,
         DEF_MAX_CLIENTS,
     },
+    session::Pipes,
     tcu::Label,
     tiles::Activity,
     vfs::{FSOperation, GenFileOp},
@@ -76,6 +77,8 @@ static BA: LazyStaticRefCell<Allocator> = LazyStaticRefCell::default();
 static IA: LazyStaticRefCell<Allocator> = LazyStaticRefCell::default();
 static SETTINGS: LazyReadOnlyCell<FsSettings> = LazyReadOnlyCell::default();
 static BACKEND: LazyStaticRefCell<Box<dyn Backend>> = LazyStaticRefCell::default();
+// the session at the pipes server for FIFOs, established on first use
+static PIPES: StaticRefCell<Option<Pipes>> = StaticRefCell::new(None);
 
 fn superblock() -> Ref<'static, SuperBlock> {
     SB.borrow()
@@ -106,6 +109,16 @@ fn backend_mut() -> RefMut<'static, Box<dyn Backend>> {
     BACKEND.borrow_mut()
 }
 
+/// Lets the pipes server accept one open of the named pipe for the FIFO `ino` and returns the token
+/// for the open
+fn auth_named_pipe(ino: InodeNo, read: bool) -> Result<u64, Error> {
+    let mut pipes = PIPES.borrow_mut();
+    if pipes.is_none() {
+        *pipes = Some(Pipes::new("pipes")?);
+    }
+    pipes.as_ref().unwrap().auth_named(ino, read)
+}
+
 fn flush_buffer() -> Result<(), Error> {
     crate::meta_buffer_mut().flush()?;
     crate::file_buffer_mut().flush()?;
@@ -147,6 +160,9 @@ int_enum! {
         const GET_SGATE     = FSOperation::GET_SGATE.val;
         const DEL_EP        = FSOperation::DEL_EP.val;
         const OPEN_PRIV     = FSOperation::OPEN_PRIV.val;
+        const COMMIT_MEM    = FSOperation::COMMIT_MEM.val;
+        const MKFIFO        = FSOperation::MKFIFO.val;
+        const OPEN_FIFO     = FSOperation::OPEN_FIFO.val;
     }
 }
 
@@ -223,6 +239,8 @@ impl M3FSRequestHandler {
             M3FSOperation::FSTAT => self.exec_on_sess(input, |sess, is| sess.fstat(is)),
             M3FSOperation::MKDIR => self.exec_on_sess(input, |sess, is| sess.mkdir(is)),
             M3FSOperation::RMDIR => self.exec_on_sess(input, |sess, is| sess.rmdir(is)),
+            M3FSOperation::MKFIFO => self.exec_on_sess(input, |sess, is| sess.mkfifo(is)),
+            M3FSOperation::OPEN_FIFO => self.exec_on_sess(input, |sess, is| sess.open_fifo(is)),
             M3FSOperation::LINK => self.exec_on_sess(input, |sess, is| sess.link(is)),
             M3FSOperation::UNLINK => self.exec_on_sess(input, |sess, is| sess.unlink(is)),
             M3FSOperation::RENAME => self.exec_on_sess(input, |sess, is| sess.rename(is)),
@@ -389,6 +407,7 @@ impl Handler<FSSession> for M3FSRequestHandler {
                         .add(crt, next_sess_id, FSSession::File(nfile_session))
                 },
                 M3FSOperation::GET_MEM => file.get_mem(data),
+                M3FSOperation::COMMIT_MEM => file.commit_mem(data),
                 _ => Err(Error::new(Code::InvArgs)),
             },
         }
@@ -200,6 +200,39 @@ fn do_create(path: &str, mode: FileMode) -> Result<(), Error> {
     }
 }
 
+/// Creates a new FIFO with given mode at given path
+pub fn create_fifo(path: &str, mode: FileMode) -> Result<(), Error> {
+    let res = do_create_fifo(path, mode);
+    log!(
+        crate::LOG_DIRS,
+        "dirs::create_fifo(path={}, mode={:o}) -> {:?}",
+        path,
+        mode,
+        res.as_ref().map_err(|e| e.code()),
+    );
+    res
+}
+
+fn do_create_fifo(path: &str, mode: FileMode) -> Result<(), Error> {
+    let (dir, name) = split_path(path);
+
+    // get parent directory
+    let parent_ino = search(dir, false)?;
+
+    // ensure that the entry doesn't exist
+    if search(path, false).is_ok() {
+        return Err(Error::new(Code::Exists));
+    }
+
+    let parinode = inodes::get(parent_ino)?;
+    let ino = inodes::create(FileMode::IFPIP | mode).map_err(|_| Error::new(Code::NoSpace))?;
+    if let Err(e) = links::create(&parinode, name, &ino) {
+        crate::open_files_mut().delete_file(ino.inode).ok();
+        return Err(e);
+    }
+    Ok(())
+}
+
 /// Removes the directory at given path if it is empty
 pub fn remove(path: &str) -> Result<(), Error> {
     log!(crate::LOG_DIRS, "dirs::remove(path={})", path);
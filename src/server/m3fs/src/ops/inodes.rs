@@ -16,8 +16,8 @@
 
 use crate::buf::LoadLimit;
 use crate::data::{
-    ExtPos, Extent, ExtentCache, ExtentRef, INodeRef, InodeNo, INODE_DIR_COUNT, NUM_EXT_BYTES,
-    NUM_INODE_BYTES,
+    BlockNo, BlockRange, ExtPos, Extent, ExtentCache, ExtentRef, INodeRef, InodeNo,
+    INODE_DIR_COUNT, NUM_EXT_BYTES, NUM_INODE_BYTES,
 };
 
 use m3::{
@@ -185,6 +185,74 @@ pub fn get_extent_mem(
     Ok((bytes, extlen))
 }
 
+/// Writes back the buffered blocks that hold the `len` bytes at `off` in the given inode.
+///
+/// This is used for data that has been changed via memory capabilities (see `get_extent_mem`).
+pub fn write_back(inode: &INodeRef, off: usize, len: usize) -> Result<(), Error> {
+    log!(
+        crate::LOG_INODES,
+        "inodes::write_back(inode={}, off={}, len={})",
+        inode.inode,
+        off,
+        len,
+    );
+
+    let blocksize = crate::superblock().block_size as usize;
+    let end = (off + len).min(inode.size as usize);
+
+    let mut indir = None;
+    let mut pos = 0;
+    for i in 0..inode.extents {
+        if pos >= end {
+            break;
+        }
+
+        let ext = get_extent(inode, i as usize, &mut indir, false)?;
+        let ext_end = pos + ext.length as usize * blocksize;
+        if ext_end > off {
+            let first = off.saturating_sub(pos) / blocksize;
+            let last = math::round_up(end.min(ext_end) - pos, blocksize) / blocksize;
+            crate::file_buffer_mut().write_back(BlockRange::new_range(
+                ext.start + first as BlockNo,
+                (last - first) as BlockNo,
+            ))?;
+        }
+        pos = ext_end;
+    }
+    Ok(())
+}
+
+/// Extends the given inode with zeroed blocks until it has at least `size` bytes.
+///
+/// This is used for accesses behind the end of the file via memory capabilities (see
+/// `get_extent_mem`), which cannot be appended like writes.
+pub fn grow(inode: &INodeRef, size: usize) -> Result<(), Error> {
+    log!(
+        crate::LOG_INODES,
+        "inodes::grow(inode={}, size={})",
+        inode.inode,
+        size,
+    );
+
+    let blocksize = crate::superblock().block_size as usize;
+    // the last block belongs to the file as a whole
+    let mut allocated = math::round_up(inode.size as usize, blocksize);
+    while allocated < size {
+        // we might get less blocks than requested
+        let mut count = (size - allocated + blocksize - 1) / blocksize;
+        let start = crate::blocks_mut().alloc(Some(&mut count))?;
+        let ext = Extent::new(start, count as u32);
+        crate::backend_mut().clear_extent(ext)?;
+        append_extent(inode, ext)?;
+        allocated += count * blocksize;
+    }
+
+    if (size as u64) > inode.size {
+        inode.as_mut().size = size as u64;
+    }
+    Ok(())
+}
+
 /// Requests an append of a new block to given inode and creates a MemGate to access the block.
 ///
 /// Note that this only requests the append, but does not append anything.
@@ -26,6 +26,7 @@ use m3::{
     com::{GateIStream, RecvGate, SendGate},
     errors::{Code, Error},
     kif::{CapRngDesc, CapType, Perm, INVALID_SEL},
+    math,
     server::{CapExchange, SessId},
     session::ServerSession,
     syscalls, tcu,
@@ -227,6 +228,24 @@ impl FileSession {
 
         let inode = inodes::get(self.ino)?;
 
+        // accesses behind the end extend the file, if it is writable (the seek would stop at the
+        // end otherwise)
+        if offset as u64 >= inode.size {
+            if !self.oflags.contains(OpenFlags::W) {
+                return Err(Error::new(Code::EndOfFile));
+            }
+            if crate::open_files_mut()
+                .get_file_mut(self.ino)
+                .unwrap()
+                .appending()
+            {
+                return Err(Error::new(Code::Exists));
+            }
+
+            let blocksize = crate::superblock().block_size as usize;
+            inodes::grow(&inode, math::round_up(offset as usize + 1, blocksize))?;
+        }
+
         // determine extent from byte offset
         let (_, extpos) = inodes::get_seek_pos(&inode, offset as usize, SeekMode::SET)?;
 
@@ -239,8 +258,11 @@ impl FileSession {
             &mut self.load_limit,
         )?;
 
+        // the capability starts at the beginning of the block
+        let capoff = extpos.off % crate::superblock().block_size as usize;
+
         data.out_caps(m3::kif::CapRngDesc::new(CapType::OBJECT, sel, 1));
-        data.out_args().push(0);
+        data.out_args().push(capoff);
         data.out_args().push(len);
 
         log!(
@@ -257,6 +279,27 @@ impl FileSession {
         Ok(())
     }
 
+    pub fn commit_mem(&mut self, data: &mut CapExchange<'_>) -> Result<(), Error> {
+        let offset: usize = data.in_args().pop()?;
+        let len: usize = data.in_args().pop()?;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] file::commit_mem(path={}, offset={}, len={})",
+            self.session_id,
+            self.filename,
+            offset,
+            len
+        );
+
+        if !self.oflags.contains(OpenFlags::W) {
+            return Err(Error::new(Code::NoPerm));
+        }
+
+        let inode = inodes::get(self.ino)?;
+        inodes::write_back(&inode, offset, len)
+    }
+
     fn revoke_cap(&mut self) {
         if self.cur_sel != m3::kif::INVALID_SEL {
             m3::tiles::Activity::own()
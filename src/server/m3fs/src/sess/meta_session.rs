@@ -33,6 +33,21 @@ use m3::{
 
 static NEXT_PRIV_ID: StaticCell<SessId> = StaticCell::new(1);
 
+fn check_perms(mode: FileMode, flags: OpenFlags) -> Result<(), Error> {
+    if (flags.contains(OpenFlags::W) && !mode.contains(FileMode::IWUSR))
+        || (flags.contains(OpenFlags::R) && !mode.contains(FileMode::IRUSR))
+    {
+        log!(
+            crate::LOG_SESSION,
+            "insufficient permissions: flags={:o}, mode={:o}",
+            flags,
+            mode,
+        );
+        return Err(Error::new(Code::NoPerm));
+    }
+    Ok(())
+}
+
 pub struct MetaSession {
     _server_session: ServerSession,
     sgates: Vec<SendGate>,
@@ -160,16 +175,12 @@ impl MetaSession {
         let inode = inodes::get(ino)?;
         let inode_mode = inode.mode;
 
-        if (flags.contains(OpenFlags::W) && !inode_mode.contains(FileMode::IWUSR))
-            || (flags.contains(OpenFlags::R) && !inode_mode.contains(FileMode::IRUSR))
-        {
-            log!(
-                crate::LOG_SESSION,
-                "insufficient permissions: flags={:o}, mode={:o}",
-                flags,
-                inode.mode,
-            );
-            return Err(Error::new(Code::NoPerm));
+        check_perms(inode_mode, flags)?;
+
+        // the data of FIFOs is exchanged via the pipes server; the client opens them via
+        // open_fifo instead
+        if inode_mode.is_pip() {
+            return Err(Error::new(Code::NotSup));
         }
 
         // only determine the current size, if we're writing and the file isn't empty
@@ -290,6 +301,23 @@ impl M3FSSession for MetaSession {
         stream.reply_error(Code::None)
     }
 
+    fn mkfifo(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let path: &str = stream.pop()?;
+        let mode = FileMode::from_bits_truncate(stream.pop::<u16>()?) & FileMode::PERM;
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] meta::mkfifo(path={}, mode={:o})",
+            self.session_id,
+            path,
+            mode
+        );
+
+        dirs::create_fifo(path, mode)?;
+
+        stream.reply_error(Code::None)
+    }
+
     fn rmdir(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
         let path: &str = stream.pop()?;
 
@@ -354,6 +382,36 @@ impl M3FSSession for MetaSession {
         stream.reply_error(Code::None)
     }
 
+    fn open_fifo(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        let path = stream.pop::<&str>()?;
+        let flags = OpenFlags::from_bits_truncate(stream.pop::<u32>()?);
+
+        log!(
+            crate::LOG_SESSION,
+            "[{}] meta::open_fifo(path={}, flags={:?})",
+            self.session_id,
+            path,
+            flags
+        );
+
+        let read = match flags & OpenFlags::RW {
+            OpenFlags::R => true,
+            OpenFlags::W => false,
+            _ => return Err(Error::new(Code::InvArgs)),
+        };
+
+        let ino = dirs::search(path, false)?;
+        let inode = inodes::get(ino)?;
+        if !inode.mode.is_pip() {
+            return Err(Error::new(Code::InvArgs));
+        }
+        check_perms(inode.mode, flags)?;
+
+        let token = crate::auth_named_pipe(ino, read)?;
+
+        reply_vmsg!(stream, 0, token)
+    }
+
     fn open_priv(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
         let path = stream.pop::<&str>()?;
         let flags = OpenFlags::from_bits_truncate(stream.pop::<u32>()?);
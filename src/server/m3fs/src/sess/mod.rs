@@ -108,6 +108,20 @@ impl M3FSSession for FSSession {
         }
     }
 
+    fn mkfifo(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.mkfifo(stream),
+            FSSession::File(f) => f.mkfifo(stream),
+        }
+    }
+
+    fn open_fifo(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        match self {
+            FSSession::Meta(m) => m.open_fifo(stream),
+            FSSession::File(f) => f.open_fifo(stream),
+        }
+    }
+
     fn link(&mut self, stream: &mut GateIStream<'_>) -> Result<(), Error> {
         match self {
             FSSession::Meta(m) => m.link(stream),
@@ -185,6 +199,12 @@ pub trait M3FSSession {
     fn rmdir(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
+    fn mkfifo(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
+    fn open_fifo(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
+        Err(Error::new(Code::NotSup))
+    }
     fn link(&mut self, _stream: &mut GateIStream<'_>) -> Result<(), Error> {
         Err(Error::new(Code::NotSup))
     }
@@ -427,6 +427,9 @@ pub fn main() -> i32 {
         settings.gateway,
     );
 
+    // the interface is configured now, so that our clients can be started
+    serv.set_ready().expect("Unable to signal readiness");
+
     let rgatec = handler.rgate.clone();
     let start = TimeInstant::now();
 
@@ -14,23 +14,29 @@
  */
 
 use m3::cap::Selector;
+use m3::cell::RefCell;
 use m3::cfg;
-use m3::col::Vec;
+use m3::col::{String, Vec};
 use m3::com::{GateIStream, RecvGate, SGateArgs, SendGate};
 use m3::errors::{Code, Error};
 use m3::goff;
 use m3::kif::{PageFlags, Perm};
 use m3::log;
 use m3::math;
+use m3::rc::Rc;
 use m3::reply_vmsg;
 use m3::serialize::M3Deserializer;
 use m3::server::SessId;
-use m3::session::{MapFlags, ServerSession};
+use m3::session::{
+    Advice, MapFlags, MapStats, PagerAddrSpaceInfo, PagerMappingInfo, ServerSession, ShmFlags,
+};
 use m3::tcu::Label;
 use m3::tiles::Activity;
 use resmng::childs;
 
 use crate::dataspace::DataSpace;
+use crate::shm::{self, Segment};
+use crate::swap;
 
 const MAX_VIRT_ADDR: goff = cfg::MEM_CAP_END as goff - 1;
 
@@ -42,6 +48,10 @@ pub struct AddrSpace {
     owner: Option<Selector>,
     sgates: Vec<SendGate>,
     ds: Vec<DataSpace>,
+    // the shared-memory segments that have been opened via this address space
+    shms: Vec<Rc<RefCell<Segment>>>,
+    // the position of the clock hand for eviction
+    clock: usize,
 }
 
 impl AddrSpace {
@@ -59,6 +69,8 @@ impl AddrSpace {
             owner: None,
             sgates: Vec::new(),
             ds: Vec::new(),
+            shms: Vec::new(),
+            clock: 0,
         }
     }
 
@@ -78,6 +90,10 @@ impl AddrSpace {
         self.parent
     }
 
+    pub fn dataspaces(&self) -> &[DataSpace] {
+        &self.ds
+    }
+
     pub fn has_owner(&self) -> bool {
         self.owner.is_some()
     }
@@ -180,19 +196,26 @@ impl AddrSpace {
     }
 
     pub(crate) fn pagefault_at(&mut self, virt: goff, access: Perm) -> Result<(), Error> {
-        if let Some(ds) = self.find_ds_mut(virt) {
-            if (ds.perm() & access) != access {
+        if let Some(idx) = self.find_ds_idx(virt) {
+            let ds = &self.ds[idx];
+            if ds.prot().is_empty() || (ds.prot() & access) != access {
                 log!(
                     crate::LOG_DEF,
                     "Access at {:#x} for {:#x} not allowed: {:#x}",
                     virt,
                     access,
-                    ds.perm()
+                    ds.prot()
                 );
                 return Err(Error::new(Code::InvArgs));
             }
 
-            ds.handle_pf(virt)
+            self.fault_in(idx, virt, access)?;
+
+            if self.ds[idx].sequential() {
+                // it's just a guess; don't fail the page fault because of that
+                self.ds[idx].read_ahead(virt).ok();
+            }
+            Ok(())
         }
         else {
             log!(crate::LOG_DEF, "No dataspace at {:#x}", virt);
@@ -200,6 +223,54 @@ impl AddrSpace {
         }
     }
 
+    fn fault_in(&mut self, idx: usize, virt: goff, access: Perm) -> Result<(), Error> {
+        loop {
+            match self.ds[idx].handle_pf(virt, access) {
+                // make room by swapping out other memory of this address space
+                Err(e) if e.code() == Code::NoSpace && swap::enabled() => {
+                    self.evict().map_err(|_| e)?
+                },
+                res => break res,
+            }
+        }
+    }
+
+    /// Swaps out one region according to the clock algorithm
+    ///
+    /// Regions that have been referenced since the last visit of the clock hand are unmapped and
+    /// spared, so that the next access marks them as referenced again.
+    fn evict(&mut self) -> Result<(), Error> {
+        let mut cands = Vec::new();
+        for (d, ds) in self.ds.iter().enumerate().filter(|(_, ds)| ds.swappable()) {
+            for (r, reg) in ds.regions().iter().enumerate() {
+                if reg.can_swap_out() {
+                    cands.push((d, r));
+                }
+            }
+        }
+        if cands.is_empty() {
+            return Err(Error::new(Code::NoSpace));
+        }
+
+        // after one round, all regions are unreferenced
+        for _ in 0..cands.len() * 2 {
+            let (d, r) = cands[self.clock % cands.len()];
+            self.clock = (self.clock + 1) % cands.len();
+
+            let reg = self.ds[d].regions_mut().get_mut(r);
+            if reg.is_referenced() {
+                reg.unreference();
+                swap::count_second_chance();
+            }
+            else {
+                reg.swap_out()?;
+                log!(crate::LOG_SWAP, "[{}] {:?}", self.id(), swap::stats());
+                return Ok(());
+            }
+        }
+        unreachable!();
+    }
+
     pub fn map_ds(&mut self, args: &mut M3Deserializer<'_>) -> Result<(Selector, goff), Error> {
         if !self.has_owner() {
             return Err(Error::new(Code::InvArgs));
@@ -338,6 +409,135 @@ impl AddrSpace {
         Ok((sel, virt))
     }
 
+    pub fn open_shm(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        if !self.has_owner() {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let name: &str = is.pop()?;
+        let flags = ShmFlags::from_bits_truncate(is.pop::<u32>()?);
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::open_shm(name={}, flags={:?})",
+            self.id(),
+            name,
+            flags,
+        );
+
+        // the child can only use the segments that are in its config
+        let (gname, mem) = {
+            let childs = childs::borrow_mut();
+            let child = childs.child_by_id(self.child.unwrap()).unwrap();
+            let cfg = child.cfg();
+            let desc = cfg.get_shm(name).ok_or_else(|| Error::new(Code::NoPerm))?;
+            (desc.name().global().clone(), child.mem().clone())
+        };
+
+        let seg = shm::open(&gname, flags, &mem)?;
+        let (id, size) = {
+            let seg = seg.borrow();
+            (seg.id(), seg.size())
+        };
+        self.shms.push(seg);
+
+        reply_vmsg!(is, Code::None as u32, id, size)
+    }
+
+    pub fn resize_shm(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let id: u64 = is.pop()?;
+        let size: goff = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::resize_shm(id={}, size={:#x})",
+            self.id(),
+            id,
+            size,
+        );
+
+        self.get_shm(id)?.borrow_mut().resize(size)?;
+
+        is.reply_error(Code::None)
+    }
+
+    pub fn close_shm(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let id: u64 = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::close_shm(id={})",
+            self.id(),
+            id
+        );
+
+        // the mappings keep the segment alive
+        let idx = self
+            .shms
+            .iter()
+            .position(|s| s.borrow().id() == id)
+            .ok_or_else(|| Error::new(Code::InvArgs))?;
+        self.shms.remove(idx);
+
+        is.reply_error(Code::None)
+    }
+
+    pub fn map_shm(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        if !self.has_owner() {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let virt: goff = is.pop()?;
+        let id: u64 = is.pop()?;
+        let off: goff = is.pop()?;
+        let len: goff = is.pop()?;
+        let perm = Perm::from_bits_truncate(is.pop::<u32>()?);
+        let flags = MapFlags::from_bits_truncate(is.pop::<u32>()?);
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::map_shm(virt={:#x}, id={}, off={:#x}, len={:#x}, perm={:?}, flags={:?})",
+            self.id(),
+            virt,
+            id,
+            off,
+            len,
+            perm,
+            flags,
+        );
+
+        self.check_map_args(virt, len, perm)?;
+
+        let seg = self.get_shm(id)?;
+        let seg_end = math::round_up(seg.borrow().size(), cfg::PAGE_SIZE as goff);
+        if !math::is_aligned(off, cfg::PAGE_SIZE as goff) || off + len > seg_end {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let ds = DataSpace::new_shm(
+            self.owner.unwrap(),
+            self.child.unwrap(),
+            virt,
+            len,
+            perm,
+            flags,
+            off,
+            seg,
+        );
+        self.ds.push(ds);
+
+        reply_vmsg!(is, Code::None as u32, virt)
+    }
+
+    /// Returns the segment with given id, if it has been opened via this address space
+    fn get_shm(&self, id: u64) -> Result<Rc<RefCell<Segment>>, Error> {
+        self.shms
+            .iter()
+            .find(|s| s.borrow().id() == id)
+            .cloned()
+            .ok_or_else(|| Error::new(Code::InvArgs))
+    }
+
     pub fn unmap(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
         let virt: goff = is.pop()?;
 
@@ -349,7 +549,14 @@ impl AddrSpace {
         );
 
         if let Some(idx) = self.find_ds_idx(virt) {
-            self.ds.remove(idx);
+            // remove all parts of the mapping, in case it has been split, but write back the
+            // changes to shared file mappings first
+            let mapping = self.ds[idx].mapping();
+            for ds in self.ds.iter_mut().filter(|ds| ds.mapping() == mapping) {
+                let (virt, size) = (ds.virt(), ds.size());
+                ds.sync(virt, size)?;
+            }
+            self.ds.retain(|ds| ds.mapping() != mapping);
         }
         else {
             log!(crate::LOG_DEF, "No dataspace at {:#x}", virt);
@@ -359,9 +566,161 @@ impl AddrSpace {
         is.reply_error(Code::None)
     }
 
+    pub fn protect(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let virt: goff = is.pop()?;
+        let len: goff = is.pop()?;
+        let prot = Perm::from_bits_truncate(is.pop::<u32>()?);
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::protect(virt={:#x}, len={:#x}, prot={:?})",
+            self.id(),
+            virt,
+            len,
+            prot,
+        );
+
+        self.check_range(virt, len)?;
+        // check all permissions upfront to not leave the range partially changed
+        if self.ds_in(virt, len).any(|ds| !ds.perm().contains(prot)) {
+            return Err(Error::new(Code::NoPerm));
+        }
+
+        for idx in self.isolate(virt, len) {
+            self.ds[idx].protect(prot)?;
+        }
+
+        is.reply_error(Code::None)
+    }
+
+    pub fn advise(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let virt: goff = is.pop()?;
+        let len: goff = is.pop()?;
+        let advice: Advice = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::advise(virt={:#x}, len={:#x}, advice={:?})",
+            self.id(),
+            virt,
+            len,
+            advice,
+        );
+
+        self.check_range(virt, len)?;
+
+        match advice {
+            Advice::NORMAL | Advice::SEQUENTIAL => {
+                for idx in self.isolate(virt, len) {
+                    self.ds[idx].set_sequential(advice == Advice::SEQUENTIAL);
+                }
+            },
+
+            Advice::WILLNEED => {
+                for page in (virt..virt + len).step_by(cfg::PAGE_SIZE) {
+                    let idx = self.find_ds_idx(page).unwrap();
+                    // guard pages stay inaccessible
+                    if !self.ds[idx].prot().is_empty() {
+                        self.fault_in(idx, page, Perm::empty())?;
+                    }
+                }
+            },
+
+            Advice::DONTNEED => {
+                if self.ds_in(virt, len).any(|ds| !ds.discardable()) {
+                    return Err(Error::new(Code::NotSup));
+                }
+                for idx in self.isolate(virt, len) {
+                    self.ds[idx].discard()?;
+                }
+            },
+
+            _ => return Err(Error::new(Code::InvArgs)),
+        }
+
+        is.reply_error(Code::None)
+    }
+
+    pub fn sync(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let virt: goff = is.pop()?;
+        let len: goff = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pager::sync(virt={:#x}, len={:#x})",
+            self.id(),
+            virt,
+            len,
+        );
+
+        self.check_range(virt, len)?;
+
+        for ds in &mut self.ds {
+            if math::overlaps(ds.virt(), ds.virt() + ds.size(), virt, virt + len) {
+                ds.sync(virt, len)?;
+            }
+        }
+
+        is.reply_error(Code::None)
+    }
+
+    pub fn map_stats(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        log!(crate::LOG_DEF, "[{}] pager::map_stats()", self.id());
+
+        let mut stats = MapStats::default();
+        for reg in self.ds.iter().flat_map(|ds| ds.regions().iter()) {
+            let (large, small) = reg.mapped_pages();
+            stats.large_pages += large as usize;
+            stats.small_pages += small as usize;
+        }
+
+        reply_vmsg!(is, Code::None as u32, stats)
+    }
+
+    pub fn info(&self) -> PagerAddrSpaceInfo {
+        let name = self
+            .child
+            .and_then(|id| {
+                childs::borrow_mut()
+                    .child_by_id(id)
+                    .map(|c| c.name().clone())
+            })
+            .unwrap_or_else(String::new);
+
+        PagerAddrSpaceInfo {
+            id: self.id(),
+            parent: self.parent,
+            name,
+            mappings: self.ds.len(),
+            charged: self.ds.iter().map(|ds| ds.charged()).sum::<goff>() as usize,
+        }
+    }
+
+    pub fn mapping_info(&self, idx: usize) -> Result<PagerMappingInfo, Error> {
+        self.ds
+            .get(idx)
+            .map(|ds| ds.info())
+            .ok_or_else(|| Error::new(Code::InvArgs))
+    }
+
     pub fn close(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
         log!(crate::LOG_DEF, "[{}] pager::close()", self.id());
 
+        // the file system has the changes to shared file mappings anyway, but write them back now
+        // as we would on unmap
+        for ds in &mut self.ds {
+            let (virt, size) = (ds.virt(), ds.size());
+            if let Err(e) = ds.sync(virt, size) {
+                log!(
+                    crate::LOG_DEF,
+                    "Unable to write back {:#x}..{:#x}: {:?}",
+                    virt,
+                    virt + size - 1,
+                    e
+                );
+            }
+        }
+
         is.reply_error(Code::None)
     }
 
@@ -382,8 +741,54 @@ impl AddrSpace {
         Ok(())
     }
 
-    fn find_ds_mut(&mut self, virt: goff) -> Option<&mut DataSpace> {
-        self.find_ds_idx(virt).map(move |idx| &mut self.ds[idx])
+    /// Checks whether the range is page aligned and completely covered by dataspaces
+    fn check_range(&self, virt: goff, len: goff) -> Result<(), Error> {
+        if len == 0
+            || !math::is_aligned(virt, cfg::PAGE_SIZE as goff)
+            || !math::is_aligned(len, cfg::PAGE_SIZE as goff)
+        {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let mut pos = virt;
+        while pos < virt + len {
+            match self.find_ds_idx(pos) {
+                Some(idx) => pos = self.ds[idx].virt() + self.ds[idx].size(),
+                None => {
+                    log!(crate::LOG_DEF, "No dataspace at {:#x}", pos);
+                    return Err(Error::new(Code::NotFound));
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all dataspaces that overlap with the given range
+    fn ds_in(&self, virt: goff, len: goff) -> impl Iterator<Item = &DataSpace> {
+        self.ds
+            .iter()
+            .filter(move |ds| math::overlaps(ds.virt(), ds.virt() + ds.size(), virt, virt + len))
+    }
+
+    /// Splits the dataspaces at the borders of the given range and returns the indices of the
+    /// dataspaces within the range
+    fn isolate(&mut self, virt: goff, len: goff) -> Vec<usize> {
+        for &border in &[virt, virt + len] {
+            if let Some(idx) = self.find_ds_idx(border) {
+                let ds_virt = self.ds[idx].virt();
+                if ds_virt != border {
+                    let nds = self.ds[idx].split(border - ds_virt);
+                    self.ds.push(nds);
+                }
+            }
+        }
+
+        self.ds
+            .iter()
+            .enumerate()
+            .filter(|(_, ds)| ds.virt() >= virt && ds.virt() + ds.size() <= virt + len)
+            .map(|(idx, _)| idx)
+            .collect()
     }
 
     fn find_ds_idx(&self, virt: goff) -> Option<usize> {
@@ -0,0 +1,193 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Writes ELF core files for crashed childs
+//!
+//! The core file contains a note segment with the registers (`NT_PRSTATUS`) and the fault address
+//! (`NT_SIGINFO`) as recorded by TileMux, followed by a load segment for every region of the
+//! child's address space that is backed by memory.
+
+use core::cmp;
+
+use m3::cfg;
+use m3::col::{String, Vec};
+use m3::elf;
+use m3::errors::Error;
+use m3::format;
+use m3::io::Write;
+use m3::kif::tilemux;
+use m3::log;
+use m3::math;
+use m3::mem::size_of;
+use m3::util;
+use m3::vec;
+use m3::vfs::{FileMode, OpenFlags, VFS};
+
+use crate::addrspace::AddrSpace;
+
+/// The directory in which the core files are stored
+pub const CORE_DIR: &str = "/coredumps";
+
+const NOTE_NAME: &[u8] = b"CORE\0\0\0\0";
+const SIGINFO_SIZE: usize = 128;
+
+// offsets within elf_prstatus and siginfo_t
+#[cfg(target_pointer_width = "64")]
+const PRSTATUS_PID: usize = 32;
+#[cfg(target_pointer_width = "64")]
+const PRSTATUS_REGS: usize = 112;
+#[cfg(target_pointer_width = "64")]
+const SIGINFO_ADDR: usize = 16;
+#[cfg(target_pointer_width = "32")]
+const PRSTATUS_PID: usize = 24;
+#[cfg(target_pointer_width = "32")]
+const PRSTATUS_REGS: usize = 72;
+#[cfg(target_pointer_width = "32")]
+const SIGINFO_ADDR: usize = 12;
+
+fn put(buf: &mut [u8], off: usize, bytes: &[u8]) {
+    buf[off..off + bytes.len()].copy_from_slice(bytes);
+}
+
+fn build_note(notes: &mut Vec<u8>, ty: elf::NT, desc: &[u8]) {
+    let hdr = elf::Nhdr {
+        namesz: 5,
+        descsz: desc.len() as u32,
+        ty: ty.val,
+    };
+    notes.extend_from_slice(util::object_to_bytes(&hdr));
+    notes.extend_from_slice(NOTE_NAME);
+    notes.extend_from_slice(desc);
+    // the descriptor is padded to 4 bytes
+    notes.resize((notes.len() + 3) & !3, 0);
+}
+
+fn build_notes(id: u32, info: &tilemux::CrashInfo) -> Vec<u8> {
+    let mut notes = Vec::new();
+
+    let reg_size = size_of::<usize>();
+    let regs = cmp::min(info.reg_count as usize, tilemux::CRASH_REGS);
+    // the registers are followed by pr_fpvalid and padding
+    let mut prstatus = vec![0u8; PRSTATUS_REGS + (regs + 1) * reg_size];
+    put(&mut prstatus, 0, &(info.signal as u32).to_le_bytes());
+    put(&mut prstatus, 12, &(info.signal as u16).to_le_bytes());
+    put(&mut prstatus, PRSTATUS_PID, &id.to_le_bytes());
+    for (i, r) in info.regs[0..regs].iter().enumerate() {
+        put(
+            &mut prstatus,
+            PRSTATUS_REGS + i * reg_size,
+            &(*r as usize).to_le_bytes(),
+        );
+    }
+    build_note(&mut notes, elf::NT::PRSTATUS, &prstatus);
+
+    let mut siginfo = [0u8; SIGINFO_SIZE];
+    put(&mut siginfo, 0, &(info.signal as u32).to_le_bytes());
+    put(
+        &mut siginfo,
+        SIGINFO_ADDR,
+        &(info.fault_addr as usize).to_le_bytes(),
+    );
+    build_note(&mut notes, elf::NT::SIGINFO, &siginfo);
+
+    notes
+}
+
+/// Writes a core file for the child with given id and name, based on the given address space and
+/// crash information. Returns the path of the written file.
+pub fn write(
+    id: u32,
+    name: &str,
+    aspace: &AddrSpace,
+    info: &tilemux::CrashInfo,
+) -> Result<String, Error> {
+    // collect all regions that are backed by memory; the others were never touched
+    let mut segs = Vec::new();
+    for ds in aspace.dataspaces() {
+        for r in ds.regions().iter().filter(|r| r.has_mem()) {
+            segs.push((r, ds.perm()));
+        }
+    }
+
+    let notes = build_notes(id, info);
+
+    let phnum = 1 + segs.len();
+    let notes_off = size_of::<elf::Ehdr>() + phnum * size_of::<elf::Phdr>();
+    let data_off = math::round_up(notes_off + notes.len(), cfg::PAGE_SIZE);
+
+    let mut hdr = elf::Ehdr {
+        ty: elf::ET::CORE.val,
+        machine: elf::EM,
+        version: 1,
+        phoff: size_of::<elf::Ehdr>(),
+        ehsize: size_of::<elf::Ehdr>() as u16,
+        phentsize: size_of::<elf::Phdr>() as u16,
+        phnum: phnum as u16,
+        ..Default::default()
+    };
+    hdr.ident[0..7].copy_from_slice(&[0x7F, b'E', b'L', b'F', elf::ELFCLASS, 1, 1]);
+
+    let mut headers = Vec::from(util::object_to_bytes(&hdr));
+    let note_phdr = elf::Phdr {
+        ty: elf::PT::NOTE.val,
+        offset: notes_off as _,
+        filesz: notes.len() as _,
+        ..Default::default()
+    };
+    headers.extend_from_slice(util::object_to_bytes(&note_phdr));
+
+    let mut off = data_off;
+    for (r, perm) in &segs {
+        let phdr = elf::Phdr {
+            ty: elf::PT::LOAD.val,
+            flags: elf::PF::from(*perm).bits(),
+            offset: off as _,
+            vaddr: r.virt() as usize,
+            filesz: r.size() as _,
+            memsz: r.size() as _,
+            align: cfg::PAGE_SIZE as _,
+            ..Default::default()
+        };
+        headers.extend_from_slice(util::object_to_bytes(&phdr));
+        off += r.size() as usize;
+    }
+
+    if VFS::stat(CORE_DIR).is_err() {
+        VFS::mkdir(CORE_DIR, FileMode::from_bits(0o755).unwrap())?;
+    }
+
+    let path = format!(
+        "{}/{}-{}.core",
+        CORE_DIR,
+        name.rsplit('/').next().unwrap(),
+        id
+    );
+    log!(
+        crate::LOG_DEF,
+        "Writing core file {} with {} segments",
+        path,
+        segs.len()
+    );
+
+    let mut file = VFS::open(&path, OpenFlags::W | OpenFlags::CREATE | OpenFlags::TRUNC)?;
+    file.write_all(&headers)?;
+    file.write_all(&notes)?;
+    file.write_all(&vec![0u8; data_off - (notes_off + notes.len())])?;
+    for (r, ..) in &segs {
+        r.read_to(|buf| file.write_all(buf))?;
+    }
+
+    Ok(path)
+}
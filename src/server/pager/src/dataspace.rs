@@ -16,18 +16,20 @@
 use m3::cap::Selector;
 use m3::cell::{RefCell, StaticCell};
 use m3::cfg;
+use m3::col::{String, ToString};
 use m3::com::MemGate;
-use m3::errors::Error;
+use m3::errors::{Code, Error};
 use m3::goff;
 use m3::kif;
 use m3::log;
 use m3::math;
 use m3::rc::Rc;
-use m3::session::{ClientSession, MapFlags, M3FS};
+use m3::session::{ClientSession, MapFlags, PagerMappingInfo, PagerMemKind, M3FS};
 use resmng::childs;
 
 use crate::physmem::PhysMem;
-use crate::regions::RegionList;
+use crate::regions::{Region, RegionList};
+use crate::shm::{self, Segment};
 
 const MAX_ANON_PAGES: usize = 4;
 const MAX_EXT_PAGES: usize = 8;
@@ -63,16 +65,32 @@ impl Clone for FileMapping {
     }
 }
 
+#[derive(Clone)]
+struct ShmMapping {
+    seg: Rc<RefCell<Segment>>,
+    offset: goff,
+}
+
 pub struct DataSpace {
     id: u64,
+    // the id of the mapping this dataspace originates from; mappings are split by protect/advise
+    mapping: u64,
     child: childs::Id,
     virt: goff,
     size: goff,
+    // the permissions the mapping has been created with
     perms: kif::Perm,
+    // the current permissions, which are a subset of `perms`
+    prot: kif::Perm,
     flags: MapFlags,
     regions: RegionList,
     owner: Selector,
     file: Option<FileMapping>,
+    shm: Option<ShmMapping>,
+    // whether the memory is allocated by us and can therefore be swapped out
+    swappable: bool,
+    // whether to fault in the next region on page faults
+    sequential: bool,
 }
 
 impl DataSpace {
@@ -87,16 +105,22 @@ impl DataSpace {
         off: goff,
         sel: Selector,
     ) -> Self {
+        let id = alloc_id();
         DataSpace {
-            id: alloc_id(),
+            id,
+            mapping: id,
             child,
             virt,
             size,
             perms,
+            prot: perms,
             flags,
             owner,
             regions: RegionList::new(owner, child, virt, size),
             file: Some(FileMapping::new(sel, off)),
+            shm: None,
+            swappable: false,
+            sequential: false,
         }
     }
 
@@ -108,37 +132,119 @@ impl DataSpace {
         perms: kif::Perm,
         flags: MapFlags,
     ) -> Self {
+        let id = alloc_id();
         DataSpace {
-            id: alloc_id(),
+            id,
+            mapping: id,
             child,
             virt,
             size,
             perms,
+            prot: perms,
             flags,
             owner,
             regions: RegionList::new(owner, child, virt, size),
             file: None,
+            shm: None,
+            swappable: true,
+            sequential: false,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_shm(
+        owner: Selector,
+        child: childs::Id,
+        virt: goff,
+        size: goff,
+        perms: kif::Perm,
+        flags: MapFlags,
+        off: goff,
+        seg: Rc<RefCell<Segment>>,
+    ) -> Self {
+        let id = alloc_id();
+        DataSpace {
+            id,
+            mapping: id,
+            child,
+            virt,
+            size,
+            perms,
+            prot: perms,
+            // the memory of segments is always shared
+            flags: flags | MapFlags::SHARED,
+            owner,
+            regions: RegionList::new(owner, child, virt, size),
+            file: None,
+            shm: Some(ShmMapping { seg, offset: off }),
+            swappable: false,
+            sequential: false,
         }
     }
 
     pub fn clone_for(&self, owner: Selector) -> Self {
         DataSpace {
             id: self.id,
+            mapping: self.mapping,
             child: self.child,
             virt: self.virt,
             size: self.size,
             perms: self.perms,
+            prot: self.prot,
             flags: self.flags,
             owner,
             regions: RegionList::new(owner, self.child, self.virt, self.size),
             file: self.file.clone(),
+            shm: self.shm.clone(),
+            swappable: self.swappable,
+            sequential: self.sequential,
         }
     }
 
+    /// Splits the dataspace at `off` and returns the dataspace for the second part
+    ///
+    /// Both parts get new ids, because they differ from the dataspace that clones might have.
+    pub fn split(&mut self, off: goff) -> Self {
+        let regions = self.regions.split(off, self.virt + off);
+        let file = self.file.clone().map(|mut f| {
+            f.offset += off;
+            f
+        });
+        let shm = self.shm.clone().map(|mut s| {
+            s.offset += off;
+            s
+        });
+
+        let nds = DataSpace {
+            id: alloc_id(),
+            mapping: self.mapping,
+            child: self.child,
+            virt: self.virt + off,
+            size: self.size - off,
+            perms: self.perms,
+            prot: self.prot,
+            flags: self.flags,
+            owner: self.owner,
+            regions,
+            file,
+            shm,
+            swappable: self.swappable,
+            sequential: self.sequential,
+        };
+
+        self.id = alloc_id();
+        self.size = off;
+        nds
+    }
+
     pub fn id(&self) -> u64 {
         self.id
     }
 
+    pub fn mapping(&self) -> u64 {
+        self.mapping
+    }
+
     pub fn virt(&self) -> goff {
         self.virt
     }
@@ -151,53 +257,215 @@ impl DataSpace {
         self.perms
     }
 
+    pub fn prot(&self) -> kif::Perm {
+        self.prot
+    }
+
+    pub fn regions(&self) -> &RegionList {
+        &self.regions
+    }
+
+    pub fn regions_mut(&mut self) -> &mut RegionList {
+        &mut self.regions
+    }
+
+    pub fn swappable(&self) -> bool {
+        self.swappable
+    }
+
+    /// Returns true if the memory can be dropped and obtained again on the next access
+    pub fn discardable(&self) -> bool {
+        self.file.is_some() || self.swappable
+    }
+
+    pub fn sequential(&self) -> bool {
+        self.sequential
+    }
+
+    pub fn set_sequential(&mut self, seq: bool) {
+        self.sequential = seq;
+    }
+
+    pub fn kind(&self) -> PagerMemKind {
+        if self.file.is_some() {
+            PagerMemKind::File
+        }
+        else if self.shm.is_some() {
+            PagerMemKind::Shm
+        }
+        else if self.swappable {
+            PagerMemKind::Anon
+        }
+        else {
+            PagerMemKind::Mem
+        }
+    }
+
+    /// Returns the number of bytes of memory that is accounted to this dataspace
+    pub fn charged(&self) -> goff {
+        self.regions
+            .iter()
+            .filter(|r| r.is_charged())
+            .map(|r| r.size())
+            .sum()
+    }
+
+    pub fn info(&self) -> PagerMappingInfo {
+        let (offset, name) = match (&self.file, &self.shm) {
+            (Some(f), _) => (f.offset, String::new()),
+            (_, Some(s)) => (s.offset, s.seg.borrow().name().to_string()),
+            _ => (0, String::new()),
+        };
+
+        let pages = |f: fn(&Region) -> bool| {
+            self.regions
+                .iter()
+                .filter(|r| f(r))
+                .map(|r| (r.size() / cfg::PAGE_SIZE as goff) as usize)
+                .sum::<usize>()
+        };
+        // readonly regions are marked as copy-on-write as well, but are never copied
+        let writable = self.cow_perms().contains(kif::Perm::W);
+
+        PagerMappingInfo {
+            virt: self.virt,
+            size: self.size as usize,
+            perm: self.perms,
+            prot: self.prot,
+            flags: self.flags,
+            kind: self.kind(),
+            offset,
+            name,
+            resident: pages(|r| r.has_mem()),
+            swapped: pages(|r| r.is_swapped_out()),
+            cow: if writable {
+                pages(|r| r.has_mem() && r.is_cow())
+            }
+            else {
+                0
+            },
+        }
+    }
+
     pub fn inherit(&mut self, ds: &mut DataSpace) -> Result<(), Error> {
         self.id = ds.id;
+        self.sequential = ds.sequential;
+        let prot_changed = self.prot != ds.prot;
+        self.prot = ds.prot;
 
         // if it's not writable, but we have already regions, we can simply keep them
-        if !ds.perms.contains(kif::Perm::W) && !self.regions.is_empty() {
-            return Ok(());
+        let ds_perm = ds.cow_perms();
+        if !ds_perm.contains(kif::Perm::W) && !self.regions.is_empty() {
+            return if prot_changed { self.remap() } else { Ok(()) };
+        }
+
+        let ds_prot = ds.prot();
+        self.regions.clone(&mut ds.regions, ds_perm, ds_prot)
+    }
+
+    /// Changes the current permissions to `prot` and updates the existing mappings
+    pub fn protect(&mut self, prot: kif::Perm) -> Result<(), Error> {
+        if !self.perms.contains(prot) {
+            return Err(Error::new(Code::NoPerm));
         }
 
-        let ds_perm = ds.perm();
-        self.regions.clone(&mut ds.regions, ds_perm)
+        self.prot = prot;
+        self.remap()
+    }
+
+    /// Writes back the changes within the `len` bytes at `virt` to the file, if it's a file mapping
+    pub fn sync(&mut self, virt: goff, len: goff) -> Result<(), Error> {
+        let file = match self.file {
+            Some(ref f) => f,
+            None => return Ok(()),
+        };
+
+        for reg in self.regions.iter_mut() {
+            if reg.needs_writeback()
+                && math::overlaps(reg.virt(), reg.virt() + reg.size(), virt, virt + len)
+            {
+                log!(
+                    crate::LOG_DEF,
+                    "Writing back {:#x}..{:#x}",
+                    reg.virt(),
+                    reg.virt() + reg.size() - 1
+                );
+
+                // make it read-only first to notice writes during and after the write-back
+                reg.set_clean(self.prot)?;
+                M3FS::commit_mem(&file.sess, file.offset + reg.offset(), reg.size())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops all memory, so that the next access gets fresh memory or refetches the file
+    pub fn discard(&mut self) -> Result<(), Error> {
+        if !self.discardable() {
+            return Err(Error::new(Code::NotSup));
+        }
+        self.regions.free()
+    }
+
+    // shared memory is never copied on write
+    fn cow_perms(&self) -> kif::Perm {
+        if self.flags.contains(MapFlags::SHARED) {
+            self.perms & !kif::Perm::W
+        }
+        else {
+            self.perms
+        }
+    }
+
+    fn remap(&mut self) -> Result<(), Error> {
+        for reg in self.regions.iter_mut().filter(|r| r.is_mapped()) {
+            reg.map(self.prot)?;
+        }
+        Ok(())
     }
 
     pub fn populate(&mut self, sel: Selector) {
         self.regions.populate(sel);
+        // the memory is not ours
+        self.swappable = false;
     }
 
-    pub fn handle_pf(&mut self, virt: goff) -> Result<(), Error> {
+    pub fn handle_pf(&mut self, virt: goff, access: kif::Perm) -> Result<(), Error> {
         let pf_off = math::round_dn(virt - self.virt, cfg::PAGE_SIZE as goff);
+        let cow_perms = self.cow_perms();
         let reg = self.regions.pagefault(pf_off);
 
+        // a fault for mapped file memory means that the file system revoked the memory (e.g., on
+        // an eviction from its buffer) or that it is the first write after a write-back. in both
+        // cases, fetching it again is correct, because the file system has written back the
+        // changes. the memory of segments might have been replaced by a resize, so always fetch it
+        // again.
+        if reg.has_foreign_mem()
+            && (self.shm.is_some() || (self.file.is_some() && reg.is_mapped() && !reg.is_cow()))
+        {
+            log!(
+                crate::LOG_DEF,
+                "Memory for {:#x}..{:#x} has been revoked",
+                reg.virt(),
+                reg.virt() + reg.size() - 1
+            );
+            reg.free()?;
+        }
+
+        // if it has been swapped out, get it back
+        if reg.is_swapped_out() {
+            reg.swap_in()?;
+            if access.contains(kif::Perm::W) {
+                reg.set_dirty();
+            }
+        }
         // if it isn't backed with memory yet, allocate memory for it
-        if !reg.has_mem() {
+        else if !reg.has_mem() {
             if let Some(ref f) = self.file {
                 // get memory cap for the region
                 // TODO add a cache for that; we request the same caps over and over again
                 let (off, len, sel) = M3FS::get_mem(&f.sess, f.offset + pf_off)?;
-
-                // first, resize the region to not be too large
-                reg.limit_to(pf_off, MAX_EXT_PAGES as goff);
-
-                // now, align the region with the memory capability that we got
-                let cap_begin = f.offset + pf_off - off;
-                // if it starts before the region, just remember this offset in the region
-                if cap_begin < f.offset + reg.offset() {
-                    reg.set_mem_off(f.offset + reg.offset() - cap_begin);
-                }
-                // otherwise, let the region start at the capability
-                else {
-                    let old_off = reg.offset();
-                    reg.set_offset(cap_begin - f.offset);
-                    reg.set_size(reg.size() - (reg.offset() - old_off));
-                }
-
-                // ensure that we don't exceed the memcap size
-                if reg.mem_off() + reg.size() > len {
-                    reg.set_size(math::round_up(len - reg.mem_off(), cfg::PAGE_SIZE as goff));
-                }
+                fit_region(reg, f.offset, pf_off, off, len, MAX_EXT_PAGES as goff);
 
                 // if it's writable and should not be shared, create a copy
                 if !self.flags.contains(MapFlags::SHARED) && self.perms.contains(kif::Perm::W) {
@@ -205,7 +473,11 @@ impl DataSpace {
                     let mut childs = childs::borrow_mut();
                     let child = childs.child_by_id_mut(self.child).unwrap();
                     let mgate = child.alloc_local(reg.size(), kif::Perm::RWX)?;
-                    let mem = Rc::new(RefCell::new(PhysMem::new((self.owner, self.virt), mgate)?));
+                    let mem = Rc::new(RefCell::new(PhysMem::new(
+                        (self.owner, self.virt),
+                        mgate,
+                        self.child,
+                    )?));
                     reg.set_mem(mem);
                     reg.copy_from(&src);
                     reg.set_mem_off(0);
@@ -215,6 +487,14 @@ impl DataSpace {
                         (self.owner, self.virt),
                         sel,
                     ))));
+
+                    // track the changes to shared file memory to write them back later
+                    if self.flags.contains(MapFlags::SHARED) && self.perms.contains(kif::Perm::W) {
+                        reg.set_writeback();
+                        if access.contains(kif::Perm::W) {
+                            reg.set_dirty();
+                        }
+                    }
                 }
 
                 log!(
@@ -224,54 +504,142 @@ impl DataSpace {
                     reg.virt() + reg.size() - 1
                 );
             }
+            else if let Some(ref s) = self.shm {
+                let (off, len, sel) = s.seg.borrow_mut().get_mem(s.offset + pf_off)?;
+                fit_region(reg, s.offset, pf_off, off, len, shm::CHUNK_PAGES as goff);
+
+                reg.set_mem(Rc::new(RefCell::new(PhysMem::new_bind(
+                    (self.owner, self.virt),
+                    sel,
+                ))));
+
+                log!(
+                    crate::LOG_DEF,
+                    "Obtained memory of segment '{}' for {:#x}..{:#x}",
+                    s.seg.borrow().name(),
+                    reg.virt(),
+                    reg.virt() + reg.size() - 1
+                );
+            }
             else {
-                let max = if !self.flags.contains(MapFlags::NOLPAGE)
-                    && math::is_aligned(virt, cfg::LPAGE_SIZE as goff)
-                    && reg.size() >= cfg::LPAGE_SIZE as goff
+                let lpage = cfg::LPAGE_SIZE as goff;
+                let lpage_virt = math::round_dn(virt, lpage);
+
+                let mut childs = childs::borrow_mut();
+                let child = childs.child_by_id_mut(self.child).unwrap();
+
+                // use a large page, if the surrounding large page is not in use yet
+                let mut lpage_mem = None;
+                if !self.flags.contains(MapFlags::NOLPAGE)
+                    && lpage_virt >= reg.virt()
+                    && lpage_virt + lpage <= reg.virt() + reg.size()
                 {
-                    cfg::LPAGE_SIZE / cfg::PAGE_SIZE
+                    // this might fail due to fragmentation; use small pages in this case
+                    match child.alloc_local(lpage, kif::Perm::RWX) {
+                        Ok(mgate) => {
+                            reg.set_offset(lpage_virt - self.virt);
+                            reg.set_size(lpage);
+                            lpage_mem = Some(mgate);
+                        },
+                        Err(e) => log!(
+                            crate::LOG_DEF,
+                            "Unable to allocate large page for {:#x}: {:?}",
+                            lpage_virt,
+                            e
+                        ),
+                    }
                 }
-                else {
-                    MAX_ANON_PAGES
-                };
 
-                // don't allocate too much at once
-                reg.limit_to(pf_off, max as goff);
+                let mgate = match lpage_mem {
+                    Some(mgate) => mgate,
+                    None => {
+                        // don't allocate too much at once
+                        reg.limit_to(pf_off, MAX_ANON_PAGES as goff);
+                        child.alloc_local(reg.size(), kif::Perm::RWX)?
+                    },
+                };
 
                 log!(
                     crate::LOG_DEF,
-                    "Allocating anonymous memory for {:#x}..{:#x}",
+                    "Allocated anonymous memory for {:#x}..{:#x}",
                     reg.virt(),
                     reg.virt() + reg.size() - 1
                 );
 
-                let mut childs = childs::borrow_mut();
-                let child = childs.child_by_id_mut(self.child).unwrap();
-                let mgate = child.alloc_local(reg.size(), kif::Perm::RWX)?;
+                reg.set_mem_off(0);
                 reg.set_mem(Rc::new(RefCell::new(PhysMem::new(
                     (self.owner, self.virt),
                     mgate,
+                    self.child,
                 )?)));
 
                 if !self.flags.contains(MapFlags::UNINIT) {
                     // zero the memory
                     reg.clear();
                 }
+                // the content does not exist in swap yet
+                reg.set_dirty();
             }
         }
         // if we have memory, but COW is in progress
         else if reg.is_cow() {
-            reg.handle_cow(self.perms)?;
+            reg.handle_cow(cow_perms)?;
+        }
+        // the first write to memory that has been swapped in
+        else if reg.is_clean() && access.contains(kif::Perm::W) {
+            reg.set_dirty();
         }
         else if reg.is_mapped() {
             // nothing to do
             return Ok(());
         }
 
-        reg.map(self.perms)
+        reg.touch();
+        reg.map(self.prot)
+    }
+
+    /// Faults in the region behind the one containing `virt`, if there is any
+    pub fn read_ahead(&mut self, virt: goff) -> Result<(), Error> {
+        let pf_off = math::round_dn(virt - self.virt, cfg::PAGE_SIZE as goff);
+        let next = {
+            let reg = self.regions.pagefault(pf_off);
+            reg.virt() + reg.size()
+        };
+        if next < self.virt + self.size {
+            self.handle_pf(next, kif::Perm::empty())?;
+        }
+        Ok(())
     }
 
     pub fn kill(&mut self) {
         self.regions.kill();
     }
 }
+
+/// Limits `reg` to at most `pages` pages around `pf_off` and aligns it with the memory capability
+/// of `len` bytes that we got for `pf_off`, which is at offset `cap_off` within the capability
+///
+/// `base` is the offset of the dataspace within the file or segment.
+fn fit_region(reg: &mut Region, base: goff, pf_off: goff, cap_off: goff, len: goff, pages: goff) {
+    // first, resize the region to not be too large
+    reg.limit_to(pf_off, pages);
+
+    // now, align the region with the memory capability that we got
+    let cap_begin = base + pf_off - cap_off;
+    // if it starts before the region, just remember this offset in the region
+    if cap_begin < base + reg.offset() {
+        reg.set_mem_off(base + reg.offset() - cap_begin);
+    }
+    // otherwise, let the region start at the capability
+    else {
+        let old_off = reg.offset();
+        reg.set_offset(cap_begin - base);
+        reg.set_size(reg.size() - (reg.offset() - old_off));
+        reg.set_mem_off(0);
+    }
+
+    // ensure that we don't exceed the memcap size
+    if reg.mem_off() + reg.size() > len {
+        reg.set_size(math::round_up(len - reg.mem_off(), cfg::PAGE_SIZE as goff));
+    }
+}
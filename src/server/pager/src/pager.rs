@@ -16,10 +16,13 @@
 #![no_std]
 
 mod addrspace;
+mod coredump;
 mod dataspace;
 mod mapper;
 mod physmem;
 mod regions;
+mod shm;
+mod swap;
 
 use core::ops::DerefMut;
 
@@ -30,14 +33,14 @@ use m3::com::{GateIStream, MGateArgs, MemGate, RecvGate, SGateArgs, SendGate};
 use m3::env;
 use m3::errors::{Code, Error, VerboseError};
 use m3::format;
+use m3::goff;
 use m3::kif;
 use m3::log;
 use m3::math;
 use m3::println;
-use m3::server::{
-    CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer, DEF_MSG_SIZE,
-};
-use m3::session::{ClientSession, Pager, PagerOp, ResMng, M3FS};
+use m3::reply_vmsg;
+use m3::server::{CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer};
+use m3::session::{ClientSession, Pager, PagerInfoResult, PagerOp, ResMng, M3FS};
 use m3::tcu::{Label, TileId};
 use m3::tiles::{Activity, ActivityArgs, ChildActivity};
 use m3::vfs;
@@ -47,6 +50,12 @@ use resmng::childs::{self, Child, OwnChild};
 use resmng::{requests, sendqueue, subsys};
 
 pub const LOG_DEF: bool = false;
+pub const LOG_SWAP: bool = false;
+
+/// The default size of the swap file
+const DEF_SWAP_SIZE: usize = 32 * 1024 * 1024;
+/// The size of request messages, which need to hold up to seven arguments or a segment name
+const MSG_SIZE: usize = 128;
 
 static PGHDL: LazyStaticRefCell<PagerReqHandler> = LazyStaticRefCell::default();
 static REQHDL: LazyReadOnlyCell<RequestHandler> = LazyReadOnlyCell::default();
@@ -199,7 +208,8 @@ fn start_child_async(child: &mut OwnChild) -> Result<(), VerboseError> {
         ActivityArgs::new(child.name())
             .resmng(ResMng::new(resmng_sgate))
             .pager(Pager::new(sess, pager_sgate, child_sgate)?)
-            .kmem(child.kmem().unwrap()),
+            .kmem(child.kmem().unwrap())
+            .sysc_budget(child.cfg().sysc_budget()),
     )?;
 
     // TODO make that more flexible
@@ -240,6 +250,81 @@ fn start_child_async(child: &mut OwnChild) -> Result<(), VerboseError> {
         .map_err(|e| VerboseError::new(e.code(), "Unable to start Activity".to_string()))
 }
 
+fn write_core(id: childs::Id, name: &str, info: &kif::tilemux::CrashInfo) {
+    let mut res = None;
+    PGHDL.borrow_mut().sessions.for_each(|aspace| {
+        // the child's own address space is the only one without parent
+        if res.is_none() && aspace.child_id() == Some(id) && aspace.parent().is_none() {
+            res = Some(coredump::write(id, name, aspace, info));
+        }
+    });
+
+    match res {
+        Some(Ok(path)) => println!("Wrote core file of '{}' to {}", name, path),
+        Some(Err(e)) => println!("Unable to write core file of '{}': {}", name, e),
+        None => {},
+    }
+}
+
+fn get_info(
+    sessions: &mut SessionContainer<AddrSpace>,
+    is: &mut GateIStream<'_>,
+) -> Result<(), Error> {
+    let aspace_idx: usize = is.pop()?;
+    let map_idx: usize = is.pop()?;
+
+    log!(
+        crate::LOG_DEF,
+        "[{}] pager::get_info(aspace={}, mapping={})",
+        is.label(),
+        aspace_idx,
+        map_idx
+    );
+
+    // the clones of a session share its child and therefore its permissions
+    let child = sessions.get(is.label() as SessId).unwrap().child_id();
+    let allowed = child.map_or(false, |id| {
+        childs::borrow_mut()
+            .child_by_id(id)
+            .map_or(false, |c| c.cfg().can_get_info())
+    });
+    if !allowed {
+        return Err(Error::new(Code::NoPerm));
+    }
+
+    // sessions that have not been initialized yet don't belong to an activity
+    let mut count = 0;
+    let mut res = Err(Error::new(Code::InvArgs));
+    sessions.for_each(|aspace| {
+        if !aspace.has_owner() {
+            return;
+        }
+        if count == aspace_idx {
+            res = if map_idx == usize::MAX {
+                Ok(PagerInfoResult::AddrSpace(aspace.info()))
+            }
+            else {
+                aspace.mapping_info(map_idx).map(PagerInfoResult::Mapping)
+            };
+        }
+        count += 1;
+    });
+
+    if aspace_idx == usize::MAX {
+        res = Ok(PagerInfoResult::Count(count));
+    }
+    res.and_then(|info| reply_vmsg!(is, Code::None as u32, info))
+}
+
+fn swap_stats(is: &mut GateIStream<'_>) -> Result<(), Error> {
+    log!(crate::LOG_DEF, "[{}] pager::swap_stats()", is.label());
+
+    if !swap::enabled() {
+        return Err(Error::new(Code::NotSup));
+    }
+    reply_vmsg!(is, Code::None as u32, swap::stats())
+}
+
 fn handle_request(op: PagerOp, is: &mut GateIStream<'_>) -> Result<(), Error> {
     let mut hdl = PGHDL.borrow_mut();
     let sid = is.label() as SessId;
@@ -256,6 +341,14 @@ fn handle_request(op: PagerOp, is: &mut GateIStream<'_>) -> Result<(), Error> {
             Err(Error::new(Code::InvArgs))
         }
     }
+    // info is special, because it covers all sessions
+    else if op == PagerOp::GET_INFO {
+        get_info(&mut hdl.sessions, is)
+    }
+    // the swap space is shared by all sessions
+    else if op == PagerOp::SWAP_STATS {
+        swap_stats(is)
+    }
     else {
         let aspace = hdl.sessions.get_mut(sid).unwrap();
 
@@ -263,6 +356,14 @@ fn handle_request(op: PagerOp, is: &mut GateIStream<'_>) -> Result<(), Error> {
             PagerOp::PAGEFAULT => aspace.pagefault(is),
             PagerOp::MAP_ANON => aspace.map_anon(is),
             PagerOp::UNMAP => aspace.unmap(is),
+            PagerOp::PROTECT => aspace.protect(is),
+            PagerOp::ADVISE => aspace.advise(is),
+            PagerOp::SYNC => aspace.sync(is),
+            PagerOp::SHM_OPEN => aspace.open_shm(is),
+            PagerOp::SHM_RESIZE => aspace.resize_shm(is),
+            PagerOp::SHM_CLOSE => aspace.close_shm(is),
+            PagerOp::MAP_SHM => aspace.map_shm(is),
+            PagerOp::MAP_STATS => aspace.map_stats(is),
             PagerOp::CLOSE => aspace
                 .close(is)
                 .map(|_| hdl.close_sess(0, is.label() as SessId, is.rgate())),
@@ -286,15 +387,31 @@ fn workloop(serv: &Server) {
 #[derive(Clone, Debug)]
 pub struct PagerSettings {
     fs_size: usize,
+    swap_file: Option<String>,
+    swap_size: usize,
 }
 
 fn parse_args() -> Result<PagerSettings, String> {
+    let mut swap_file = None;
+    let mut swap_size = DEF_SWAP_SIZE;
+    for arg in env::args() {
+        if let Some(path) = arg.strip_prefix("swap=") {
+            swap_file = Some(path.to_string());
+        }
+        else if let Some(size) = arg.strip_prefix("swapsize=") {
+            swap_size =
+                m3::parse::size(size).map_err(|_| String::from("Failed to parse swap size"))?;
+        }
+    }
+
     Ok(PagerSettings {
         fs_size: env::args()
             .last()
             .ok_or("File system size missing")?
             .parse::<usize>()
             .map_err(|_| String::from("Failed to parse FS size"))?,
+        swap_file,
+        swap_size,
     })
 }
 
@@ -318,6 +435,15 @@ pub fn main() -> i32 {
         .borrow_mut()
         .push(("m3fs".to_string(), "/".to_string()));
 
+    // swap anonymous memory out to the given file if the childs' memory quota is exhausted
+    let (swap_file, swap_size) = {
+        let settings = SETTINGS.borrow();
+        (settings.swap_file.clone(), settings.swap_size)
+    };
+    if let Some(path) = swap_file {
+        swap::init(&path, swap_size as goff).expect("Unable to create swap file");
+    }
+
     // create server
     let mut hdl = PagerReqHandler {
         sel: 0,
@@ -326,9 +452,10 @@ pub fn main() -> i32 {
     let serv = Server::new_private("pager", &mut hdl).expect("Unable to create service");
     hdl.sel = serv.sel();
     PGHDL.set(hdl);
+    childs::borrow_mut().set_crash_handler(write_core);
 
     REQHDL.set(
-        RequestHandler::new_with(args.max_clients, DEF_MSG_SIZE)
+        RequestHandler::new_with(args.max_clients, MSG_SIZE)
             .expect("Unable to create request handler"),
     );
 
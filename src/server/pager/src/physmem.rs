@@ -20,6 +20,7 @@ use m3::com::MemGate;
 use m3::errors::Error;
 use m3::goff;
 use m3::mem;
+use resmng::childs;
 
 static ZEROS: mem::AlignedBuf<{ cfg::PAGE_SIZE }> = mem::AlignedBuf::new_zeroed();
 static BUF: StaticRefCell<mem::AlignedBuf<{ cfg::PAGE_SIZE }>> =
@@ -35,7 +36,33 @@ pub fn copy_block(src: &MemGate, dst: &MemGate, src_off: goff, size: goff) {
     }
 }
 
-fn clear_block(mem: &MemGate, size: goff) {
+pub fn read_block<F>(src: &MemGate, src_off: goff, size: goff, mut func: F) -> Result<(), Error>
+where
+    F: FnMut(&[u8]) -> Result<(), Error>,
+{
+    let mut buf = BUF.borrow_mut();
+    let pages = size / cfg::PAGE_SIZE as goff;
+    for i in 0..pages {
+        src.read(&mut buf[..], src_off + i * cfg::PAGE_SIZE as goff)?;
+        func(&buf[..])?;
+    }
+    Ok(())
+}
+
+pub fn write_block<F>(dst: &MemGate, size: goff, mut func: F) -> Result<(), Error>
+where
+    F: FnMut(&mut [u8]) -> Result<(), Error>,
+{
+    let mut buf = BUF.borrow_mut();
+    let pages = size / cfg::PAGE_SIZE as goff;
+    for i in 0..pages {
+        func(&mut buf[..])?;
+        dst.write(&buf[..], i * cfg::PAGE_SIZE as goff)?;
+    }
+    Ok(())
+}
+
+pub fn clear_block(mem: &MemGate, size: goff) {
     let pages = size / cfg::PAGE_SIZE as goff;
     for i in 0..pages {
         mem.write(&ZEROS[..], i * cfg::PAGE_SIZE as goff).unwrap();
@@ -44,41 +71,96 @@ fn clear_block(mem: &MemGate, size: goff) {
 
 pub struct PhysMem {
     mgate: MemGate,
+    // the physical address of the memory, determined on first use
+    phys: Option<goff>,
     owner_mem: Option<(Selector, goff)>,
+    // the child that the memory has been allocated for (none for memory from elsewhere)
+    alloc_child: Option<childs::Id>,
+    // the number of parts of split regions that use the memory
+    parts: usize,
 }
 
 impl PhysMem {
-    pub fn new(owner_mem: (Selector, goff), mem: MemGate) -> Result<Self, Error> {
+    pub fn new(
+        owner_mem: (Selector, goff),
+        mem: MemGate,
+        child: childs::Id,
+    ) -> Result<Self, Error> {
         Ok(PhysMem {
             mgate: mem,
+            phys: None,
             owner_mem: Some(owner_mem),
+            alloc_child: Some(child),
+            parts: 0,
         })
     }
 
-    pub fn new_with_mem(owner_mem: (Selector, goff), mem: MemGate) -> Self {
+    pub fn new_with_mem(owner_mem: (Selector, goff), mem: MemGate, child: childs::Id) -> Self {
         PhysMem {
             mgate: mem,
+            phys: None,
             owner_mem: Some(owner_mem),
+            alloc_child: Some(child),
+            parts: 0,
         }
     }
 
     pub fn new_bind(owner_mem: (Selector, goff), sel: Selector) -> Self {
         PhysMem {
             mgate: MemGate::new_bind(sel),
+            phys: None,
             owner_mem: Some(owner_mem),
+            alloc_child: None,
+            parts: 0,
         }
     }
 
+    pub fn is_owned(&self) -> bool {
+        self.alloc_child.is_some()
+    }
+
     pub fn gate(&self) -> &MemGate {
         &self.mgate
     }
 
+    /// Returns the physical address of the memory
+    pub fn phys(&mut self) -> Result<goff, Error> {
+        if self.phys.is_none() {
+            self.phys = Some(self.mgate.region()?.0.raw());
+        }
+        Ok(self.phys.unwrap())
+    }
+
     pub fn deactivate(&mut self) {
         self.mgate.deactivate();
     }
 
-    pub fn replace_gate(&mut self, mem: MemGate) -> MemGate {
-        mem::replace(&mut self.mgate, mem)
+    /// Replaces the memory with `mem`, allocated for `child`, and returns the old memory
+    pub fn replace_mem(
+        &mut self,
+        mem: MemGate,
+        child: childs::Id,
+        owner_mem: (Selector, goff),
+    ) -> PhysMem {
+        PhysMem {
+            mgate: mem::replace(&mut self.mgate, mem),
+            phys: self.phys.take(),
+            owner_mem: Some(owner_mem),
+            alloc_child: self.alloc_child.replace(child),
+            parts: 0,
+        }
+    }
+
+    /// Gives the memory back to the child it has been allocated for, if any
+    pub fn free(self) -> Result<(), Error> {
+        if let Some(id) = self.alloc_child {
+            let mut childs = childs::borrow_mut();
+            // if the child is already gone, its memory has been freed anyway
+            if let Some(child) = childs.child_by_id_mut(id) {
+                return child.free_local(self.mgate);
+            }
+        }
+        Ok(())
     }
 
     pub fn owner_mem(&self) -> Option<(Selector, goff)> {
@@ -93,6 +175,19 @@ impl PhysMem {
         self.owner_mem = None;
     }
 
+    /// Returns the number of parts of split regions that use the memory
+    pub fn parts(&self) -> usize {
+        self.parts
+    }
+
+    pub fn add_part(&mut self) {
+        self.parts += 1;
+    }
+
+    pub fn remove_part(&mut self) {
+        self.parts -= 1;
+    }
+
     pub fn clear(&self, size: goff) {
         clear_block(&self.mgate, size);
     }
@@ -26,16 +26,26 @@ use m3::errors::Error;
 use m3::goff;
 use m3::kif::{CapRngDesc, CapType, Perm, INVALID_SEL};
 use m3::log;
+use m3::math;
 use m3::rc::Rc;
 use m3::syscalls;
 use resmng::childs;
 
-use crate::physmem::{copy_block, PhysMem};
+use crate::physmem::{copy_block, read_block, PhysMem};
+use crate::swap::{self, SwapSlot};
 
 bitflags! {
     struct RegionFlags : u64 {
-        const MAPPED = 0x1;
-        const COW    = 0x2;
+        const MAPPED     = 0x1;
+        const COW        = 0x2;
+        // set on every page fault and cleared by the eviction's clock hand
+        const REFERENCED = 0x4;
+        // the memory differs from the copy in the swap slot or the file (if any)
+        const DIRTY      = 0x8;
+        // the memory belongs to a file and changes need to be written back
+        const WRITEBACK  = 0x10;
+        // the memory is shared with the other parts of a split region
+        const SPLIT      = 0x20;
     }
 }
 
@@ -43,12 +53,38 @@ pub struct Region {
     owner: Selector,
     child: childs::Id,
     mem: Option<Rc<RefCell<PhysMem>>>,
+    // shared with clones as long as none of them modified the memory
+    swap: Option<Rc<SwapSlot>>,
+    // our offset within the swap slot
+    swap_off: goff,
     mem_off: goff,
     ds_off: goff,
     off: goff,
     size: goff,
     perm: Perm,
     flags: RegionFlags,
+    // the physical address the region is mapped to, if it might use large pages
+    map_phys: Option<goff>,
+}
+
+/// Returns the number of large pages the kernel uses to map `size` bytes at `virt` to `phys`
+///
+/// Large pages require that both addresses are aligned to the large page size, so that only the
+/// part in between the first and last large page boundary can be mapped with large pages.
+fn large_pages(virt: goff, phys: goff, size: goff) -> goff {
+    let lpage = cfg::LPAGE_SIZE as goff;
+    if (virt & (lpage - 1)) != (phys & (lpage - 1)) {
+        return 0;
+    }
+
+    let start = math::round_up(virt, lpage);
+    let end = math::round_dn(virt + size, lpage);
+    if end > start {
+        (end - start) / lpage
+    }
+    else {
+        0
+    }
 }
 
 impl Region {
@@ -57,26 +93,33 @@ impl Region {
             owner,
             child,
             mem: None,
+            swap: None,
+            swap_off: 0,
             mem_off: 0,
             ds_off,
             off,
             size,
             perm: Perm::empty(),
             flags: RegionFlags::empty(),
+            map_phys: None,
         }
     }
 
     pub fn clone_for(&self, owner: Selector) -> Self {
+        // the clone is no part of our region
         Region {
             owner,
             child: self.child,
             mem: self.mem.clone(),
+            swap: self.swap.clone(),
+            swap_off: self.swap_off,
             mem_off: self.mem_off,
             ds_off: self.ds_off,
             off: self.off,
             size: self.size,
             perm: self.perm,
-            flags: self.flags,
+            flags: self.flags - RegionFlags::SPLIT,
+            map_phys: self.map_phys,
         }
     }
 
@@ -124,11 +167,204 @@ impl Region {
         self.flags.contains(RegionFlags::COW)
     }
 
-    pub fn handle_cow(&mut self, ds_perms: Perm) -> Result<(), Error> {
+    pub fn is_swapped_out(&self) -> bool {
+        self.mem.is_none() && self.swap.is_some()
+    }
+
+    /// Returns true if the memory has been allocated by us and is accounted to this region
+    ///
+    /// Memory that is shared copy-on-write is accounted to the region of its owner.
+    pub fn is_charged(&self) -> bool {
+        self.mem.as_ref().map_or(false, |m| {
+            let m = m.borrow();
+            m.is_owned()
+                && (!self.is_cow() || m.owner_mem().map_or(true, |(act, _)| act == self.owner))
+        })
+    }
+
+    /// Returns the number of large and small pages the region is currently mapped with
+    pub fn mapped_pages(&self) -> (goff, goff) {
+        if self.mem.is_none() || !self.is_mapped() {
+            return (0, 0);
+        }
+
+        let large = self
+            .map_phys
+            .map_or(0, |phys| large_pages(self.virt(), phys, self.size));
+        let small = (self.size - large * cfg::LPAGE_SIZE as goff) / cfg::PAGE_SIZE as goff;
+        (large, small)
+    }
+
+    pub fn is_referenced(&self) -> bool {
+        self.flags.contains(RegionFlags::REFERENCED)
+    }
+
+    /// Returns true if the memory has a copy (in swap or in the file) that is still up to date
+    pub fn is_clean(&self) -> bool {
+        (self.swap.is_some() || self.flags.contains(RegionFlags::WRITEBACK))
+            && !self.flags.contains(RegionFlags::DIRTY)
+    }
+
+    /// Returns true if the memory is not ours, but has been obtained from somewhere else
+    pub fn has_foreign_mem(&self) -> bool {
+        self.mem.as_ref().map_or(false, |m| !m.borrow().is_owned())
+    }
+
+    pub fn needs_writeback(&self) -> bool {
+        self.flags
+            .contains(RegionFlags::WRITEBACK | RegionFlags::DIRTY)
+    }
+
+    pub fn set_dirty(&mut self) {
+        self.flags.insert(RegionFlags::DIRTY);
+    }
+
+    /// Tracks the changes to the memory, which need to be written back to the file
+    pub fn set_writeback(&mut self) {
+        self.flags.insert(RegionFlags::WRITEBACK);
+    }
+
+    /// Marks the memory as written back and maps it read-only to notice the next write
+    pub fn set_clean(&mut self, perm: Perm) -> Result<(), Error> {
+        self.flags.remove(RegionFlags::DIRTY);
+        if self.is_mapped() {
+            self.map(perm)?;
+        }
+        Ok(())
+    }
+
+    /// Marks the region as used, which protects it from the next eviction attempt
+    pub fn touch(&mut self) {
+        self.flags.insert(RegionFlags::REFERENCED);
+    }
+
+    /// Gives the region a second chance during eviction
+    ///
+    /// The region is unmapped to notice the next access via a page fault.
+    pub fn unreference(&mut self) {
+        self.flags.remove(RegionFlags::REFERENCED);
+        self.unmap();
+    }
+
+    /// Returns true if the region has memory that can be given back after a swap out
+    ///
+    /// Memory that is shared with other parts of a split region is given back as soon as all parts
+    /// have been swapped out.
+    pub fn can_swap_out(&self) -> bool {
+        match self.mem {
+            Some(ref mem) => {
+                let users = if self.flags.contains(RegionFlags::SPLIT) {
+                    mem.borrow().parts()
+                }
+                else {
+                    1
+                };
+                Rc::strong_count(mem) == users && !self.is_cow()
+            },
+            None => false,
+        }
+    }
+
+    /// Stops sharing the memory with the other parts of a split region, if we did so
+    fn leave_parts(&mut self) {
+        if self.flags.contains(RegionFlags::SPLIT) {
+            if let Some(ref mem) = self.mem {
+                mem.borrow_mut().remove_part();
+            }
+            self.flags.remove(RegionFlags::SPLIT);
+        }
+    }
+
+    /// Writes the memory to swap, if required, and frees the memory
+    pub fn swap_out(&mut self) -> Result<(), Error> {
+        // unmap it first to prevent further changes during the write
+        self.unmap();
+
+        let clean = self.is_clean();
+        // don't overwrite a slot that is still used by clones
+        if !clean
+            && self
+                .swap
+                .as_ref()
+                .map_or(false, |s| Rc::strong_count(s) > 1)
+        {
+            self.swap = None;
+        }
+        if self.swap.is_none() {
+            self.swap = Some(Rc::new(swap::alloc(self.size)?));
+            self.swap_off = 0;
+        }
+
+        let slot = self.swap.as_ref().unwrap();
+        swap::write(
+            slot,
+            self.swap_off,
+            self.size,
+            self.mem.as_ref().unwrap().borrow().gate(),
+            self.mem_off,
+            clean,
+        )?;
+
+        log!(
+            crate::LOG_SWAP,
+            "Swapped out {:#x}..{:#x} to {:?} ({})",
+            self.virt(),
+            self.virt() + self.size - 1,
+            slot,
+            if clean { "clean" } else { "dirty" },
+        );
+
+        self.leave_parts();
+        let mem = self.mem.take().unwrap();
+        self.flags
+            .remove(RegionFlags::DIRTY | RegionFlags::REFERENCED);
+        match Rc::try_unwrap(mem) {
+            Ok(mem) => mem.into_inner().free(),
+            // the other parts still use the memory
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Allocates new memory and reads the content from swap into it
+    pub fn swap_in(&mut self) -> Result<(), Error> {
+        let mgate = {
+            let mut childs = childs::borrow_mut();
+            let child = childs.child_by_id_mut(self.child).unwrap();
+            child.alloc_local(self.size, Perm::RWX)?
+        };
+
+        let slot = self.swap.as_ref().unwrap();
+        if let Err(e) = swap::read(slot, self.swap_off, self.size, &mgate) {
+            let mut childs = childs::borrow_mut();
+            let child = childs.child_by_id_mut(self.child).unwrap();
+            child.free_local(mgate).ok();
+            return Err(e);
+        }
+
+        log!(
+            crate::LOG_SWAP,
+            "Swapped in {:#x}..{:#x} from {:?}",
+            self.virt(),
+            self.virt() + self.size - 1,
+            slot,
+        );
+
+        let mut mem = PhysMem::new_with_mem((self.owner, self.ds_off), mgate, self.child);
+        // we only need the gate again for the next swap out
+        mem.deactivate();
+        self.mem = Some(Rc::new(RefCell::new(mem)));
+        self.mem_off = 0;
+        // the memory is our own copy now
         self.flags.remove(RegionFlags::COW);
+        Ok(())
+    }
 
+    pub fn handle_cow(&mut self, ds_perms: Perm) -> Result<(), Error> {
         // writable memory needs to be copied
         if ds_perms.contains(Perm::W) {
+            // the memory will differ from the swapped out version, if any
+            self.flags.insert(RegionFlags::DIRTY);
+
             let nmem = {
                 let mem = self.mem.as_ref().unwrap();
 
@@ -136,14 +372,16 @@ impl Region {
                 if Rc::strong_count(mem) == 1 {
                     // we are the owner now
                     mem.borrow_mut().set_owner(self.owner, self.ds_off);
+                    self.leave_parts();
+                    self.flags.remove(RegionFlags::COW);
                     return Ok(());
                 }
 
                 let mut mem = mem.borrow_mut();
 
                 // either copy from owner memory or the physical memory
-                let (off, osel) = if let Some((oact, ovirt)) = mem.owner_mem() {
-                    (ovirt + self.off, oact)
+                let (off, osel) = if let Some((oact, _)) = mem.owner_mem() {
+                    (self.virt(), oact)
                 }
                 else {
                     (self.mem_off, INVALID_SEL)
@@ -181,39 +419,107 @@ impl Region {
                     copy_block(&omem, &ngate, 0, self.size);
                 }
 
-                // are we the owner?
-                if self.owner == osel {
+                // are we the owner of the whole memory? (the other parts of a split region would
+                // otherwise get the new memory as well)
+                if self.owner == osel && !self.flags.contains(RegionFlags::SPLIT) {
                     // deactivate the MemGate, because we'll probably not need it again
                     ngate.deactivate();
 
-                    // give the others the new memory gate
-                    let old = mem.replace_gate(ngate);
+                    // give the others the new memory gate and us the old memory with a new
+                    // PhysMem object
                     let owner_virt = mem.owner_mem().unwrap().1;
+                    let old = mem.replace_mem(ngate, self.child, (self.owner, owner_virt));
                     // there is no owner anymore
                     mem.remove_owner();
-                    // give us the old memory with a new PhysMem object
-                    Rc::new(RefCell::new(PhysMem::new_with_mem(
-                        (self.owner, owner_virt),
-                        old,
-                    )))
+                    Rc::new(RefCell::new(old))
                 }
                 else {
-                    // the others keep the old mem; we take the new one
+                    // the others keep the old mem; we take the new one. if we were the owner, the
+                    // others have to copy from the old memory from now on
+                    if self.owner == osel {
+                        mem.remove_owner();
+                    }
                     Rc::new(RefCell::new(PhysMem::new_with_mem(
                         (self.owner, self.ds_off),
                         ngate,
+                        self.child,
                     )))
                 }
             };
 
             // it's not that likely that we'll use this gate again, so deactivate it
             nmem.borrow_mut().deactivate();
+            self.leave_parts();
             self.mem = Some(nmem);
         }
 
+        // only remove it now, because we need to try again if the allocation failed
+        self.flags.remove(RegionFlags::COW);
         Ok(())
     }
 
+    /// Splits the region at `off` (relative to the region) and returns the second part
+    ///
+    /// Both parts share the memory, which is given back as soon as no part uses it anymore.
+    pub fn split(&mut self, off: goff) -> Region {
+        assert!(off > 0 && off < self.size);
+
+        if let Some(ref mem) = self.mem {
+            let mut mem = mem.borrow_mut();
+            if !self.flags.contains(RegionFlags::SPLIT) {
+                mem.add_part();
+                self.flags.insert(RegionFlags::SPLIT);
+            }
+            mem.add_part();
+        }
+
+        let nreg = Region {
+            owner: self.owner,
+            child: self.child,
+            mem: self.mem.clone(),
+            swap: self.swap.clone(),
+            swap_off: self.swap_off + off,
+            mem_off: self.mem_off + off,
+            ds_off: self.ds_off,
+            off: self.off + off,
+            size: self.size - off,
+            perm: self.perm,
+            flags: self.flags,
+            map_phys: self.map_phys.map(|p| p + off),
+        };
+
+        self.size = off;
+        nreg
+    }
+
+    /// Unmaps the region and drops its memory and swap slot
+    ///
+    /// The memory is given back if nobody else uses it.
+    pub fn free(&mut self) -> Result<(), Error> {
+        self.unmap();
+        self.leave_parts();
+        self.swap = None;
+        self.flags.remove(
+            RegionFlags::COW
+                | RegionFlags::DIRTY
+                | RegionFlags::REFERENCED
+                | RegionFlags::WRITEBACK,
+        );
+
+        match self.mem.take().map(Rc::try_unwrap) {
+            Some(Ok(mem)) => mem.into_inner().free(),
+            Some(Err(mem)) => {
+                // the others cannot copy from our address space anymore
+                let mut mem = mem.borrow_mut();
+                if mem.owner_mem().map_or(false, |(act, _)| act == self.owner) {
+                    mem.remove_owner();
+                }
+                Ok(())
+            },
+            None => Ok(()),
+        }
+    }
+
     pub fn limit_to(&mut self, pos: goff, pages: goff) {
         if self.size > pages * cfg::PAGE_SIZE as goff {
             let end = self.off + self.size;
@@ -232,6 +538,17 @@ impl Region {
         }
     }
 
+    pub fn read_to<F>(&self, func: F) -> Result<(), Error>
+    where
+        F: FnMut(&[u8]) -> Result<(), Error>,
+    {
+        let mem = self.mem.as_ref().unwrap();
+        let res = read_block(mem.borrow().gate(), self.mem_off, self.size, func);
+        // see above
+        mem.borrow_mut().deactivate();
+        res
+    }
+
     pub fn clear(&self) {
         let mem = self.mem.as_ref().unwrap();
         mem.borrow().clear(self.size);
@@ -240,6 +557,21 @@ impl Region {
     }
 
     pub fn map(&mut self, perm: Perm) -> Result<(), Error> {
+        // without permissions, the region is not accessible at all
+        if perm.is_empty() {
+            self.unmap();
+            return Ok(());
+        }
+
+        // map clean memory and memory that is shared copy-on-write read-only to notice the first
+        // write
+        let perm = if self.is_clean() || self.is_cow() {
+            perm & !Perm::W
+        }
+        else {
+            perm
+        };
+
         if let Some(ref mem) = self.mem {
             syscalls::create_map(
                 (self.virt() >> cfg::PAGE_BITS as goff) as Selector,
@@ -250,19 +582,32 @@ impl Region {
                 perm,
             )?;
             self.flags.insert(RegionFlags::MAPPED);
+
+            // remember where we mapped it to, so that we know which parts the kernel mapped with
+            // large pages. smaller regions cannot use large pages; no need to ask in this case.
+            self.map_phys = if self.size >= cfg::LPAGE_SIZE as goff {
+                match mem.borrow_mut().phys() {
+                    Ok(phys) => Some(phys + self.mem_off),
+                    Err(e) => {
+                        log!(
+                            crate::LOG_DEF,
+                            "Unable to get physical address of {:#x}: {:?}",
+                            self.virt(),
+                            e
+                        );
+                        None
+                    },
+                }
+            }
+            else {
+                None
+            };
         }
 
         Ok(())
     }
 
-    pub fn kill(&mut self) {
-        // don't revoke the mapping caps, if the address space got destroyed
-        self.flags.remove(RegionFlags::MAPPED);
-    }
-}
-
-impl Drop for Region {
-    fn drop(&mut self) {
+    pub fn unmap(&mut self) {
         if self.mem.is_some() && self.flags.contains(RegionFlags::MAPPED) {
             syscalls::revoke(
                 self.owner,
@@ -274,8 +619,22 @@ impl Drop for Region {
                 true,
             )
             .ok();
+            self.flags.remove(RegionFlags::MAPPED);
+            self.map_phys = None;
         }
     }
+
+    pub fn kill(&mut self) {
+        // don't revoke the mapping caps, if the address space got destroyed
+        self.flags.remove(RegionFlags::MAPPED);
+        self.map_phys = None;
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        self.unmap();
+    }
 }
 
 impl fmt::Debug for Region {
@@ -315,7 +674,24 @@ impl RegionList {
         self.regs.is_empty()
     }
 
-    pub fn clone(&mut self, rl: &mut RegionList, ds_perms: Perm) -> Result<(), Error> {
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regs.iter().map(|r| r.as_ref())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Region> {
+        self.regs.iter_mut().map(|r| r.as_mut())
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> &mut Region {
+        &mut self.regs[idx]
+    }
+
+    pub fn clone(
+        &mut self,
+        rl: &mut RegionList,
+        ds_perms: Perm,
+        ds_prot: Perm,
+    ) -> Result<(), Error> {
         // for the case that we already have regions and the DS is writable, just remove them.
         // because there is no point in trying to keep them:
         // 1. we have already our own copy
@@ -328,8 +704,8 @@ impl RegionList {
 
         for r in &mut rl.regs {
             // make it readonly, if it's writable and we have not done that yet
-            if !r.is_cow() && ds_perms.contains(Perm::W) {
-                r.map(ds_perms ^ Perm::W)?;
+            if !r.is_cow() && ds_prot.contains(Perm::W) {
+                r.map(ds_prot ^ Perm::W)?;
             }
 
             let mut nreg = Box::new(r.clone_for(self.owner));
@@ -361,6 +737,42 @@ impl RegionList {
         self.regs.push(r);
     }
 
+    /// Splits the list at `off` and returns the list for the second part, starting at `ds_off`
+    pub fn split(&mut self, off: goff, ds_off: goff) -> RegionList {
+        // split the region that spans over `off`, if there is any
+        if let Some(idx) = self
+            .regs
+            .iter()
+            .position(|r| off > r.off && off < r.off + r.size)
+        {
+            let nreg = self.regs[idx].split(off - self.regs[idx].off);
+            self.regs.insert(idx + 1, Box::new(nreg));
+        }
+
+        let mut nlist = RegionList::new(self.owner, self.child, ds_off, self.size - off);
+        let first = self
+            .regs
+            .iter()
+            .position(|r| r.off >= off)
+            .unwrap_or(self.regs.len());
+        for mut r in self.regs.drain(first..) {
+            r.ds_off = ds_off;
+            r.off -= off;
+            nlist.regs.push(r);
+        }
+        self.size = off;
+        nlist
+    }
+
+    /// Removes all regions and gives their memory back (see `Region::free`)
+    pub fn free(&mut self) -> Result<(), Error> {
+        for r in &mut self.regs {
+            r.free()?;
+        }
+        self.regs.clear();
+        Ok(())
+    }
+
     pub fn pagefault(&mut self, off: goff) -> &mut Region {
         let idx = self.do_pagefault(off);
         &mut self.regs[idx]
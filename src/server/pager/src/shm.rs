@@ -0,0 +1,206 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use core::cmp;
+use m3::cap::Selector;
+use m3::cell::{RefCell, StaticCell, StaticRefCell};
+use m3::cfg;
+use m3::col::{String, ToString, Vec};
+use m3::com::MemGate;
+use m3::errors::{Code, Error};
+use m3::goff;
+use m3::kif::Perm;
+use m3::log;
+use m3::math;
+use m3::rc::{Rc, Weak};
+use m3::session::ShmFlags;
+use m3::vec;
+use resmng::childs::ChildMem;
+use resmng::memory::Allocation;
+
+use crate::physmem::{clear_block, copy_block};
+
+/// The number of pages that are allocated at once for a segment
+pub const CHUNK_PAGES: usize = 8;
+const CHUNK_SIZE: goff = (CHUNK_PAGES * cfg::PAGE_SIZE) as goff;
+
+static NEXT_ID: StaticCell<u64> = StaticCell::new(0);
+// the segments are owned by the address spaces that opened or mapped them
+static SEGMENTS: StaticRefCell<Vec<Weak<RefCell<Segment>>>> = StaticRefCell::new(Vec::new());
+
+struct Chunk {
+    mgate: MemGate,
+    alloc: Allocation,
+}
+
+/// A named shared-memory segment
+///
+/// The memory is allocated in chunks on the first access and accounted to the memory of the child
+/// that created the segment. The segment owns its memory, so that it stays valid until the segment
+/// is destroyed, even if the creator exits in the meantime.
+pub struct Segment {
+    id: u64,
+    name: String,
+    size: goff,
+    mem: Rc<ChildMem>,
+    chunks: Vec<Option<Chunk>>,
+}
+
+impl Segment {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn size(&self) -> goff {
+        self.size
+    }
+
+    /// Changes the size of the segment to `size` bytes
+    ///
+    /// Memory behind the new end is freed, which revokes all mappings of it. New memory is
+    /// allocated on demand.
+    pub fn resize(&mut self, size: goff) -> Result<(), Error> {
+        let count = (math::round_up(size, CHUNK_SIZE) / CHUNK_SIZE) as usize;
+
+        if size < self.size {
+            for c in self.chunks.drain(count..).flatten() {
+                self.mem.free_detached(c.mgate, c.alloc);
+            }
+
+            // the last chunk might be partially behind the new end. replace it with a copy of the
+            // remaining content to revoke the mappings of the rest as well.
+            let rem = size % CHUNK_SIZE;
+            if rem != 0 && self.chunks[count - 1].is_some() {
+                let mut nchunk = alloc_chunk(&self.mem)?;
+                let old = self.chunks[count - 1].as_mut().unwrap();
+                let full = math::round_dn(rem, cfg::PAGE_SIZE as goff);
+                copy_block(&old.mgate, &nchunk.mgate, 0, full);
+                if full != rem {
+                    let mut buf = vec![0u8; (rem - full) as usize];
+                    old.mgate.read(&mut buf, full)?;
+                    nchunk.mgate.write(&buf, full)?;
+                }
+
+                // see Region::copy_from
+                nchunk.mgate.deactivate();
+                let old = self.chunks[count - 1].replace(nchunk).unwrap();
+                self.mem.free_detached(old.mgate, old.alloc);
+            }
+        }
+        else {
+            self.chunks.resize_with(count, || None);
+        }
+
+        log!(
+            crate::LOG_DEF,
+            "Resized segment '{}' from {:#x} to {:#x}",
+            self.name,
+            self.size,
+            size
+        );
+        self.size = size;
+        Ok(())
+    }
+
+    /// Returns the memory for the offset `off` within the segment as the offset within the memory
+    /// capability, the size of the capability, and its selector
+    ///
+    /// If the memory does not exist yet, it is allocated.
+    pub fn get_mem(&mut self, off: goff) -> Result<(goff, goff, Selector), Error> {
+        let end = math::round_up(self.size, cfg::PAGE_SIZE as goff);
+        if off >= end {
+            return Err(Error::new(Code::OutOfBounds));
+        }
+
+        let idx = (off / CHUNK_SIZE) as usize;
+        let begin = idx as goff * CHUNK_SIZE;
+        if self.chunks[idx].is_none() {
+            log!(
+                crate::LOG_DEF,
+                "Allocating memory for segment '{}' at {:#x}..{:#x}",
+                self.name,
+                begin,
+                begin + CHUNK_SIZE - 1
+            );
+            self.chunks[idx] = Some(alloc_chunk(&self.mem)?);
+        }
+
+        let sel = self.chunks[idx].as_ref().unwrap().mgate.sel();
+        Ok((off - begin, cmp::min(CHUNK_SIZE, end - begin), sel))
+    }
+}
+
+impl Drop for Segment {
+    fn drop(&mut self) {
+        log!(crate::LOG_DEF, "Destroying segment '{}'", self.name);
+        for c in self.chunks.drain(..).flatten() {
+            self.mem.free_detached(c.mgate, c.alloc);
+        }
+    }
+}
+
+fn alloc_chunk(mem: &ChildMem) -> Result<Chunk, Error> {
+    let (mut mgate, alloc) = mem.alloc_detached(CHUNK_SIZE, Perm::RWX)?;
+    clear_block(&mgate, CHUNK_SIZE);
+    // see Region::copy_from
+    mgate.deactivate();
+    Ok(Chunk { mgate, alloc })
+}
+
+/// Opens the segment with given name or creates it, depending on `flags`
+///
+/// A new segment is charged to `mem`.
+pub fn open(
+    name: &str,
+    flags: ShmFlags,
+    mem: &Rc<ChildMem>,
+) -> Result<Rc<RefCell<Segment>>, Error> {
+    let mut segs = SEGMENTS.borrow_mut();
+    // forget the segments that are not used anymore
+    segs.retain(|s| s.strong_count() > 0);
+
+    if let Some(seg) = segs
+        .iter()
+        .filter_map(|s| s.upgrade())
+        .find(|s| s.borrow().name() == name)
+    {
+        if flags.contains(ShmFlags::CREATE | ShmFlags::EXCL) {
+            return Err(Error::new(Code::Exists));
+        }
+        return Ok(seg);
+    }
+
+    if !flags.contains(ShmFlags::CREATE) {
+        return Err(Error::new(Code::NoSuchFile));
+    }
+
+    let id = NEXT_ID.get();
+    NEXT_ID.set(id + 1);
+
+    log!(crate::LOG_DEF, "Creating segment '{}' with id {}", name, id);
+    let seg = Rc::new(RefCell::new(Segment {
+        id,
+        name: name.to_string(),
+        size: 0,
+        mem: mem.clone(),
+        chunks: Vec::new(),
+    }));
+    segs.push(Rc::downgrade(&seg));
+    Ok(seg)
+}
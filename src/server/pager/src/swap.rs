@@ -0,0 +1,139 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! The swap space for anonymous memory, which is a file in one of our file systems
+
+use core::fmt;
+
+use m3::cell::LazyStaticRefCell;
+use m3::cfg;
+use m3::com::MemGate;
+use m3::errors::Error;
+use m3::goff;
+use m3::io::{Read, Write};
+use m3::log;
+use m3::mem::MemMap;
+use m3::session::SwapStats;
+use m3::vfs::{FileRef, GenericFile, OpenFlags, Seek, SeekMode, VFS};
+
+use crate::physmem::{read_block, write_block};
+
+static SWAP: LazyStaticRefCell<SwapSpace> = LazyStaticRefCell::default();
+
+struct SwapSpace {
+    file: FileRef<GenericFile>,
+    slots: MemMap,
+    stats: SwapStats,
+}
+
+/// A contiguous range of pages in the swap file, which is free'd on drop
+pub struct SwapSlot {
+    off: goff,
+    size: goff,
+}
+
+impl Drop for SwapSlot {
+    fn drop(&mut self) {
+        SWAP.borrow_mut().slots.free(self.off, self.size);
+    }
+}
+
+impl fmt::Debug for SwapSlot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "SwapSlot[{:#x}..{:#x}]",
+            self.off,
+            self.off + self.size - 1
+        )
+    }
+}
+
+/// Creates the swap file at given path with room for `size` bytes
+pub fn init(path: &str, size: goff) -> Result<(), Error> {
+    let file = VFS::open(path, OpenFlags::RW | OpenFlags::CREATE | OpenFlags::TRUNC)?;
+    log!(
+        crate::LOG_SWAP,
+        "Using {} with {} KiB as swap space",
+        path,
+        size / 1024
+    );
+    SWAP.set(SwapSpace {
+        file,
+        slots: MemMap::new(0, size),
+        stats: SwapStats::default(),
+    });
+    Ok(())
+}
+
+/// Returns true if swapping is enabled
+pub fn enabled() -> bool {
+    SWAP.is_some()
+}
+
+/// Returns the statistics about swapping
+pub fn stats() -> SwapStats {
+    SWAP.borrow().stats
+}
+
+/// Counts a region that has been spared during eviction because it was referenced
+pub fn count_second_chance() {
+    SWAP.borrow_mut().stats.second_chances += 1;
+}
+
+/// Allocates a slot for `size` bytes in the swap file
+pub fn alloc(size: goff) -> Result<SwapSlot, Error> {
+    let off = SWAP
+        .borrow_mut()
+        .slots
+        .allocate(size, cfg::PAGE_SIZE as goff)?;
+    Ok(SwapSlot { off, size })
+}
+
+/// Writes `size` bytes of the memory `mem` at `mem_off` to the given slot at `off`, or only counts
+/// them if they are `clean`
+pub fn write(
+    slot: &SwapSlot,
+    off: goff,
+    size: goff,
+    mem: &MemGate,
+    mem_off: goff,
+    clean: bool,
+) -> Result<(), Error> {
+    assert!(off + size <= slot.size);
+    let mut swap = SWAP.borrow_mut();
+    let pages = size / cfg::PAGE_SIZE as goff;
+    swap.stats.swap_outs += 1;
+    if clean {
+        swap.stats.pages_clean += pages;
+        return Ok(());
+    }
+
+    swap.file.seek((slot.off + off) as usize, SeekMode::Set)?;
+    read_block(mem, mem_off, size, |buf| swap.file.write_all(buf))?;
+    swap.stats.pages_out += pages;
+    Ok(())
+}
+
+/// Reads `size` bytes from the given slot at `off` into the memory `mem`
+pub fn read(slot: &SwapSlot, off: goff, size: goff, mem: &MemGate) -> Result<(), Error> {
+    assert!(off + size <= slot.size);
+    let mut swap = SWAP.borrow_mut();
+    swap.file.seek((slot.off + off) as usize, SeekMode::Set)?;
+    write_block(mem, size, |buf| swap.file.read_exact(buf))?;
+    swap.stats.swap_ins += 1;
+    swap.stats.pages_in += size / cfg::PAGE_SIZE as goff;
+    Ok(())
+}
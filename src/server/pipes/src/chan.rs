@@ -18,12 +18,14 @@ use m3::cap::Selector;
 use m3::cell::{Cell, RefCell};
 use m3::com::{GateIStream, MemGate, RecvGate, SGateArgs, SendGate};
 use m3::errors::{Code, Error};
+use m3::goff;
 use m3::kif;
 use m3::log;
 use m3::rc::Rc;
 use m3::reply_vmsg;
 use m3::server::SessId;
 use m3::tcu::Label;
+use m3::tiles::Activity;
 use m3::vfs::{FileEvent, FileInfo, FileMode};
 
 use crate::pipe::{Flags, State};
@@ -32,16 +34,22 @@ use crate::pipe::{Flags, State};
 pub enum ChanType {
     READ,
     WRITE,
+    /// One of the two ends of a duplex pipe
+    DUPLEX(usize),
 }
 
 pub struct Channel {
     ty: ChanType,
     id: SessId,
     pipe: SessId,
-    state: Rc<RefCell<State>>,
+    // the state of the direction we read from and write to, respectively
+    rstate: Option<Rc<RefCell<State>>>,
+    wstate: Option<Rc<RefCell<State>>>,
     mem: Option<MemGate>,
     sgate: SendGate,
     ep_cap: Option<Selector>,
+    // the EP that is configured by other file servers for splicing
+    splice_mem: Option<MemGate>,
     promised_events: Rc<Cell<FileEvent>>,
 }
 
@@ -51,7 +59,8 @@ impl Channel {
         sel: Selector,
         ty: ChanType,
         pipe: SessId,
-        state: Rc<RefCell<State>>,
+        rstate: Option<Rc<RefCell<State>>>,
+        wstate: Option<Rc<RefCell<State>>>,
         rgate: &RecvGate,
     ) -> Result<Self, Error> {
         let sgate = SendGate::new_with(
@@ -64,10 +73,12 @@ impl Channel {
             ty,
             id,
             pipe,
-            state,
+            rstate,
+            wstate,
             mem: None,
             sgate,
             ep_cap: None,
+            splice_mem: None,
             promised_events: Rc::new(Cell::from(FileEvent::empty())),
         })
     }
@@ -76,32 +87,55 @@ impl Channel {
         self.id
     }
 
-    pub fn ty(&self) -> ChanType {
-        self.ty
-    }
-
     pub fn pipe(&self) -> SessId {
         self.pipe
     }
 
+    pub fn read_state(&self) -> Option<&Rc<RefCell<State>>> {
+        self.rstate.as_ref()
+    }
+
+    pub fn write_state(&self) -> Option<&Rc<RefCell<State>>> {
+        self.wstate.as_ref()
+    }
+
     pub fn crd(&self) -> kif::CapRngDesc {
         kif::CapRngDesc::new(kif::CapType::OBJECT, self.sgate.sel() - 1, 2)
     }
 
     pub fn clone(&self, id: SessId, sel: Selector, rgate: &RecvGate) -> Result<Channel, Error> {
-        Channel::new(id, sel, self.ty, self.pipe, self.state.clone(), rgate)
+        Channel::new(
+            id,
+            sel,
+            self.ty,
+            self.pipe,
+            self.rstate.clone(),
+            self.wstate.clone(),
+            rgate,
+        )
     }
 
     pub fn set_ep(&mut self, ep: Selector) {
         self.ep_cap = Some(ep);
     }
 
+    /// Returns the state of the only direction of this channel
+    ///
+    /// Notifications are not supported for duplex channels, because they cover two directions.
+    fn single_state(&self) -> Result<&Rc<RefCell<State>>, Error> {
+        match (&self.rstate, &self.wstate) {
+            (Some(s), None) | (None, Some(s)) => Ok(s),
+            _ => Err(Error::new(Code::NotSup)),
+        }
+    }
+
     pub fn enable_notify(&mut self, sgate: Selector) -> Result<(), Error> {
-        if self.state.borrow_mut().get_notify_gate(self.id).is_some() {
+        let state = self.single_state()?;
+        if state.borrow_mut().get_notify_gate(self.id).is_some() {
             return Err(Error::new(Code::Exists));
         }
 
-        self.state
+        state
             .borrow_mut()
             .enable_notify(self.id, sgate, self.promised_events.clone())
     }
@@ -117,7 +151,9 @@ impl Channel {
             events
         );
 
-        self.state.borrow_mut().request_notify(self.id, events)?;
+        self.single_state()?
+            .borrow_mut()
+            .request_notify(self.id, events)?;
 
         is.reply_error(Code::None)
     }
@@ -127,12 +163,14 @@ impl Channel {
 
         log!(crate::LOG_DEF, "[{}] pipes::next_in()", self.id);
 
-        let res = match self.ty {
-            ChanType::READ => self.read(is, 0),
-            ChanType::WRITE => Err(Error::new(Code::InvArgs)),
+        let res = match self.rstate {
+            Some(_) => self.read(is, 0),
+            None => Err(Error::new(Code::InvArgs)),
         };
 
-        self.state.borrow_mut().handle_pending_writes(is.rgate());
+        if let Some(ref s) = self.rstate {
+            s.borrow_mut().handle_pending_writes(is.rgate());
+        }
         res
     }
 
@@ -141,12 +179,30 @@ impl Channel {
 
         log!(crate::LOG_DEF, "[{}] pipes::next_out()", self.id);
 
-        let res = match self.ty {
-            ChanType::READ => Err(Error::new(Code::InvArgs)),
-            ChanType::WRITE => self.write(is, 0),
+        let res = match self.wstate {
+            Some(ref s) => {
+                // in packet mode, the client tells us the packet size to check whether it fits
+                let too_large = if s.borrow().is_packet() {
+                    let size: usize = is.pop()?;
+                    size > s.borrow().get_write_size()
+                }
+                else {
+                    false
+                };
+
+                if too_large {
+                    Err(Error::new(Code::InvArgs))
+                }
+                else {
+                    self.write(is, 0)
+                }
+            },
+            None => Err(Error::new(Code::InvArgs)),
         };
 
-        self.state.borrow_mut().handle_pending_reads(is.rgate());
+        if let Some(ref s) = self.wstate {
+            s.borrow_mut().handle_pending_reads(is.rgate());
+        }
         res
     }
 
@@ -161,15 +217,63 @@ impl Channel {
             nbytes
         );
 
-        let res = match self.ty {
-            ChanType::READ => self.read(is, nbytes),
-            ChanType::WRITE => self.write(is, nbytes),
+        let res = if self.is_writing() {
+            self.write(is, nbytes)
+        }
+        else {
+            self.read(is, nbytes)
         };
 
         self.handle_pending(is.rgate());
         res
     }
 
+    /// Returns the selector of the EP that is used to access the memory of other files when
+    /// splicing, allocating it on first use
+    pub fn splice_ep(&mut self) -> Result<Selector, Error> {
+        if self.splice_mem.is_none() {
+            let mgate = MemGate::new_bind(kif::INVALID_SEL);
+            mgate.activate()?;
+            self.splice_mem = Some(mgate);
+        }
+        Ok(self.splice_mem.as_ref().unwrap().ep().unwrap().sel())
+    }
+
+    pub fn splice(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _: usize = is.pop()?;
+        let to_pipe: bool = is.pop()?;
+        let pos: usize = is.pop()?;
+        let other_off: goff = is.pop()?;
+        let amount: usize = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] pipes::splice(to_pipe={}, pos={:#x}, other_off={:#x}, amount={:#x})",
+            self.id,
+            to_pipe,
+            pos,
+            other_off,
+            amount
+        );
+
+        let other = self
+            .splice_mem
+            .as_ref()
+            .ok_or_else(|| Error::new(Code::InvArgs))?;
+        let state = if to_pipe {
+            self.wstate.as_ref()
+        }
+        else {
+            self.rstate.as_ref()
+        };
+        let state = state.ok_or_else(|| Error::new(Code::InvArgs))?;
+        state
+            .borrow()
+            .splice(other, to_pipe, pos, other_off, amount)?;
+
+        is.reply_error(Code::None)
+    }
+
     pub fn stat(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
         let info = FileInfo {
             mode: FileMode::IFCHR | FileMode::IRUSR | FileMode::IWUSR,
@@ -182,19 +286,42 @@ impl Channel {
     }
 
     pub fn close(&mut self, _sids: &mut [SessId], rgate: &RecvGate) -> Result<(), Error> {
-        let res = match self.ty {
-            ChanType::READ => self.close_reader(),
-            ChanType::WRITE => self.close_writer(),
-        };
+        let mut res = Ok(());
+        if self.rstate.is_some() {
+            res = self.close_reader();
+        }
+        if self.wstate.is_some() {
+            res = res.and(self.close_writer());
+        }
+
+        // revoke the access to the memory of other files
+        if let Some(ep) = self.splice_mem.as_ref().and_then(|m| m.ep()) {
+            let crd = kif::CapRngDesc::new(kif::CapType::OBJECT, ep.sel(), 1);
+            Activity::own().revoke(crd, true).ok();
+        }
 
         self.handle_pending(rgate);
         res
     }
 
+    /// Returns true if the commit of this channel refers to a write
+    ///
+    /// Duplex channels can have a read and a write in progress. Since clients in packet mode commit
+    /// their writes right away, the write takes precedence.
+    fn is_writing(&self) -> bool {
+        match (&self.rstate, &self.wstate) {
+            (None, Some(_)) => true,
+            (Some(_), Some(s)) => matches!(s.borrow().last_write, Some((id, _)) if id == self.id),
+            _ => false,
+        }
+    }
+
     fn handle_pending(&mut self, rgate: &RecvGate) {
-        match self.ty {
-            ChanType::READ => self.state.borrow_mut().handle_pending_writes(rgate),
-            ChanType::WRITE => self.state.borrow_mut().handle_pending_reads(rgate),
+        if let Some(ref s) = self.rstate {
+            s.borrow_mut().handle_pending_writes(rgate);
+        }
+        if let Some(ref s) = self.wstate {
+            s.borrow_mut().handle_pending_reads(rgate);
         }
     }
 
@@ -202,7 +329,7 @@ impl Channel {
         self.activate()?;
 
         // if a read is in progress, we have to commit it
-        let mut state = self.state.borrow_mut();
+        let mut state = self.rstate.as_ref().unwrap().borrow_mut();
         if let Some((last_id, last_amount)) = state.last_read {
             // if that wasn't the same client, queue the read request
             if last_id != self.id {
@@ -217,7 +344,7 @@ impl Channel {
             // this client is the current reader, so commit the read by pulling it from the ringbuf
             let amount = if commit == 0 { last_amount } else { commit };
             log!(crate::LOG_DEF, "[{}] pipes::read_pull({})", self.id, amount);
-            state.rbuf.pull(amount);
+            state.pull(amount);
             state.last_read = None;
         }
 
@@ -250,7 +377,7 @@ impl Channel {
                 amount,
                 pos
             );
-            reply_vmsg!(is, Code::None as u32, pos, amount)
+            reply_vmsg!(is, Code::None as u32, state.mem_pos(pos), amount)
         }
         else {
             // nothing to read; if there is no writer left, report EOF
@@ -276,7 +403,7 @@ impl Channel {
         self.activate()?;
 
         // if there are no readers left, report EOF
-        let mut state = self.state.borrow_mut();
+        let mut state = self.wstate.as_ref().unwrap().borrow_mut();
         if state.flags().contains(Flags::READ_EOF) {
             log!(crate::LOG_DEF, "[{}] pipes::write(): EOF", self.id);
             return is.reply_error(Code::EndOfFile);
@@ -302,7 +429,7 @@ impl Channel {
                 self.id,
                 amount
             );
-            state.rbuf.push(last_amount, amount);
+            state.push(last_amount, amount);
             state.last_write = None;
         }
 
@@ -332,7 +459,7 @@ impl Channel {
                 amount,
                 pos
             );
-            reply_vmsg!(is, Code::None as u32, pos, amount)
+            reply_vmsg!(is, Code::None as u32, state.mem_pos(pos), amount)
         }
         else {
             // if we promised the client that input would be available, report WouldBlock
@@ -348,7 +475,7 @@ impl Channel {
     }
 
     fn close_reader(&mut self) -> Result<(), Error> {
-        let mut state = self.state.borrow_mut();
+        let mut state = self.rstate.as_ref().unwrap().borrow_mut();
         state.remove_pending(true, self.id);
 
         // if we're already at read-EOF, there is something wrong
@@ -386,7 +513,7 @@ impl Channel {
     }
 
     fn close_writer(&mut self) -> Result<(), Error> {
-        let mut state = self.state.borrow_mut();
+        let mut state = self.wstate.as_ref().unwrap().borrow_mut();
         state.remove_pending(false, self.id);
 
         // if we're already at write-EOF, there is something wrong
@@ -399,7 +526,7 @@ impl Channel {
             // push it to the ring buffer, if it's this client's read
             if last_id == self.id {
                 log!(crate::LOG_DEF, "[{}] pipes::write_push(): 0", self.id);
-                state.rbuf.push(last_amount, 0);
+                state.push(last_amount, 0);
                 state.last_write = None;
             }
             // otherwise, we ignore it because the client violated the protocol
@@ -425,9 +552,10 @@ impl Channel {
 
     fn activate(&mut self) -> Result<(), Error> {
         // did we get an EP cap from the client?
+        // the client might have switched to a different EP (e.g., for splicing)
         if let Some(ep_sel) = self.ep_cap.take() {
-            assert!(self.mem.is_none());
-            self.mem = Some(self.state.borrow().get_mem(self.id, self.ty, ep_sel)?);
+            let state = self.rstate.as_ref().or(self.wstate.as_ref()).unwrap();
+            self.mem = Some(state.borrow().get_mem(self.id, self.ty, ep_sel)?);
         }
         Ok(())
     }
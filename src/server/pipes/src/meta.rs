@@ -18,24 +18,38 @@ use m3::col::Vec;
 use m3::com::RecvGate;
 use m3::errors::Error;
 use m3::server::SessId;
+use m3::session::PipeFlags;
 
 use crate::pipe::Pipe;
 
-#[derive(Default)]
 pub struct Meta {
     pipes: Vec<SessId>,
+    // whether the session belongs to a file system, which can authorize opens of named pipes
+    fs: bool,
 }
 
 impl Meta {
+    pub fn new(fs: bool) -> Self {
+        Meta {
+            pipes: Vec::new(),
+            fs,
+        }
+    }
+
+    pub fn is_fs(&self) -> bool {
+        self.fs
+    }
+
     pub fn create_pipe(
         &mut self,
         sel: Selector,
         sid: SessId,
         mem_size: usize,
+        flags: PipeFlags,
         rgate: &RecvGate,
     ) -> Result<Pipe, Error> {
         self.pipes.push(sid);
-        Pipe::new(sel, sid, mem_size, rgate)
+        Pipe::new(sel, sid, mem_size, flags, rgate)
     }
 
     pub fn close(&mut self, sids: &mut Vec<SessId>) -> Result<(), Error> {
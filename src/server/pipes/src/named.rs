@@ -0,0 +1,135 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use m3::col::{Vec, VecDeque};
+use m3::com::MemGate;
+use m3::errors::{Code, Error};
+use m3::server::SessId;
+use m3::time::TimeInstant;
+use m3::vfs::INodeId;
+
+/// The size of the shared memory of named pipes
+pub const NAMED_PIPE_SIZE: usize = 0x10000;
+/// The maximum number of named pipes that exist at the same time
+const MAX_NAMED_PIPES: usize = 16;
+/// The maximum number of authorized opens that have not been performed yet
+const MAX_PENDING_OPENS: usize = 16;
+
+/// A pipe that belongs to a FIFO in a file system, identified by the session of the file system
+/// and the inode number
+struct NamedPipe {
+    fs: SessId,
+    ino: INodeId,
+    pipe: SessId,
+    // the memory is owned by us, because there is no client that provides it
+    _mem: MemGate,
+}
+
+/// An open of a named pipe that has been authorized by a file system
+#[derive(Copy, Clone, Debug)]
+pub struct AuthOpen {
+    pub fs: SessId,
+    pub ino: INodeId,
+    pub read: bool,
+    token: u64,
+}
+
+#[derive(Default)]
+pub struct NamedPipes {
+    pipes: Vec<NamedPipe>,
+    opens: VecDeque<AuthOpen>,
+    // the state for the generation of tokens
+    seed: u64,
+}
+
+impl NamedPipes {
+    /// Returns the session id of the pipe for the FIFO `ino` of the file system `fs`
+    pub fn get(&self, fs: SessId, ino: INodeId) -> Option<SessId> {
+        self.pipes
+            .iter()
+            .find(|p| p.fs == fs && p.ino == ino)
+            .map(|p| p.pipe)
+    }
+
+    pub fn contains(&self, pipe: SessId) -> bool {
+        self.pipes.iter().any(|p| p.pipe == pipe)
+    }
+
+    /// Returns true if another named pipe can be added
+    pub fn can_add(&self) -> bool {
+        self.pipes.len() < MAX_NAMED_PIPES
+    }
+
+    pub fn add(&mut self, fs: SessId, ino: INodeId, pipe: SessId, mem: MemGate) {
+        assert!(self.can_add());
+        self.pipes.push(NamedPipe {
+            fs,
+            ino,
+            pipe,
+            _mem: mem,
+        });
+    }
+
+    /// Removes the pipe with given session id, if it is a named pipe, which frees its memory
+    pub fn remove(&mut self, pipe: SessId) {
+        self.pipes.retain(|p| p.pipe != pipe);
+    }
+
+    /// Authorizes one open of the FIFO `ino` of the file system `fs` and returns the token for it
+    ///
+    /// If too many opens are outstanding, the oldest one is dropped.
+    pub fn authorize(&mut self, fs: SessId, ino: INodeId, read: bool) -> u64 {
+        if self.opens.len() == MAX_PENDING_OPENS {
+            self.opens.pop_front();
+        }
+
+        let token = loop {
+            let token = self.next_token();
+            if !self.opens.iter().any(|o| o.token == token) {
+                break token;
+            }
+        };
+        self.opens.push_back(AuthOpen {
+            fs,
+            ino,
+            read,
+            token,
+        });
+        token
+    }
+
+    fn next_token(&mut self) -> u64 {
+        if self.seed == 0 {
+            self.seed = TimeInstant::now().as_nanos();
+        }
+
+        // splitmix64
+        self.seed = self.seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Takes the authorized open with given token, which can be used only once
+    pub fn take_auth(&mut self, token: u64, read: bool) -> Result<AuthOpen, Error> {
+        let idx = self
+            .opens
+            .iter()
+            .position(|o| o.token == token && o.read == read)
+            .ok_or_else(|| Error::new(Code::NoPerm))?;
+        Ok(self.opens.remove(idx).unwrap())
+    }
+}
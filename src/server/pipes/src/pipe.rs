@@ -14,21 +14,28 @@
  */
 
 use bitflags::bitflags;
+use core::cmp;
 use m3::cap::Selector;
 use m3::cell::{Cell, RefCell};
-use m3::col::{VarRingBuf, Vec};
+use m3::col::{VarRingBuf, Vec, VecDeque};
 use m3::com::{GateIStream, MemGate, RGateArgs, RecvGate, SGateArgs, SendGate, EP};
 use m3::errors::{Code, Error};
+use m3::goff;
 use m3::kif;
 use m3::log;
 use m3::rc::Rc;
 use m3::send_vmsg;
 use m3::server::SessId;
+use m3::session::PipeFlags;
 use m3::tcu::{Label, Message};
+use m3::vec;
 use m3::vfs::FileEvent;
 
 use crate::chan::{ChanType, Channel};
 
+/// The size of the buffer that is used to copy the data when splicing
+const SPLICE_BUF_SIZE: usize = 4096;
+
 macro_rules! reply_vmsg_late {
     ( $rgate:expr, $msg:expr, $( $args:expr ),* ) => ({
         let mut msg = m3::mem::MsgBuf::borrow_def();
@@ -103,7 +110,11 @@ pub struct State {
     flags: Flags,
     mem: Option<MemGate>,
     mem_size: usize,
+    // the offset of the ring buffer within the memory
+    mem_off: usize,
     pub rbuf: VarRingBuf,
+    // the sizes of the packets in the ring buffer (only in packet mode)
+    packets: Option<VecDeque<usize>>,
     pub last_read: Option<(SessId, usize)>,
     pub last_write: Option<(SessId, usize)>,
     pending_reads: Vec<PendingRequest>,
@@ -114,12 +125,14 @@ pub struct State {
 }
 
 impl State {
-    fn new(mem_size: usize) -> Self {
+    fn new(mem_size: usize, mem_off: usize, packet: bool) -> Self {
         State {
             flags: Flags::empty(),
             mem: None,
             mem_size,
+            mem_off,
             rbuf: VarRingBuf::new(mem_size),
+            packets: if packet { Some(VecDeque::new()) } else { None },
             last_read: None,
             last_write: None,
             pending_reads: Vec::new(),
@@ -146,9 +159,17 @@ impl State {
         !self.pending_writes.is_empty()
     }
 
+    pub fn is_packet(&self) -> bool {
+        self.packets.is_some()
+    }
+
     pub fn get_read_size(&self) -> usize {
         assert!(!self.reader.is_empty());
-        self.rbuf.size() / (4 * self.reader.len())
+        match self.packets {
+            // readers always get exactly one packet
+            Some(ref p) => p.front().copied().unwrap_or(0),
+            None => self.rbuf.size() / (4 * self.reader.len()),
+        }
     }
 
     pub fn get_write_size(&self) -> usize {
@@ -156,6 +177,72 @@ impl State {
         self.rbuf.size() / (4 * self.writer.len())
     }
 
+    /// Converts the position `pos` within the ring buffer into the position within the memory
+    pub fn mem_pos(&self, pos: usize) -> usize {
+        self.mem_off + pos
+    }
+
+    /// Pushes the `amount` bytes of the current write into the ring buffer
+    ///
+    /// `req_size` is the size of the write position that has been handed out (see
+    /// `VarRingBuf::push`). In packet mode, the bytes form a new packet.
+    pub fn push(&mut self, req_size: usize, amount: usize) {
+        self.rbuf.push(req_size, amount);
+        if let Some(ref mut p) = self.packets {
+            if amount > 0 {
+                p.push_back(amount);
+            }
+        }
+    }
+
+    /// Pulls `amount` bytes of the current read from the ring buffer
+    ///
+    /// In packet mode, the whole packet is pulled, dropping the bytes that have not been read.
+    pub fn pull(&mut self, amount: usize) {
+        match self.packets {
+            Some(ref mut p) => self.rbuf.pull(p.pop_front().unwrap()),
+            None => self.rbuf.pull(amount),
+        }
+    }
+
+    /// Copies `amount` bytes between the memory at `pos` and `other` at `other_off`
+    ///
+    /// If `to_pipe` is true, the data is copied from `other` into the pipe, otherwise from the pipe
+    /// into `other`.
+    pub fn splice(
+        &self,
+        other: &MemGate,
+        to_pipe: bool,
+        pos: usize,
+        other_off: goff,
+        amount: usize,
+    ) -> Result<(), Error> {
+        let mem = match self.mem {
+            Some(ref m) => m,
+            None => return Err(Error::new(Code::InvArgs)),
+        };
+        if pos < self.mem_off || pos + amount > self.mem_off + self.rbuf.size() {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let mut buf = vec![0u8; cmp::min(amount, SPLICE_BUF_SIZE)];
+        let mut done = 0;
+        while done < amount {
+            let count = cmp::min(buf.len(), amount - done);
+            let (pipe_pos, other_pos) = ((pos + done) as goff, other_off + done as goff);
+            if to_pipe {
+                other.read(&mut buf[0..count], other_pos)?;
+                mem.write(&buf[0..count], pipe_pos)?;
+            }
+            else {
+                mem.read(&mut buf[0..count], pipe_pos)?;
+                other.write(&buf[0..count], other_pos)?;
+            }
+            done += count;
+        }
+        Ok(())
+    }
+
     pub fn get_notify_gate(&mut self, sess: SessId) -> Option<&mut NotifyGate> {
         self.notify_gates.iter_mut().find(|n| n.sess == sess)
     }
@@ -223,11 +310,13 @@ impl State {
         // did we get a memory cap from the client?
         if let Some(mem) = &self.mem {
             // derive read-only/write-only mem cap
-            let perm = match ty {
-                ChanType::READ => kif::Perm::R,
-                ChanType::WRITE => kif::Perm::W,
+            let (size, perm) = match ty {
+                ChanType::READ => (self.mem_size, kif::Perm::R),
+                ChanType::WRITE => (self.mem_size, kif::Perm::W),
+                // duplex channels access both directions via the same EP
+                ChanType::DUPLEX(_) => (self.mem_size * 2, kif::Perm::RW),
             };
-            let cmem = mem.derive(0, self.mem_size, perm)?;
+            let cmem = mem.derive(0, size, perm)?;
             // activate it on client's EP
             log!(
                 crate::LOG_DEF,
@@ -278,7 +367,8 @@ impl State {
                     amount,
                     pos
                 );
-                reply_vmsg_late!(rgate, req.msg, Code::None as u32, pos, amount).ok();
+                let mpos = self.mem_pos(pos);
+                reply_vmsg_late!(rgate, req.msg, Code::None as u32, mpos, amount).ok();
 
                 // remove write request
                 self.pending_reads.pop();
@@ -334,7 +424,8 @@ impl State {
                     amount,
                     pos
                 );
-                reply_vmsg_late!(rgate, req.msg, Code::None as u32, pos, amount).ok();
+                let mpos = self.mem_pos(pos);
+                reply_vmsg_late!(rgate, req.msg, Code::None as u32, mpos, amount).ok();
 
                 // remove write request
                 self.pending_writes.pop();
@@ -373,7 +464,8 @@ impl State {
 pub struct Pipe {
     id: SessId,
     _sgate: SendGate,
-    state: Rc<RefCell<State>>,
+    // one state per direction; duplex pipes have two
+    states: Vec<Rc<RefCell<State>>>,
 }
 
 impl Pipe {
@@ -381,6 +473,7 @@ impl Pipe {
         sel: Selector,
         id: SessId,
         mem_size: usize,
+        flags: PipeFlags,
         rgate: &RecvGate,
     ) -> Result<Self, Error> {
         let sgate = SendGate::new_with(
@@ -389,19 +482,39 @@ impl Pipe {
                 .credits(1)
                 .sel(sel + 1),
         )?;
+
+        let states = if flags.contains(PipeFlags::DUPLEX) {
+            // each direction gets one half of the memory
+            let half = mem_size / 2;
+            vec![
+                Rc::new(RefCell::new(State::new(half, 0, true))),
+                Rc::new(RefCell::new(State::new(half, half, true))),
+            ]
+        }
+        else {
+            let packet = flags.contains(PipeFlags::PACKET);
+            vec![Rc::new(RefCell::new(State::new(mem_size, 0, packet)))]
+        };
+
         Ok(Pipe {
             id,
             _sgate: sgate,
-            state: Rc::new(RefCell::new(State::new(mem_size))),
+            states,
         })
     }
 
+    pub fn is_duplex(&self) -> bool {
+        self.states.len() == 2
+    }
+
     pub fn has_mem(&self) -> bool {
-        self.state.borrow().mem.is_some()
+        self.states[0].borrow().mem.is_some()
     }
 
     pub fn set_mem(&mut self, sel: Selector) {
-        self.state.borrow_mut().mem = Some(MemGate::new_bind(sel));
+        for s in &self.states {
+            s.borrow_mut().mem = Some(MemGate::new_bind(sel));
+        }
     }
 
     pub fn new_chan(
@@ -411,19 +524,51 @@ impl Pipe {
         ty: ChanType,
         rgate: &RecvGate,
     ) -> Result<Channel, Error> {
-        Channel::new(sid, sel, ty, self.id, self.state.clone(), rgate)
+        let (rstate, wstate) = match ty {
+            ChanType::READ => (Some(self.states[0].clone()), None),
+            ChanType::WRITE => (None, Some(self.states[0].clone())),
+            // each end reads what the other end writes
+            ChanType::DUPLEX(end) => (
+                Some(self.states[end].clone()),
+                Some(self.states[1 - end].clone()),
+            ),
+        };
+        Channel::new(sid, sel, ty, self.id, rstate, wstate, rgate)
+    }
+
+    /// Returns true if there are no channels attached to this pipe anymore
+    pub fn is_unused(&self) -> bool {
+        let state = self.states[0].borrow();
+        state.reader.is_empty() && state.writer.is_empty()
     }
 
     pub fn attach(&mut self, chan: &Channel) {
         assert!(chan.pipe() == self.id);
-        match chan.ty() {
-            ChanType::READ => self.state.borrow_mut().reader.push(chan.id()),
-            ChanType::WRITE => self.state.borrow_mut().writer.push(chan.id()),
+        if let Some(s) = chan.read_state() {
+            s.borrow_mut().reader.push(chan.id());
+        }
+        if let Some(s) = chan.write_state() {
+            s.borrow_mut().writer.push(chan.id());
+        }
+    }
+
+    /// Attaches the channel of a named pipe, which revokes a previous EOF on its side
+    ///
+    /// In contrast to other pipes, named pipes can be reopened after all readers or writers are
+    /// gone.
+    pub fn reattach(&mut self, chan: &Channel) {
+        if let Some(s) = chan.read_state() {
+            s.borrow_mut().flags.remove(Flags::READ_EOF);
+        }
+        if let Some(s) = chan.write_state() {
+            s.borrow_mut().flags.remove(Flags::WRITE_EOF);
         }
+        self.attach(chan);
     }
 
     pub fn close(&mut self, sids: &mut Vec<SessId>) -> Result<(), Error> {
-        let state = self.state.borrow();
+        // all channels read from or write to the first state
+        let state = self.states[0].borrow();
         sids.extend_from_slice(&state.reader);
         sids.extend_from_slice(&state.writer);
         Ok(())
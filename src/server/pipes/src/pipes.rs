@@ -17,13 +17,14 @@
 
 mod chan;
 mod meta;
+mod named;
 mod pipe;
 mod sess;
 
 use m3::cap::Selector;
 use m3::cell::LazyReadOnlyCell;
 use m3::col::{String, Vec};
-use m3::com::{GateIStream, RecvGate};
+use m3::com::{GateIStream, MemGate, RecvGate};
 use m3::env;
 use m3::errors::{Code, Error};
 use m3::int_enum;
@@ -34,14 +35,16 @@ use m3::server::{
     server_loop, CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer,
     DEF_MAX_CLIENTS, DEF_MSG_SIZE,
 };
-use m3::session::{PipeOperation, ServerSession};
+use m3::session::{PipeFlags, PipeOperation, ServerSession};
 use m3::tcu::Label;
 use m3::tiles::Activity;
 use m3::vec;
-use m3::vfs::GenFileOp;
+use m3::vfs::{GenFileOp, INodeId};
 
 use chan::{ChanType, Channel};
 use meta::Meta;
+use named::{NamedPipes, NAMED_PIPE_SIZE};
+use pipe::Pipe;
 use sess::{PipesSession, SessionData};
 
 pub const LOG_DEF: bool = false;
@@ -66,12 +69,17 @@ int_enum! {
         const OPEN_CHAN     = PipeOperation::OPEN_CHAN.val;
         const SET_MEM       = PipeOperation::SET_MEM.val;
         const CLOSE_PIPE    = PipeOperation::CLOSE_PIPE.val;
+        const OPEN_NAMED    = PipeOperation::OPEN_NAMED.val;
+        const GET_SPLICE_EP = PipeOperation::GET_SPLICE_EP.val;
+        const SPLICE        = PipeOperation::SPLICE.val;
+        const AUTH_NAMED    = PipeOperation::AUTH_NAMED.val;
     }
 }
 
 struct PipesHandler {
     sel: Selector,
     sessions: SessionContainer<PipesSession>,
+    named: NamedPipes,
 }
 
 impl PipesHandler {
@@ -105,15 +113,169 @@ impl PipesHandler {
                     SessionData::Chan(ref mut c) => c.close(&mut sids, rgate),
                 };
 
+                let chan_pipe = match sess.data() {
+                    SessionData::Chan(c) => Some(c.pipe()),
+                    _ => None,
+                };
+
                 let crt = sess.creator();
                 self.sessions.remove(crt, id);
                 // ignore all potentially outstanding messages of this session
                 rgate.drop_msgs_with(id as Label);
+
+                // named pipes are destroyed as soon as the last channel is closed
+                self.named.remove(id);
+                if let Some(pid) = chan_pipe {
+                    if self.named.contains(pid) && self.is_unused_pipe(pid) {
+                        sids.push(pid);
+                    }
+                }
             }
         }
         Ok(())
     }
 
+    fn is_unused_pipe(&self, pid: SessId) -> bool {
+        match self.sessions.get(pid).map(|s| s.data()) {
+            Some(SessionData::Pipe(p)) => p.is_unused(),
+            _ => false,
+        }
+    }
+
+    fn auth_named(&mut self, sid: SessId, xchg: &mut CapExchange<'_>) -> Result<(), Error> {
+        // only file systems can decide who is allowed to open a FIFO
+        match self.sessions.get(sid).unwrap().data() {
+            SessionData::Meta(m) if m.is_fs() => {},
+            _ => return Err(Error::new(Code::NoPerm)),
+        }
+
+        let ino: INodeId = xchg.in_args().pop()?;
+        let read: bool = xchg.in_args().pop()?;
+
+        let token = self.named.authorize(sid, ino, read);
+        log!(
+            crate::LOG_DEF,
+            "[{}] pipes::auth_named(ino={}, read={}) -> {:#x}",
+            sid,
+            ino,
+            read,
+            token
+        );
+
+        xchg.out_args().push(token);
+        Ok(())
+    }
+
+    fn open_named(
+        &mut self,
+        crt: usize,
+        sid: SessId,
+        xchg: &mut CapExchange<'_>,
+    ) -> Result<(), Error> {
+        // named pipes are opened via meta sessions
+        if !matches!(self.sessions.get(sid).unwrap().data(), SessionData::Meta(_)) {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        let token: u64 = xchg.in_args().pop()?;
+        let read: bool = xchg.in_args().pop()?;
+        let rgate = REQHDL.get().recv_gate();
+
+        // the file system has checked the permissions and told us the FIFO
+        let auth = self.named.take_auth(token, read)?;
+
+        // create the pipe on the first open
+        let pid = match self.named.get(auth.fs, auth.ino) {
+            Some(pid) => pid,
+            None => {
+                if !self.named.can_add() {
+                    return Err(Error::new(Code::NoSpace));
+                }
+
+                let pid = self.sessions.next_id()?;
+                let sel = Activity::own().alloc_sels(2);
+                log!(
+                    crate::LOG_DEF,
+                    "[{}] pipes::create_named(sid={}, sel={}, fs={}, ino={})",
+                    sid,
+                    pid,
+                    sel,
+                    auth.fs,
+                    auth.ino
+                );
+
+                let mem = MemGate::new(NAMED_PIPE_SIZE, kif::Perm::RW)?;
+                let mut pipe = Pipe::new(sel, pid, NAMED_PIPE_SIZE, PipeFlags::empty(), rgate)?;
+                pipe.set_mem(mem.sel());
+                let psess = self.new_sub_sess(crt, sel, pid, SessionData::Pipe(pipe))?;
+                self.sessions.add(crt, pid, psess)?;
+                self.named.add(auth.fs, auth.ino, pid, mem);
+
+                // we need another session for the channel
+                if !self.sessions.can_add(crt) {
+                    self.close_sess(pid, rgate)?;
+                    return Err(Error::new(Code::NoSpace));
+                }
+                pid
+            },
+        };
+
+        let nsid = self.sessions.next_id()?;
+        let sel = Activity::own().alloc_sels(2);
+        let ty = if read {
+            ChanType::READ
+        }
+        else {
+            ChanType::WRITE
+        };
+        log!(
+            crate::LOG_DEF,
+            "[{}] pipes::open_named(sid={}, sel={}, pipe={}, ty={:?})",
+            sid,
+            nsid,
+            sel,
+            pid,
+            ty
+        );
+
+        let chan = match self.sessions.get_mut(pid).unwrap().data_mut() {
+            SessionData::Pipe(ref mut p) => {
+                let chan = p.new_chan(nsid, sel, ty, rgate)?;
+                p.reattach(&chan);
+                chan
+            },
+            _ => unreachable!(),
+        };
+        let crd = chan.crd();
+        let nsess = self.new_sub_sess(crt, sel, nsid, SessionData::Chan(chan))?;
+        self.sessions.add(crt, nsid, nsess)?;
+
+        xchg.out_caps(crd);
+
+        Ok(())
+    }
+
+    fn get_splice_ep(&mut self, sid: SessId, xchg: &mut CapExchange<'_>) -> Result<(), Error> {
+        if xchg.in_caps() != 1 {
+            return Err(Error::new(Code::InvArgs));
+        }
+
+        match self.sessions.get_mut(sid).unwrap().data_mut() {
+            SessionData::Chan(ref mut c) => {
+                let sel = c.splice_ep()?;
+                log!(
+                    crate::LOG_DEF,
+                    "[{}] pipes::get_splice_ep() -> {}",
+                    sid,
+                    sel
+                );
+                xchg.out_caps(kif::CapRngDesc::new(kif::CapType::OBJECT, sel, 1));
+                Ok(())
+            },
+            _ => Err(Error::new(Code::InvArgs)),
+        }
+    }
+
     fn with_chan<F, R>(&mut self, is: &mut GateIStream<'_>, func: F) -> Result<R, Error>
     where
         F: Fn(&mut Channel, &mut GateIStream<'_>) -> Result<R, Error>,
@@ -135,14 +297,21 @@ impl Handler<PipesSession> for PipesHandler {
         &mut self,
         crt: usize,
         srv_sel: Selector,
-        _arg: &str,
+        arg: &str,
     ) -> Result<(Selector, SessId), Error> {
+        // the argument is given by the config, so that clients cannot claim to be a file system
+        let fs = arg == "fs";
         self.sessions.add_next(crt, srv_sel, true, |sess| {
-            log!(crate::LOG_DEF, "[{}] pipes::new_meta()", sess.ident());
+            log!(
+                crate::LOG_DEF,
+                "[{}] pipes::new_meta(fs={})",
+                sess.ident(),
+                fs
+            );
             Ok(PipesSession::new(
                 crt,
                 sess,
-                SessionData::Meta(Meta::default()),
+                SessionData::Meta(Meta::new(fs)),
             ))
         })
     }
@@ -157,6 +326,13 @@ impl Handler<PipesSession> for PipesHandler {
             op
         );
 
+        if op == Operation::GET_SPLICE_EP {
+            return self.get_splice_ep(sid, xchg);
+        }
+        if op == Operation::AUTH_NAMED {
+            return self.auth_named(sid, xchg);
+        }
+
         if xchg.in_caps() != 2 {
             return Err(Error::new(Code::InvArgs));
         }
@@ -164,6 +340,10 @@ impl Handler<PipesSession> for PipesHandler {
             return Err(Error::new(Code::NoSpace));
         }
 
+        if op == Operation::OPEN_NAMED {
+            return self.open_named(crt, sid, xchg);
+        }
+
         let res: Result<_, Error> = {
             let nsid = self.sessions.next_id()?;
             let osess = self.sessions.get_mut(sid).unwrap();
@@ -176,15 +356,17 @@ impl Handler<PipesSession> for PipesHandler {
 
                     let sel = Activity::own().alloc_sels(2);
                     let msize: usize = xchg.in_args().pop()?;
+                    let flags = PipeFlags::from_bits_truncate(xchg.in_args().pop()?);
                     log!(
                         crate::LOG_DEF,
-                        "[{}] pipes::open_pipe(sid={}, sel={}, size={:#x})",
+                        "[{}] pipes::open_pipe(sid={}, sel={}, size={:#x}, flags={:?})",
                         sid,
                         nsid,
                         sel,
-                        msize
+                        msize,
+                        flags
                     );
-                    let pipe = m.create_pipe(sel, nsid, msize, REQHDL.get().recv_gate())?;
+                    let pipe = m.create_pipe(sel, nsid, msize, flags, REQHDL.get().recv_gate())?;
                     let nsess = self.new_sub_sess(crt, sel, nsid, SessionData::Pipe(pipe))?;
                     Ok((nsid, nsess, false))
                 },
@@ -196,9 +378,18 @@ impl Handler<PipesSession> for PipesHandler {
                     }
 
                     let sel = Activity::own().alloc_sels(2);
-                    let ty = match xchg.in_args().pop()? {
-                        1 => ChanType::READ,
-                        _ => ChanType::WRITE,
+                    let ty = if p.is_duplex() {
+                        // duplex pipes have two ends that can both read and write
+                        match xchg.in_args().pop()? {
+                            end @ 0..=1 => ChanType::DUPLEX(end),
+                            _ => return Err(Error::new(Code::InvArgs)),
+                        }
+                    }
+                    else {
+                        match xchg.in_args().pop()? {
+                            1 => ChanType::READ,
+                            _ => ChanType::WRITE,
+                        }
                     };
                     log!(
                         crate::LOG_DEF,
@@ -385,6 +576,7 @@ pub fn main() -> i32 {
     let mut hdl = PipesHandler {
         sel: 0,
         sessions: SessionContainer::new(settings.max_clients),
+        named: NamedPipes::default(),
     };
     let s = Server::new("pipes", &mut hdl).expect("Unable to create service 'pipes'");
     hdl.sel = s.sel();
@@ -403,6 +595,7 @@ pub fn main() -> i32 {
                 Operation::NEXT_OUT => hdl.with_chan(is, |c, is| c.next_out(is)),
                 Operation::COMMIT => hdl.with_chan(is, |c, is| c.commit(is)),
                 Operation::REQ_NOTIFY => hdl.with_chan(is, |c, is| c.request_notify(is)),
+                Operation::SPLICE => hdl.with_chan(is, |c, is| c.splice(is)),
                 Operation::CLOSE | Operation::CLOSE_PIPE => {
                     let sid = is.label() as SessId;
                     // reply before we destroy the client's sgate. otherwise the client might
@@ -21,13 +21,15 @@ use m3::boxed::Box;
 use m3::cap::Selector;
 use m3::cell::{LazyReadOnlyCell, StaticCell};
 use m3::cfg;
-use m3::col::ToString;
+use m3::col::{ToString, Vec};
 use m3::com::{MemGate, RGateArgs, RecvGate, SGateArgs, SendGate};
 use m3::errors::{Code, Error, VerboseError};
 use m3::goff;
-use m3::kif;
+use m3::kif::{self, systrace};
 use m3::log;
 use m3::math;
+use m3::mem::size_of;
+use m3::println;
 use m3::session::ResMng;
 use m3::syscalls;
 use m3::tcu;
@@ -40,13 +42,15 @@ use resmng::{memory, requests, sendqueue, subsys};
 static SUBSYS: LazyReadOnlyCell<subsys::Subsystem> = LazyReadOnlyCell::default();
 static BMODS: StaticCell<u64> = StaticCell::new(0);
 
-fn find_mod(name: &str) -> Option<(MemGate, usize)> {
+fn find_mod(name: &str, reuse: bool) -> Option<(MemGate, usize)> {
+    // restarted and dynamically added childs can use any module with that name, because we never
+    // write to modules
     SUBSYS
         .get()
         .mods()
         .iter()
         .enumerate()
-        .position(|(idx, m)| (BMODS.get() & (1 << idx)) == 0 && m.name() == name)
+        .position(|(idx, m)| (reuse || (BMODS.get() & (1 << idx)) == 0) && m.name() == name)
         .map(|idx| {
             BMODS.set(BMODS.get() | 1 << idx);
             (
@@ -57,7 +61,11 @@ fn find_mod(name: &str) -> Option<(MemGate, usize)> {
 }
 
 fn start_child_async(child: &mut OwnChild) -> Result<(), VerboseError> {
-    let bmod = find_mod(child.cfg().name()).ok_or_else(|| Error::new(Code::NotFound))?;
+    let bmod = find_mod(
+        child.cfg().name(),
+        child.restarts() > 0 || child.is_dynamic(),
+    )
+    .ok_or_else(|| Error::new(Code::NotFound))?;
 
     #[allow(clippy::useless_conversion)]
     let sgate = SendGate::new_with(
@@ -70,7 +78,8 @@ fn start_child_async(child: &mut OwnChild) -> Result<(), VerboseError> {
         child.child_tile().unwrap().tile_obj().clone(),
         ActivityArgs::new(child.name())
             .resmng(ResMng::new(sgate))
-            .kmem(child.kmem().unwrap()),
+            .kmem(child.kmem().unwrap())
+            .sysc_budget(child.cfg().sysc_budget()),
     )
     .map_err(|e| VerboseError::new(e.code(), "Unable to create Activity".to_string()))?;
 
@@ -119,6 +128,34 @@ fn create_rgate(
     Ok(rgate)
 }
 
+fn dump_systrace() -> Result<(), Error> {
+    let sel = Activity::own().alloc_sel();
+    syscalls::sysc_trace(sel)?;
+    let mgate = MemGate::new_bind(sel);
+
+    // print the trace in the format expected by tools/systrace.py
+    let hdr: systrace::Header = mgate.read_obj(0)?;
+    let mut entries = Vec::new();
+    for i in 0..hdr.entries as usize {
+        let off = size_of::<systrace::Header>() + i * size_of::<systrace::Entry>();
+        let e: systrace::Entry = mgate.read_obj(off as goff)?;
+        if e.seq != 0 {
+            entries.push(e);
+        }
+    }
+    entries.sort_by_key(|e| e.seq);
+
+    let total = entries.last().map(|e| e.seq).unwrap_or(0);
+    println!("systrace: total={} entries={}", total, entries.len());
+    for e in entries {
+        println!(
+            "systrace: {} {} {:#x} {:#x} {} {} {}",
+            e.act, e.op, e.args[0], e.args[1], e.error, e.start, e.duration
+        );
+    }
+    Ok(())
+}
+
 fn workloop() {
     requests::workloop(|| {}, start_child_async).expect("Running the workloop failed");
 }
@@ -186,5 +223,9 @@ pub fn main() -> i32 {
 
     log!(resmng::LOG_DEF, "All childs gone. Exiting.");
 
+    if args.systrace {
+        dump_systrace().expect("Unable to dump syscall trace");
+    }
+
     0
 }
@@ -30,11 +30,11 @@ use m3::server::{
     server_loop, CapExchange, Handler, RequestHandler, Server, SessId, SessionContainer,
     DEF_MAX_CLIENTS,
 };
-use m3::session::ServerSession;
+use m3::session::{ServerSession, VTermOperation};
 use m3::tcu::{Label, Message};
 use m3::tiles::Activity;
 use m3::vec;
-use m3::vfs::{FileEvent, FileInfo, FileMode, GenFileOp};
+use m3::vfs::{EditAction, FileEvent, FileInfo, FileMode, GenFileOp, LineEditor, TMode};
 use m3::{build_vmsg, goff, send_vmsg};
 
 pub const LOG_DEF: bool = false;
@@ -43,17 +43,38 @@ pub const LOG_INOUT: bool = false;
 const BUF_SIZE: usize = 256;
 
 int_enum! {
-    struct Mode : u64 {
-        const RAW       = 0;
-        const COOKED    = 1;
+    struct Operation : u64 {
+        const STAT          = GenFileOp::STAT.val;
+        const SEEK          = GenFileOp::SEEK.val;
+        const NEXT_IN       = GenFileOp::NEXT_IN.val;
+        const NEXT_OUT      = GenFileOp::NEXT_OUT.val;
+        const COMMIT        = GenFileOp::COMMIT.val;
+        const CLOSE         = GenFileOp::CLOSE.val;
+        const GET_TMODE     = GenFileOp::GET_TMODE.val;
+        const SET_TMODE     = GenFileOp::SET_TMODE.val;
+        const REQ_NOTIFY    = GenFileOp::REQ_NOTIFY.val;
+        const GET_WINSIZE   = VTermOperation::GET_WINSIZE.val;
+        const SET_WINSIZE   = VTermOperation::SET_WINSIZE.val;
+        const SET_ECHO      = VTermOperation::SET_ECHO.val;
     }
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum SizeQuery {
+    Unsent,
+    Pending,
+    Done,
+}
+
 static REQHDL: LazyReadOnlyCell<RequestHandler> = LazyReadOnlyCell::default();
-static BUFFER: StaticRefCell<Vec<u8>> = StaticRefCell::new(Vec::new());
+static EDITOR: StaticRefCell<LineEditor> = StaticRefCell::new(LineEditor::new());
 static INPUT: StaticRefCell<Vec<u8>> = StaticRefCell::new(Vec::new());
 static EOF: StaticCell<bool> = StaticCell::new(false);
-static MODE: StaticCell<Mode> = StaticCell::new(Mode::COOKED);
+static MODE: StaticCell<TMode> = StaticCell::new(TMode::COOKED);
+static ECHO: StaticCell<bool> = StaticCell::new(true);
+// the window size (columns and rows) as reported by the terminal or set by an application
+static WINSIZE: StaticCell<(usize, usize)> = StaticCell::new((80, 24));
+static SIZE_QUERY: StaticCell<SizeQuery> = StaticCell::new(SizeQuery::Unsent);
 static TMP_BUF: StaticRefCell<[u8; BUF_SIZE]> = StaticRefCell::new([0u8; BUF_SIZE]);
 
 macro_rules! reply_vmsg_late {
@@ -149,7 +170,7 @@ impl Channel {
 
     fn set_tmode(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
         let _fid: usize = is.pop()?;
-        let mode = is.pop::<Mode>()?;
+        let mode = is.pop::<TMode>()?;
 
         log!(
             crate::LOG_DEF,
@@ -163,6 +184,58 @@ impl Channel {
         is.reply_error(Code::None)
     }
 
+    fn get_winsize(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _fid: usize = is.pop()?;
+
+        let (cols, rows) = WINSIZE.get();
+        log!(
+            crate::LOG_DEF,
+            "[{}] vterm::get_winsize() -> ({}, {})",
+            self.id,
+            cols,
+            rows
+        );
+
+        reply_vmsg!(is, Code::None as u32, cols, rows)
+    }
+
+    fn set_winsize(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _fid: usize = is.pop()?;
+        let cols: usize = is.pop()?;
+        let rows: usize = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] vterm::set_winsize(cols={}, rows={})",
+            self.id,
+            cols,
+            rows
+        );
+        WINSIZE.set((cols, rows));
+        // there is no need to ask the terminal anymore. a pending query still needs to be answered
+        // though to filter the answer from the input.
+        if SIZE_QUERY.get() == SizeQuery::Unsent {
+            SIZE_QUERY.set(SizeQuery::Done);
+        }
+
+        is.reply_error(Code::None)
+    }
+
+    fn set_echo(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
+        let _fid: usize = is.pop()?;
+        let echo: bool = is.pop()?;
+
+        log!(
+            crate::LOG_DEF,
+            "[{}] vterm::set_echo(echo={})",
+            self.id,
+            echo
+        );
+        ECHO.set(echo);
+
+        is.reply_error(Code::None)
+    }
+
     fn next_in(&mut self, is: &mut GateIStream<'_>) -> Result<(), Error> {
         let _: usize = is.pop()?;
 
@@ -572,56 +645,70 @@ fn receive_acks(hdl: &mut VTermHandler) {
     });
 }
 
+fn query_winsize() {
+    // move the cursor to the bottom right corner and ask the terminal for its position. the
+    // answer arrives as input and is handled by the line editor.
+    Serial::new()
+        .write(b"\x1b7\x1b[999;999H\x1b[6n\x1b8")
+        .unwrap();
+    SIZE_QUERY.set(SizeQuery::Pending);
+}
+
+fn set_reported_size(cols: usize, rows: usize) {
+    log!(crate::LOG_DEF, "Terminal reported size {}x{}", cols, rows);
+    WINSIZE.set((cols, rows));
+    SIZE_QUERY.set(SizeQuery::Done);
+}
+
 fn handle_input(hdl: &mut VTermHandler, msg: &'static Message) {
     let mut input = INPUT.borrow_mut();
-    let mut buffer = BUFFER.borrow_mut();
 
     let bytes =
         unsafe { core::slice::from_raw_parts(msg.data.as_ptr(), msg.header.length as usize) };
+    // only query the window size if someone is using the terminal interactively, because the
+    // escape sequences would otherwise only end up in the log
+    if SIZE_QUERY.get() == SizeQuery::Unsent {
+        query_winsize();
+    }
+
     let mut flush = false;
     let mut eof = false;
-    if MODE.get() == Mode::RAW {
-        input.extend_from_slice(bytes);
+    let mut editor = EDITOR.borrow_mut();
+    if MODE.get() == TMode::RAW {
+        // the answer to our query is not meant for the application. in case we got the answer
+        // in the middle of this message, the filter might still hold back a few bytes.
+        if SIZE_QUERY.get() == SizeQuery::Pending || editor.in_escape() {
+            for b in bytes {
+                if let Some((cols, rows)) = editor.filter_report(*b, &mut input) {
+                    set_reported_size(cols, rows);
+                }
+            }
+        }
+        else {
+            input.extend_from_slice(bytes);
+        }
     }
     else {
         let mut output = vec![];
         for b in bytes {
-            match b {
-                // ^D
-                0x04 => eof = true,
-                // ^C
-                0x03 => add_signal(hdl),
-                // backspace
-                0x7f => {
-                    output.push(0x08);
-                    output.push(b' ');
-                    output.push(0x08);
-                    buffer.pop();
+            match editor.input(*b, &mut output) {
+                EditAction::None => {},
+                EditAction::Line => {
+                    input.extend_from_slice(&editor.take_line());
+                    flush = true;
                 },
-                b => {
-                    if *b == 27 {
-                        buffer.push(b'^');
-                        output.push(b'^');
-                    }
-                    else if *b == b'\n' {
-                        flush = true;
-                    }
-                    if *b == b'\n' || !b.is_ascii_control() {
-                        buffer.push(*b);
-                    }
+                EditAction::Eof => {
+                    input.extend_from_slice(&editor.take_partial());
+                    eof = true;
                 },
-            }
-
-            if *b == b'\n' || !b.is_ascii_control() {
-                output.push(*b);
+                EditAction::Signal => add_signal(hdl),
+                EditAction::WinSize(cols, rows) => set_reported_size(cols, rows),
             }
         }
 
-        if eof || flush {
-            input.extend_from_slice(&buffer);
-            buffer.clear();
+        if ECHO.get() {
+            Serial::new().write(&output).unwrap();
         }
-        Serial::new().write(&output).unwrap();
     }
 
     add_input(hdl, eof, eof || flush, &mut input);
@@ -667,10 +754,10 @@ pub fn main() -> i32 {
 
         REQHDL.get().handle(|op, is| {
             match op {
-                GenFileOp::NEXT_IN => hdl.with_chan(is, |c, is| c.next_in(is)),
-                GenFileOp::NEXT_OUT => hdl.with_chan(is, |c, is| c.next_out(is)),
-                GenFileOp::COMMIT => hdl.with_chan(is, |c, is| c.commit(is)),
-                GenFileOp::CLOSE => {
+                Operation::NEXT_IN => hdl.with_chan(is, |c, is| c.next_in(is)),
+                Operation::NEXT_OUT => hdl.with_chan(is, |c, is| c.next_out(is)),
+                Operation::COMMIT => hdl.with_chan(is, |c, is| c.commit(is)),
+                Operation::CLOSE => {
                     let sid = is.label() as SessId;
                     // reply before we destroy the client's sgate. otherwise the client might
                     // notice the invalidated sgate before getting the reply and therefore give
@@ -679,11 +766,14 @@ pub fn main() -> i32 {
                     is.reply_error(Code::None).ok();
                     hdl.close_sess(sid, is.rgate())
                 },
-                GenFileOp::STAT => hdl.with_chan(is, |c, is| c.stat(is)),
-                GenFileOp::SEEK => Err(Error::new(Code::NotSup)),
-                GenFileOp::GET_TMODE => hdl.with_chan(is, |c, is| c.get_tmode(is)),
-                GenFileOp::SET_TMODE => hdl.with_chan(is, |c, is| c.set_tmode(is)),
-                GenFileOp::REQ_NOTIFY => hdl.with_chan(is, |c, is| c.request_notify(is)),
+                Operation::STAT => hdl.with_chan(is, |c, is| c.stat(is)),
+                Operation::SEEK => Err(Error::new(Code::NotSup)),
+                Operation::GET_TMODE => hdl.with_chan(is, |c, is| c.get_tmode(is)),
+                Operation::SET_TMODE => hdl.with_chan(is, |c, is| c.set_tmode(is)),
+                Operation::GET_WINSIZE => hdl.with_chan(is, |c, is| c.get_winsize(is)),
+                Operation::SET_WINSIZE => hdl.with_chan(is, |c, is| c.set_winsize(is)),
+                Operation::SET_ECHO => hdl.with_chan(is, |c, is| c.set_echo(is)),
+                Operation::REQ_NOTIFY => hdl.with_chan(is, |c, is| c.request_notify(is)),
                 _ => Err(Error::new(Code::InvArgs)),
             }
         })
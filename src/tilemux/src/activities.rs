@@ -20,7 +20,7 @@ use base::col::{BoxList, Vec};
 use base::errors::{Code, Error};
 use base::goff;
 use base::impl_boxitem;
-use base::kif;
+use base::kif::{self, tilemux::SchedClass};
 use base::log;
 use base::math;
 use base::mem::{size_of, GlobAddr, MsgBuf};
@@ -125,6 +125,27 @@ pub enum Event {
     EpInvalid,
     Timeout,
     Start,
+    Signal,
+}
+
+/// The CPU state of an activity, which is kept in one place to transfer it to another tile.
+#[repr(C)]
+pub struct CPUState {
+    user: arch::State,
+    #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
+    fpu: arch::FPUState,
+    cmd: helper::TCUCmdState,
+    act_reg: tcu::Reg,
+    // the signal handler registration is transferred as well
+    sig_entry: usize,
+    sig_mask: u32,
+}
+
+/// The context of an activity that has been interrupted by a signal handler
+struct SignalContext {
+    user: arch::State,
+    #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
+    fpu: arch::FPUState,
 }
 
 pub struct Activity {
@@ -133,23 +154,30 @@ pub struct Activity {
     next: Option<NonNull<Activity>>,
     aspace: Option<paging::AddrSpace<PTAllocator>>,
     frames: Vec<Phys>,
-    #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
-    fpu_state: arch::FPUState,
-    user_state: arch::State,
+    cpu: CPUState,
     user_state_addr: usize,
     scheduled: TimeInstant,
     time_quota: Rc<TimeQuota>,
+    deadline: TimeInstant,
+    reserved: u64,
     cpu_time: TimeDuration,
     ctxsws: u64,
+    // the usage before the last reset of the statistics
+    reset_usage: kif::tilemux::Usage,
+    pagefaults: u64,
+    // the number of received messages and the message count of CUR_ACT we have seen last
+    recv_msgs: u64,
+    seen_msgs: u16,
     wait_timeout: bool,
     wait_irq: Option<tmif::IRQId>,
     wait_ep: Option<tcu::EpId>,
     irq_mask: u32,
-    act_reg: tcu::Reg,
+    sig_pending: u32,
+    sig_ctx: Option<Box<SignalContext>>,
     eps_start: tcu::EpId,
-    cmd: helper::TCUCmdState,
     pf_state: Option<PfState>,
     cont: Option<fn(&mut Activity) -> ContResult>,
+    suspended: bool,
     has_refs: bool,
 }
 
@@ -291,6 +319,8 @@ pub fn add(
 ) -> Result<(), Error> {
     log!(crate::LOG_ACTS, "Created Activity {}", id);
 
+    crate::crash::reset(id);
+
     let time_quota = quota::get_time(time_quota).unwrap();
     if time_quota.total() == 0 {
         return Err(Error::new(Code::NoSpace));
@@ -403,45 +433,37 @@ pub fn schedule(mut action: ScheduleAction) -> usize {
     res
 }
 
+fn pop_next(now: TimeInstant) -> Option<Box<Activity>> {
+    let mut rdy = RDY.borrow_mut();
+    // take the first activity with the best rank to keep the round-robin order within a rank
+    let best = rdy.iter().map(|a| a.rank(now)).min()?;
+    rdy.remove_if(|a| a.rank(now) == best)
+}
+
 fn do_schedule(mut action: ScheduleAction) -> usize {
     let now = TimeInstant::now();
-    let mut next = RDY
-        .borrow_mut()
-        .pop_front()
+    let mut next = pop_next(now)
         // safety: we know that idle is stored in a Box
         .unwrap_or_else(|| unsafe { Box::from_raw(IDLE.get_mut().as_mut()) });
 
     let old_time = if let Some(mut old) = try_cur() {
         // reduce budget now in case we decide not to switch below
-        old.time_quota.set_left(
-            old.time_quota
-                .left()
-                .saturating_sub((now - old.scheduled).as_nanos() as u64),
-        );
+        let elapsed = now - old.scheduled;
+        old.charge(elapsed);
 
         // save TCU command registers; do that first while still running with that activity
-        old.cmd.save();
+        old.cpu.cmd.save();
 
         // now change activity
         let old_id = tcu::TCU::xchg_activity(next.activity_reg()).unwrap();
+        old.set_activity_reg(old_id);
 
+        let mut keep = false;
         // are there messages left we care about?
         if action == ScheduleAction::Block && !old.can_block((old_id >> 16) as u16) {
             // if the activity has budget left (or there is no one else ready), continue with it
-            if old.time_quota.left() > 0 || next.id() == kif::tilemux::IDLE_ID {
-                let next_id = tcu::TCU::xchg_activity(old_id).unwrap();
-                next.set_activity_reg(next_id);
-                if next.id() != kif::tilemux::IDLE_ID {
-                    let next_budget = TimeDuration::from_nanos(next.time_quota.left());
-                    make_ready(next, next_budget);
-                }
-                else {
-                    Box::into_raw(next);
-                }
-                let last_sched = old.scheduled;
-                old.cpu_time += now - last_sched;
-                old.scheduled = now;
-                return old.user_state_addr;
+            if !old.budget_left().is_zero() || next.id() == kif::tilemux::IDLE_ID {
+                keep = true;
             }
             // otherwise, preempt it
             else {
@@ -449,11 +471,35 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
             }
         }
 
-        old.set_activity_reg(old_id);
+        // don't switch to an activity of a lower scheduling class or priority
+        if (action == ScheduleAction::Preempt || action == ScheduleAction::Yield)
+            && next.id() != kif::tilemux::IDLE_ID
+            && old.rank(now) < next.rank(now)
+        {
+            old.refill(now);
+            keep = true;
+        }
+
+        if keep {
+            let next_id = tcu::TCU::xchg_activity(old_id).unwrap();
+            next.set_activity_reg(next_id);
+            if next.id() != kif::tilemux::IDLE_ID {
+                let next_budget = next.budget_left();
+                make_ready(next, next_budget);
+            }
+            else {
+                Box::into_raw(next);
+            }
+            let last_sched = old.scheduled;
+            old.cpu_time += now - last_sched;
+            old.scheduled = now;
+            return old.user_state_addr;
+        }
+
         // pass the old budget from here to make_ready below, because we might share the budget with
         // the next activity (which prevented others from running, because we would just switch between
         // these two)
-        TimeDuration::from_nanos(old.time_quota.left())
+        old.budget_left()
     }
     else {
         tcu::TCU::xchg_activity(next.activity_reg()).unwrap();
@@ -470,18 +516,11 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
     next.state = ActState::Running;
 
     next.scheduled = now;
-    // budget is immediately refilled but we prefer other activities while a budget is 0 (see make_ready)
-    if next.time_quota.left() == 0 {
-        // to keep it simple, we divide the time slice by the number of users to ensure that activities
-        // that share a time slice don't receive more than their share in total. the better approach
-        // might be to actually schedule quotas and not activities, but that seems like overkill here.
-        next.time_quota
-            .set_left(next.time_quota.total() / next.time_quota.users());
-    }
-    let next_budget = next.time_quota.left();
+    next.refill(now);
+    let next_budget = next.budget_left().as_nanos() as u64;
 
     // restore TCU command registers
-    next.cmd.restore();
+    next.cpu.cmd.restore();
 
     // exchange CUR
     // safety: we do no longer hold a reference to `own`
@@ -490,7 +529,7 @@ fn do_schedule(mut action: ScheduleAction) -> usize {
             crate::LOG_CTXSWS,
             "Switching from {} (budget {}) to {} (budget {}): {:?} old Activity",
             old.id(),
-            old.time_quota.left(),
+            old.budget_left().as_nanos(),
             next_id,
             next_budget,
             action
@@ -586,6 +625,7 @@ pub fn remove(id: Id, status: i32, notify: bool, sched: bool) {
             base::build_vmsg!(msg_buf, kif::tilemux::Calls::EXIT, kif::tilemux::Exit {
                 act_id: old.id() as tcu::ActId,
                 status,
+                crash: crate::crash::global_addr(old.id()),
             });
             sendqueue::send(&msg_buf).unwrap();
 
@@ -622,24 +662,37 @@ impl Activity {
             next: None,
             aspace,
             frames: Vec::new(),
-            act_reg: id,
             state: ActState::Blocked,
-            #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
-            fpu_state: arch::FPUState::default(),
-            user_state: arch::State::default(),
+            cpu: CPUState {
+                user: arch::State::default(),
+                #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
+                fpu: arch::FPUState::default(),
+                cmd: helper::TCUCmdState::new(),
+                act_reg: id,
+                sig_entry: 0,
+                sig_mask: 0,
+            },
             user_state_addr: 0,
             time_quota,
+            deadline: TimeInstant::from_nanos(0),
+            reserved: 0,
             cpu_time: TimeDuration::ZERO,
             ctxsws: 0,
+            reset_usage: kif::tilemux::Usage::default(),
+            pagefaults: 0,
+            recv_msgs: 0,
+            seen_msgs: 0,
             scheduled: TimeInstant::now(),
             wait_timeout: false,
             wait_irq: None,
             wait_ep: None,
             irq_mask: 0,
+            sig_pending: 0,
+            sig_ctx: None,
             eps_start,
-            cmd: helper::TCUCmdState::new(),
             pf_state: None,
             cont: None,
+            suspended: false,
             has_refs: false,
         }
     }
@@ -662,7 +715,7 @@ impl Activity {
     }
 
     pub fn id(&self) -> Id {
-        self.act_reg & 0xFFFF
+        self.cpu.act_reg & 0xFFFF
     }
 
     pub fn state(&self) -> ActState {
@@ -670,16 +723,23 @@ impl Activity {
     }
 
     pub fn activity_reg(&self) -> tcu::Reg {
-        self.act_reg
+        self.cpu.act_reg
     }
 
     pub fn set_activity_reg(&mut self, val: tcu::Reg) {
-        self.act_reg = val;
+        self.cpu.act_reg = val;
+        // the TCU increments the message count on every message that arrives while the activity is
+        // running; account all messages that arrived since we have seen the count last
+        let count = self.msgs();
+        if count > self.seen_msgs {
+            self.recv_msgs += (count - self.seen_msgs) as u64;
+        }
+        self.seen_msgs = count;
     }
 
     #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
     pub fn fpu_state(&mut self) -> &mut arch::FPUState {
-        &mut self.fpu_state
+        &mut self.cpu.fpu
     }
 
     pub fn eps_start(&self) -> tcu::EpId {
@@ -687,7 +747,7 @@ impl Activity {
     }
 
     pub fn msgs(&self) -> u16 {
-        (self.act_reg >> 16) as u16
+        (self.cpu.act_reg >> 16) as u16
     }
 
     pub fn has_msgs(&self) -> bool {
@@ -695,30 +755,103 @@ impl Activity {
     }
 
     pub fn add_msg(&mut self) {
-        self.act_reg += 1 << 16;
+        self.cpu.act_reg += 1 << 16;
+        self.recv_msgs += 1;
+        self.seen_msgs = self.msgs();
     }
 
     pub fn rem_msgs(&mut self, count: u16) {
         assert!(self.msgs() >= count);
-        self.act_reg -= (count as u64) << 16;
+        self.cpu.act_reg -= (count as u64) << 16;
+        self.seen_msgs = self.msgs();
     }
 
     pub fn budget_left(&self) -> TimeDuration {
-        TimeDuration::from_nanos(self.time_quota.left())
+        if self.has_reservation() {
+            TimeDuration::from_nanos(self.reserved)
+        }
+        else {
+            TimeDuration::from_nanos(self.time_quota.left())
+        }
+    }
+
+    fn has_reservation(&self) -> bool {
+        self.time_quota.sched().class == SchedClass::EDF && self.reserved > 0
+    }
+
+    /// Returns the rank of this activity for scheduling; lower ranks are preferred.
+    fn rank(&self, now: TimeInstant) -> (u64, u64) {
+        let sched = self.time_quota.sched();
+        match sched.class {
+            // EDF activities with budget left in their period are ordered by deadline
+            SchedClass::EDF if now >= self.deadline => (0, now.as_nanos() + sched.period),
+            SchedClass::EDF if self.reserved > 0 => (0, self.deadline.as_nanos()),
+            SchedClass::PRIO => (1, u64::MAX - sched.prio),
+            // round-robin and EDF activities that have exhausted their budget
+            _ => (2, 0),
+        }
+    }
+
+    /// Charges the given CPU time to the reservation, if any, and to the time slice otherwise.
+    fn charge(&mut self, time: TimeDuration) {
+        let nanos = time.as_nanos() as u64;
+        if self.has_reservation() {
+            self.reserved = self.reserved.saturating_sub(nanos);
+        }
+        else {
+            self.time_quota
+                .set_left(self.time_quota.left().saturating_sub(nanos));
+        }
+    }
+
+    fn refill(&mut self, now: TimeInstant) {
+        let sched = self.time_quota.sched();
+        if sched.class == SchedClass::EDF && now >= self.deadline {
+            // like the time slice below, the budget is shared by all users of the reservation
+            self.reserved = sched.budget / quota::reservation_users(&self.time_quota);
+            self.deadline = now + TimeDuration::from_nanos(sched.period);
+        }
+
+        // budget is immediately refilled but we prefer other activities while a budget is 0 (see make_ready)
+        if self.time_quota.left() == 0 {
+            // to keep it simple, we divide the time slice by the number of users to ensure that activities
+            // that share a time slice don't receive more than their share in total. the better approach
+            // might be to actually schedule quotas and not activities, but that seems like overkill here.
+            self.time_quota
+                .set_left(self.time_quota.total() / self.time_quota.users());
+        }
     }
 
     pub fn user_state(&mut self) -> &mut arch::State {
-        &mut self.user_state
+        &mut self.cpu.user
     }
 
-    pub fn reset_stats(&mut self) -> TimeDuration {
-        let now = TimeInstant::now();
-        let old_time = if self.state == ActState::Running {
+    fn cur_cpu_time(&self, now: TimeInstant) -> TimeDuration {
+        if self.state == ActState::Running {
             self.cpu_time + (now - self.scheduled)
         }
         else {
             self.cpu_time
-        };
+        }
+    }
+
+    /// Returns the resources this activity has used since its creation
+    pub fn usage(&self) -> kif::tilemux::Usage {
+        let now = TimeInstant::now();
+        kif::tilemux::Usage {
+            cpu_time: self.reset_usage.cpu_time + self.cur_cpu_time(now).as_nanos() as u64,
+            ctxsws: self.reset_usage.ctxsws + self.ctxsws,
+            pagefaults: self.pagefaults,
+            msgs: self.recv_msgs,
+        }
+    }
+
+    pub fn reset_stats(&mut self) -> TimeDuration {
+        let now = TimeInstant::now();
+        let old_time = self.cur_cpu_time(now);
+        // the usage is not affected by resets
+        self.reset_usage.cpu_time += old_time.as_nanos() as u64;
+        self.reset_usage.ctxsws += self.ctxsws;
         log!(
             crate::LOG_ACTS,
             "Activity{} consumed {:?} CPU time and was suspended {} times",
@@ -740,8 +873,81 @@ impl Activity {
         self.irq_mask |= 1 << irq;
     }
 
+    pub fn set_sig_action(&mut self, entry: usize, mask: u32) {
+        self.cpu.sig_entry = entry;
+        self.cpu.sig_mask = if entry == 0 { 0 } else { mask };
+        self.sig_pending &= self.cpu.sig_mask;
+    }
+
+    pub fn has_sig_handler(&self, sig: u32) -> bool {
+        (self.cpu.sig_mask & (1 << sig)) != 0
+    }
+
+    /// Marks the given signal as pending, which is delivered as soon as the activity runs again.
+    pub fn add_signal(&mut self, sig: u32) {
+        self.sig_pending |= 1 << sig;
+        self.unblock(Event::Signal);
+    }
+
+    /// Redirects the activity to its signal handler, if a signal is pending and no handler is
+    /// running. Returns true if the handler has been entered.
+    pub fn enter_signal(&mut self) -> bool {
+        if self.sig_pending == 0 || self.sig_ctx.is_some() {
+            return false;
+        }
+
+        let sig = self.sig_pending.trailing_zeros();
+        self.sig_pending &= !(1 << sig);
+
+        log!(
+            crate::LOG_SIGNALS,
+            "Delivering signal {} to Activity {}",
+            sig,
+            self.id()
+        );
+
+        // write the FPU registers back so that the handler can use the FPU
+        arch::flush_fpu(self);
+        self.sig_ctx = Some(Box::new(SignalContext {
+            user: self.cpu.user.clone(),
+            #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
+            fpu: self.cpu.fpu.clone(),
+        }));
+        arch::enter_signal(&mut self.cpu.user, self.cpu.sig_entry, sig);
+        true
+    }
+
+    /// Restores the context that has been interrupted by the current signal handler.
+    ///
+    /// Returns the value of the result register of the interrupted context.
+    pub fn leave_signal(&mut self) -> Result<usize, Error> {
+        let ctx = self
+            .sig_ctx
+            .take()
+            .ok_or_else(|| Error::new(Code::InvState))?;
+
+        log!(
+            crate::LOG_SIGNALS,
+            "Returning from signal handler of Activity {}",
+            self.id()
+        );
+
+        // the FPU registers might still contain the values of the handler
+        arch::forget_fpu(self.id());
+        self.cpu.user = ctx.user;
+        #[cfg(any(target_arch = "riscv64", target_arch = "x86_64"))]
+        {
+            self.cpu.fpu = ctx.fpu;
+        }
+        Ok(self.cpu.user.r[isr::TMC_ARG0])
+    }
+
     fn can_block(&self, msgs: u16) -> bool {
-        if let Some(wep) = self.wait_ep {
+        // suspended activities are not allowed to run, regardless of pending messages
+        if self.suspended {
+            true
+        }
+        else if let Some(wep) = self.wait_ep {
             !tcu::TCU::has_msgs(wep)
         }
         else {
@@ -793,6 +999,7 @@ impl Activity {
             Event::Timeout => true,
             Event::EpInvalid => true,
             Event::Start => true,
+            Event::Signal => true,
         }
     }
 
@@ -804,8 +1011,8 @@ impl Activity {
             event
         );
 
-        // activity not ready yet?
-        if self.user_state_addr == 0 {
+        // activity not ready yet or suspended?
+        if self.user_state_addr == 0 || self.suspended {
             return false;
         }
 
@@ -819,7 +1026,7 @@ impl Activity {
                 timer::remove(act.id());
                 act.wait_timeout = false;
             }
-            let budget = TimeDuration::from_nanos(act.time_quota.left());
+            let budget = act.budget_left();
             make_ready(act, budget);
         }
         if self.state != ActState::Running {
@@ -831,17 +1038,14 @@ impl Activity {
     pub fn consume_time(&mut self) {
         let now = TimeInstant::now();
         let duration = now - self.scheduled;
-        self.time_quota.set_left(
-            self.time_quota
-                .left()
-                .saturating_sub(duration.as_nanos() as u64),
-        );
-        if self.time_quota.left() == 0 && has_ready() {
+        self.charge(duration);
+        if self.budget_left().is_zero() && has_ready() {
             crate::reg_scheduling(ScheduleAction::Preempt);
         }
     }
 
     pub fn start_pf(&mut self, pf_state: PfState) {
+        self.pagefaults += 1;
         self.pf_state = Some(pf_state);
     }
 
@@ -860,12 +1064,68 @@ impl Activity {
         crate::app_env().platform = pex_env().platform;
         if self.id() != kif::tilemux::IDLE_ID {
             arch::init_state(
-                &mut self.user_state,
+                &mut self.cpu.user,
                 crate::app_env().entry as usize,
                 crate::app_env().sp as usize,
             );
         }
-        self.user_state_addr = &self.user_state as *const _ as usize;
+        self.user_state_addr = &self.cpu.user as *const _ as usize;
+    }
+
+    /// Suspends this activity so that its state can be transferred to another tile.
+    ///
+    /// Returns the global address and size of the CPU state.
+    pub fn suspend(&mut self) -> Result<(GlobAddr, usize), Error> {
+        // we cannot transfer activities that are in the middle of a page fault or signal handler or
+        // use interrupts
+        if self.pf_state.is_some() || self.cont.is_some() || self.sig_ctx.is_some() {
+            return Err(Error::new(Code::InProgress));
+        }
+        if self.irq_mask != 0 {
+            return Err(Error::new(Code::NotSup));
+        }
+
+        self.suspended = true;
+        match self.state {
+            ActState::Running => crate::reg_scheduling(ScheduleAction::Block),
+            ActState::Ready => {
+                let act = RDY.borrow_mut().remove_if(|v| v.id() == self.id()).unwrap();
+                make_blocked(act);
+            },
+            ActState::Blocked => {},
+        }
+
+        // write the FPU registers back, if they are still held by the FPU
+        arch::flush_fpu(self);
+
+        let (mem_tile, mem_base, _, _) = tcu::TCU::unpack_mem_ep(0).unwrap();
+        let base = GlobAddr::new_with(mem_tile, mem_base);
+        let addr = &self.cpu as *const _ as usize;
+        Ok((
+            base + (addr - cfg::MEM_OFFSET) as goff,
+            size_of::<CPUState>(),
+        ))
+    }
+
+    /// Resumes this activity after it has been suspended.
+    ///
+    /// If the activity has been transferred from another tile, it continues with the CPU state that
+    /// has been written to it.
+    pub fn resume(&mut self) {
+        self.suspended = false;
+
+        if self.user_state_addr == 0 {
+            // ensure that the mappings established during the transfer are considered
+            if let Some(ref aspace) = self.aspace {
+                aspace.flush_tlb();
+            }
+            // the environment has been copied from the old tile
+            crate::app_env().tile_id = pex_env().tile_id;
+            crate::app_env().platform = pex_env().platform;
+            self.user_state_addr = &self.cpu.user as *const _ as usize;
+        }
+
+        self.unblock(Event::Start);
     }
 
     pub fn switch_to(&self) {
@@ -1070,6 +1330,7 @@ impl Drop for Activity {
         if self.wait_timeout {
             timer::remove(self.id());
         }
+        timer::set_alarm(self.id(), None);
         irqs::remove(self);
         arch::forget_fpu(self.id());
     }
@@ -17,7 +17,7 @@
  */
 
 use base::errors::Error;
-use base::kif::PageFlags;
+use base::kif::{tilemux, PageFlags};
 
 use core::arch::asm;
 
@@ -47,10 +47,43 @@ pub fn init(state: &mut State) {
     }
 }
 
+pub fn crash_cause(state: &State) -> (u64, usize) {
+    let signal = match isr::Vector::from(state.vec) {
+        isr::Vector::UNDEF_INSTR => tilemux::SIGILL,
+        isr::Vector::PREFETCH_ABORT | isr::Vector::DATA_ABORT => tilemux::SIGSEGV,
+        _ => tilemux::SIGBUS,
+    };
+    (signal, 0)
+}
+
+pub fn crash_info(state: &State, info: &mut tilemux::CrashInfo) {
+    info.cause = state.vec as u64;
+    // r0 .. r12, sp, lr, pc, cpsr, orig_r0
+    for (i, r) in state.r.iter().enumerate() {
+        info.regs[i] = *r as u64;
+    }
+    let regs = [state.sp, state.lr, state.pc, state.cpsr, state.r[0]];
+    for (i, r) in regs.iter().enumerate() {
+        info.regs[state.r.len() + i] = *r as u64;
+    }
+    info.reg_count = (state.r.len() + regs.len()) as u64;
+}
+
+pub fn enter_signal(state: &mut State, entry: usize, sig: u32) {
+    state.sp &= !0x7;
+    state.lr = 0; // the handler does not return
+    state.r[0] = sig as usize;
+    state.pc = entry;
+}
+
 pub fn forget_fpu(_act_id: activities::Id) {
     // no FPU support
 }
 
+pub fn flush_fpu(_act: &mut activities::Activity) {
+    // no FPU support
+}
+
 pub fn disable_fpu() {
     // no FPU support
 }
@@ -25,6 +25,7 @@ use crate::vma;
 
 pub type State = isr::State;
 
+#[derive(Clone)]
 #[repr(C, align(8))]
 pub struct FPUState {
     r: [MaybeUninit<usize>; 32],
@@ -125,12 +126,53 @@ pub fn init_state(state: &mut State, entry: usize, sp: usize) {
     state.status = set_fpu_mode(state.status, FSMode::OFF);
 }
 
+pub fn crash_cause(state: &State) -> (u64, usize) {
+    let signal = match isr::Vector::from(state.cause & 0x1F) {
+        isr::Vector::ILLEGAL_INSTR => tilemux::SIGILL,
+        isr::Vector::BREAKPOINT => tilemux::SIGTRAP,
+        isr::Vector::INSTR_ACC_FAULT
+        | isr::Vector::LOAD_ACC_FAULT
+        | isr::Vector::STORE_ACC_FAULT => tilemux::SIGSEGV,
+        _ => tilemux::SIGBUS,
+    };
+    (signal, read_csr!("stval"))
+}
+
+pub fn crash_info(state: &State, info: &mut tilemux::CrashInfo) {
+    info.cause = state.cause as u64;
+    // pc, x1 .. x31
+    info.regs[0] = state.epc as u64;
+    for (i, r) in state.r.iter().enumerate() {
+        info.regs[1 + i] = *r as u64;
+    }
+    info.reg_count = 1 + state.r.len() as u64;
+}
+
+pub fn enter_signal(state: &mut State, entry: usize, sig: u32) {
+    state.r[1] &= !0xF; // sp
+    state.r[0] = 0; // ra; the handler does not return
+    state.r[9] = sig as usize; // a0
+    state.epc = entry;
+}
+
 pub fn forget_fpu(act_id: activities::Id) {
     if FPU_OWNER.get() == act_id {
         FPU_OWNER.set(tilemux::ACT_ID);
     }
 }
 
+pub fn flush_fpu(act: &mut activities::Activity) {
+    if FPU_OWNER.get() == act.id() {
+        // enable FPU so that we can save the FPU registers
+        write_csr!("sstatus", set_fpu_mode(read_csr!("sstatus"), FSMode::CLEAN));
+        save_fpu(act.fpu_state());
+
+        // the next FPU use will restore the state
+        act.user_state().status = set_fpu_mode(act.user_state().status, FSMode::OFF);
+        FPU_OWNER.set(tilemux::ACT_ID);
+    }
+}
+
 pub fn disable_fpu() {
     let mut cur = activities::cur();
     if cur.id() != FPU_OWNER.get() {
@@ -139,8 +181,6 @@ pub fn disable_fpu() {
 }
 
 pub fn handle_fpu_ex(state: &mut State) {
-    let mut cur = activities::cur();
-
     // if the FPU is enabled and we receive an illegal instruction exception, kill activity
     if get_fpu_mode(state.status) != FSMode::OFF {
         log!(
@@ -148,10 +188,13 @@ pub fn handle_fpu_ex(state: &mut State) {
             "Illegal instruction with user state:\n{:?}",
             state
         );
+        crate::crash::record(&mut activities::cur(), tilemux::SIGILL, state.epc);
         activities::remove_cur(1);
         return;
     }
 
+    let mut cur = activities::cur();
+
     // enable FPU
     state.status = set_fpu_mode(state.status, FSMode::CLEAN);
 
@@ -33,6 +33,7 @@ const CR0_TASK_SWITCHED: usize = 1 << 3;
 
 static FPU_OWNER: StaticCell<activities::Id> = StaticCell::new(tilemux::ACT_ID);
 
+#[derive(Copy, Clone)]
 #[repr(C, packed)]
 pub struct FPUState {
     data: [MaybeUninit<u8>; 512],
@@ -76,12 +77,87 @@ pub fn init_state(state: &mut State, entry: usize, sp: usize) {
     state.ss = ((isr::Segment::UDATA.val << 3) | isr::DPL::USER.val) as usize;
 }
 
+pub fn crash_cause(state: &State) -> (u64, usize) {
+    let signal = match state.irq {
+        0x00 | 0x10 | 0x13 => tilemux::SIGFPE,
+        0x01 | 0x03 => tilemux::SIGTRAP,
+        0x06 => tilemux::SIGILL,
+        0x0D | 0x0E => tilemux::SIGSEGV,
+        _ => tilemux::SIGBUS,
+    };
+    (signal, 0)
+}
+
+pub fn crash_info(state: &State, info: &mut tilemux::CrashInfo) {
+    info.cause = state.irq as u64;
+    // the order of user_regs_struct: r15, r14, r13, r12, rbp, rbx, r11, r10, r9, r8, rax, rcx,
+    // rdx, rsi, rdi, orig_rax, rip, cs, eflags, rsp, ss, fs_base, gs_base, ds, es, fs, gs
+    let regs = [
+        state.r[0],
+        state.r[1],
+        state.r[2],
+        state.r[3],
+        state.r[8],
+        state.r[13],
+        state.r[4],
+        state.r[5],
+        state.r[6],
+        state.r[7],
+        state.r[14],
+        state.r[12],
+        state.r[11],
+        state.r[9],
+        state.r[10],
+        !0,
+        state.rip,
+        state.cs,
+        state.rflags,
+        state.rsp,
+        state.ss,
+        0,
+        0,
+        0,
+        0,
+        0,
+        0,
+    ];
+    for (i, r) in regs.iter().enumerate() {
+        info.regs[i] = *r as u64;
+    }
+    info.reg_count = regs.len() as u64;
+}
+
+pub fn enter_signal(state: &mut State, entry: usize, sig: u32) {
+    // skip the red zone and align the stack as if the handler was called
+    state.rsp = ((state.rsp - 128) & !0xF) - 8;
+    state.r[10] = sig as usize; // rdi
+    state.rip = entry;
+}
+
 pub fn forget_fpu(act_id: activities::Id) {
     if FPU_OWNER.get() == act_id {
         FPU_OWNER.set(tilemux::ACT_ID);
     }
 }
 
+pub fn flush_fpu(act: &mut activities::Activity) {
+    if FPU_OWNER.get() == act.id() {
+        cpu::write_cr0(cpu::read_cr0() & !CR0_TASK_SWITCHED);
+
+        let fpu_state = act.fpu_state();
+        unsafe {
+            asm!(
+                "fxsave [{0}]",
+                in(reg) &fpu_state.data,
+                options(nostack),
+            )
+        };
+
+        // the next FPU use will restore the state
+        FPU_OWNER.set(tilemux::ACT_ID);
+    }
+}
+
 pub fn disable_fpu() {
     if activities::cur().id() != FPU_OWNER.get() {
         cpu::write_cr0(cpu::read_cr0() | CR0_TASK_SWITCHED);
@@ -0,0 +1,78 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+use base::cell::StaticRefCell;
+use base::cfg;
+use base::kif::tilemux::{CrashInfo, CRASH_REGS};
+use base::log;
+
+use crate::activities;
+use crate::arch;
+use crate::helper;
+
+const NO_CRASH: CrashInfo = CrashInfo {
+    signal: 0,
+    cause: 0,
+    fault_addr: 0,
+    reg_count: 0,
+    regs: [0; CRASH_REGS],
+};
+
+// the crash information is indexed by activity id and kept separately from the activity, because
+// the kernel reads it after the activity has already been removed. crashes of our own or the idle
+// activity are not recorded.
+static CRASHES: StaticRefCell<[CrashInfo; cfg::MAX_ACTS]> =
+    StaticRefCell::new([NO_CRASH; cfg::MAX_ACTS]);
+
+/// Records the state of the given activity, which crashed with given signal at given address.
+///
+/// The activity is expected to be removed afterwards, which reports the crash to the kernel.
+pub fn record(act: &mut activities::Activity, signal: u64, fault_addr: usize) {
+    let mut crashes = CRASHES.borrow_mut();
+    let info = match crashes.get_mut(act.id() as usize) {
+        Some(info) => info,
+        None => return,
+    };
+    info.signal = signal;
+    info.fault_addr = fault_addr as u64;
+    arch::crash_info(act.user_state(), info);
+
+    log!(
+        crate::LOG_ACTS,
+        "Activity {} crashed with signal {} at {:#x}",
+        act.id(),
+        signal,
+        fault_addr
+    );
+}
+
+/// Forgets a previously recorded crash of the given activity
+pub fn reset(id: activities::Id) {
+    if let Some(info) = CRASHES.borrow_mut().get_mut(id as usize) {
+        *info = NO_CRASH;
+    }
+}
+
+/// Returns the global address of the crash information of the given activity or 0 if it did not
+/// crash
+pub fn global_addr(id: activities::Id) -> u64 {
+    let crashes = CRASHES.borrow();
+    let info = match crashes.get(id as usize) {
+        Some(info) if info.signal != 0 => info,
+        _ => return 0,
+    };
+
+    helper::global_addr(info as *const _ as usize).raw()
+}
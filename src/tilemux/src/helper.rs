@@ -13,7 +13,10 @@
  * General Public License version 2 for more details.
  */
 
+use base::cfg;
 use base::envdata;
+use base::goff;
+use base::mem::GlobAddr;
 use base::tcu;
 use core::sync::atomic;
 
@@ -29,6 +32,12 @@ pub fn flush_invalidate() {
     }
 }
 
+/// Returns the global address of the given address within TileMux's memory
+pub fn global_addr(addr: usize) -> GlobAddr {
+    let (mem_tile, mem_base, _, _) = tcu::TCU::unpack_mem_ep(0).unwrap();
+    GlobAddr::new_with(mem_tile, mem_base) + (addr - cfg::MEM_OFFSET) as goff
+}
+
 pub struct TCUCmdState {
     cmd_regs: [tcu::Reg; 3],
 }
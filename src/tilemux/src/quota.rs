@@ -18,7 +18,7 @@ use base::cell::StaticCell;
 use base::cell::StaticRefCell;
 use base::col::Vec;
 use base::errors::{Code, Error};
-use base::kif;
+use base::kif::{self, tilemux::SchedClass, tilemux::SchedParams};
 use base::log;
 use base::rc::Rc;
 use base::time::TimeDuration;
@@ -33,22 +33,38 @@ pub const IDLE_ID: Id = 0;
 
 pub const DEF_TIME_SLICE: TimeDuration = TimeDuration::from_millis(1);
 
+// the fixed-point representation of a utilization of 100%
+const EDF_UTIL_MAX: u128 = 1_000_000;
+
 pub struct Quota<T> {
     id: Id,
     parent: Option<Id>,
+    // whether the amount has not been taken from the parent, but is shared with it
+    shared: bool,
     users: Cell<u64>,
     total: Cell<T>,
     left: Cell<T>,
+    // only used for time quotas
+    sched: Cell<SchedParams>,
+    // the quota that holds the EDF reservation (only used for EDF time quotas)
+    reservation: Cell<Id>,
 }
 
 impl<T: PrimInt + fmt::Display> Quota<T> {
     pub fn new(id: Id, parent: Option<Id>, amount: T) -> Rc<Self> {
+        Self::new_with(id, parent, false, amount)
+    }
+
+    fn new_with(id: Id, parent: Option<Id>, shared: bool, amount: T) -> Rc<Self> {
         Rc::new(Self {
             id,
             parent,
+            shared,
             users: Cell::from(0),
             total: Cell::from(amount),
             left: Cell::from(amount),
+            sched: Cell::from(SchedParams::new_rr()),
+            reservation: Cell::from(id),
         })
     }
 
@@ -57,6 +73,11 @@ impl<T: PrimInt + fmt::Display> Quota<T> {
         Ok(Self::new(NEXT_ID.get() - 1, Some(self.id), amount))
     }
 
+    fn derive_shared(&self) -> Rc<Self> {
+        NEXT_ID.set(NEXT_ID.get() + 1);
+        Self::new_with(NEXT_ID.get() - 1, Some(self.id), true, self.total())
+    }
+
     pub fn id(&self) -> Id {
         self.id
     }
@@ -88,18 +109,35 @@ impl<T: PrimInt + fmt::Display> Quota<T> {
     pub fn set_left(&self, val: T) {
         self.left.set(val);
     }
+
+    pub fn sched(&self) -> SchedParams {
+        self.sched.get()
+    }
+
+    pub fn reservation(&self) -> Id {
+        self.reservation.get()
+    }
+
+    fn set_sched(&self, parent: &Self, sched: SchedParams) {
+        // children with the parent's EDF parameters share the parent's reservation
+        if sched.class == SchedClass::EDF && sched == parent.sched() {
+            self.reservation.set(parent.reservation());
+        }
+        self.sched.set(sched);
+    }
 }
 
 impl<T: fmt::Display + Copy> fmt::Debug for Quota<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "Q[{}: {} of {}, users={}, parent={:?}]",
+            "Q[{}: {} of {}, users={}, parent={:?}, sched={:?}]",
             self.id,
             self.left.get(),
             self.total.get(),
             self.users.get(),
-            self.parent
+            self.parent,
+            self.sched.get()
         )
     }
 }
@@ -119,6 +157,16 @@ pub fn get_pt(id: Id) -> Option<Rc<PTQuota>> {
     PT_QUOTAS.borrow().iter().find(|q| q.id == id).cloned()
 }
 
+/// Returns the number of users of all time quotas that share the EDF reservation of `q`
+pub fn reservation_users(q: &TimeQuota) -> u64 {
+    TIME_QUOTAS
+        .borrow()
+        .iter()
+        .filter(|o| o.reservation() == q.reservation())
+        .map(|o| o.users())
+        .sum()
+}
+
 pub fn init(pts: usize) {
     // for idle and ourself
     TIME_QUOTAS.borrow_mut().push(TimeQuota::new(
@@ -176,24 +224,77 @@ pub fn set(id: Id, time: TimeDuration, pts: usize) -> Result<(), Error> {
     Ok(())
 }
 
+fn check_sched(parent: &SchedParams, sched: &SchedParams, privileged: bool) -> Result<(), Error> {
+    match sched.class {
+        SchedClass::RR => Ok(()),
+        SchedClass::PRIO => {
+            if sched.prio > kif::tilemux::MAX_PRIO {
+                return Err(Error::new(Code::NoPerm));
+            }
+            // without a root tile capability, a child can only keep or lower its parent's priority
+            if !privileged && (parent.class != SchedClass::PRIO || sched.prio > parent.prio) {
+                return Err(Error::new(Code::NoPerm));
+            }
+            Ok(())
+        },
+        // the same parameters as the parent's mean that the child shares the parent's reservation
+        SchedClass::EDF if sched == parent => Ok(()),
+        SchedClass::EDF => {
+            if sched.period == 0 || sched.budget == 0 || sched.budget > sched.period {
+                return Err(Error::new(Code::InvArgs));
+            }
+            // new reservations require a root tile capability
+            if !privileged {
+                return Err(Error::new(Code::NoPerm));
+            }
+
+            // admission control: the reserved budgets cannot exceed the available time
+            let util = |s: &SchedParams| (s.budget as u128 * EDF_UTIL_MAX) / s.period as u128;
+            let used: u128 = TIME_QUOTAS
+                .borrow()
+                .iter()
+                .filter(|q| q.sched().class == SchedClass::EDF && q.reservation() == q.id)
+                .map(|q| util(&q.sched()))
+                .sum();
+            if used + util(sched) > EDF_UTIL_MAX {
+                return Err(Error::new(Code::NoSpace));
+            }
+            Ok(())
+        },
+        _ => Err(Error::new(Code::InvArgs)),
+    }
+}
+
 pub fn derive(
     parent_time: Id,
     parent_pts: Id,
     time: Option<TimeDuration>,
     pts: Option<usize>,
+    sched: SchedParams,
+    privileged: bool,
 ) -> Result<(Id, Id), Error> {
     let ptime = get_time(parent_time).ok_or_else(|| Error::new(Code::InvArgs))?;
     let ppt = get_pt(parent_pts).ok_or_else(|| Error::new(Code::InvArgs))?;
 
     log!(
         crate::LOG_QUOTAS,
-        "quota::derive(ptime={}, ppt={}, time={:?}, pts={:?})",
+        "quota::derive(ptime={}, ppt={}, time={:?}, pts={:?}, sched={:?}, priv={})",
         parent_time,
         parent_pts,
         time,
-        pts
+        pts,
+        sched,
+        privileged
     );
 
+    let sched = if sched.class == SchedClass::INHERIT {
+        ptime.sched()
+    }
+    else {
+        sched
+    };
+    check_sched(&ptime.sched(), &sched, privileged)?;
+
     let time_id = if let Some(t) = time {
         let total = TimeDuration::from_nanos(ptime.total());
         if total < t {
@@ -207,6 +308,21 @@ pub fn derive(
         ptime.set_left(ptime.left().saturating_sub(t.as_nanos() as u64));
 
         let ctime = ptime.derive(t.as_nanos() as u64)?;
+        ctime.set_sched(&ptime, sched);
+        log!(
+            crate::LOG_QUOTAS,
+            "time-quota: parent={:?}, child={:?}",
+            ptime,
+            ctime
+        );
+        TIME_QUOTAS.borrow_mut().push(ctime.clone());
+        ctime.id
+    }
+    else if sched != ptime.sched() {
+        // different scheduling parameters require a separate quota, but without a time slice of
+        // its own, we let the child use the same time slice length without taking it from the parent
+        let ctime = ptime.derive_shared();
+        ctime.set_sched(&ptime, sched);
         log!(
             crate::LOG_QUOTAS,
             "time-quota: parent={:?}, child={:?}",
@@ -258,7 +374,7 @@ pub fn remove(time: Option<Id>, pts: Option<Id>) -> Result<(), Error> {
         let time = get_time(id).ok_or_else(|| Error::new(Code::InvArgs))?;
         log!(crate::LOG_QUOTAS, "time-quota: removing {:?}", time);
         // give quota back to parent object
-        if let Some(parent) = time.parent {
+        if let (Some(parent), false) = (time.parent, time.shared) {
             let ptime = get_time(parent).unwrap();
             ptime.set_total(ptime.total() + time.total());
         }
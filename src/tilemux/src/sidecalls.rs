@@ -13,6 +13,7 @@
  * General Public License version 2 for more details.
  */
 
+use base::cell::StaticRefCell;
 use base::cfg;
 use base::errors::{Code, Error};
 use base::kif;
@@ -26,9 +27,20 @@ use crate::activities;
 use crate::helper;
 use crate::quota;
 use crate::sendqueue;
+use crate::signals;
 
 const SIDE_RBUF_ADDR: usize = cfg::TILEMUX_RBUF_SPACE + cfg::KPEX_RBUF_SIZE;
 
+const NO_USAGE: kif::tilemux::Usage = kif::tilemux::Usage {
+    cpu_time: 0,
+    ctxsws: 0,
+    pagefaults: 0,
+    msgs: 0,
+};
+
+static USAGES: StaticRefCell<[kif::tilemux::Usage; cfg::MAX_ACTS]> =
+    StaticRefCell::new([NO_USAGE; cfg::MAX_ACTS]);
+
 fn get_request<'de, R: Deserialize<'de>>(msg: &'static tcu::Message) -> Result<R, Error> {
     let mut de = M3Deserializer::new(msg.as_words());
     de.skip(1);
@@ -92,6 +104,72 @@ fn activity_ctrl(msg: &'static tcu::Message) -> Result<(), Error> {
     }
 }
 
+fn activity_suspend(msg: &'static tcu::Message) -> Result<(GlobAddr, usize), Error> {
+    let r: kif::tilemux::ActSuspend = get_request(msg)?;
+
+    log!(
+        crate::LOG_SIDECALLS,
+        "sidecall::activity_suspend(act={})",
+        r.act_id
+    );
+
+    let mut act = activities::get_mut(r.act_id).ok_or_else(|| Error::new(Code::ActivityGone))?;
+    act.suspend()
+}
+
+fn activity_resume(msg: &'static tcu::Message) -> Result<(), Error> {
+    let r: kif::tilemux::ActResume = get_request(msg)?;
+
+    log!(
+        crate::LOG_SIDECALLS,
+        "sidecall::activity_resume(act={})",
+        r.act_id
+    );
+
+    let cur = activities::cur();
+    assert!(cur.id() != r.act_id);
+    let mut act = activities::get_mut(r.act_id).ok_or_else(|| Error::new(Code::ActivityGone))?;
+    // temporary switch to the activity to access the environment
+    act.switch_to();
+    act.resume();
+    // now switch back
+    cur.switch_to();
+    Ok(())
+}
+
+fn activity_signal(msg: &'static tcu::Message) -> Result<(), Error> {
+    let r: kif::tilemux::ActSignal = get_request(msg)?;
+
+    log!(
+        crate::LOG_SIDECALLS,
+        "sidecall::activity_signal(act={}, signal={})",
+        r.act_id,
+        r.signal
+    );
+
+    signals::send(r.act_id, r.signal)
+}
+
+fn activity_usage(msg: &'static tcu::Message) -> Result<GlobAddr, Error> {
+    let r: kif::tilemux::ActUsage = get_request(msg)?;
+
+    log!(
+        crate::LOG_SIDECALLS,
+        "sidecall::activity_usage(act={})",
+        r.act_id
+    );
+
+    let act = activities::get_mut(r.act_id).ok_or_else(|| Error::new(Code::ActivityGone))?;
+    // the kernel reads the usage from our memory; use one slot per activity to not overwrite the
+    // usage of another activity that has not been read yet
+    let mut usages = USAGES.borrow_mut();
+    let usage = usages
+        .get_mut(r.act_id as usize)
+        .ok_or_else(|| Error::new(Code::InvArgs))?;
+    *usage = act.usage();
+    Ok(helper::global_addr(usage as *const _ as usize))
+}
+
 fn map(msg: &'static tcu::Message) -> Result<(), Error> {
     let r: kif::tilemux::Map = get_request(msg)?;
 
@@ -194,11 +272,13 @@ fn derive_quota(msg: &'static tcu::Message) -> Result<(u64, u64), Error> {
 
     log!(
         crate::LOG_SIDECALLS,
-        "sidecall::derive_quota(ptime={}, ppts={}, time={:?}, pts={:?})",
+        "sidecall::derive_quota(ptime={}, ppts={}, time={:?}, pts={:?}, sched={:?}, priv={})",
         r.parent_time,
         r.parent_pts,
         r.time,
-        r.pts
+        r.pts,
+        r.sched,
+        r.privileged
     );
 
     quota::derive(
@@ -206,6 +286,8 @@ fn derive_quota(msg: &'static tcu::Message) -> Result<(u64, u64), Error> {
         r.parent_pts,
         r.time.map(TimeDuration::from_nanos),
         r.pts,
+        r.sched,
+        r.privileged,
     )
 }
 
@@ -287,6 +369,13 @@ fn handle_sidecall(msg: &'static tcu::Message) {
         kif::tilemux::Sidecalls::SET_QUOTA => set_quota(msg),
         kif::tilemux::Sidecalls::REMOVE_QUOTAS => remove_quotas(msg),
         kif::tilemux::Sidecalls::RESET_STATS => reset_stats(msg),
+        kif::tilemux::Sidecalls::ACT_SUSPEND => activity_suspend(msg).map(|(addr, size)| {
+            val1 = addr.raw();
+            val2 = size as u64;
+        }),
+        kif::tilemux::Sidecalls::ACT_RESUME => activity_resume(msg),
+        kif::tilemux::Sidecalls::ACT_SIGNAL => activity_signal(msg),
+        kif::tilemux::Sidecalls::ACT_USAGE => activity_usage(msg).map(|addr| val1 = addr.raw()),
         _ => Err(Error::new(Code::NotSup)),
     };
 
@@ -0,0 +1,60 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Delivers asynchronous signals to activities
+//!
+//! Signals are delivered via upcalls: if the activity has registered a handler for the signal, the
+//! signal is marked as pending and, as soon as the activity continues to run, its current context is
+//! saved and the activity continues at the registered entry point. The entry point returns to the
+//! saved context via `tmif::sig_return`. Signals without handler terminate the activity, except for
+//! `Signal::SERV_RESTART`, which is ignored by default.
+
+use base::errors::{Code, Error};
+use base::log;
+use base::tmif;
+
+use crate::activities;
+use crate::arch;
+
+/// The exit code of activities that are terminated by `sig` is `SIG_EXIT_BASE + sig`.
+pub const SIG_EXIT_BASE: i32 = 128;
+
+pub fn send(id: activities::Id, sig: u32) -> Result<(), Error> {
+    let mut act = activities::get_mut(id).ok_or_else(|| Error::new(Code::ActivityGone))?;
+
+    log!(
+        crate::LOG_SIGNALS,
+        "Sending signal {} to Activity {}",
+        sig,
+        id
+    );
+
+    if act.has_sig_handler(sig) {
+        act.add_signal(sig);
+    }
+    else if sig != tmif::Signal::SERV_RESTART.val {
+        drop(act);
+        activities::remove(id, SIG_EXIT_BASE + sig as i32, true, true);
+    }
+    Ok(())
+}
+
+pub fn deliver() {
+    let entered = activities::try_cur().map_or(false, |mut cur| cur.enter_signal());
+    if entered {
+        // the handler loads the FPU registers on its first use
+        arch::disable_fpu();
+    }
+}
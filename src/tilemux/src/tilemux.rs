@@ -21,11 +21,13 @@ extern crate heap;
 mod activities;
 mod arch;
 mod corereq;
+mod crash;
 mod helper;
 mod irqs;
 mod quota;
 mod sendqueue;
 mod sidecalls;
+mod signals;
 mod timer;
 mod tmcalls;
 mod vma;
@@ -67,6 +69,8 @@ pub const LOG_IRQS: bool = false;
 pub const LOG_SQUEUE: bool = false;
 /// Logs quota operations
 pub const LOG_QUOTAS: bool = false;
+/// Logs signal delivery
+pub const LOG_SIGNALS: bool = false;
 
 extern "C" {
     fn __m3_init_libc(argc: i32, argv: *const *const u8, envp: *const *const u8);
@@ -129,6 +133,9 @@ fn leave(state: &mut arch::State) -> *mut libc::c_void {
         state as *mut _ as *mut libc::c_void
     };
 
+    // enter the signal handler of the activity that runs next, if required
+    signals::deliver();
+
     if NEED_TIMER.replace(false) {
         timer::reprogram();
     }
@@ -150,6 +157,8 @@ pub fn reg_timer_reprogram() {
 
 pub extern "C" fn unexpected_irq(state: &mut arch::State) -> *mut libc::c_void {
     log!(LOG_ERR, "Unexpected IRQ with user state:\n{:?}", state);
+    let (signal, fault_addr) = arch::crash_cause(state);
+    crash::record(&mut activities::cur(), signal, fault_addr);
     activities::remove_cur(1);
 
     leave(state)
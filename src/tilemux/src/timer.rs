@@ -19,6 +19,7 @@ use base::kif;
 use base::log;
 use base::tcu;
 use base::time::{TimeDuration, TimeInstant};
+use base::tmif;
 use core::cmp;
 
 use crate::activities;
@@ -26,6 +27,8 @@ use crate::activities;
 struct Timeout {
     end: TimeInstant,
     act: activities::Id,
+    // alarms deliver a signal instead of unblocking the activity
+    alarm: bool,
 }
 
 static LIST: StaticRefCell<Vec<Timeout>> = StaticRefCell::new(Vec::new());
@@ -34,6 +37,7 @@ pub fn add(act: activities::Id, duration: TimeDuration) {
     let timeout = Timeout {
         end: TimeInstant::now() + duration,
         act,
+        alarm: false,
     };
 
     log!(
@@ -44,6 +48,10 @@ pub fn add(act: activities::Id, duration: TimeDuration) {
         timeout.end
     );
 
+    insert(timeout);
+}
+
+fn insert(timeout: Timeout) {
     // insert new timeout in descending order of timeouts
     let mut list = LIST.borrow_mut();
     if let Some(idx) = list.iter().position(|t| t.end < timeout.end) {
@@ -58,8 +66,29 @@ pub fn add(act: activities::Id, duration: TimeDuration) {
 
 pub fn remove(act: activities::Id) {
     log!(crate::LOG_TIMER, "timer: removing Activity {}", act);
-    LIST.borrow_mut().retain(|t| t.act != act);
+    LIST.borrow_mut().retain(|t| t.act != act || t.alarm);
+    crate::reg_timer_reprogram();
+}
+
+/// Replaces the alarm of the given activity. If `duration` is `None`, the alarm is only removed.
+pub fn set_alarm(act: activities::Id, duration: Option<TimeDuration>) {
+    LIST.borrow_mut().retain(|t| t.act != act || !t.alarm);
     crate::reg_timer_reprogram();
+
+    if let Some(d) = duration {
+        log!(
+            crate::LOG_TIMER,
+            "timer: setting alarm for Activity {} in {} ns",
+            act,
+            d.as_nanos()
+        );
+
+        insert(Timeout {
+            end: TimeInstant::now() + d,
+            act,
+            alarm: true,
+        });
+    }
 }
 
 // this function should only be called from the root module; others can request it by calling
@@ -113,15 +142,31 @@ pub fn trigger() {
     let now = TimeInstant::now();
     while !list.is_empty() && now >= list[list.len() - 1].end {
         let timeout = list.pop().unwrap();
-        log!(
-            crate::LOG_TIMER,
-            "timer: unblocking Activity {} @ {:?}",
-            timeout.act,
-            now
-        );
-        activities::get_mut(timeout.act)
-            .unwrap()
-            .unblock(activities::Event::Timeout);
+        // unblocking or removing the activity might change the list
+        drop(list);
+
+        if timeout.alarm {
+            log!(
+                crate::LOG_TIMER,
+                "timer: alarm for Activity {} @ {:?}",
+                timeout.act,
+                now
+            );
+            crate::signals::send(timeout.act, tmif::Signal::ALRM.val).ok();
+        }
+        else {
+            log!(
+                crate::LOG_TIMER,
+                "timer: unblocking Activity {} @ {:?}",
+                timeout.act,
+                now
+            );
+            activities::get_mut(timeout.act)
+                .unwrap()
+                .unblock(activities::Event::Timeout);
+        }
+
+        list = LIST.borrow_mut();
     }
     drop(list);
 
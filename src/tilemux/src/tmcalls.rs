@@ -147,6 +147,44 @@ fn tmcall_flush_inv(_state: &mut arch::State) -> Result<(), Error> {
     Ok(())
 }
 
+fn tmcall_sig_action(state: &mut arch::State) -> Result<(), Error> {
+    let entry = state.r[isr::TMC_ARG1] as usize;
+    let mask = state.r[isr::TMC_ARG2] as u32;
+
+    log!(
+        crate::LOG_CALLS,
+        "tmcall::sig_action(entry={:#x}, mask={:#x})",
+        entry,
+        mask
+    );
+
+    activities::cur().set_sig_action(entry, mask);
+
+    Ok(())
+}
+
+fn tmcall_sig_return(_state: &mut arch::State) -> Result<isize, Error> {
+    log!(crate::LOG_CALLS, "tmcall::sig_return()");
+
+    let res = activities::cur().leave_signal()?;
+    // the FPU registers of the interrupted context are loaded on the next use
+    arch::disable_fpu();
+    Ok(res as isize)
+}
+
+fn tmcall_alarm(state: &mut arch::State) -> Result<(), Error> {
+    let duration = match state.r[isr::TMC_ARG1] {
+        usize::MAX => None,
+        t => Some(TimeDuration::from_nanos(t as u64)),
+    };
+
+    log!(crate::LOG_CALLS, "tmcall::alarm(duration={:?})", duration);
+
+    timer::set_alarm(activities::cur().id(), duration);
+
+    Ok(())
+}
+
 fn tmcall_noop(_state: &mut arch::State) -> Result<(), Error> {
     log!(crate::LOG_CALLS, "tmcall::noop()");
 
@@ -165,6 +203,10 @@ pub fn handle_call(state: &mut arch::State) {
         tmif::Operation::TRANSL_FAULT => tmcall_transl_fault(state).map(|_| 0isize),
         tmif::Operation::FLUSH_INV => tmcall_flush_inv(state).map(|_| 0isize),
         tmif::Operation::NOOP => tmcall_noop(state).map(|_| 0isize),
+        tmif::Operation::SIG_ACTION => tmcall_sig_action(state).map(|_| 0isize),
+        // continue with the result register of the interrupted context
+        tmif::Operation::SIG_RETURN => tmcall_sig_return(state),
+        tmif::Operation::ALARM => tmcall_alarm(state).map(|_| 0isize),
 
         _ => Err(Error::new(Code::NotSup)),
     };
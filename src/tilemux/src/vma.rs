@@ -16,7 +16,7 @@
 
 use base::cfg;
 use base::errors::Error;
-use base::kif::PageFlags;
+use base::kif::{self, PageFlags};
 use base::log;
 use base::mem::MsgBuf;
 use base::tcu;
@@ -102,6 +102,7 @@ fn recv_pf_resp(cur: &mut activities::Activity) -> activities::ContResult {
                 pf_state.perm,
                 cur.user_state()
             );
+            crate::crash::record(cur, kif::tilemux::SIGSEGV, pf_state.virt);
             activities::ContResult::Failure
         }
         else {
@@ -115,7 +116,7 @@ fn recv_pf_resp(cur: &mut activities::Activity) -> activities::ContResult {
 
 pub fn handle_xlate(virt: usize, perm: PageFlags) {
     // perform page table walk
-    let act = activities::cur();
+    let mut act = activities::cur();
     let pte = act.translate(virt, perm);
 
     // page fault?
@@ -127,6 +128,7 @@ pub fn handle_xlate(virt: usize, perm: PageFlags) {
                 "Unable to handle page fault for {:#x}",
                 virt
             );
+            crate::crash::record(&mut activities::cur(), kif::tilemux::SIGSEGV, virt);
             activities::remove_cur(1);
         }
     }
@@ -135,6 +137,8 @@ pub fn handle_xlate(virt: usize, perm: PageFlags) {
         // ensure that we only insert user-accessible pages into the TLB
         if (pte & PageFlags::U.bits()) == 0 {
             log!(crate::LOG_ERR, "No permission to access {:#x}", virt);
+            crate::crash::record(&mut act, kif::tilemux::SIGSEGV, virt);
+            drop(act);
             activities::remove_cur(1);
         }
         else {
@@ -163,6 +167,7 @@ pub fn handle_pf(
             virt,
             state
         );
+        crate::crash::record(&mut activities::cur(), kif::tilemux::SIGSEGV, virt);
         return Err(e);
     }
 
@@ -1,4 +1,5 @@
 dirs = [
+    'cfgcheck',
     'elf2hex',
     'exm3fs',
     'gem52otf',
@@ -0,0 +1,5 @@
+def build(gen, env):
+    # cfgcheck uses the parser and checks of the resource manager
+    deps = env.glob('../../libs/rust/resmngcfg/**/*.rs', recursive = True)
+    bin = env.cargo(gen, out = 'cfgcheck', deps = deps)
+    env.install(gen, env['TOOLDIR'], bin)
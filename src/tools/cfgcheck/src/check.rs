@@ -0,0 +1,262 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+//! Validates boot configurations against the rules of the resource manager
+//!
+//! The apps are checked by the same code as in the resource manager (see the resmngcfg crate).
+//! This module only checks the parts that are interpreted by the kernel and execute.sh.
+
+use resmngcfg::check::{self as rcheck, Ty};
+use resmngcfg::expand;
+use resmngcfg::xml::Element;
+
+use crate::error::Error;
+
+/// The variables that are substituted by execute.sh before the config is passed to the kernel
+const HOST_VARS: &[(&str, &str)] = &[
+    ("fs.path", "$fs.path"),
+    ("fs.size", "$fs.size"),
+    ("hd.path", "$hd.path"),
+];
+
+fn error<T>(elem: &Element, msg: String) -> Result<T, Error> {
+    Err(resmngcfg::Error::new(elem.loc.clone(), msg).into())
+}
+
+/// Checks the given boot configuration, which consists of the `<config>` root with the kernel
+/// arguments and the root domain.
+pub fn check(root: &Element) -> Result<(), Error> {
+    if root.name != "config" {
+        return error(root, format!("expected <config>, found <{}>", root.name));
+    }
+    rcheck::check_attrs(root, &[])?;
+
+    let mut kernel = false;
+    for c in &root.childs {
+        match c.name.as_ref() {
+            "kernel" => {
+                rcheck::check_attrs(c, &[("args", Ty::Str)])?;
+                kernel = true;
+            },
+            "dom" => {
+                rcheck::check_attrs(c, &[])?;
+                for a in &c.childs {
+                    if a.name != "app" {
+                        return error(a, format!("expected <app> in <dom>, found <{}>", a.name));
+                    }
+
+                    rcheck::check_app(&expand::expand_app(a, HOST_VARS)?, true)?.check()?;
+                }
+            },
+            tag => return error(c, format!("unknown tag <{}> in <config>", tag)),
+        }
+    }
+
+    if !kernel {
+        return error(root, "<config> requires a <kernel>".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use resmngcfg::xml;
+
+    fn check_cfg(apps: &str) -> Result<(), String> {
+        let cfg = format!(
+            "<config>\n<kernel args=\"kernel\" />\n<dom>\n{}\n</dom>\n</config>",
+            apps
+        );
+        let root = xml::parse("test.xml", &cfg, None).map_err(|e| e.to_string())?;
+        super::check(&root).map_err(|e| e.to_string())
+    }
+
+    fn check_app(body: &str) -> Result<(), String> {
+        check_cfg(&format!("<app args=\"root\">\n{}\n</app>", body))
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(
+            check_app(
+                r#"<dom><app args="m3fs" daemon="1"><serv name="m3fs" /></app></dom>
+                   <dom><app args="hello" usermem="16M" time="1ms" prio="1">
+                       <sess name="m3fs" />
+                   </app></dom>"#
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn syntax() {
+        assert_eq!(
+            check_cfg("<app args=\"root\">"),
+            Err("test.xml:5:1: expected </app>, found </dom>".to_string())
+        );
+        assert_eq!(
+            check_app("<dom></app>"),
+            Err("test.xml:5:6: expected </dom>, found </app>".to_string())
+        );
+        assert_eq!(
+            check_cfg("<app args=\"root\" args=\"a\" />"),
+            Err("test.xml:4:18: duplicate attribute 'args' in <app>".to_string())
+        );
+    }
+
+    #[test]
+    fn structure() {
+        assert_eq!(
+            xml::parse("test.xml", "<config><dom /></config>", None)
+                .map_err(|e| e.to_string())
+                .and_then(|r| super::check(&r).map_err(|e| e.to_string())),
+            Err("test.xml:1:1: <config> requires a <kernel>".to_string())
+        );
+        assert_eq!(
+            check_app("<foo />"),
+            Err("test.xml:5:1: unknown tag <foo> in <app>".to_string())
+        );
+        assert_eq!(
+            check_app("<dom />"),
+            Err("test.xml:5:1: <dom> requires at least one <app>".to_string())
+        );
+        assert_eq!(
+            check_app("<mount fs=\"m3fs\" />"),
+            Err("test.xml:5:1: <mount> requires the attributes 'fs' and 'path'".to_string())
+        );
+        assert_eq!(
+            check_app("<dom><app args=\"a\" restart=\"always\" /></dom>"),
+            Err("test.xml:5:6: app 'a' can only be restarted if it is a daemon".to_string())
+        );
+    }
+
+    #[test]
+    fn attributes() {
+        assert_eq!(
+            check_app("<dom><app args=\"a\" foo=\"1\" /></dom>"),
+            Err("test.xml:5:20: unknown attribute 'foo' in <app>".to_string())
+        );
+        assert_eq!(
+            check_app("<dom><app args=\"a\" usermem=\"1X\" /></dom>"),
+            Err("test.xml:5:20: invalid value '1X' for attribute 'usermem' in <app>".to_string())
+        );
+        assert_eq!(
+            check_app("<dom><app args=\"a\" prio=\"1\" period=\"1ms\" /></dom>"),
+            Err("test.xml:5:29: app 'a' cannot use 'period' together with 'prio'".to_string())
+        );
+        assert_eq!(
+            check_app("<dom><app args=\"a\" prio=\"256\" /></dom>"),
+            Err("test.xml:5:20: app 'a' has priority 256, but the maximum is 255".to_string())
+        );
+        assert_eq!(
+            check_app("<dom><app args=\"a\" prio=\"255\" /></dom>"),
+            Ok(())
+        );
+        // values with variables are only checked after the expansion
+        assert_eq!(
+            check_app(
+                "<var name=\"mem\" value=\"1G\" /><dom><app args=\"a\" usermem=\"$mem\" /></dom>"
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn expansion() {
+        assert_eq!(
+            check_app(
+                r#"<var name="mem" value="16M" />
+<template name="srv">
+    <dom><app args="$name" daemon="1" usermem="$mem"><serv name="$name" /></app></dom>
+</template>
+<use template="srv" name="a" />
+<use template="srv" name="b" />
+<dom><app args="c"><sess name="a" /><sess name="b" /><mount fs="m3fs" path="$fs.path" /></app></dom>"#
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check_app("<dom><app args=\"$foo\" /></dom>"),
+            Err("test.xml:5:11: unknown variable '$foo'".to_string())
+        );
+        assert_eq!(
+            check_app("<use template=\"foo\" />"),
+            Err("test.xml:5:1: unknown template 'foo'".to_string())
+        );
+        assert_eq!(
+            check_app(
+                "<template name=\"t\"><use template=\"t\" /></template><use template=\"t\" />"
+            ),
+            Err("test.xml:5:20: templates nested too deeply at 't'".to_string())
+        );
+        // malformed definitions are refused instead of crashing the resource manager
+        assert_eq!(
+            check_app("<var name=\"a\" value=\"1 />"),
+            Err("test.xml:8:10: unexpected end of input".to_string())
+        );
+        assert_eq!(
+            check_app("<var name=\"a\" />"),
+            Err("test.xml:5:1: <var> expects the attributes 'name' and 'value'".to_string())
+        );
+    }
+
+    #[test]
+    fn services() {
+        assert_eq!(
+            check_app("<dom><app args=\"a\"><sess name=\"m3fs\" /></app></dom>"),
+            Err("test.xml:5:20: config 'a': service 'm3fs' does not exist".to_string())
+        );
+        assert_eq!(
+            check_app(
+                r#"<dom><app args="a" daemon="1"><serv name="s" /></app></dom>
+<dom><app args="b" daemon="1"><serv name="s" /></app></dom>"#
+            ),
+            Err("test.xml:6:31: config 'b': service 's' does already exist".to_string())
+        );
+        assert_eq!(
+            check_app(
+                r#"<dom><app args="a"><serv name="s1" /><sess name="s2" /></app></dom>
+<dom><app args="b"><serv name="s2" /><sess name="s1" /></app></dom>"#
+            ),
+            Err("test.xml:5:6: config: dependency cycle 'a' -> 'b' -> 'a'".to_string())
+        );
+    }
+
+    #[test]
+    fn gates() {
+        assert_eq!(
+            check_app(
+                r#"<dom><app args="a"><rgate name="r" slots="1" /></app></dom>
+<dom><app args="b"><sgate name="r" /></app></dom>
+<dom><app args="c"><sgate name="r" /></app></dom>"#
+            ),
+            Err("test.xml:7:20: config 'c': not enough slots in rgate 'r'".to_string())
+        );
+        assert_eq!(
+            check_app("<dom><app args=\"a\"><sgate name=\"r\" /></app></dom>"),
+            Err("test.xml:5:20: config 'a': rgate 'r' does not exist".to_string())
+        );
+    }
+
+    #[test]
+    fn includes() {
+        assert_eq!(
+            check_app("<include file=\"foo.xml\" />"),
+            Err(
+                "test.xml:5:1: unable to include 'foo.xml': includes are not supported".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,185 @@
+/*
+ * Copyright (C) 2022 Nils Asmussen, Barkhausen Institut
+ *
+ * This file is part of M3 (Microkernel-based SysteM for Heterogeneous Manycores).
+ *
+ * M3 is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License version 2 as
+ * published by the Free Software Foundation.
+ *
+ * M3 is distributed in the hope that it will be useful, but
+ * WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+ * General Public License version 2 for more details.
+ */
+
+mod check;
+mod error;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use error::Error;
+use resmngcfg::xml::{self, Element, Includer};
+
+/// Reads included files relative to the including file
+struct FileIncluder;
+
+impl Includer for FileIncluder {
+    fn read(&self, from: &str, file: &str) -> Result<(String, String), String> {
+        let path = Path::new(from)
+            .parent()
+            .unwrap_or_else(|| Path::new(""))
+            .join(file);
+        match fs::read_to_string(&path) {
+            Ok(xml) => Ok((path.display().to_string(), xml)),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+}
+
+fn parse_file(path: &Path) -> Result<Element, Error> {
+    let xml = fs::read_to_string(path)?;
+    Ok(xml::parse(
+        &path.display().to_string(),
+        &xml,
+        Some(&FileIncluder),
+    )?)
+}
+
+fn usage(prog: &str) -> ! {
+    eprintln!("Usage: {} (<file>|<dir>)...", prog);
+    eprintln!("       {} -i <file>", prog);
+    eprintln!();
+    eprintln!("Checks the given boot configurations. Directories are searched recursively");
+    eprintln!(
+        "for XML files (e.g., '{} boot' checks all boot configurations), except for",
+        prog
+    );
+    eprintln!("directories named 'include', which contain fragments for <include>.");
+    eprintln!();
+    eprintln!("With -i, the given configuration is printed with all includes resolved.");
+    exit(1)
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    if path.is_dir() {
+        let mut entries = fs::read_dir(path)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+        for e in entries {
+            if e.is_dir() && e.file_name().map_or(false, |n| n == "include") {
+                continue;
+            }
+            if e.is_dir() || e.extension().map_or(false, |ext| ext == "xml") {
+                collect_files(&e, files)?;
+            }
+        }
+    }
+    else {
+        files.push(path.to_path_buf());
+    }
+    Ok(())
+}
+
+fn check_file(path: &Path) -> Result<(), Error> {
+    let root = parse_file(path)?;
+    check::check(&root)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        usage(&args[0]);
+    }
+
+    if args[1] == "-i" {
+        if args.len() != 3 {
+            usage(&args[0]);
+        }
+        match parse_file(Path::new(&args[2])) {
+            Ok(root) => print!("{}", root),
+            Err(e @ Error::Config(..)) => {
+                eprintln!("{}", e);
+                exit(1);
+            },
+            Err(e) => {
+                eprintln!("{}: {}", args[2], e);
+                exit(1);
+            },
+        }
+        return;
+    }
+
+    let mut files = Vec::new();
+    for a in &args[1..] {
+        if let Err(e) = collect_files(Path::new(a), &mut files) {
+            eprintln!("{}: {}", a, e);
+            exit(1);
+        }
+    }
+
+    let mut failed = 0;
+    for f in &files {
+        match check_file(f) {
+            Err(e @ Error::Config(..)) => eprintln!("{}", e),
+            Err(e) => eprintln!("{}: {}", f.display(), e),
+            Ok(_) => continue,
+        }
+        failed += 1;
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} configurations are invalid", failed, files.len());
+        exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    #[test]
+    fn includes() {
+        let dir = std::env::temp_dir().join(format!("cfgcheck-{}", std::process::id()));
+        fs::create_dir_all(dir.join("include")).unwrap();
+        fs::write(
+            dir.join("include/a.xml"),
+            "<!-- a -->\n<b /><include file=\"c.xml\" />",
+        )
+        .unwrap();
+        fs::write(dir.join("include/c.xml"), "<c /><d />").unwrap();
+        fs::write(
+            dir.join("cfg.xml"),
+            "<x>\n  <include file=\"include/a.xml\" />\n  <include file=\"e.xml\" />\n</x>",
+        )
+        .unwrap();
+
+        let cfg = dir.join("cfg.xml");
+        let err = super::parse_file(&cfg).err().unwrap().to_string();
+        assert_eq!(
+            err,
+            format!(
+                "{}:3:3: unable to include 'e.xml': {}: No such file or directory (os error 2)",
+                cfg.display(),
+                dir.join("e.xml").display()
+            )
+        );
+
+        fs::write(dir.join("e.xml"), "<e />").unwrap();
+        let root = super::parse_file(&cfg).ok().unwrap();
+        assert_eq!(
+            root.to_string(),
+            "<x>\n    <b />\n    <c />\n    <d />\n    <e />\n</x>\n"
+        );
+        assert_eq!(
+            *root.childs[1].loc.file,
+            dir.join("include/c.xml").display().to_string()
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
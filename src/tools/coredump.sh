@@ -0,0 +1,37 @@
+#!/bin/bash
+
+# Opens a core file in gdb that the pager wrote to /coredumps on m3fs for a crashed child.
+# The core files are taken from the FS image that is written back after the run, if it exists.
+
+if [ $# -lt 4 ]; then
+    echo "Usage: $0 <crossprefix> <build> <binary> <fsimg> [<core>]" >&2
+    exit 1
+fi
+
+crossprefix=$1
+build=$2
+binary=$3
+fsimg=$build/$4
+if [ -f "$fsimg.out" ]; then
+    fsimg=$fsimg.out
+fi
+
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+
+"$build/tools/exm3fs" "$fsimg" "$tmp/fs" || exit 1
+
+if [ "$5" != "" ]; then
+    core=$tmp/fs/coredumps/$5
+else
+    # take the core file of the child with the highest id
+    core=$(find "$tmp/fs/coredumps" -name "$(basename "$binary")-*.core" 2>/dev/null | \
+        sort -V | tail -n 1)
+fi
+
+if [ ! -f "$core" ]; then
+    echo "No core file for $(basename "$binary") found in $fsimg" >&2
+    exit 1
+fi
+
+RUST_GDB=${crossprefix}gdb rust-gdb "$binary" "$core"
@@ -47,13 +47,16 @@ generate_config() {
     hd=$M3_HDD_PATH
     fs=build/$M3_TARGET-$M3_ISA-$M3_BUILD/$M3_FS
     fssize=$(stat --format="%s" "$fs")
+    # resolve includes here, because the root resource manager has no file system
+    "$build/tools/cfgcheck" -i "$1" > "$2/boot-inc.xml" || exit 1
     sed "
         s#\$fs.path#$fs#g;
         s#\$fs.size#$fssize#g;
         s#\$hd.path#$hd#g;
-    " < "$1" > "$2/boot-all.xml"
+    " < "$2/boot-inc.xml" > "$2/boot-all.xml"
 
     xmllint --schema misc/boot.xsd --noout "$2/boot-all.xml" > /dev/null || exit 1
+    "$build/tools/cfgcheck" "$2/boot-all.xml" || exit 1
     # this can fail if there is no app element (e.g., standalone.xml)
     xmllint --xpath /config/dom/app "$2/boot-all.xml" > "$2/boot.xml" || true
 }
@@ -0,0 +1,61 @@
+#!/usr/bin/env python3
+
+# Decodes the system call trace that root prints on exit if started with the "systrace" argument.
+# The operation and error names are taken from the sources to stay in sync with the kernel.
+
+import os
+import re
+import sys
+
+if len(sys.argv) < 2:
+    print("Usage: {} <log-file> [--summary]".format(sys.argv[0]))
+    sys.exit(1)
+
+base = os.path.join(os.path.dirname(os.path.abspath(__file__)), '..', 'libs', 'rust', 'base', 'src')
+
+def read_ops():
+    ops = {}
+    with open(os.path.join(base, 'kif', 'syscalls.rs'), 'r') as f:
+        src = f.read()
+    block = re.search(r"pub struct Operation : u64 \{(.*?)\n    \}", src, re.S)
+    for m in re.finditer(r"const ([A-Z_]+) = (\d+);", block[1]):
+        ops[int(m[2])] = m[1].lower()
+    return ops
+
+def read_errors():
+    errors = []
+    with open(os.path.join(base, 'errors.rs'), 'r') as f:
+        src = f.read()
+    block = re.search(r"pub enum Code \{(.*?)\n\}", src, re.S)
+    for line in block[1].split('\n'):
+        m = re.match(r"^\s*([A-Za-z]+)(\s*=\s*\d+)?,", line)
+        if m:
+            errors.append(m[1])
+    return errors
+
+ops = read_ops()
+errors = read_errors()
+
+entries = []
+with open(sys.argv[1], 'r') as f:
+    for line in f.readlines():
+        m = re.search(r"systrace: (\d+) (\d+) (0x[0-9a-f]+) (0x[0-9a-f]+) (\d+) (\d+) (\d+)", line)
+        if m:
+            entries.append((int(m[1]), int(m[2]), int(m[3], 16), int(m[4], 16),
+                            int(m[5]), int(m[6]), int(m[7])))
+
+if len(sys.argv) > 2 and sys.argv[2] == '--summary':
+    stats = {}
+    for (act, op, _a0, _a1, err, _start, dur) in entries:
+        (count, fails, total) = stats.get(op, (0, 0, 0))
+        stats[op] = (count + 1, fails + (1 if err != 0 else 0), total + dur)
+    print("{:>15} {:>8} {:>8} {:>12} {:>12}".format("syscall", "count", "failed", "total ns", "avg ns"))
+    for op in sorted(stats, key=lambda o: stats[o][2], reverse=True):
+        (count, fails, total) = stats[op]
+        print("{:>15} {:>8} {:>8} {:>12} {:>12}".format(
+            ops.get(op, str(op)), count, fails, total, total // count))
+else:
+    for (act, op, a0, a1, err, start, dur) in entries:
+        print("[{:>14}] act={:<3} {:>15}({:#x}, {:#x}) -> {} ({} ns)".format(
+            start, act, ops.get(op, str(op)), a0, a1,
+            errors[err] if err < len(errors) else err, dur))